@@ -76,20 +76,28 @@ impl SlashCommand for TerminalSlashCommand {
             return Task::ready(Err(anyhow::anyhow!("no active terminal")));
         };
 
-        let line_count = arguments
-            .get(0)
-            .and_then(|s| s.parse::<usize>().ok())
-            .unwrap_or(DEFAULT_CONTEXT_LINES);
-
-        let lines = active_terminal
-            .read(cx)
-            .model()
-            .read(cx)
-            .last_n_non_empty_lines(line_count);
+        let terminal = active_terminal.read(cx).model().read(cx);
+        let line_count = arguments.get(0).and_then(|s| s.parse::<usize>().ok());
 
         let mut text = String::new();
-        text.push_str("Terminal output:\n");
-        text.push_str(&lines.join("\n"));
+        // Prefer the exact boundaries of the last command when the shell reports them.
+        match (line_count, terminal.last_command_output()) {
+            (None, Some(command_output)) => {
+                text.push_str("Terminal command:\n");
+                text.push_str(&command_output.command);
+                if let Some(exit_code) = command_output.exit_code {
+                    text.push_str(&format!("\nExit code: {exit_code}"));
+                }
+                text.push_str("\nTerminal output:\n");
+                text.push_str(command_output.output.trim_end());
+            }
+            (line_count, _) => {
+                let lines =
+                    terminal.last_n_non_empty_lines(line_count.unwrap_or(DEFAULT_CONTEXT_LINES));
+                text.push_str("Terminal output:\n");
+                text.push_str(&lines.join("\n"));
+            }
+        }
         let range = 0..text.len();
 
         Task::ready(Ok(SlashCommandOutput {
@@ -1,10 +1,14 @@
 use crate::tests::TestServer;
 use call::ActiveCall;
 use collections::HashSet;
+use command_palette_hooks::CommandPaletteInterceptor;
 use extension::ExtensionHostProxy;
 use fs::{FakeFs, Fs as _};
 use futures::StreamExt as _;
-use gpui::{BackgroundExecutor, Context as _, SemanticVersion, TestAppContext, UpdateGlobal as _};
+use gpui::{
+    BackgroundExecutor, Context as _, SemanticVersion, TestAppContext, UpdateGlobal as _,
+    VisualTestContext,
+};
 use http_client::BlockedHttpClient;
 use language::{
     language_settings::{
@@ -24,6 +28,8 @@ use remote_server::{HeadlessAppState, HeadlessProject};
 use serde_json::json;
 use settings::SettingsStore;
 use std::{path::Path, sync::Arc};
+use util::paths;
+use vim_mode_setting::VimModeSetting;
 
 #[gpui::test(iterations = 10)]
 async fn test_sharing_an_ssh_remote_project(
@@ -521,3 +527,122 @@ async fn test_ssh_collaboration_formatting_with_prettier(
         "Prettier formatting was not applied to client buffer after host's request"
     );
 }
+
+#[gpui::test]
+async fn test_ssh_vim_commands_with_paths(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    server_cx: &mut TestAppContext,
+) {
+    cx_a.update(|cx| {
+        release_channel::init(SemanticVersion::default(), cx);
+    });
+    server_cx.update(|cx| {
+        release_channel::init(SemanticVersion::default(), cx);
+    });
+
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+
+    // Set up project on remote FS, along with the remote user's home directory.
+    let (opts, server_ssh) = SshRemoteClient::fake_server(cx_a, server_cx);
+    let remote_fs = FakeFs::new(server_cx.executor());
+    remote_fs
+        .insert_tree(
+            "/code/project1",
+            json!({
+                "src": {
+                    "lib.rs": "fn one() -> usize { 1 }"
+                }
+            }),
+        )
+        .await;
+    let home_dir = paths::home_dir();
+    remote_fs.create_dir(home_dir).await.unwrap();
+
+    // User A connects to the remote project via SSH.
+    server_cx.update(HeadlessProject::init);
+    let remote_http_client = Arc::new(BlockedHttpClient);
+    let node = NodeRuntime::unavailable();
+    let languages = Arc::new(LanguageRegistry::new(server_cx.executor()));
+    let _headless_project = server_cx.new_model(|cx| {
+        client::init_settings(cx);
+        HeadlessProject::new(
+            HeadlessAppState {
+                session: server_ssh,
+                fs: remote_fs.clone(),
+                http_client: remote_http_client,
+                node_runtime: node,
+                languages,
+                extension_host_proxy: Arc::new(ExtensionHostProxy::new()),
+            },
+            cx,
+        )
+    });
+
+    let client_ssh = SshRemoteClient::fake_client(opts, cx_a).await;
+    let (project_a, worktree_id) = client_a
+        .build_ssh_project("/code/project1", client_ssh, cx_a)
+        .await;
+    executor.run_until_parked();
+
+    cx_a.update(|cx| {
+        command_palette_hooks::init(cx);
+        vim::init(cx);
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<VimModeSetting>(cx, |setting| *setting = Some(true));
+        });
+    });
+    let (workspace, cx_a) = client_a.build_workspace(&project_a, cx_a);
+    workspace
+        .update(cx_a, |workspace, cx| {
+            workspace.open_path((worktree_id, "src/lib.rs"), None, true, cx)
+        })
+        .await
+        .unwrap();
+
+    let run_command = |command: &str, cx: &mut VisualTestContext| {
+        let action = cx.update(|cx| {
+            CommandPaletteInterceptor::try_global(cx)
+                .unwrap()
+                .intercept(command, cx)
+                .into_iter()
+                .next()
+                .unwrap()
+                .action
+        });
+        cx.update(|cx| cx.dispatch_action(action));
+        executor.run_until_parked();
+    };
+
+    // Writing to a new file under `~/` creates it in the remote home directory.
+    run_command(":w ~/notes.rs", cx_a);
+    assert_eq!(
+        remote_fs.load(&home_dir.join("notes.rs")).await.unwrap(),
+        "fn one() -> usize { 1 }"
+    );
+
+    // Relative paths resolve against the remote worktree.
+    run_command(":sav src/two.rs", cx_a);
+    assert_eq!(
+        remote_fs
+            .load("/code/project1/src/two.rs".as_ref())
+            .await
+            .unwrap(),
+        "fn one() -> usize { 1 }"
+    );
+
+    run_command(":e ~/notes.rs", cx_a);
+    workspace.update(cx_a, |workspace, cx| {
+        let project_path = workspace.active_item(cx).unwrap().project_path(cx).unwrap();
+        let worktree = workspace
+            .project()
+            .read(cx)
+            .worktree_for_id(project_path.worktree_id, cx)
+            .unwrap();
+        assert_eq!(
+            worktree.read(cx).abs_path().join(&project_path.path),
+            home_dir.join("notes.rs")
+        );
+    });
+}
@@ -178,22 +178,24 @@ impl CommandPaletteDelegate {
     ) {
         self.updating_matches.take();
 
-        let mut intercept_result = CommandPaletteInterceptor::try_global(cx)
-            .and_then(|interceptor| interceptor.intercept(&query, cx));
+        let mut intercept_results = CommandPaletteInterceptor::try_global(cx)
+            .map(|interceptor| interceptor.intercept(&query, cx))
+            .unwrap_or_default();
 
         if parse_zed_link(&query, cx).is_some() {
-            intercept_result = Some(CommandInterceptResult {
+            intercept_results = vec![CommandInterceptResult {
                 action: OpenZedUrl { url: query.clone() }.boxed_clone(),
                 string: query.clone(),
                 positions: vec![],
-            })
+            }];
         }
 
-        if let Some(CommandInterceptResult {
+        let mut intercepted_matches = Vec::new();
+        for CommandInterceptResult {
             action,
             string,
             positions,
-        }) = intercept_result
+        } in intercept_results
         {
             if let Some(idx) = matches
                 .iter()
@@ -205,18 +207,16 @@ impl CommandPaletteDelegate {
                 name: string.clone(),
                 action,
             });
-            matches.insert(
-                0,
-                StringMatch {
-                    candidate_id: commands.len() - 1,
-                    string,
-                    positions,
-                    score: 0.0,
-                },
-            )
+            intercepted_matches.push(StringMatch {
+                candidate_id: commands.len() - 1,
+                string,
+                positions,
+                score: 0.0,
+            })
         }
+        intercepted_matches.append(&mut matches);
         self.commands = commands;
-        self.matches = matches;
+        self.matches = intercepted_matches;
         if self.matches.is_empty() {
             self.selected_ix = 0;
         } else {
@@ -340,6 +340,19 @@ impl PickerDelegate for CommandPaletteDelegate {
         }
     }
 
+    fn confirm_completion(
+        &mut self,
+        _query: String,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<String> {
+        // Only intercepted commands can be completed, as they may take arguments.
+        let selected = self.matches.get(self.selected_ix)?;
+        if selected.candidate_id < self.all_commands.len() {
+            return None;
+        }
+        Some(selected.string.clone())
+    }
+
     fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
         self.command_palette
             .update(cx, |_, cx| cx.emit(DismissEvent))
@@ -353,6 +366,13 @@ impl PickerDelegate for CommandPaletteDelegate {
         }
         let action_ix = self.matches[self.selected_ix].candidate_id;
         let command = self.commands.swap_remove(action_ix);
+        if action_ix >= self.all_commands.len()
+            && CommandPaletteInterceptor::try_global(cx).is_some()
+        {
+            CommandPaletteInterceptor::update_global(cx, |interceptor, cx| {
+                interceptor.confirm(&command.name, cx)
+            });
+        }
 
         telemetry::event!(
             "Action Invoked",
@@ -106,9 +106,10 @@ pub struct CommandInterceptResult {
 
 /// An interceptor for the command palette.
 #[derive(Default)]
-pub struct CommandPaletteInterceptor(
-    Option<Box<dyn Fn(&str, &AppContext) -> Option<CommandInterceptResult>>>,
-);
+pub struct CommandPaletteInterceptor {
+    handler: Option<Box<dyn Fn(&str, &AppContext) -> Vec<CommandInterceptResult>>>,
+    confirm_handler: Option<Box<dyn Fn(&str, &mut AppContext)>>,
+}
 
 #[derive(Default)]
 struct GlobalCommandPaletteInterceptor(CommandPaletteInterceptor);
@@ -131,24 +132,38 @@ impl CommandPaletteInterceptor {
     }
 
     /// Intercepts the given query from the command palette.
-    pub fn intercept(&self, query: &str, cx: &AppContext) -> Option<CommandInterceptResult> {
-        let handler = self.0.as_ref()?;
+    ///
+    /// The results are shown, in order, above the commands matching the query.
+    pub fn intercept(&self, query: &str, cx: &AppContext) -> Vec<CommandInterceptResult> {
+        let Some(handler) = self.handler.as_ref() else {
+            return Vec::new();
+        };
 
         (handler)(query, cx)
     }
 
+    /// Notifies the interceptor that one of its results was run from the command palette.
+    pub fn confirm(&self, string: &str, cx: &mut AppContext) {
+        if let Some(handler) = self.confirm_handler.as_ref() {
+            (handler)(string, cx)
+        }
+    }
+
     /// Clears the global interceptor.
     pub fn clear(&mut self) {
-        self.0 = None;
+        self.handler = None;
+        self.confirm_handler = None;
     }
 
     /// Sets the global interceptor.
     ///
     /// This will override the previous interceptor, if it exists.
-    pub fn set(
-        &mut self,
-        handler: Box<dyn Fn(&str, &AppContext) -> Option<CommandInterceptResult>>,
-    ) {
-        self.0 = Some(handler);
+    pub fn set(&mut self, handler: Box<dyn Fn(&str, &AppContext) -> Vec<CommandInterceptResult>>) {
+        self.handler = Some(handler);
+    }
+
+    /// Sets the handler called with the string of an intercepted result when it is run.
+    pub fn set_confirm_handler(&mut self, handler: Box<dyn Fn(&str, &mut AppContext)>) {
+        self.confirm_handler = Some(handler);
     }
 }
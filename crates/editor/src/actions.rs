@@ -211,6 +211,9 @@ impl_actions!(
 gpui::actions!(
     editor,
     [
+        AcceptConflictBoth,
+        AcceptConflictOurs,
+        AcceptConflictTheirs,
         AcceptInlineCompletion,
         AcceptPartialCopilotSuggestion,
         AcceptPartialInlineCompletion,
@@ -264,6 +267,7 @@ gpui::actions!(
         ToggleFoldRecursive,
         Format,
         FormatSelections,
+        GoToConflict,
         GoToDeclaration,
         GoToDeclarationSplit,
         GoToDefinition,
@@ -272,6 +276,7 @@ gpui::actions!(
         GoToHunk,
         GoToImplementation,
         GoToImplementationSplit,
+        GoToPrevConflict,
         GoToPrevDiagnostic,
         GoToPrevHunk,
         GoToTypeDefinition,
@@ -362,6 +367,8 @@ gpui::actions!(
         SortLinesCaseInsensitive,
         SortLinesCaseSensitive,
         SplitSelectionIntoLines,
+        StageHunk,
+        StageSelectedLines,
         SwitchSourceHeader,
         Tab,
         TabPrev,
@@ -385,6 +392,7 @@ gpui::actions!(
         UnfoldRecursive,
         UniqueLinesCaseInsensitive,
         UniqueLinesCaseSensitive,
+        UnstageHunk,
     ]
 );
 
@@ -0,0 +1,505 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use collections::{HashMap, HashSet};
+use futures::future::join_all;
+use gpui::{Model, ModelContext, Task, ViewContext};
+use language::{language_settings::LanguageSettings, Buffer, BufferId, Point};
+use multi_buffer::{Anchor, ToPoint as _};
+use project::{CodeLens, Project};
+use text::{OffsetRangeExt as _, ToOffset as _, ToPoint as _};
+use ui::prelude::*;
+use util::ResultExt;
+use workspace::notifications::NotifyTaskExt;
+
+use crate::{
+    lsp_data::{FetchedLspData, LspData},
+    BlockPlacement, BlockProperties, BlockStyle, CustomBlockId, Editor,
+};
+
+const FETCH_DEBOUNCE: Duration = Duration::from_millis(250);
+const RESOLVE_DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// The code lenses of the buffers in an editor whose language enables them,
+/// and the blocks that show them above the lines they belong to.
+#[derive(Default)]
+pub(crate) struct CodeLensState {
+    fetched: FetchedLspData<Self>,
+    lines: Vec<CodeLensLine>,
+    blocks: HashSet<CustomBlockId>,
+    resolve_task: Option<Task<()>>,
+}
+
+/// The code lenses starting on one line of an excerpt, shown in a single block.
+struct CodeLensLine {
+    buffer_id: BufferId,
+    position: Anchor,
+    indent: u32,
+    lens_ixs: Vec<usize>,
+}
+
+impl LspData for CodeLensState {
+    type Key = ();
+    type Data = Vec<CodeLens>;
+
+    const FETCH_DEBOUNCE: Duration = FETCH_DEBOUNCE;
+
+    fn fetched(editor: &mut Editor) -> &mut FetchedLspData<Self> {
+        &mut editor.code_lens.fetched
+    }
+
+    fn key(settings: &LanguageSettings) -> Option<Self::Key> {
+        settings.code_lens.then_some(())
+    }
+
+    fn refetch_on(event: &project::Event) -> bool {
+        matches!(
+            event,
+            project::Event::RefreshCodeLens | project::Event::LanguageServerAdded(..)
+        )
+    }
+
+    fn fetch(
+        project: &mut Project,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Project>,
+    ) -> Task<Result<Self::Data>> {
+        project.code_lens(buffer, cx)
+    }
+
+    fn prepare(lenses: &mut Self::Data, previous: Option<&Self::Data>, buffer: &Buffer) {
+        lenses.sort_by(|a, b| a.range.start.cmp(&b.range.start, buffer));
+        if let Some(previous) = previous {
+            keep_resolved_commands(lenses, previous, buffer);
+        }
+    }
+
+    fn updated(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+        editor.update_code_lens_blocks(cx);
+        editor.resolve_visible_code_lenses(cx);
+    }
+}
+
+impl Editor {
+    /// Resolves the commands of the code lenses on screen that the server sent without one.
+    pub(crate) fn resolve_visible_code_lenses(&mut self, cx: &mut ViewContext<Self>) {
+        if self.code_lens.fetched.buffers.is_empty() {
+            return;
+        }
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+
+        let multi_buffer = self.buffer.read(cx);
+        let snapshot = multi_buffer.snapshot(cx);
+        let visible_start = self.scroll_manager.anchor().anchor.to_point(&snapshot);
+        let visible_end = snapshot.clip_point(
+            visible_start + Point::new(self.visible_line_count().unwrap_or(0.).ceil() as u32, 0),
+            text::Bias::Left,
+        );
+
+        let mut unresolved = Vec::new();
+        for (buffer, visible_range, _) in
+            multi_buffer.range_to_buffer_ranges(visible_start..visible_end, cx)
+        {
+            let buffer = buffer.read(cx);
+            let buffer_id = buffer.remote_id();
+            let Some(buffer_lenses) = self.code_lens.fetched.buffers.get(&buffer_id) else {
+                continue;
+            };
+            for lens in &buffer_lenses.data {
+                let start = lens.range.start.to_offset(buffer);
+                if lens.lsp_lens.command.is_none() && visible_range.contains(&start) {
+                    unresolved.push((buffer_lenses.buffer.clone(), lens.clone()));
+                }
+            }
+        }
+        if unresolved.is_empty() {
+            return;
+        }
+
+        self.code_lens.resolve_task = Some(cx.spawn(|editor, mut cx| async move {
+            cx.background_executor().timer(RESOLVE_DEBOUNCE).await;
+            let Some(resolves) = project
+                .update(&mut cx, |project, cx| {
+                    unresolved
+                        .into_iter()
+                        .map(|(buffer, lens)| {
+                            let buffer_id = buffer.read(cx).remote_id();
+                            let resolved = project.resolve_code_lens(&buffer, lens, cx);
+                            async move { (buffer_id, resolved.await) }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .log_err()
+            else {
+                return;
+            };
+            let resolved = join_all(resolves).await;
+
+            editor
+                .update(&mut cx, |editor, cx| {
+                    for (buffer_id, lens) in resolved {
+                        let Some(lens) = lens.log_err() else {
+                            continue;
+                        };
+                        // The lenses may have been refetched while this one was resolving.
+                        if let Some(existing) = editor
+                            .code_lens
+                            .fetched
+                            .buffers
+                            .get_mut(&buffer_id)
+                            .and_then(|buffer_lenses| {
+                                buffer_lenses.data.iter_mut().find(|existing| {
+                                    existing.range == lens.range
+                                        && existing.lsp_lens.command.is_none()
+                                })
+                            })
+                        {
+                            *existing = lens;
+                        }
+                    }
+                    cx.notify();
+                })
+                .ok();
+        }));
+    }
+
+    fn update_code_lens_blocks(&mut self, cx: &mut ViewContext<Self>) {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let mut lines = Vec::new();
+        for (excerpt_id, buffer, excerpt_range) in snapshot.excerpts() {
+            let buffer_id = buffer.remote_id();
+            let Some(buffer_lenses) = self.code_lens.fetched.buffers.get(&buffer_id) else {
+                continue;
+            };
+            let context = excerpt_range.context;
+            let mut current_line: Option<(u32, CodeLensLine)> = None;
+            for (ix, lens) in buffer_lenses.data.iter().enumerate() {
+                if lens.range.start.cmp(&context.start, buffer).is_lt()
+                    || lens.range.start.cmp(&context.end, buffer).is_gt()
+                {
+                    continue;
+                }
+                let row = lens.range.start.to_point(buffer).row;
+                if let Some((current_row, line)) = current_line.as_mut() {
+                    if *current_row == row {
+                        line.lens_ixs.push(ix);
+                        continue;
+                    }
+                }
+                let Some(position) = snapshot
+                    .anchor_in_excerpt(excerpt_id, buffer.anchor_before(Point::new(row, 0)))
+                else {
+                    continue;
+                };
+                lines.extend(current_line.take().map(|(_, line)| line));
+                current_line = Some((
+                    row,
+                    CodeLensLine {
+                        buffer_id,
+                        position,
+                        indent: buffer.indent_size_for_line(row).len,
+                        lens_ixs: vec![ix],
+                    },
+                ));
+            }
+            lines.extend(current_line.map(|(_, line)| line));
+        }
+
+        let old_blocks = std::mem::take(&mut self.code_lens.blocks);
+        self.remove_blocks(old_blocks, None, cx);
+        let blocks = (0..lines.len())
+            .map(|line_ix| self.code_lens_block(line_ix, lines[line_ix].position, cx))
+            .collect::<Vec<_>>();
+        self.code_lens.blocks = self.insert_blocks(blocks, None, cx).into_iter().collect();
+        self.code_lens.lines = lines;
+        cx.notify();
+    }
+
+    fn code_lens_block(
+        &self,
+        line_ix: usize,
+        position: Anchor,
+        cx: &mut ViewContext<Self>,
+    ) -> BlockProperties<Anchor> {
+        BlockProperties {
+            placement: BlockPlacement::Above(position),
+            height: 1,
+            style: BlockStyle::Sticky,
+            priority: 0,
+            render: Arc::new({
+                let editor = cx.view().clone();
+                move |cx| {
+                    let state = &editor.read(cx).code_lens;
+                    let Some(line) = state.lines.get(line_ix) else {
+                        return div().into_any_element();
+                    };
+                    let lenses = state
+                        .fetched
+                        .buffers
+                        .get(&line.buffer_id)
+                        .map(|buffer_lenses| {
+                            line.lens_ixs
+                                .iter()
+                                .filter_map(|ix| buffer_lenses.data.get(*ix))
+                                .filter(|lens| lens.lsp_lens.command.is_some())
+                                .cloned()
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default();
+
+                    let indent = cx.em_width * line.indent as f32;
+                    let mut row = h_flex()
+                        .id(cx.block_id)
+                        .block_mouse_down()
+                        .h(cx.line_height)
+                        .w_full()
+                        .pl(cx.gutter_dimensions.full_width() + indent)
+                        .gap_1();
+                    for (ix, lens) in lenses.into_iter().enumerate() {
+                        if ix > 0 {
+                            row = row
+                                .child(Label::new("|").size(LabelSize::Small).color(Color::Muted));
+                        }
+                        let title = lens
+                            .lsp_lens
+                            .command
+                            .as_ref()
+                            .map(|command| command.title.clone())
+                            .unwrap_or_default();
+                        row = row.child(
+                            Button::new(("code-lens", ix), title)
+                                .style(ButtonStyle::Subtle)
+                                .label_size(LabelSize::Small)
+                                .color(Color::Muted)
+                                .on_click({
+                                    let editor = editor.clone();
+                                    move |_event, cx| {
+                                        editor.update(cx, |editor, cx| {
+                                            editor.execute_code_lens(lens.clone(), cx);
+                                        });
+                                    }
+                                }),
+                        );
+                    }
+                    row.into_any_element()
+                }
+            }),
+        }
+    }
+
+    fn execute_code_lens(&mut self, lens: CodeLens, cx: &mut ViewContext<Self>) {
+        let (Some(project), Some(workspace)) = (self.project.clone(), self.workspace()) else {
+            return;
+        };
+        let Some(buffer) = lens
+            .range
+            .start
+            .buffer_id
+            .and_then(|buffer_id| self.code_lens.fetched.buffers.get(&buffer_id))
+            .map(|buffer_lenses| buffer_lenses.buffer.clone())
+        else {
+            return;
+        };
+        let title = lens
+            .lsp_lens
+            .command
+            .as_ref()
+            .map(|command| command.title.clone())
+            .unwrap_or_default();
+
+        let execute = project.update(cx, |project, cx| {
+            project.execute_code_lens(buffer, lens, cx)
+        });
+        let workspace = workspace.downgrade();
+        cx.spawn(|editor, cx| async move {
+            let project_transaction = execute.await?;
+            Self::open_project_transaction(&editor, workspace, project_transaction, title, cx).await
+        })
+        .detach_and_notify_err(cx);
+    }
+}
+
+/// Gives the refetched lenses that still cover the same text as a resolved one its command,
+/// so that they don't need resolving again.
+fn keep_resolved_commands(
+    lenses: &mut [CodeLens],
+    previous_lenses: &[CodeLens],
+    snapshot: &text::BufferSnapshot,
+) {
+    let resolved_commands = previous_lenses
+        .iter()
+        .filter_map(|lens| {
+            let command = lens.lsp_lens.command.as_ref()?;
+            Some((lens.range.to_offset(snapshot), command))
+        })
+        .collect::<HashMap<_, _>>();
+    for lens in lenses {
+        if lens.lsp_lens.command.is_none() {
+            lens.lsp_lens.command = resolved_commands
+                .get(&lens.range.to_offset(snapshot))
+                .map(|command| (*command).clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt as _;
+    use parking_lot::Mutex;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        actions::MoveDown, editor_tests::init_test, lsp_data::LspDataRefetch,
+        test::editor_lsp_test_context::EditorLspTestContext,
+    };
+
+    #[gpui::test]
+    async fn test_code_lens(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+        let mut cx = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                code_lens_provider: Some(lsp::CodeLensOptions {
+                    resolve_provider: Some(true),
+                }),
+                execute_command_provider: Some(lsp::ExecuteCommandOptions {
+                    commands: vec!["run".to_string()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+
+        fn unresolved_lens(name: &str, row: u32) -> lsp::CodeLens {
+            lsp::CodeLens {
+                range: lsp::Range::new(
+                    lsp::Position::new(row, 3),
+                    lsp::Position::new(row, 3 + name.len() as u32),
+                ),
+                command: None,
+                data: Some(json!(name)),
+            }
+        }
+        let mut lens_requests =
+            cx.handle_request::<lsp::request::CodeLensRequest, _, _>(|_, _, _| async move {
+                Ok(Some(vec![
+                    unresolved_lens("first", 0),
+                    unresolved_lens("last", 301),
+                ]))
+            });
+        let resolved_lenses = Arc::new(Mutex::new(Vec::new()));
+        cx.handle_request::<lsp::request::CodeLensResolve, _, _>({
+            let resolved_lenses = resolved_lenses.clone();
+            move |_, lens, _| {
+                let name = lens.data.clone().unwrap();
+                resolved_lenses.lock().push(name.clone());
+                async move {
+                    Ok(lsp::CodeLens {
+                        command: Some(lsp::Command {
+                            title: format!("Run {}", name.as_str().unwrap()),
+                            command: "run".to_string(),
+                            arguments: Some(vec![name]),
+                        }),
+                        ..lens
+                    })
+                }
+            }
+        });
+        let executed_commands = Arc::new(Mutex::new(Vec::new()));
+        cx.handle_request::<lsp::request::ExecuteCommand, _, _>({
+            let executed_commands = executed_commands.clone();
+            move |_, params, _| {
+                executed_commands
+                    .lock()
+                    .push((params.command, params.arguments));
+                async move { Ok(None) }
+            }
+        });
+
+        cx.set_state(&format!(
+            "ˇfn first() {{}}\n{}fn last() {{}}\n",
+            "\n".repeat(300)
+        ));
+        cx.update_editor(|editor, cx| editor.set_visible_line_count(20., cx));
+        cx.executor().advance_clock(FETCH_DEBOUNCE);
+        lens_requests.next().await;
+        cx.executor().advance_clock(RESOLVE_DEBOUNCE);
+        cx.run_until_parked();
+        while let Ok(Some(())) = lens_requests.try_next() {}
+
+        assert_eq!(
+            *resolved_lenses.lock(),
+            [json!("first")],
+            "Only the lens on screen should be resolved"
+        );
+        assert_eq!(lens_titles(&mut cx), [Some("Run first".to_string()), None]);
+
+        let first_lens = cx.update_editor(|editor, _| {
+            editor
+                .code_lens
+                .fetched
+                .buffers
+                .values()
+                .next()
+                .unwrap()
+                .data[0]
+                .clone()
+        });
+        cx.update_editor(|editor, cx| editor.execute_code_lens(first_lens, cx));
+        cx.run_until_parked();
+        assert_eq!(
+            *executed_commands.lock(),
+            [("run".to_string(), vec![json!("first")])]
+        );
+
+        // Refreshing without edits doesn't fetch the lenses again.
+        cx.update_editor(|editor, cx| editor.refresh_lsp_data(LspDataRefetch::Edited, false, cx));
+        cx.run_until_parked();
+        assert!(lens_requests.try_next().is_err());
+
+        // After an edit, the lenses are fetched again, and the ones that are still over
+        // the same text keep their command without being resolved again.
+        cx.update_editor(|editor, cx| {
+            editor.move_down(&MoveDown, cx);
+            editor.handle_input("// first", cx);
+        });
+        cx.executor().advance_clock(FETCH_DEBOUNCE);
+        lens_requests.next().await;
+        cx.executor().advance_clock(RESOLVE_DEBOUNCE);
+        cx.run_until_parked();
+        assert_eq!(*resolved_lenses.lock(), [json!("first")]);
+        assert_eq!(lens_titles(&mut cx), [Some("Run first".to_string()), None]);
+
+        // The server asking for a refresh fetches the lenses again without an edit.
+        cx.lsp
+            .request::<lsp::request::CodeLensRefresh>(())
+            .await
+            .expect("code lens refresh request failed");
+        cx.executor().advance_clock(FETCH_DEBOUNCE);
+        lens_requests.next().await;
+        cx.run_until_parked();
+        assert_eq!(lens_titles(&mut cx), [Some("Run first".to_string()), None]);
+    }
+
+    fn lens_titles(cx: &mut EditorLspTestContext) -> Vec<Option<String>> {
+        cx.update_editor(|editor, _| {
+            editor
+                .code_lens
+                .fetched
+                .buffers
+                .values()
+                .flat_map(|buffer_lenses| &buffer_lenses.data)
+                .map(|lens| {
+                    lens.lsp_lens
+                        .command
+                        .as_ref()
+                        .map(|command| command.title.clone())
+                })
+                .collect()
+        })
+    }
+}
@@ -46,7 +46,7 @@ use inlay_map::{InlayMap, InlaySnapshot};
 pub use inlay_map::{InlayOffset, InlayPoint};
 use invisibles::{is_invisible, replacement};
 use language::{
-    language_settings::language_settings, ChunkRenderer, OffsetUtf16, Point,
+    language_settings::language_settings, BufferId, ChunkRenderer, HighlightId, OffsetUtf16, Point,
     Subscription as BufferSubscription,
 };
 use lsp::DiagnosticSeverity;
@@ -86,6 +86,26 @@ pub trait ToDisplayPoint {
 type TextHighlights = TreeMap<TypeId, Arc<(HighlightStyle, Vec<Range<Anchor>>)>>;
 type InlayHighlights = TreeMap<TypeId, TreeMap<InlayId, (HighlightStyle, InlayHighlight)>>;
 
+/// Syntax highlights derived from language server semantic tokens, which take precedence
+/// over tree-sitter's highlights.
+#[derive(Debug, Default)]
+pub(crate) struct SemanticTokenHighlights {
+    /// The highlighted token ranges, ordered and non-overlapping.
+    pub tokens: Vec<(Range<Anchor>, HighlightId)>,
+    /// The ranges in which tree-sitter highlights are not shown at all.
+    pub syntax_replaced: Vec<Range<Anchor>>,
+}
+
+/// Folding ranges reported by language servers, which replace indentation-based
+/// fold range suggestions in the buffers they were requested for.
+#[derive(Debug, Default)]
+pub(crate) struct LanguageServerFoldingRanges {
+    /// The foldable ranges, ordered by their start.
+    pub ranges: Vec<Range<Anchor>>,
+    /// The buffers whose rows can only be folded where one of the ranges starts.
+    pub buffers: HashSet<BufferId>,
+}
+
 /// Decides how text in a [`MultiBuffer`] should be displayed in a buffer, handling inlay hints,
 /// folding, hard tabs, soft wrapping, custom blocks (like diagnostics), and highlighting.
 ///
@@ -108,6 +128,10 @@ pub struct DisplayMap {
     text_highlights: TextHighlights,
     /// Regions of inlays that should be highlighted.
     inlay_highlights: InlayHighlights,
+    /// Syntax highlights reported by language servers.
+    semantic_token_highlights: Arc<SemanticTokenHighlights>,
+    /// Foldable ranges reported by language servers.
+    folding_ranges: Arc<LanguageServerFoldingRanges>,
     /// A container for explicitly foldable ranges, which supersede indentation based fold range suggestions.
     crease_map: CreaseMap,
     pub(crate) fold_placeholder: FoldPlaceholder,
@@ -160,6 +184,8 @@ impl DisplayMap {
             fold_placeholder,
             text_highlights: Default::default(),
             inlay_highlights: Default::default(),
+            semantic_token_highlights: Default::default(),
+            folding_ranges: Default::default(),
             clip_at_line_ends: false,
             masked: false,
         }
@@ -187,6 +213,8 @@ impl DisplayMap {
             crease_snapshot: self.crease_map.snapshot(),
             text_highlights: self.text_highlights.clone(),
             inlay_highlights: self.inlay_highlights.clone(),
+            semantic_token_highlights: self.semantic_token_highlights.clone(),
+            folding_ranges: self.folding_ranges.clone(),
             clip_at_line_ends: self.clip_at_line_ends,
             masked: self.masked,
             fold_placeholder: self.fold_placeholder.clone(),
@@ -493,6 +521,14 @@ impl DisplayMap {
         let highlights = self.text_highlights.get(&type_id)?;
         Some((highlights.0, &highlights.1))
     }
+    pub(crate) fn set_semantic_token_highlights(&mut self, highlights: SemanticTokenHighlights) {
+        self.semantic_token_highlights = Arc::new(highlights);
+    }
+
+    pub(crate) fn set_folding_ranges(&mut self, folding_ranges: LanguageServerFoldingRanges) {
+        self.folding_ranges = Arc::new(folding_ranges);
+    }
+
     pub fn clear_highlights(&mut self, type_id: TypeId) -> bool {
         let mut cleared = self.text_highlights.remove(&type_id).is_some();
         cleared |= self.inlay_highlights.remove(&type_id).is_some();
@@ -565,6 +601,7 @@ impl DisplayMap {
 pub(crate) struct Highlights<'a> {
     pub text_highlights: Option<&'a TextHighlights>,
     pub inlay_highlights: Option<&'a InlayHighlights>,
+    pub semantic_token_highlights: Option<&'a SemanticTokenHighlights>,
     pub styles: HighlightStyles,
 }
 
@@ -699,6 +736,8 @@ pub struct DisplaySnapshot {
     block_snapshot: BlockSnapshot,
     text_highlights: TextHighlights,
     inlay_highlights: InlayHighlights,
+    semantic_token_highlights: Arc<SemanticTokenHighlights>,
+    folding_ranges: Arc<LanguageServerFoldingRanges>,
     clip_at_line_ends: bool,
     masked: bool,
     pub(crate) fold_placeholder: FoldPlaceholder,
@@ -886,6 +925,7 @@ impl DisplaySnapshot {
             Highlights {
                 text_highlights: Some(&self.text_highlights),
                 inlay_highlights: Some(&self.inlay_highlights),
+                semantic_token_highlights: Some(&self.semantic_token_highlights),
                 styles: highlight_styles,
             },
         )
@@ -1195,6 +1235,41 @@ impl DisplaySnapshot {
             .unwrap_or(false)
     }
 
+    /// Returns whether the given row can be folded without an explicit crease, either
+    /// because a language server reported a folding range starting on it, or because
+    /// it starts an indented block.
+    pub fn starts_fold(&self, buffer_row: MultiBufferRow) -> bool {
+        if self.folds_by_language_server(buffer_row) {
+            self.language_server_fold(buffer_row).is_some()
+        } else {
+            self.starts_indent(buffer_row)
+        }
+    }
+
+    fn folds_by_language_server(&self, buffer_row: MultiBufferRow) -> bool {
+        !self.folding_ranges.buffers.is_empty()
+            && self
+                .buffer_snapshot
+                .buffer_line_for_row(buffer_row)
+                .map_or(false, |(buffer, _)| {
+                    self.folding_ranges.buffers.contains(&buffer.remote_id())
+                })
+    }
+
+    /// Returns the outermost language server folding range that starts on the given row.
+    fn language_server_fold(&self, buffer_row: MultiBufferRow) -> Option<Range<Point>> {
+        let ranges = &self.folding_ranges.ranges;
+        let start_ix = ranges.partition_point(|range| {
+            range.start.to_point(&self.buffer_snapshot).row < buffer_row.0
+        });
+        ranges[start_ix..]
+            .iter()
+            .map(|range| range.to_point(&self.buffer_snapshot))
+            .take_while(|range| range.start.row == buffer_row.0)
+            .filter(|range| range.end.row > range.start.row)
+            .max_by_key(|range| range.end)
+    }
+
     pub fn crease_for_buffer_row(&self, buffer_row: MultiBufferRow) -> Option<Crease<Point>> {
         let start = MultiBufferPoint::new(buffer_row.0, self.buffer_snapshot.line_len(buffer_row));
         if let Some(crease) = self
@@ -1231,6 +1306,17 @@ impl DisplaySnapshot {
                     render_toggle: render_toggle.clone(),
                 }),
             }
+        } else if self.folds_by_language_server(buffer_row) {
+            if self.is_line_folded(buffer_row) {
+                return None;
+            }
+            Some(Crease::Inline {
+                range: self.language_server_fold(buffer_row)?,
+                placeholder: self.fold_placeholder.clone(),
+                render_toggle: None,
+                render_trailer: None,
+                metadata: None,
+            })
         } else if self.starts_indent(MultiBufferRow(start.row))
             && !self.is_line_folded(MultiBufferRow(start.row))
         {
@@ -2522,6 +2608,106 @@ pub mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_chunks_with_semantic_token_highlights(cx: &mut gpui::TestAppContext) {
+        cx.update(|cx| init_test(cx, |_| {}));
+
+        let theme = SyntaxTheme::new_test(vec![
+            ("operator", Hsla::red()),
+            ("string", Hsla::green()),
+            ("type", Hsla::blue()),
+        ]);
+        let language = Arc::new(
+            Language::new(
+                LanguageConfig {
+                    name: "Test".into(),
+                    matcher: LanguageMatcher {
+                        path_suffixes: vec![".test".to_string()],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                Some(tree_sitter_rust::LANGUAGE.into()),
+            )
+            .with_highlights_query(
+                r#"
+                ":" @operator
+                (string_literal) @string
+                "#,
+            )
+            .unwrap(),
+        );
+        language.set_theme(&theme);
+
+        let (text, token_ranges) = marked_text_ranges(r#"const a: «B» = "c d""#, false);
+
+        let buffer = cx.new_model(|cx| Buffer::local(text, cx).with_language(language, cx));
+        cx.condition(&buffer, |buf, _| !buf.is_parsing()).await;
+
+        let buffer = cx.new_model(|cx| MultiBuffer::singleton(buffer, cx));
+        let buffer_snapshot = buffer.read_with(cx, |buffer, cx| buffer.snapshot(cx));
+
+        let map = cx.new_model(|cx| {
+            DisplayMap::new(
+                buffer,
+                font("Courier"),
+                px(16.0),
+                None,
+                true,
+                1,
+                1,
+                1,
+                FoldPlaceholder::test(),
+                cx,
+            )
+        });
+
+        let type_id = language::HighlightMap::new(&["type"], &theme).get(0);
+        let tokens = token_ranges
+            .into_iter()
+            .map(|range| {
+                (
+                    buffer_snapshot.anchor_after(range.start)
+                        ..buffer_snapshot.anchor_before(range.end),
+                    type_id,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        map.update(cx, |map, _| {
+            map.set_semantic_token_highlights(SemanticTokenHighlights {
+                tokens: tokens.clone(),
+                syntax_replaced: Vec::new(),
+            })
+        });
+        assert_eq!(
+            cx.update(|cx| syntax_chunks(DisplayRow(0)..DisplayRow(10), &map, &theme, cx)),
+            [
+                ("const a".to_string(), None),
+                (":".to_string(), Some(Hsla::red())),
+                (" ".to_string(), None),
+                ("B".to_string(), Some(Hsla::blue())),
+                (" = ".to_string(), None),
+                ("\"c d\"".to_string(), Some(Hsla::green())),
+            ]
+        );
+
+        map.update(cx, |map, _| {
+            map.set_semantic_token_highlights(SemanticTokenHighlights {
+                tokens,
+                syntax_replaced: vec![Anchor::min()..Anchor::max()],
+            })
+        });
+        assert_eq!(
+            cx.update(|cx| syntax_chunks(DisplayRow(0)..DisplayRow(10), &map, &theme, cx)),
+            [
+                ("const a: ".to_string(), None),
+                ("B".to_string(), Some(Hsla::blue())),
+                (" = \"c d\"".to_string(), None),
+            ]
+        );
+    }
+
     #[gpui::test]
     fn test_clip_point(cx: &mut gpui::AppContext) {
         init_test(cx, |_| {});
@@ -2629,6 +2815,64 @@ pub mod tests {
         });
     }
 
+    #[gpui::test]
+    fn test_language_server_folding_ranges(cx: &mut gpui::AppContext) {
+        init_test(cx, |_| {});
+
+        let text = "// a\n// b\nfn c() {\n    d\n}\n";
+        let buffer = MultiBuffer::build_simple(text, cx);
+        let map = cx.new_model(|cx| {
+            DisplayMap::new(
+                buffer.clone(),
+                font("Helvetica"),
+                px(14.0),
+                None,
+                true,
+                1,
+                1,
+                0,
+                FoldPlaceholder::test(),
+                cx,
+            )
+        });
+
+        let fold_range = |snapshot: &DisplaySnapshot, row| {
+            snapshot
+                .crease_for_buffer_row(MultiBufferRow(row))
+                .map(|crease| crease.range().clone())
+        };
+
+        let snapshot = map.update(cx, |map, cx| map.snapshot(cx));
+        assert!(!snapshot.starts_fold(MultiBufferRow(0)));
+        assert!(snapshot.starts_fold(MultiBufferRow(2)));
+        assert_eq!(
+            fold_range(&snapshot, 2),
+            Some(Point::new(2, 8)..Point::new(3, 5))
+        );
+
+        // Once a language server reports folding ranges for the buffer, only rows where
+        // one of them starts can be folded.
+        let buffer_snapshot = buffer.read(cx).snapshot(cx);
+        let buffer_id = buffer.read(cx).as_singleton().unwrap().read(cx).remote_id();
+        map.update(cx, |map, _| {
+            map.set_folding_ranges(LanguageServerFoldingRanges {
+                ranges: vec![
+                    buffer_snapshot.anchor_after(Point::new(0, 4))
+                        ..buffer_snapshot.anchor_before(Point::new(1, 4)),
+                ],
+                buffers: HashSet::from_iter([buffer_id]),
+            })
+        });
+        let snapshot = map.update(cx, |map, cx| map.snapshot(cx));
+        assert!(snapshot.starts_fold(MultiBufferRow(0)));
+        assert!(!snapshot.starts_fold(MultiBufferRow(2)));
+        assert_eq!(
+            fold_range(&snapshot, 0),
+            Some(Point::new(0, 4)..Point::new(1, 4))
+        );
+        assert_eq!(fold_range(&snapshot, 2), None);
+    }
+
     #[gpui::test]
     fn test_tabs_with_multibyte_chars(cx: &mut gpui::AppContext) {
         init_test(cx, |_| {});
@@ -1,6 +1,6 @@
 use collections::BTreeMap;
 use gpui::HighlightStyle;
-use language::Chunk;
+use language::{Chunk, HighlightId};
 use multi_buffer::{Anchor, MultiBufferChunks, MultiBufferSnapshot, ToOffset as _};
 use std::{
     any::TypeId,
@@ -12,6 +12,8 @@ use std::{
 };
 use sum_tree::TreeMap;
 
+use super::SemanticTokenHighlights;
+
 pub struct CustomHighlightsChunks<'a> {
     buffer_chunks: MultiBufferChunks<'a>,
     buffer_chunk: Option<Chunk<'a>>,
@@ -21,6 +23,12 @@ pub struct CustomHighlightsChunks<'a> {
     highlight_endpoints: Peekable<vec::IntoIter<HighlightEndpoint>>,
     active_highlights: BTreeMap<TypeId, HighlightStyle>,
     text_highlights: Option<&'a TreeMap<TypeId, Arc<(HighlightStyle, Vec<Range<Anchor>>)>>>,
+
+    language_aware: bool,
+    semantic_endpoints: Peekable<vec::IntoIter<SemanticEndpoint>>,
+    active_semantic_token: Option<HighlightId>,
+    syntax_replaced_depth: usize,
+    semantic_token_highlights: Option<&'a SemanticTokenHighlights>,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -31,11 +39,25 @@ struct HighlightEndpoint {
     style: HighlightStyle,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct SemanticEndpoint {
+    offset: usize,
+    is_start: bool,
+    kind: SemanticEndpointKind,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum SemanticEndpointKind {
+    Token(HighlightId),
+    SyntaxReplaced,
+}
+
 impl<'a> CustomHighlightsChunks<'a> {
     pub fn new(
         range: Range<usize>,
         language_aware: bool,
         text_highlights: Option<&'a TreeMap<TypeId, Arc<(HighlightStyle, Vec<Range<Anchor>>)>>>,
+        semantic_token_highlights: Option<&'a SemanticTokenHighlights>,
         multibuffer_snapshot: &'a MultiBufferSnapshot,
     ) -> Self {
         Self {
@@ -51,16 +73,33 @@ impl<'a> CustomHighlightsChunks<'a> {
             ),
             active_highlights: Default::default(),
             multibuffer_snapshot,
+
+            language_aware,
+            semantic_endpoints: create_semantic_endpoints(
+                &range,
+                semantic_token_highlights,
+                multibuffer_snapshot,
+            ),
+            active_semantic_token: None,
+            syntax_replaced_depth: 0,
+            semantic_token_highlights,
         }
     }
 
     pub fn seek(&mut self, new_range: Range<usize>) {
         self.highlight_endpoints =
             create_highlight_endpoints(&new_range, self.text_highlights, self.multibuffer_snapshot);
+        self.semantic_endpoints = create_semantic_endpoints(
+            &new_range,
+            self.semantic_token_highlights,
+            self.multibuffer_snapshot,
+        );
         self.offset = new_range.start;
         self.buffer_chunks.seek(new_range);
         self.buffer_chunk.take();
-        self.active_highlights.clear()
+        self.active_highlights.clear();
+        self.active_semantic_token = None;
+        self.syntax_replaced_depth = 0;
     }
 }
 
@@ -112,6 +151,51 @@ fn create_highlight_endpoints(
     highlight_endpoints.into_iter().peekable()
 }
 
+fn create_semantic_endpoints(
+    range: &Range<usize>,
+    semantic_token_highlights: Option<&SemanticTokenHighlights>,
+    buffer: &MultiBufferSnapshot,
+) -> iter::Peekable<vec::IntoIter<SemanticEndpoint>> {
+    let mut endpoints = Vec::new();
+    if let Some(highlights) = semantic_token_highlights {
+        let start = buffer.anchor_after(range.start);
+        let end = buffer.anchor_after(range.end);
+        let mut push_range = |range: &Range<Anchor>, kind| {
+            endpoints.push(SemanticEndpoint {
+                offset: range.start.to_offset(buffer),
+                is_start: true,
+                kind,
+            });
+            endpoints.push(SemanticEndpoint {
+                offset: range.end.to_offset(buffer),
+                is_start: false,
+                kind,
+            });
+        };
+
+        let start_ix = highlights
+            .tokens
+            .partition_point(|(probe, _)| probe.end.cmp(&start, buffer).is_le());
+        for (token_range, highlight_id) in &highlights.tokens[start_ix..] {
+            if token_range.start.cmp(&end, buffer).is_ge() {
+                break;
+            }
+            push_range(token_range, SemanticEndpointKind::Token(*highlight_id));
+        }
+        for replaced_range in &highlights.syntax_replaced {
+            if replaced_range.end.cmp(&start, buffer).is_gt()
+                && replaced_range.start.cmp(&end, buffer).is_lt()
+            {
+                push_range(replaced_range, SemanticEndpointKind::SyntaxReplaced);
+            }
+        }
+
+        // Tokens may be adjacent, so end one before starting the next.
+        endpoints.sort_by_key(|endpoint| (endpoint.offset, endpoint.is_start));
+    }
+    endpoints.into_iter().peekable()
+}
+
 impl<'a> Iterator for CustomHighlightsChunks<'a> {
     type Item = Chunk<'a>;
 
@@ -130,6 +214,27 @@ impl<'a> Iterator for CustomHighlightsChunks<'a> {
                 break;
             }
         }
+        while let Some(endpoint) = self.semantic_endpoints.peek().copied() {
+            if endpoint.offset <= self.offset {
+                match endpoint.kind {
+                    SemanticEndpointKind::Token(highlight_id) => {
+                        self.active_semantic_token = endpoint.is_start.then_some(highlight_id);
+                    }
+                    SemanticEndpointKind::SyntaxReplaced => {
+                        if endpoint.is_start {
+                            self.syntax_replaced_depth += 1;
+                        } else {
+                            self.syntax_replaced_depth =
+                                self.syntax_replaced_depth.saturating_sub(1);
+                        }
+                    }
+                }
+                self.semantic_endpoints.next();
+            } else {
+                next_highlight_endpoint = next_highlight_endpoint.min(endpoint.offset);
+                break;
+            }
+        }
 
         let chunk = self
             .buffer_chunk
@@ -148,6 +253,14 @@ impl<'a> Iterator for CustomHighlightsChunks<'a> {
             text: prefix,
             ..chunk.clone()
         };
+        if self.language_aware {
+            if self.syntax_replaced_depth > 0 {
+                prefix.syntax_highlight_id = None;
+            }
+            if let Some(highlight_id) = self.active_semantic_token {
+                prefix.syntax_highlight_id = Some(highlight_id);
+            }
+        }
         if !self.active_highlights.is_empty() {
             let mut highlight_style = HighlightStyle::default();
             for active_highlight in self.active_highlights.values() {
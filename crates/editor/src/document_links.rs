@@ -0,0 +1,131 @@
+use std::{ops::Range, time::Duration};
+
+use anyhow::Result;
+use gpui::{AsyncWindowContext, Model, ModelContext, Task, ViewContext, WeakView};
+use language::{language_settings::LanguageSettings, Buffer};
+use project::{DocumentLink, Project, ResolvedPath};
+use util::ResultExt;
+
+use crate::{
+    hover_links::HoverLink,
+    lsp_data::{FetchedLspData, LspData},
+    Editor,
+};
+
+const FETCH_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// The document links of the buffers in an editor, as reported by their language servers.
+#[derive(Default)]
+pub(crate) struct DocumentLinksState {
+    fetched: FetchedLspData<Self>,
+}
+
+impl DocumentLinksState {
+    fn link_at(&self, buffer: &Buffer, position: text::Anchor) -> Option<&DocumentLink> {
+        self.fetched
+            .buffers
+            .get(&buffer.remote_id())?
+            .data
+            .iter()
+            .find(|link| {
+                link.range.start.cmp(&position, buffer).is_le()
+                    && link.range.end.cmp(&position, buffer).is_ge()
+            })
+    }
+}
+
+impl LspData for DocumentLinksState {
+    type Key = ();
+    type Data = Vec<DocumentLink>;
+
+    const FETCH_DEBOUNCE: Duration = FETCH_DEBOUNCE;
+
+    fn fetched(editor: &mut Editor) -> &mut FetchedLspData<Self> {
+        &mut editor.document_links.fetched
+    }
+
+    fn key(_: &LanguageSettings) -> Option<Self::Key> {
+        Some(())
+    }
+
+    /// Servers can't ask for document links to be refetched, so they are refetched
+    /// whenever the servers ask for inlay hints to be.
+    fn refetch_on(event: &project::Event) -> bool {
+        matches!(
+            event,
+            project::Event::RefreshInlayHints | project::Event::LanguageServerAdded(..)
+        )
+    }
+
+    fn fetch(
+        project: &mut Project,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Project>,
+    ) -> Task<Result<Self::Data>> {
+        project.document_links(buffer, cx)
+    }
+
+    fn updated(_: &mut Editor, _: &mut ViewContext<Editor>) {}
+}
+
+/// Returns the range and target of the document link at the given position, resolving
+/// the link first if the language server sent it without a target.
+pub(crate) async fn find_document_link(
+    editor: &WeakView<Editor>,
+    buffer: &Model<Buffer>,
+    position: text::Anchor,
+    cx: &mut AsyncWindowContext,
+) -> Option<(Range<text::Anchor>, HoverLink)> {
+    let (project, resolve) = editor
+        .update(cx, |editor, cx| {
+            let link = editor
+                .document_links
+                .link_at(buffer.read(cx), position)?
+                .clone();
+            let project = editor.project.clone()?;
+            let resolve = project.update(cx, |project, cx| {
+                project.resolve_document_link(buffer, link, cx)
+            });
+            Some((project, resolve))
+        })
+        .ok()??;
+    let link = resolve.await.log_err()?;
+    let target = link.lsp_link.target.clone()?;
+
+    editor
+        .update(cx, |editor, cx| {
+            let buffer_id = buffer.read(cx).remote_id();
+            let existing = editor
+                .document_links
+                .fetched
+                .buffers
+                .get_mut(&buffer_id)
+                .and_then(|buffer_links| {
+                    buffer_links
+                        .data
+                        .iter_mut()
+                        .find(|existing| existing.range == link.range)
+                });
+            if let Some(existing) = existing {
+                *existing = link.clone();
+            }
+        })
+        .ok()?;
+
+    let hover_link = if target.scheme() == "file" {
+        let path = target.to_file_path().ok()?;
+        let resolved_path = project
+            .update(cx, |project, cx| {
+                project.resolve_path_in_buffer(&path.to_string_lossy(), buffer, cx)
+            })
+            .ok()?
+            .await;
+        HoverLink::File(resolved_path.unwrap_or(ResolvedPath::AbsPath {
+            path,
+            is_dir: false,
+        }))
+    } else {
+        HoverLink::Url(target.to_string())
+    };
+    Some((link.range, hover_link))
+}
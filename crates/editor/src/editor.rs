@@ -17,10 +17,13 @@ mod blame_entry_tooltip;
 mod blink_manager;
 mod clangd_ext;
 mod code_context_menus;
+mod code_lens;
 pub mod display_map;
+mod document_links;
 mod editor_settings;
 mod editor_settings_controls;
 mod element;
+mod folding_ranges;
 mod git;
 mod highlight_matching_bracket;
 mod hover_links;
@@ -30,6 +33,7 @@ mod indent_guides;
 mod inlay_hint_cache;
 pub mod items;
 mod linked_editing_ranges;
+mod lsp_data;
 mod lsp_ext;
 mod mouse_context_menu;
 pub mod movement;
@@ -38,6 +42,7 @@ mod proposed_changes_editor;
 mod rust_analyzer_ext;
 pub mod scroll;
 mod selections_collection;
+mod semantic_tokens;
 pub mod tasks;
 
 #[cfg(test)]
@@ -68,14 +73,20 @@ use element::LineWithInvisibles;
 pub use element::{
     CursorLayout, EditorElement, HighlightedRange, HighlightedRangeLine, PointForPosition,
 };
-use futures::{future, FutureExt};
+use futures::{
+    future::{self, LocalBoxFuture},
+    FutureExt,
+};
 use fuzzy::StringMatchCandidate;
 
 use code_context_menus::{
     AvailableCodeAction, CodeActionContents, CodeActionsItem, CodeActionsMenu, CodeContextMenu,
     CompletionEntry, CompletionsMenu, ContextMenuOrigin,
 };
-use git::blame::GitBlame;
+use code_lens::CodeLensState;
+use document_links::DocumentLinksState;
+use folding_ranges::FoldingRangesState;
+use git::{blame::GitBlame, conflicts::ConflictMap};
 use gpui::{
     div, impl_actions, point, prelude::*, px, relative, size, Action, AnyElement, AppContext,
     AsyncWindowContext, AvailableSpace, Bounds, ClipboardEntry, ClipboardItem, Context,
@@ -97,13 +108,16 @@ use inline_completion::{InlineCompletionProvider, InlineCompletionProviderHandle
 pub use items::MAX_TAB_TITLE_LEN;
 use itertools::Itertools;
 use language::{
-    language_settings::{self, all_language_settings, language_settings, InlayHintSettings},
+    language_settings::{
+        self, all_language_settings, language_settings, InlayHintSettings, SelectionRangesSource,
+    },
     markdown, point_from_lsp, AutoindentMode, BracketPair, Buffer, Capability, CharKind, CodeLabel,
     CursorShape, Diagnostic, Documentation, IndentKind, IndentSize, Language, OffsetRangeExt,
     Point, Selection, SelectionGoal, TransactionId,
 };
 use language::{point_to_lsp, BufferRow, CharClassifier, Runnable, RunnableRange};
 use linked_editing_ranges::refresh_linked_ranges;
+use lsp_data::LspDataRefetch;
 use mouse_context_menu::MouseContextMenu;
 pub use proposed_changes_editor::{
     ProposedChangeLocation, ProposedChangesEditor, ProposedChangesEditorToolbar,
@@ -139,6 +153,7 @@ use scroll::{Autoscroll, OngoingScroll, ScrollAnchor, ScrollManager, ScrollbarAu
 use selections_collection::{
     resolve_selections, MutableSelectionsCollection, SelectionsCollection,
 };
+use semantic_tokens::SemanticTokensState;
 use serde::{Deserialize, Serialize};
 use settings::{update_settings_file, Settings, SettingsLocation, SettingsStore};
 use smallvec::SmallVec;
@@ -331,6 +346,7 @@ pub fn init(cx: &mut AppContext) {
         }
     });
     git::project_diff::init(cx);
+    git::conflicts::init(cx);
 }
 
 pub struct SearchWithinRange;
@@ -547,6 +563,8 @@ impl RunnableTasks {
 struct ResolvedTasks {
     templates: SmallVec<[(TaskSourceKind, ResolvedTask); 1]>,
     position: Anchor,
+    /// The context the tasks were resolved in, to schedule them with.
+    context: task::TaskContext,
 }
 #[derive(Copy, Clone, Debug)]
 struct MultiBufferOffset(usize);
@@ -590,6 +608,7 @@ pub struct Editor {
     autoclose_regions: Vec<AutocloseRegion>,
     snippet_stack: InvalidationStack<SnippetState>,
     select_larger_syntax_node_stack: Vec<Box<[Selection<usize>]>>,
+    selection_ranges_task: Option<Task<()>>,
     ime_transaction: Option<TransactionId>,
     active_diagnostics: Option<ActiveDiagnosticGroup>,
     soft_wrap_mode_override: Option<language_settings::SoftWrap>,
@@ -657,6 +676,11 @@ pub struct Editor {
     show_inline_completions_override: Option<bool>,
     inlay_hint_cache: InlayHintCache,
     diff_map: DiffMap,
+    conflict_map: ConflictMap,
+    semantic_tokens: SemanticTokensState,
+    code_lens: CodeLensState,
+    folding_ranges: FoldingRangesState,
+    document_links: DocumentLinksState,
     next_inlay_id: usize,
     _subscriptions: Vec<Subscription>,
     pixel_position_of_newest_cursor: Option<gpui::Point<Pixels>>,
@@ -1153,6 +1177,7 @@ impl Editor {
                     }));
                 }
                 project_subscriptions.push(cx.subscribe(project, |editor, _, event, cx| {
+                    editor.refresh_lsp_data(LspDataRefetch::Requested(event), true, cx);
                     if let project::Event::RefreshInlayHints = event {
                         editor.refresh_inlay_hints(InlayHintRefreshReason::RefreshRequested, cx);
                     } else if let project::Event::SnippetEdit(id, snippet_edits) = event {
@@ -1225,6 +1250,7 @@ impl Editor {
             autoclose_regions: Default::default(),
             snippet_stack: Default::default(),
             select_larger_syntax_node_stack: Vec::new(),
+            selection_ranges_task: None,
             ime_transaction: Default::default(),
             active_diagnostics: None,
             soft_wrap_mode_override,
@@ -1289,6 +1315,11 @@ impl Editor {
             active_inline_completion: None,
             inlay_hint_cache: InlayHintCache::new(inlay_hint_settings),
             diff_map: DiffMap::default(),
+            conflict_map: ConflictMap::default(),
+            semantic_tokens: SemanticTokensState::default(),
+            code_lens: CodeLensState::default(),
+            folding_ranges: FoldingRangesState::default(),
+            document_links: DocumentLinksState::default(),
             gutter_hovered: false,
             pixel_position_of_newest_cursor: None,
             last_bounds: None,
@@ -1358,6 +1389,9 @@ impl Editor {
                 this.start_git_blame_inline(false, cx);
             }
 
+            this.refresh_conflicts(false, cx);
+            this.refresh_lsp_data(LspDataRefetch::Edited, false, cx);
+
             if let Some(buffer) = buffer.read(cx).as_singleton() {
                 if let Some(project) = this.project.as_ref() {
                     let lsp_store = project.read(cx).lsp_store();
@@ -4066,6 +4100,7 @@ impl Editor {
                                         multibuffer_point.row,
                                         tasks.column,
                                     )),
+                                    context: task_context,
                                 })
                             });
                         let spawn_straight_away = resolved_tasks
@@ -4134,11 +4169,13 @@ impl Editor {
 
         match action {
             CodeActionsItem::Task(task_source_kind, resolved_task) => {
+                let task_context = actions_menu.actions.tasks.as_ref()?.context.clone();
                 workspace.update(cx, |workspace, cx| {
-                    workspace::tasks::schedule_resolved_task(
+                    workspace::tasks::schedule_task_with_inputs(
                         workspace,
                         task_source_kind,
-                        resolved_task,
+                        resolved_task.original_task().clone(),
+                        task_context,
                         false,
                         cx,
                     );
@@ -4978,17 +5015,18 @@ impl Editor {
         let task_context = Self::build_tasks_context(&project, &buffer, buffer_row, &tasks, cx);
         cx.spawn(|_, mut cx| async move {
             let context = task_context.await?;
-            let (task_source_kind, mut resolved_task) = tasks.resolve(&context).next()?;
+            let (task_source_kind, resolved_task) = tasks.resolve(&context).next()?;
 
-            let resolved = resolved_task.resolved.as_mut()?;
-            resolved.reveal = reveal_strategy;
+            let mut template = resolved_task.original_task().clone();
+            template.reveal = reveal_strategy;
 
             workspace
                 .update(&mut cx, |workspace, cx| {
-                    workspace::tasks::schedule_resolved_task(
+                    workspace::tasks::schedule_task_with_inputs(
                         workspace,
                         task_source_kind,
-                        resolved_task,
+                        template,
+                        context,
                         false,
                         cx,
                     );
@@ -8750,6 +8788,94 @@ impl Editor {
         &mut self,
         _: &SelectLargerSyntaxNode,
         cx: &mut ViewContext<Self>,
+    ) {
+        let old_selections = self.selections.all::<usize>(cx);
+        let requests = self.selection_range_requests(&old_selections, cx);
+        if requests.is_empty() {
+            self.select_larger_ranges(HashMap::default(), cx);
+            return;
+        }
+
+        self.selection_ranges_task = Some(cx.spawn(|editor, mut cx| async move {
+            let responses = future::join_all(requests).await;
+            editor
+                .update(&mut cx, |editor, cx| {
+                    // Ignore the response if the selections moved while it was requested.
+                    if editor.selections.all::<usize>(cx) != old_selections {
+                        return;
+                    }
+                    let snapshot = editor.buffer.read(cx).snapshot(cx);
+                    let mut language_server_ranges = HashMap::default();
+                    for (selection_id, excerpt_id, ranges) in responses {
+                        let Some(ranges) = ranges.log_err().and_then(|mut ranges| ranges.pop())
+                        else {
+                            continue;
+                        };
+                        let ranges = ranges
+                            .into_iter()
+                            .filter_map(|range| {
+                                let start = snapshot.anchor_in_excerpt(excerpt_id, range.start)?;
+                                let end = snapshot.anchor_in_excerpt(excerpt_id, range.end)?;
+                                Some(start.to_offset(&snapshot)..end.to_offset(&snapshot))
+                            })
+                            .collect::<Vec<_>>();
+                        language_server_ranges.insert(selection_id, ranges);
+                    }
+                    editor.select_larger_ranges(language_server_ranges, cx);
+                })
+                .ok();
+        }));
+    }
+
+    /// Requests selection ranges for the selections in buffers whose language expands
+    /// selections using the language server.
+    fn selection_range_requests(
+        &self,
+        selections: &[Selection<usize>],
+        cx: &mut ViewContext<Self>,
+    ) -> Vec<LocalBoxFuture<'static, (usize, ExcerptId, Result<Vec<Vec<Range<text::Anchor>>>>)>>
+    {
+        let Some(project) = self.project.clone() else {
+            return Vec::new();
+        };
+        let multi_buffer = self.buffer.read(cx);
+        let snapshot = multi_buffer.snapshot(cx);
+        let positions = selections
+            .iter()
+            .filter_map(|selection| {
+                let (buffer, position) =
+                    multi_buffer.text_anchor_for_position(selection.start, cx)?;
+                let buffer_ref = buffer.read(cx);
+                let settings = language_settings(
+                    buffer_ref.language().map(|language| language.name()),
+                    buffer_ref.file(),
+                    cx,
+                );
+                if settings.selection_ranges != SelectionRangesSource::LanguageServer {
+                    return None;
+                }
+                let excerpt_id = snapshot.anchor_before(selection.start).excerpt_id;
+                Some((selection.id, excerpt_id, buffer, position))
+            })
+            .collect::<Vec<_>>();
+
+        project.update(cx, |project, cx| {
+            positions
+                .into_iter()
+                .map(|(selection_id, excerpt_id, buffer, position)| {
+                    let ranges = project.selection_ranges(&buffer, vec![position], cx);
+                    async move { (selection_id, excerpt_id, ranges.await) }.boxed_local()
+                })
+                .collect()
+        })
+    }
+
+    /// Expands each selection to the smallest enclosing range that isn't folded, taking
+    /// the ranges from the language server where given and from the syntax tree otherwise.
+    fn select_larger_ranges(
+        &mut self,
+        language_server_ranges: HashMap<usize, Vec<Range<usize>>>,
+        cx: &mut ViewContext<Self>,
     ) {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let buffer = self.buffer.read(cx).snapshot(cx);
@@ -8761,15 +8887,31 @@ impl Editor {
             .iter()
             .map(|selection| {
                 let old_range = selection.start..selection.end;
+                let is_visible = |range: &Range<usize>| {
+                    !display_map.intersects_fold(range.start)
+                        && !display_map.intersects_fold(range.end)
+                };
                 let mut new_range = old_range.clone();
-                while let Some(containing_range) =
-                    buffer.range_for_syntax_ancestor(new_range.clone())
+                if let Some(ranges) = language_server_ranges
+                    .get(&selection.id)
+                    .filter(|ranges| !ranges.is_empty())
                 {
-                    new_range = containing_range;
-                    if !display_map.intersects_fold(new_range.start)
-                        && !display_map.intersects_fold(new_range.end)
+                    if let Some(containing_range) = ranges.iter().find(|range| {
+                        range.start <= old_range.start
+                            && range.end >= old_range.end
+                            && **range != old_range
+                            && is_visible(range)
+                    }) {
+                        new_range = containing_range.clone();
+                    }
+                } else {
+                    while let Some(containing_range) =
+                        buffer.range_for_syntax_ancestor(new_range.clone())
                     {
-                        break;
+                        new_range = containing_range;
+                        if is_visible(&new_range) {
+                            break;
+                        }
                     }
                 }
 
@@ -10387,7 +10529,7 @@ impl Editor {
         self.end_transaction_at(Instant::now(), cx)
     }
 
-    fn start_transaction_at(&mut self, now: Instant, cx: &mut ViewContext<Self>) {
+    pub fn start_transaction_at(&mut self, now: Instant, cx: &mut ViewContext<Self>) {
         self.end_selection(cx);
         if let Some(tx_id) = self
             .buffer
@@ -10401,7 +10543,7 @@ impl Editor {
         }
     }
 
-    fn end_transaction_at(
+    pub fn end_transaction_at(
         &mut self,
         now: Instant,
         cx: &mut ViewContext<Self>,
@@ -12189,6 +12331,8 @@ impl Editor {
                         }
                     }
                 }
+                self.refresh_conflicts(true, cx);
+                self.refresh_lsp_data(LspDataRefetch::Edited, true, cx);
                 cx.emit(EditorEvent::BufferEdited);
                 cx.emit(SearchEvent::MatchesInvalidated);
                 if *singleton_buffer_edited {
@@ -12252,9 +12396,13 @@ impl Editor {
                     excerpts: excerpts.clone(),
                 });
                 self.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+                self.refresh_conflicts(false, cx);
+                self.refresh_lsp_data(LspDataRefetch::Edited, false, cx);
             }
             multi_buffer::Event::ExcerptsRemoved { ids } => {
                 self.refresh_inlay_hints(InlayHintRefreshReason::ExcerptsRemoved(ids.clone()), cx);
+                self.refresh_conflicts(false, cx);
+                self.refresh_lsp_data(LspDataRefetch::Edited, false, cx);
                 let buffer = self.buffer.read(cx);
                 self.registered_buffers
                     .retain(|buffer_id, _| buffer.buffer(*buffer_id).is_some());
@@ -12273,6 +12421,7 @@ impl Editor {
             }
             multi_buffer::Event::LanguageChanged(buffer_id) => {
                 linked_editing_ranges::refresh_linked_ranges(self, cx);
+                self.refresh_lsp_data(LspDataRefetch::All, false, cx);
                 cx.emit(EditorEvent::Reparsed(*buffer_id));
                 cx.notify();
             }
@@ -12302,6 +12451,7 @@ impl Editor {
 
     fn settings_changed(&mut self, cx: &mut ViewContext<Self>) {
         self.tasks_update_task = Some(self.refresh_runnables(cx));
+        self.refresh_lsp_data(LspDataRefetch::Edited, false, cx);
         self.refresh_inline_completion(true, false, cx);
         self.refresh_inlay_hints(
             InlayHintRefreshReason::SettingsChange(inlay_hint_settings(
@@ -13983,7 +14133,7 @@ impl EditorSnapshot {
             }
         }
 
-        is_foldable |= self.starts_indent(buffer_row);
+        is_foldable |= self.starts_fold(buffer_row);
 
         if folded || (is_foldable && (row_contains_cursor || self.gutter_hovered)) {
             Some(
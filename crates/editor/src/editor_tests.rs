@@ -5478,6 +5478,110 @@ async fn test_select_larger_smaller_syntax_node(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_select_larger_smaller_language_server_range(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.selection_ranges =
+            Some(language_settings::SelectionRangesSource::LanguageServer)
+    });
+
+    let mut cx = EditorLspTestContext::new_rust(
+        lsp::ServerCapabilities {
+            selection_range_provider: Some(lsp::SelectionRangeProviderCapability::Simple(true)),
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+
+    // The server's ranges differ from the syntax tree's, expanding from the call
+    // to the assignment without its `let` and semicolon.
+    let mut requests =
+        cx.handle_request::<lsp::request::SelectionRangeRequest, _, _>(|_, params, _| async move {
+            let range = |start, end| {
+                lsp::Range::new(lsp::Position::new(1, start), lsp::Position::new(1, end))
+            };
+            let mut parent = None;
+            for (start, end) in [(8, 29), (16, 29), (16, 23)] {
+                parent = Some(Box::new(lsp::SelectionRange {
+                    range: range(start, end),
+                    parent,
+                }));
+            }
+            Ok(Some(
+                params
+                    .positions
+                    .iter()
+                    .map(|_| *parent.clone().unwrap())
+                    .collect(),
+            ))
+        });
+
+    cx.set_state(indoc! {"
+        fn main() {
+            let value = comˇpute(1, 2);
+        }
+    "});
+
+    cx.update_editor(|editor, cx| editor.select_larger_syntax_node(&SelectLargerSyntaxNode, cx));
+    requests.next().await;
+    cx.run_until_parked();
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            let value = «computeˇ»(1, 2);
+        }
+    "});
+
+    cx.update_editor(|editor, cx| editor.select_larger_syntax_node(&SelectLargerSyntaxNode, cx));
+    requests.next().await;
+    cx.run_until_parked();
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            let value = «compute(1, 2)ˇ»;
+        }
+    "});
+
+    cx.update_editor(|editor, cx| editor.select_larger_syntax_node(&SelectLargerSyntaxNode, cx));
+    requests.next().await;
+    cx.run_until_parked();
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            let «value = compute(1, 2)ˇ»;
+        }
+    "});
+
+    // Expanding past the outermost range has no effect.
+    cx.update_editor(|editor, cx| editor.select_larger_syntax_node(&SelectLargerSyntaxNode, cx));
+    requests.next().await;
+    cx.run_until_parked();
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            let «value = compute(1, 2)ˇ»;
+        }
+    "});
+
+    cx.update_editor(|editor, cx| editor.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            let value = «compute(1, 2)ˇ»;
+        }
+    "});
+
+    cx.update_editor(|editor, cx| editor.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            let value = «computeˇ»(1, 2);
+        }
+    "});
+
+    cx.update_editor(|editor, cx| editor.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            let value = comˇpute(1, 2);
+        }
+    "});
+}
+
 #[gpui::test]
 async fn test_autoindent(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -11565,6 +11669,234 @@ struct Row10;"#};
     );
 }
 
+#[gpui::test]
+async fn test_stage_and_unstage_hunks(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+    let committed_text = "one\ntwo\nthree\nfour\n";
+    cx.set_head_text(committed_text);
+    cx.set_diff_base(committed_text);
+    cx.set_state("ˇONE\ntwo\nthree\nFOUR\nfive\n");
+    cx.run_until_parked();
+
+    fn hunk_rows(cx: &mut EditorTestContext) -> Vec<(Range<u32>, DiffHunkStatus)> {
+        cx.update_editor(|editor, cx| {
+            let snapshot = editor.snapshot(cx);
+            snapshot
+                .diff_map
+                .diff_hunks(&snapshot.buffer_snapshot)
+                .map(|hunk| {
+                    (
+                        hunk.row_range.start.0..hunk.row_range.end.0,
+                        hunk_status(&hunk),
+                    )
+                })
+                .collect()
+        })
+    }
+    assert_eq!(
+        hunk_rows(&mut cx),
+        vec![
+            (0..1, DiffHunkStatus::Modified),
+            (3..5, DiffHunkStatus::Modified)
+        ]
+    );
+
+    cx.update_editor(|editor, cx| editor.stage_hunks(&StageHunk, cx));
+    assert_eq!(cx.index_text().as_deref(), Some("ONE\ntwo\nthree\nfour\n"));
+    assert_eq!(hunk_rows(&mut cx), vec![(3..5, DiffHunkStatus::Modified)]);
+
+    cx.set_selections_state("ONE\ntwo\nthree\n«FOURˇ»\nfive\n");
+    cx.update_editor(|editor, cx| editor.stage_selected_lines(&StageSelectedLines, cx));
+    assert_eq!(cx.index_text().as_deref(), Some("ONE\ntwo\nthree\nFOUR\n"));
+    assert_eq!(hunk_rows(&mut cx), vec![(4..5, DiffHunkStatus::Added)]);
+
+    cx.set_selections_state("ˇONE\ntwo\nthree\nFOUR\nfive\n");
+    cx.update_editor(|editor, cx| editor.unstage_hunks(&UnstageHunk, cx));
+    assert_eq!(cx.index_text().as_deref(), Some("one\ntwo\nthree\nFOUR\n"));
+    assert_eq!(
+        hunk_rows(&mut cx),
+        vec![
+            (0..1, DiffHunkStatus::Modified),
+            (4..5, DiffHunkStatus::Added)
+        ]
+    );
+}
+
+#[gpui::test]
+async fn test_stage_selected_lines_of_mixed_hunk(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+    let committed_text = "a\nb\nc\nd\n";
+    cx.set_head_text(committed_text);
+    cx.set_diff_base(committed_text);
+    cx.set_state("ˇa\nB\nC\nx\nd\n");
+    cx.run_until_parked();
+
+    // `b` and `c` are replaced by `B`, `C` and `x`. Staging `C` keeps `b` in the index.
+    cx.set_selections_state("a\nB\n«Cˇ»\nx\nd\n");
+    cx.update_editor(|editor, cx| editor.stage_selected_lines(&StageSelectedLines, cx));
+    assert_eq!(cx.index_text().as_deref(), Some("a\nb\nC\nd\n"));
+
+    cx.set_selections_state("a\nB\nC\n«xˇ»\nd\n");
+    cx.update_editor(|editor, cx| editor.stage_selected_lines(&StageSelectedLines, cx));
+    assert_eq!(cx.index_text().as_deref(), Some("a\nb\nC\nx\nd\n"));
+
+    // Removed lines past the last added row are staged with that row.
+    cx.set_diff_base(committed_text);
+    cx.set_state("a\nˇB\nd\n");
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| editor.stage_selected_lines(&StageSelectedLines, cx));
+    assert_eq!(cx.index_text().as_deref(), Some("a\nB\nd\n"));
+}
+
+#[gpui::test]
+async fn test_resolve_conflicts(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        ˇfn main() {
+        <<<<<<< HEAD
+            ours();
+        =======
+            theirs();
+        >>>>>>> feature
+        }
+        <<<<<<< HEAD
+        a
+        ||||||| parent of 1234567 (Change a)
+        base
+        =======
+        b
+        >>>>>>> 1234567 (Change a)
+    "});
+    cx.executor().advance_clock(Duration::from_millis(100));
+    cx.run_until_parked();
+
+    cx.update_editor(|editor, cx| editor.go_to_next_conflict(&GoToConflict, cx));
+    cx.update_editor(|editor, cx| editor.go_to_next_conflict(&GoToConflict, cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+        <<<<<<< HEAD
+            ours();
+        =======
+            theirs();
+        >>>>>>> feature
+        }
+        ˇ<<<<<<< HEAD
+        a
+        ||||||| parent of 1234567 (Change a)
+        base
+        =======
+        b
+        >>>>>>> 1234567 (Change a)
+    "});
+
+    cx.update_editor(|editor, cx| editor.accept_conflict_theirs(&AcceptConflictTheirs, cx));
+    cx.update_editor(|editor, cx| editor.go_to_prev_conflict(&GoToPrevConflict, cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+        ˇ<<<<<<< HEAD
+            ours();
+        =======
+            theirs();
+        >>>>>>> feature
+        }
+        b
+    "});
+
+    cx.update_editor(|editor, cx| editor.accept_conflict_both(&AcceptConflictBoth, cx));
+    assert_eq!(
+        cx.update_editor(|editor, cx| editor.text(cx)),
+        "fn main() {\n    ours();\n    theirs();\n}\nb\n"
+    );
+
+    cx.update_editor(|editor, cx| editor.undo(&Undo, cx));
+    cx.executor().advance_clock(Duration::from_millis(100));
+    cx.run_until_parked();
+    cx.set_selections_state(indoc! {"
+        fn main() {
+        <<<<<<< HEAD
+            ours();
+        =======
+            theiˇrs();
+        >>>>>>> feature
+        }
+        b
+    "});
+    cx.update_editor(|editor, cx| editor.accept_conflict_ours(&AcceptConflictOurs, cx));
+    assert_eq!(
+        cx.update_editor(|editor, cx| editor.text(cx)),
+        "fn main() {\n    ours();\n}\nb\n"
+    );
+}
+
+#[gpui::test]
+async fn test_refresh_conflicts_after_edits(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        ˇone
+        <<<<<<< HEAD
+        ours
+        =======
+        theirs
+        >>>>>>> feature
+        two
+    "});
+    cx.executor().advance_clock(Duration::from_millis(100));
+    cx.run_until_parked();
+
+    // Edits away from the conflict keep it in place.
+    cx.update_editor(|editor, cx| editor.handle_input("zero\n", cx));
+    cx.executor().advance_clock(Duration::from_millis(100));
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| editor.go_to_next_conflict(&GoToConflict, cx));
+    cx.assert_editor_state(indoc! {"
+        zero
+        one
+        ˇ<<<<<<< HEAD
+        ours
+        =======
+        theirs
+        >>>>>>> feature
+        two
+    "});
+
+    // Typing the markers of a new conflict is picked up.
+    cx.set_selections_state(indoc! {"
+        zero
+        one
+        <<<<<<< HEAD
+        ours
+        =======
+        theirs
+        >>>>>>> feature
+        ˇtwo
+    "});
+    cx.update_editor(|editor, cx| {
+        editor.handle_input("<<<<<<< HEAD\na\n=======\nb\n>>>>>>> other\n", cx)
+    });
+    cx.executor().advance_clock(Duration::from_millis(100));
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| editor.go_to_prev_conflict(&GoToPrevConflict, cx));
+    cx.assert_editor_state(indoc! {"
+        zero
+        one
+        <<<<<<< HEAD
+        ours
+        =======
+        theirs
+        >>>>>>> feature
+        ˇ<<<<<<< HEAD
+        a
+        =======
+        b
+        >>>>>>> other
+        two
+    "});
+}
+
 #[gpui::test]
 async fn test_multibuffer_reverts(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -315,6 +315,8 @@ impl EditorElement {
         register_action(view, cx, Editor::go_to_prev_diagnostic);
         register_action(view, cx, Editor::go_to_next_hunk);
         register_action(view, cx, Editor::go_to_prev_hunk);
+        register_action(view, cx, Editor::go_to_next_conflict);
+        register_action(view, cx, Editor::go_to_prev_conflict);
         register_action(view, cx, |editor, a, cx| {
             editor.go_to_definition(a, cx).detach_and_log_err(cx);
         });
@@ -458,6 +460,12 @@ impl EditorElement {
         register_action(view, cx, Editor::revert_selected_hunks);
         register_action(view, cx, Editor::apply_all_diff_hunks);
         register_action(view, cx, Editor::apply_selected_diff_hunks);
+        register_action(view, cx, Editor::stage_hunks);
+        register_action(view, cx, Editor::unstage_hunks);
+        register_action(view, cx, Editor::accept_conflict_ours);
+        register_action(view, cx, Editor::accept_conflict_theirs);
+        register_action(view, cx, Editor::accept_conflict_both);
+        register_action(view, cx, Editor::stage_selected_lines);
         register_action(view, cx, Editor::open_active_item_in_terminal);
         register_action(view, cx, Editor::reload_file);
         register_action(view, cx, Editor::spawn_nearest_task);
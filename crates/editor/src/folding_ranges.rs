@@ -0,0 +1,204 @@
+use std::{ops::Range, time::Duration};
+
+use anyhow::Result;
+use gpui::{Model, ModelContext, Task, ViewContext};
+use language::{
+    language_settings::{FoldingRangesSource, LanguageSettings},
+    Buffer,
+};
+use project::Project;
+
+use crate::{
+    display_map::LanguageServerFoldingRanges,
+    lsp_data::{FetchedLspData, LspData},
+    Editor,
+};
+
+const FETCH_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// The folding ranges of the buffers in an editor whose language folds by language server.
+#[derive(Default)]
+pub(crate) struct FoldingRangesState {
+    fetched: FetchedLspData<Self>,
+}
+
+impl LspData for FoldingRangesState {
+    type Key = ();
+    type Data = Vec<Range<text::Anchor>>;
+
+    const FETCH_DEBOUNCE: Duration = FETCH_DEBOUNCE;
+
+    fn fetched(editor: &mut Editor) -> &mut FetchedLspData<Self> {
+        &mut editor.folding_ranges.fetched
+    }
+
+    fn key(settings: &LanguageSettings) -> Option<Self::Key> {
+        (settings.folding_ranges == FoldingRangesSource::LanguageServer).then_some(())
+    }
+
+    /// Servers can't ask for folding ranges to be refetched, so they are refetched
+    /// whenever the servers ask for inlay hints to be.
+    fn refetch_on(event: &project::Event) -> bool {
+        matches!(
+            event,
+            project::Event::RefreshInlayHints | project::Event::LanguageServerAdded(..)
+        )
+    }
+
+    fn fetch(
+        project: &mut Project,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Project>,
+    ) -> Task<Result<Self::Data>> {
+        project.folding_ranges(buffer, cx)
+    }
+
+    fn updated(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+        editor.update_folding_ranges(cx);
+    }
+}
+
+impl Editor {
+    /// Passes the fetched folding ranges to the display map, which uses them instead of
+    /// indentation to decide which rows can be folded.
+    fn update_folding_ranges(&mut self, cx: &mut ViewContext<Self>) {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let mut folding_ranges = LanguageServerFoldingRanges::default();
+        for (excerpt_id, buffer, excerpt_range) in snapshot.excerpts() {
+            let buffer_id = buffer.remote_id();
+            let Some(buffer_ranges) = self.folding_ranges.fetched.buffers.get(&buffer_id) else {
+                continue;
+            };
+            folding_ranges.buffers.insert(buffer_id);
+
+            let context = excerpt_range.context;
+            for range in &buffer_ranges.data {
+                if range.start.cmp(&context.start, buffer).is_lt()
+                    || range.start.cmp(&context.end, buffer).is_ge()
+                {
+                    continue;
+                }
+                if let Some((start, end)) = snapshot
+                    .anchor_in_excerpt(excerpt_id, range.start)
+                    .zip(snapshot.anchor_in_excerpt(excerpt_id, range.end))
+                {
+                    folding_ranges.ranges.push(start..end);
+                }
+            }
+        }
+
+        self.display_map.update(cx, |display_map, _| {
+            display_map.set_folding_ranges(folding_ranges)
+        });
+        cx.notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt as _;
+    use indoc::indoc;
+    use language::language_settings::FoldingRangesSource;
+
+    use super::*;
+    use crate::{
+        actions::{Fold, UnfoldAll},
+        editor_tests::init_test,
+        test::editor_lsp_test_context::EditorLspTestContext,
+    };
+
+    #[gpui::test]
+    async fn test_folding_language_server_ranges(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |settings| {
+            settings.defaults.folding_ranges = Some(FoldingRangesSource::LanguageServer)
+        });
+        let mut cx = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                folding_range_provider: Some(lsp::FoldingRangeProviderCapability::Simple(true)),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+
+        // The ranges are out of order, and the last one ends past the end of the buffer.
+        let mut requests =
+            cx.handle_request::<lsp::request::FoldingRangeRequest, _, _>(|_, _, _| async move {
+                let range = |start_line, end_line, kind| lsp::FoldingRange {
+                    start_line,
+                    end_line,
+                    kind: Some(kind),
+                    ..Default::default()
+                };
+                Ok(Some(vec![
+                    range(4, 6, lsp::FoldingRangeKind::Region),
+                    range(0, 2, lsp::FoldingRangeKind::Comment),
+                    range(3, 100, lsp::FoldingRangeKind::Region),
+                ]))
+            });
+
+        cx.set_state(indoc! {"
+            ˇ// first
+            // second
+            // third
+            fn main() {
+                // region: setup
+                let a = 1;
+                // endregion
+            }"});
+        cx.executor().advance_clock(FETCH_DEBOUNCE);
+        requests.next().await;
+        cx.run_until_parked();
+
+        cx.update_editor(|editor, cx| editor.fold(&Fold, cx));
+        assert_eq!(
+            cx.display_text(),
+            indoc! {"
+                // first⋯
+                fn main() {
+                    // region: setup
+                    let a = 1;
+                    // endregion
+                }"}
+        );
+
+        cx.set_selections_state(indoc! {"
+            // first
+            // second
+            // third
+            fn main() {
+                // region: ˇsetup
+                let a = 1;
+                // endregion
+            }"});
+        cx.update_editor(|editor, cx| editor.fold(&Fold, cx));
+        assert_eq!(
+            cx.display_text(),
+            indoc! {"
+                // first⋯
+                fn main() {
+                    // region: setup⋯
+                }"}
+        );
+
+        cx.update_editor(|editor, cx| editor.unfold_all(&UnfoldAll, cx));
+        cx.set_selections_state(indoc! {"
+            // first
+            // second
+            // third
+            fn maiˇn() {
+                // region: setup
+                let a = 1;
+                // endregion
+            }"});
+        cx.update_editor(|editor, cx| editor.fold(&Fold, cx));
+        assert_eq!(
+            cx.display_text(),
+            indoc! {"
+                // first
+                // second
+                // third
+                fn main() {⋯"}
+        );
+    }
+}
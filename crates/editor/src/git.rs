@@ -1,2 +1,3 @@
 pub mod blame;
+pub mod conflicts;
 pub mod project_diff;
@@ -0,0 +1,582 @@
+use std::{ops::Range, sync::Arc, time::Duration};
+
+use collections::{HashMap, HashSet};
+use futures::future::try_join_all;
+use git::{
+    conflict::{has_conflict_marker, parse_conflicts},
+    repository::GitFileStatus,
+};
+use gpui::{actions, AppContext, Hsla, Model, Task, ViewContext};
+use language::{Buffer, BufferId, BufferSnapshot, Point};
+use multi_buffer::{Anchor, AnchorRangeExt, MultiBuffer, MultiBufferRow, MultiBufferSnapshot};
+use project::ProjectPath;
+use text::ToOffset as _;
+use ui::{prelude::*, Tooltip};
+use workspace::{
+    notifications::{NotificationId, NotifyTaskExt},
+    Toast, Workspace,
+};
+
+use crate::{
+    scroll::Autoscroll, AcceptConflictBoth, AcceptConflictOurs, AcceptConflictTheirs,
+    BlockPlacement, BlockProperties, BlockStyle, CustomBlockId, Editor, EditorMode, GoToConflict,
+    GoToPrevConflict, ToPoint, DEFAULT_MULTIBUFFER_CONTEXT,
+};
+
+actions!(project_conflicts, [Deploy]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(deploy_project_conflicts);
+    })
+    .detach();
+}
+
+const REFRESH_DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// The merge conflicts found in the buffers of an editor.
+#[derive(Default)]
+pub(crate) struct ConflictMap {
+    conflicts: Vec<EditorConflict>,
+    blocks: HashSet<CustomBlockId>,
+    buffers: HashMap<BufferId, ParsedConflicts>,
+    refresh_task: Option<Task<()>>,
+}
+
+/// The conflicts parsed from a buffer, which are kept until an edit could change them.
+struct ParsedConflicts {
+    version: clock::Global,
+    conflicts: Vec<BufferConflict>,
+    /// Whether the buffer has conflict markers outside of any well-formed conflict.
+    has_stray_markers: bool,
+}
+
+impl ParsedConflicts {
+    fn parse(buffer: &BufferSnapshot) -> Self {
+        let text = buffer.text();
+        let conflicts = parse_conflicts(&text);
+        let mut has_stray_markers = false;
+        let mut start = 0;
+        for conflict in &conflicts {
+            has_stray_markers |= has_conflict_marker(&text[start..conflict.range.start]);
+            start = conflict.range.end;
+        }
+        has_stray_markers |= has_conflict_marker(&text[start..]);
+
+        let to_anchors =
+            |range: Range<usize>| buffer.anchor_after(range.start)..buffer.anchor_before(range.end);
+        Self {
+            version: buffer.version().clone(),
+            conflicts: conflicts
+                .into_iter()
+                .map(|conflict| BufferConflict {
+                    range: to_anchors(conflict.range),
+                    ours: to_anchors(conflict.ours),
+                    base: conflict.base.map(to_anchors),
+                    theirs: to_anchors(conflict.theirs),
+                    ours_label: conflict.ours_label.into(),
+                    theirs_label: conflict.theirs_label.into(),
+                })
+                .collect(),
+            has_stray_markers,
+        }
+    }
+
+    /// Whether the edits made since parsing could have changed the conflicts. That's only
+    /// the case when an edit touches a conflict or a line with a marker, or when stray
+    /// markers could be completed into a new conflict.
+    fn is_stale(&self, buffer: &BufferSnapshot) -> bool {
+        if self.has_stray_markers {
+            return true;
+        }
+        buffer.edits_since::<Point>(&self.version).any(|edit| {
+            let start = Point::new(edit.new.start.row, 0);
+            let end = Point::new(edit.new.end.row, buffer.line_len(edit.new.end.row));
+            if has_conflict_marker(&buffer.text_for_range(start..end).collect::<String>()) {
+                return true;
+            }
+            let (start, end) = (start.to_offset(buffer), end.to_offset(buffer));
+            self.conflicts.iter().any(|conflict| {
+                conflict.range.start.to_offset(buffer) <= end
+                    && start <= conflict.range.end.to_offset(buffer)
+            })
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct EditorConflict {
+    range: Range<Anchor>,
+    ours: Range<Anchor>,
+    base: Option<Range<Anchor>>,
+    theirs: Range<Anchor>,
+    ours_label: SharedString,
+    theirs_label: SharedString,
+}
+
+#[derive(Clone, Debug)]
+struct BufferConflict {
+    range: Range<text::Anchor>,
+    ours: Range<text::Anchor>,
+    base: Option<Range<text::Anchor>>,
+    theirs: Range<text::Anchor>,
+    ours_label: SharedString,
+    theirs_label: SharedString,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ConflictResolution {
+    Ours,
+    Theirs,
+    Both,
+}
+
+enum ConflictMarkerHighlight {}
+enum ConflictOursHighlight {}
+enum ConflictTheirsHighlight {}
+
+impl Editor {
+    pub(crate) fn refresh_conflicts(&mut self, debounce: bool, cx: &mut ViewContext<Self>) {
+        if self.mode != EditorMode::Full {
+            return;
+        }
+
+        // Only buffers with edits that could have changed their conflicts are parsed again.
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let mut buffer_ids = HashSet::default();
+        let mut stale_buffers = HashMap::<BufferId, BufferSnapshot>::default();
+        for (_, buffer, _) in snapshot.excerpts() {
+            let buffer_id = buffer.remote_id();
+            if !buffer_ids.insert(buffer_id) {
+                continue;
+            }
+            match self.conflict_map.buffers.get_mut(&buffer_id) {
+                Some(parsed) if &parsed.version == buffer.version() => {}
+                Some(parsed) if !parsed.is_stale(buffer) => {
+                    parsed.version = buffer.version().clone();
+                }
+                _ => {
+                    stale_buffers.insert(buffer_id, buffer.clone());
+                }
+            }
+        }
+        self.conflict_map
+            .buffers
+            .retain(|buffer_id, _| buffer_ids.contains(buffer_id));
+
+        if stale_buffers.is_empty() {
+            self.conflict_map.refresh_task = None;
+            self.set_conflicts(cx);
+            return;
+        }
+
+        self.conflict_map.refresh_task = Some(cx.spawn(|editor, mut cx| async move {
+            if debounce {
+                cx.background_executor().timer(REFRESH_DEBOUNCE).await;
+            }
+            let parsed = cx
+                .background_executor()
+                .spawn(async move {
+                    stale_buffers
+                        .into_iter()
+                        .map(|(buffer_id, buffer)| (buffer_id, ParsedConflicts::parse(&buffer)))
+                        .collect::<Vec<_>>()
+                })
+                .await;
+            editor
+                .update(&mut cx, |editor, cx| {
+                    editor.conflict_map.buffers.extend(parsed);
+                    editor.set_conflicts(cx);
+                })
+                .ok();
+        }));
+    }
+
+    fn set_conflicts(&mut self, cx: &mut ViewContext<Self>) {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let mut conflicts = Vec::new();
+        for (excerpt_id, buffer, excerpt_range) in snapshot.excerpts() {
+            let Some(parsed) = self.conflict_map.buffers.get(&buffer.remote_id()) else {
+                continue;
+            };
+            let context = excerpt_range.context;
+            for conflict in &parsed.conflicts {
+                if conflict.range.start.cmp(&context.start, buffer).is_lt()
+                    || conflict.range.end.cmp(&context.end, buffer).is_gt()
+                {
+                    continue;
+                }
+                let to_multi_buffer = |range: &Range<text::Anchor>| {
+                    Some(
+                        snapshot.anchor_in_excerpt(excerpt_id, range.start)?
+                            ..snapshot.anchor_in_excerpt(excerpt_id, range.end)?,
+                    )
+                };
+                let Some(range) = to_multi_buffer(&conflict.range) else {
+                    continue;
+                };
+                let (Some(ours), Some(theirs)) = (
+                    to_multi_buffer(&conflict.ours),
+                    to_multi_buffer(&conflict.theirs),
+                ) else {
+                    continue;
+                };
+                conflicts.push(EditorConflict {
+                    range,
+                    ours,
+                    base: conflict.base.as_ref().and_then(to_multi_buffer),
+                    theirs,
+                    ours_label: conflict.ours_label.clone(),
+                    theirs_label: conflict.theirs_label.clone(),
+                });
+            }
+        }
+
+        self.clear_row_highlights::<ConflictMarkerHighlight>();
+        self.clear_row_highlights::<ConflictOursHighlight>();
+        self.clear_row_highlights::<ConflictTheirsHighlight>();
+        let old_blocks = std::mem::take(&mut self.conflict_map.blocks);
+        self.remove_blocks(old_blocks, None, cx);
+
+        let status = cx.theme().status();
+        let (marker_color, ours_color, theirs_color) = (
+            status.conflict_background,
+            status.created_background,
+            status.info_background,
+        );
+        for conflict in &conflicts {
+            self.highlight_conflict(
+                conflict,
+                marker_color,
+                ours_color,
+                theirs_color,
+                &snapshot,
+                cx,
+            );
+        }
+        let blocks = conflicts
+            .iter()
+            .map(|conflict| self.conflict_block(conflict, cx))
+            .collect::<Vec<_>>();
+        self.conflict_map.blocks = self.insert_blocks(blocks, None, cx).into_iter().collect();
+        self.conflict_map.conflicts = conflicts;
+        cx.notify();
+    }
+
+    fn highlight_conflict(
+        &mut self,
+        conflict: &EditorConflict,
+        marker_color: Hsla,
+        ours_color: Hsla,
+        theirs_color: Hsla,
+        snapshot: &MultiBufferSnapshot,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let separator_start = conflict.base.as_ref().unwrap_or(&conflict.ours).end;
+        let mut markers = vec![
+            conflict.range.start..conflict.ours.start,
+            separator_start..conflict.theirs.start,
+            conflict.theirs.end..conflict.range.end,
+        ];
+        if let Some(base) = &conflict.base {
+            markers.push(conflict.ours.end..base.end);
+        }
+        for range in markers {
+            if let Some(rows) = full_rows(&range, snapshot) {
+                self.highlight_rows::<ConflictMarkerHighlight>(rows, marker_color, false, cx);
+            }
+        }
+        if let Some(rows) = full_rows(&conflict.ours, snapshot) {
+            self.highlight_rows::<ConflictOursHighlight>(rows, ours_color, false, cx);
+        }
+        if let Some(rows) = full_rows(&conflict.theirs, snapshot) {
+            self.highlight_rows::<ConflictTheirsHighlight>(rows, theirs_color, false, cx);
+        }
+    }
+
+    fn conflict_block(
+        &self,
+        conflict: &EditorConflict,
+        cx: &mut ViewContext<Self>,
+    ) -> BlockProperties<Anchor> {
+        let border_color = cx.theme().colors().border_variant;
+        let bg_color = cx.theme().colors().editor_background;
+
+        BlockProperties {
+            placement: BlockPlacement::Above(conflict.range.start),
+            height: 1,
+            style: BlockStyle::Sticky,
+            priority: 0,
+            render: Arc::new({
+                let editor = cx.view().clone();
+                let conflict = conflict.clone();
+
+                move |cx| {
+                    let focus_handle = editor.focus_handle(cx);
+                    let resolve_button =
+                        |id: &'static str,
+                         label: SharedString,
+                         tooltip: &'static str,
+                         action: Box<dyn gpui::Action>,
+                         resolution: ConflictResolution| {
+                            Button::new(id, label)
+                                .style(ButtonStyle::Subtle)
+                                .label_size(LabelSize::Small)
+                                .tooltip({
+                                    let focus_handle = focus_handle.clone();
+                                    move |cx| {
+                                        Tooltip::for_action_in(
+                                            tooltip,
+                                            action.as_ref(),
+                                            &focus_handle,
+                                            cx,
+                                        )
+                                    }
+                                })
+                                .on_click({
+                                    let editor = editor.clone();
+                                    let conflict = conflict.clone();
+                                    move |_event, cx| {
+                                        editor.update(cx, |editor, cx| {
+                                            editor.resolve_conflicts(
+                                                vec![conflict.clone()],
+                                                resolution,
+                                                cx,
+                                            );
+                                        });
+                                    }
+                                })
+                        };
+
+                    h_flex()
+                        .id(cx.block_id)
+                        .block_mouse_down()
+                        .h(cx.line_height())
+                        .w_full()
+                        .pl(cx.gutter_dimensions.full_width())
+                        .gap_1()
+                        .border_t_1()
+                        .border_color(border_color)
+                        .bg(bg_color)
+                        .child(
+                            Label::new("Merge conflict")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                        .child(resolve_button(
+                            "accept-ours",
+                            format!("Accept Ours ({})", conflict.ours_label).into(),
+                            "Accept Ours",
+                            Box::new(AcceptConflictOurs),
+                            ConflictResolution::Ours,
+                        ))
+                        .child(resolve_button(
+                            "accept-theirs",
+                            format!("Accept Theirs ({})", conflict.theirs_label).into(),
+                            "Accept Theirs",
+                            Box::new(AcceptConflictTheirs),
+                            ConflictResolution::Theirs,
+                        ))
+                        .child(resolve_button(
+                            "accept-both",
+                            "Accept Both".into(),
+                            "Accept Both",
+                            Box::new(AcceptConflictBoth),
+                            ConflictResolution::Both,
+                        ))
+                        .into_any_element()
+                }
+            }),
+        }
+    }
+
+    pub fn accept_conflict_ours(&mut self, _: &AcceptConflictOurs, cx: &mut ViewContext<Self>) {
+        let conflicts = self.conflicts_in_selections(cx);
+        self.resolve_conflicts(conflicts, ConflictResolution::Ours, cx);
+    }
+
+    pub fn accept_conflict_theirs(&mut self, _: &AcceptConflictTheirs, cx: &mut ViewContext<Self>) {
+        let conflicts = self.conflicts_in_selections(cx);
+        self.resolve_conflicts(conflicts, ConflictResolution::Theirs, cx);
+    }
+
+    pub fn accept_conflict_both(&mut self, _: &AcceptConflictBoth, cx: &mut ViewContext<Self>) {
+        let conflicts = self.conflicts_in_selections(cx);
+        self.resolve_conflicts(conflicts, ConflictResolution::Both, cx);
+    }
+
+    fn conflicts_in_selections(&self, cx: &mut ViewContext<Self>) -> Vec<EditorConflict> {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let selections = self.selections.all::<usize>(cx);
+        self.conflict_map
+            .conflicts
+            .iter()
+            .filter(|conflict| {
+                let range = conflict.range.to_offset(&snapshot);
+                selections
+                    .iter()
+                    .any(|selection| selection.start < range.end && selection.end >= range.start)
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn resolve_conflicts(
+        &mut self,
+        conflicts: Vec<EditorConflict>,
+        resolution: ConflictResolution,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if conflicts.is_empty() {
+            return;
+        }
+
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let edits = conflicts
+            .into_iter()
+            .map(|conflict| {
+                let ours = snapshot.text_for_range(conflict.ours.clone());
+                let theirs = snapshot.text_for_range(conflict.theirs.clone());
+                let text = match resolution {
+                    ConflictResolution::Ours => ours.collect::<String>(),
+                    ConflictResolution::Theirs => theirs.collect::<String>(),
+                    ConflictResolution::Both => ours.chain(theirs).collect::<String>(),
+                };
+                (conflict.range, text)
+            })
+            .collect::<Vec<_>>();
+        self.transact(cx, |editor, cx| editor.edit(edits, cx));
+        self.refresh_conflicts(false, cx);
+    }
+
+    pub fn go_to_next_conflict(&mut self, _: &GoToConflict, cx: &mut ViewContext<Self>) {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let head = self.selections.newest::<Point>(cx).head();
+        let mut starts = self
+            .conflict_map
+            .conflicts
+            .iter()
+            .map(|conflict| conflict.range.start.to_point(&snapshot));
+        let target = starts
+            .clone()
+            .find(|start| start.row > head.row)
+            .or_else(|| starts.next());
+        if let Some(target) = target {
+            self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_ranges([target..target])
+            });
+        }
+    }
+
+    pub fn go_to_prev_conflict(&mut self, _: &GoToPrevConflict, cx: &mut ViewContext<Self>) {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let head = self.selections.newest::<Point>(cx).head();
+        let mut starts = self
+            .conflict_map
+            .conflicts
+            .iter()
+            .rev()
+            .map(|conflict| conflict.range.start.to_point(&snapshot));
+        let target = starts
+            .clone()
+            .find(|start| start.row < head.row)
+            .or_else(|| starts.next());
+        if let Some(target) = target {
+            self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_ranges([target..target])
+            });
+        }
+    }
+}
+
+/// Converts a range of whole lines, ending after the last line's newline, into a range
+/// suitable for row highlighting.
+fn full_rows(range: &Range<Anchor>, snapshot: &MultiBufferSnapshot) -> Option<Range<Anchor>> {
+    let start = range.start.to_point(snapshot);
+    let mut end = range.end.to_point(snapshot);
+    if end <= start {
+        return None;
+    }
+    if end.column == 0 {
+        let row = end.row - 1;
+        end = Point::new(row, snapshot.line_len(MultiBufferRow(row)));
+    }
+    Some(snapshot.anchor_after(start)..snapshot.anchor_before(end))
+}
+
+fn deploy_project_conflicts(
+    workspace: &mut Workspace,
+    _: &Deploy,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let project = workspace.project().clone();
+    let conflicted_paths = project
+        .read(cx)
+        .worktrees(cx)
+        .flat_map(|worktree| {
+            let worktree = worktree.read(cx);
+            let worktree_id = worktree.id();
+            worktree
+                .entries(false, 0)
+                .filter(|entry| {
+                    entry.is_file() && entry.git_status == Some(GitFileStatus::Conflict)
+                })
+                .map(|entry| ProjectPath {
+                    worktree_id,
+                    path: entry.path.clone(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    if conflicted_paths.is_empty() {
+        struct NoConflicts;
+        workspace.show_toast(
+            Toast::new(
+                NotificationId::unique::<NoConflicts>(),
+                "No merge conflicts in the project",
+            ),
+            cx,
+        );
+        return;
+    }
+
+    let open_buffers = conflicted_paths
+        .into_iter()
+        .map(|path| project.update(cx, |project, cx| project.open_buffer(path, cx)))
+        .collect::<Vec<_>>();
+    cx.spawn(|workspace, mut cx| async move {
+        let buffers: Vec<Model<Buffer>> = try_join_all(open_buffers).await?;
+        workspace.update(&mut cx, |workspace, cx| {
+            let capability = project.read(cx).capability();
+            let multibuffer = cx.new_model(|cx| {
+                let mut multibuffer = MultiBuffer::new(capability);
+                for buffer in buffers {
+                    let snapshot = buffer.read(cx).snapshot();
+                    let ranges = parse_conflicts(&snapshot.text())
+                        .into_iter()
+                        .map(|conflict| {
+                            snapshot.offset_to_point(conflict.range.start)
+                                ..snapshot.offset_to_point(conflict.range.end)
+                        })
+                        .collect::<Vec<_>>();
+                    if !ranges.is_empty() {
+                        multibuffer.push_excerpts_with_context_lines(
+                            buffer,
+                            ranges,
+                            DEFAULT_MULTIBUFFER_CONTEXT,
+                            cx,
+                        );
+                    }
+                }
+                multibuffer.with_title("Conflicts".into())
+            });
+            let editor = cx.new_view(|cx| {
+                Editor::for_multibuffer(multibuffer, Some(project.clone()), true, cx)
+            });
+            workspace.add_item_to_active_pane(Box::new(editor), None, true, cx);
+        })?;
+        anyhow::Ok(())
+    })
+    .detach_and_notify_err(cx);
+}
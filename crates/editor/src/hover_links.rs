@@ -1,4 +1,5 @@
 use crate::{
+    document_links::find_document_link,
     editor_settings::MultiCursorModifier,
     hover_popover::{self, InlayHover},
     scroll::ScrollAmount,
@@ -513,7 +514,18 @@ pub fn show_link_definition(
         async move {
             let result = match &trigger_point {
                 TriggerPoint::Text(_) => {
-                    if let Some((url_range, url)) = find_url(&buffer, buffer_position, cx.clone()) {
+                    if let Some((link_range, link)) =
+                        find_document_link(&this, &buffer, buffer_position, &mut cx).await
+                    {
+                        let range = maybe!({
+                            let start = snapshot.anchor_in_excerpt(excerpt_id, link_range.start)?;
+                            let end = snapshot.anchor_in_excerpt(excerpt_id, link_range.end)?;
+                            Some(RangeInEditor::Text(start..end))
+                        });
+                        Some((range, vec![link]))
+                    } else if let Some((url_range, url)) =
+                        find_url(&buffer, buffer_position, cx.clone())
+                    {
                         this.update(&mut cx, |_, _| {
                             let range = maybe!({
                                 let start =
@@ -897,6 +909,7 @@ mod tests {
         display_map::ToDisplayPoint,
         editor_tests::init_test,
         inlay_hint_cache::tests::{cached_hint_labels, visible_hint_labels},
+        lsp_data::LspDataRefetch,
         test::editor_lsp_test_context::EditorLspTestContext,
         DisplayPoint,
     };
@@ -905,6 +918,7 @@ mod tests {
     use indoc::indoc;
     use language::language_settings::InlayHintSettings;
     use lsp::request::{GotoDefinition, GotoTypeDefinition};
+    use std::time::Duration;
     use util::assert_set_eq;
     use workspace::item::Item;
 
@@ -1424,6 +1438,108 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_document_links(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+        let mut cx = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                document_link_provider: Some(lsp::DocumentLinkOptions {
+                    resolve_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                }),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+
+        let mut link_requests =
+            cx.handle_request::<lsp::request::DocumentLinkRequest, _, _>(|_, _, _| async move {
+                Ok(Some(vec![
+                    lsp::DocumentLink {
+                        range: lsp::Range::new(lsp::Position::new(0, 7), lsp::Position::new(0, 15)),
+                        target: None,
+                        tooltip: None,
+                        data: None,
+                    },
+                    lsp::DocumentLink {
+                        range: lsp::Range::new(lsp::Position::new(1, 7), lsp::Position::new(1, 15)),
+                        target: Some(lsp::Url::from_file_path("/root/dir/file2.rs").unwrap()),
+                        tooltip: None,
+                        data: None,
+                    },
+                ]))
+            });
+        let mut resolve_requests =
+            cx.handle_request::<lsp::request::DocumentLinkResolve, _, _>(|_, link, _| async move {
+                Ok(lsp::DocumentLink {
+                    target: Some(lsp::Url::parse("https://zed.dev/docs").unwrap()),
+                    ..link
+                })
+            });
+        let fs = cx.update_workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+        fs.as_fake()
+            .insert_file("/root/dir/file2.rs", "This is file2.rs".as_bytes().to_vec())
+            .await;
+
+        cx.set_state(indoc! {"
+            // see the docsˇ
+            // see the file
+        "});
+        cx.executor().advance_clock(Duration::from_secs(1));
+        link_requests.next().await;
+        cx.run_until_parked();
+        while let Ok(Some(())) = link_requests.try_next() {}
+
+        // Refreshing without edits doesn't fetch the links again.
+        cx.update_editor(|editor, cx| editor.refresh_lsp_data(LspDataRefetch::Edited, false, cx));
+        cx.run_until_parked();
+        assert!(link_requests.try_next().is_err());
+
+        // The link is only resolved once it's hovered.
+        let screen_coord = cx.pixel_position(indoc! {"
+            // see the dˇocs
+            // see the file
+        "});
+        cx.simulate_mouse_move(screen_coord, None, Modifiers::secondary_key());
+        resolve_requests.next().await;
+        cx.run_until_parked();
+        cx.assert_editor_text_highlights::<HoveredLinkState>(indoc! {"
+            // see «the docsˇ»
+            // see the file
+        "});
+
+        cx.simulate_click(screen_coord, Modifiers::secondary_key());
+        assert_eq!(cx.opened_url(), Some("https://zed.dev/docs".into()));
+
+        // Links to files in the worktree open the file.
+        let screen_coord = cx.pixel_position(indoc! {"
+            // see the docs
+            // see the fiˇle
+        "});
+        cx.simulate_mouse_move(screen_coord, None, Modifiers::secondary_key());
+        cx.run_until_parked();
+        cx.assert_editor_text_highlights::<HoveredLinkState>(indoc! {"
+            // see the docs
+            // see «the fileˇ»
+        "});
+
+        cx.simulate_click(screen_coord, Modifiers::secondary_key());
+        cx.run_until_parked();
+        cx.update_workspace(|workspace, cx| {
+            let active_editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            let buffer = active_editor
+                .read(cx)
+                .buffer()
+                .read(cx)
+                .as_singleton()
+                .unwrap();
+            let file = buffer.read(cx).file().unwrap();
+            let file_path = file.as_local().unwrap().abs_path(cx);
+            assert_eq!(file_path.to_str().unwrap(), "/root/dir/file2.rs");
+        });
+    }
+
     #[gpui::test]
     async fn test_urls_at_beginning_of_buffer(cx: &mut gpui::TestAppContext) {
         init_test(cx, |_| {});
@@ -1,29 +1,33 @@
 use collections::{HashMap, HashSet};
-use git::diff::DiffHunkStatus;
+use git::{
+    diff::{BufferDiff, DiffHunk, DiffHunkStatus},
+    repository::DiffBase,
+};
 use gpui::{
     Action, AppContext, Corner, CursorStyle, Hsla, Model, MouseButton, Subscription, Task, View,
 };
-use language::{Buffer, BufferId, Point};
+use language::{Buffer, BufferId, LineEnding, Point};
 use multi_buffer::{
     Anchor, AnchorRangeExt, ExcerptRange, MultiBuffer, MultiBufferDiffHunk, MultiBufferRow,
     MultiBufferSnapshot, ToOffset, ToPoint,
 };
-use project::buffer_store::BufferChangeSet;
+use project::{buffer_store::BufferChangeSet, Project};
 use std::{ops::Range, sync::Arc};
 use sum_tree::TreeMap;
-use text::OffsetRangeExt;
+use text::{Bias, OffsetRangeExt};
 use ui::{
     prelude::*, ActiveTheme, ContextMenu, IconButtonShape, InteractiveElement, IntoElement,
     ParentElement, PopoverMenu, Styled, Tooltip, ViewContext, VisualContext,
 };
 use util::RangeExt;
-use workspace::Item;
+use workspace::{notifications::NotifyTaskExt, Item};
 
 use crate::{
     editor_settings::CurrentLineHighlight, hunk_status, hunks_for_selections, ApplyAllDiffHunks,
     ApplyDiffHunk, BlockPlacement, BlockProperties, BlockStyle, CustomBlockId, DiffRowHighlight,
     DisplayRow, DisplaySnapshot, Editor, EditorElement, ExpandAllHunkDiffs, GoToHunk, GoToPrevHunk,
-    RevertFile, RevertSelectedHunks, ToDisplayPoint, ToggleHunkDiff,
+    RevertFile, RevertSelectedHunks, StageHunk, StageSelectedLines, ToDisplayPoint, ToggleHunkDiff,
+    UnstageHunk,
 };
 
 #[derive(Debug, Clone)]
@@ -201,6 +205,16 @@ impl Editor {
         self.diff_map.expand_all = true;
     }
 
+    /// Displays the hunks of the given change set, for buffers whose diff base isn't
+    /// tracked by the project, such as the contents of a commit.
+    pub fn add_change_set(
+        &mut self,
+        change_set: Model<BufferChangeSet>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.diff_map.add_change_set(change_set, cx);
+    }
+
     pub(super) fn toggle_hovered_hunk(
         &mut self,
         hovered_hunk: &HoveredHunk,
@@ -521,6 +535,244 @@ impl Editor {
         }
     }
 
+    pub(crate) fn stage_hunks(&mut self, _: &StageHunk, cx: &mut ViewContext<Self>) {
+        self.stage_selected_rows(false, cx)
+            .detach_and_notify_err(cx);
+    }
+
+    pub(crate) fn stage_selected_lines(
+        &mut self,
+        _: &StageSelectedLines,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.stage_selected_rows(true, cx).detach_and_notify_err(cx);
+    }
+
+    /// Writes the unstaged hunks touched by the selections to the index.
+    ///
+    /// With `only_selected_lines`, just the selected lines of those hunks are staged. See
+    /// [`stage_hunk_rows`] for how the removed lines of a hunk are matched to its selected rows.
+    fn stage_selected_rows(
+        &mut self,
+        only_selected_lines: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<anyhow::Result<()>> {
+        let project = match self.project_for_staging(cx) {
+            Ok(Some(project)) => project,
+            Ok(None) => return Task::ready(Ok(())),
+            Err(error) => return Task::ready(Err(error)),
+        };
+
+        let mut tasks = Vec::new();
+        for (buffer, selected_rows) in self.selected_buffer_rows(cx) {
+            let Some(diff_base) = self.diff_map.diff_bases.get(&buffer.read(cx).remote_id()) else {
+                continue;
+            };
+            let change_set = diff_base.change_set.read(cx);
+            let Some(index_text) = change_set.base_text.as_ref() else {
+                continue;
+            };
+            let mut index_text = index_text.read(cx).text();
+            let buffer_snapshot = buffer.read(cx).text_snapshot();
+
+            let mut edits = Vec::new();
+            for hunk in change_set.diff_hunks_intersecting_range(
+                text::Anchor::MIN..text::Anchor::MAX,
+                &buffer_snapshot,
+            ) {
+                if !selected_rows
+                    .iter()
+                    .any(|rows| hunk_touches_rows(&hunk.row_range, rows))
+                {
+                    continue;
+                }
+                let new_text = if only_selected_lines {
+                    stage_hunk_rows(
+                        &index_text[hunk.diff_base_byte_range.clone()],
+                        &hunk.row_range,
+                        &selected_rows,
+                        &buffer_snapshot,
+                    )
+                } else {
+                    text_for_rows(&buffer_snapshot, hunk.row_range.clone())
+                };
+                edits.push((hunk.diff_base_byte_range, new_text));
+            }
+            if edits.is_empty() {
+                continue;
+            }
+
+            for (range, new_text) in edits.into_iter().rev() {
+                index_text.replace_range(range, &new_text);
+            }
+            tasks.push(project.update(cx, |project, cx| {
+                project.set_index_text(&buffer, Some(index_text), cx)
+            }));
+        }
+
+        cx.spawn(|_, _| async move {
+            futures::future::try_join_all(tasks).await?;
+            anyhow::Ok(())
+        })
+    }
+
+    pub(crate) fn unstage_hunks(&mut self, _: &UnstageHunk, cx: &mut ViewContext<Self>) {
+        self.unstage_selected_rows(cx).detach_and_notify_err(cx);
+    }
+
+    /// Restores the committed contents in the index for the staged hunks touched by the selections.
+    fn unstage_selected_rows(&mut self, cx: &mut ViewContext<Self>) -> Task<anyhow::Result<()>> {
+        let project = match self.project_for_staging(cx) {
+            Ok(Some(project)) => project,
+            Ok(None) => return Task::ready(Ok(())),
+            Err(error) => return Task::ready(Err(error)),
+        };
+
+        let mut tasks = Vec::new();
+        for (buffer, selected_rows) in self.selected_buffer_rows(cx) {
+            let Some(diff_base) = self.diff_map.diff_bases.get(&buffer.read(cx).remote_id()) else {
+                continue;
+            };
+            let change_set = diff_base.change_set.read(cx);
+            let Some(index_buffer) = change_set.base_text.as_ref() else {
+                continue;
+            };
+            let index_snapshot = index_buffer.read(cx).text_snapshot();
+            let buffer_snapshot = buffer.read(cx).text_snapshot();
+
+            // Staged hunks are found by diffing the index against HEAD, so the selected rows need
+            // to be translated from the buffer to the index first.
+            let unstaged_hunks = change_set
+                .diff_hunks_intersecting_range(
+                    text::Anchor::MIN..text::Anchor::MAX,
+                    &buffer_snapshot,
+                )
+                .collect::<Vec<_>>();
+            let index_rows = selected_rows
+                .iter()
+                .map(|rows| {
+                    buffer_row_to_base_row(rows.start, false, &unstaged_hunks, &index_snapshot)
+                        ..buffer_row_to_base_row(rows.end, true, &unstaged_hunks, &index_snapshot)
+                })
+                .collect::<Vec<_>>();
+
+            let committed_text = project.read(cx).load_committed_text(&buffer, cx);
+            let project = project.clone();
+            tasks.push(cx.spawn(|_, mut cx| async move {
+                let committed_text = committed_text.await?;
+                let is_new_file = committed_text.is_none();
+                let mut committed_text = committed_text.unwrap_or_default();
+                LineEnding::normalize(&mut committed_text);
+
+                let (committed_text, staged_diff) = cx
+                    .background_executor()
+                    .spawn({
+                        let index_snapshot = index_snapshot.clone();
+                        async move {
+                            let diff = BufferDiff::build(&committed_text, &index_snapshot).await;
+                            (committed_text, diff)
+                        }
+                    })
+                    .await;
+
+                let mut edits = Vec::new();
+                for hunk in staged_diff
+                    .hunks_intersecting_range(text::Anchor::MIN..text::Anchor::MAX, &index_snapshot)
+                {
+                    if index_rows
+                        .iter()
+                        .any(|rows| hunk_touches_rows(&hunk.row_range, rows))
+                    {
+                        edits.push((
+                            hunk.buffer_range.to_offset(&index_snapshot),
+                            &committed_text[hunk.diff_base_byte_range],
+                        ));
+                    }
+                }
+                if edits.is_empty() {
+                    return Ok(());
+                }
+
+                let mut index_text = index_snapshot.text();
+                for (range, committed_text) in edits.into_iter().rev() {
+                    index_text.replace_range(range, committed_text);
+                }
+                let index_text = if is_new_file && index_text.is_empty() {
+                    None
+                } else {
+                    Some(index_text)
+                };
+                project
+                    .update(&mut cx, |project, cx| {
+                        project.set_index_text(&buffer, index_text, cx)
+                    })?
+                    .await
+            }));
+        }
+
+        cx.spawn(|_, _| async move {
+            futures::future::try_join_all(tasks).await?;
+            anyhow::Ok(())
+        })
+    }
+
+    /// Returns the project the displayed hunks can be staged in, failing when buffers aren't
+    /// diffed against the index.
+    fn project_for_staging(&self, cx: &AppContext) -> anyhow::Result<Option<Model<Project>>> {
+        let Some(project) = self.project.clone() else {
+            return Ok(None);
+        };
+        if *project.read(cx).diff_base(cx) != DiffBase::Index {
+            anyhow::bail!("Hunks can only be staged while diffing against the index");
+        }
+        Ok(Some(project))
+    }
+
+    /// Returns the rows covered by the selections in each buffer, with overlapping rows merged.
+    fn selected_buffer_rows(
+        &self,
+        cx: &mut ViewContext<Self>,
+    ) -> Vec<(Model<Buffer>, Vec<Range<u32>>)> {
+        let mut rows_by_buffer = HashMap::<BufferId, (Model<Buffer>, Vec<Range<u32>>)>::default();
+        for selection in self.selections.all::<Point>(cx) {
+            for (buffer, range, _) in self
+                .buffer
+                .read(cx)
+                .range_to_buffer_ranges(selection.range(), cx)
+            {
+                let snapshot = buffer.read(cx);
+                let start = snapshot.offset_to_point(range.start);
+                let end = snapshot.offset_to_point(range.end);
+                // A selection that ends at the start of a line doesn't include that line.
+                let end_row = if end.column == 0 && end.row > start.row {
+                    end.row
+                } else {
+                    end.row + 1
+                };
+                rows_by_buffer
+                    .entry(snapshot.remote_id())
+                    .or_insert_with(|| (buffer.clone(), Vec::new()))
+                    .1
+                    .push(start.row..end_row);
+            }
+        }
+
+        rows_by_buffer
+            .into_values()
+            .map(|(buffer, mut rows)| {
+                rows.sort_unstable_by_key(|rows| rows.start);
+                let mut merged_rows: Vec<Range<u32>> = Vec::new();
+                for rows in rows {
+                    match merged_rows.last_mut() {
+                        Some(last) if last.end >= rows.start => last.end = last.end.max(rows.end),
+                        _ => merged_rows.push(rows),
+                    }
+                }
+                (buffer, merged_rows)
+            })
+            .collect()
+    }
+
     fn has_multiple_hunks(&self, cx: &AppContext) -> bool {
         let snapshot = self.buffer.read(cx).snapshot(cx);
         let mut hunks = self.diff_map.snapshot.diff_hunks(&snapshot);
@@ -1118,6 +1370,91 @@ pub(crate) fn to_diff_hunk(
     })
 }
 
+/// Whether a hunk is affected by the given rows. As with reverting, removed hunks are also
+/// affected by the rows just above and below them.
+fn hunk_touches_rows(hunk_rows: &Range<u32>, rows: &Range<u32>) -> bool {
+    if hunk_rows.is_empty() {
+        rows.start <= hunk_rows.start && hunk_rows.start <= rows.end
+    } else {
+        hunk_rows.overlaps(rows)
+    }
+}
+
+/// Builds the index text for a hunk when staging only some of its rows. The hunk's removed
+/// lines are paired with its added rows in order: a selected row replaces the removed line it's
+/// paired with, while unselected rows leave that line in the index. Removed lines past the last
+/// added row are staged along with the hunk's last row, or with any selection that touches a
+/// hunk that only removes lines.
+fn stage_hunk_rows(
+    base_text: &str,
+    hunk_rows: &Range<u32>,
+    selected_rows: &[Range<u32>],
+    buffer: &text::BufferSnapshot,
+) -> String {
+    let is_selected = |row: u32| selected_rows.iter().any(|rows| rows.contains(&row));
+    let base_lines = base_text.split_inclusive('\n').collect::<Vec<_>>();
+    let added_rows = hunk_rows.len();
+    let last_row_selected = hunk_rows.is_empty() || is_selected(hunk_rows.end - 1);
+
+    let mut new_text = String::new();
+    for ix in 0..base_lines.len().max(added_rows) {
+        let selected = if ix < added_rows {
+            is_selected(hunk_rows.start + ix as u32)
+        } else {
+            last_row_selected
+        };
+        if let Some(base_line) = base_lines.get(ix).filter(|_| !selected) {
+            new_text.push_str(base_line);
+        }
+        if ix < added_rows && selected {
+            // a kept line at the end of a file without a trailing newline.
+            if !new_text.is_empty() && !new_text.ends_with('\n') {
+                new_text.push('\n');
+            }
+            let row = hunk_rows.start + ix as u32;
+            new_text.push_str(&text_for_rows(buffer, row..row + 1));
+        }
+    }
+    new_text
+}
+
+fn text_for_rows(buffer: &text::BufferSnapshot, rows: Range<u32>) -> String {
+    let start = buffer.clip_point(Point::new(rows.start, 0), Bias::Left);
+    let end = buffer.clip_point(Point::new(rows.end, 0), Bias::Left);
+    buffer.text_for_range(start..end).collect()
+}
+
+/// Maps a buffer row to the corresponding row of the diff base. Rows inside a hunk map to the
+/// start or, with `prefer_end`, the end of the hunk's base rows.
+fn buffer_row_to_base_row(
+    row: u32,
+    prefer_end: bool,
+    hunks: &[DiffHunk],
+    base: &text::BufferSnapshot,
+) -> u32 {
+    let mut base_row = row;
+    for hunk in hunks {
+        let base_range = hunk.diff_base_byte_range.to_point(base);
+        let base_end_row = if base_range.end.column > 0 {
+            base_range.end.row + 1
+        } else {
+            base_range.end.row
+        };
+        if hunk.row_range.end <= row {
+            base_row = row - hunk.row_range.end + base_end_row;
+        } else if hunk.row_range.start < row {
+            return if prefer_end {
+                base_end_row
+            } else {
+                base_range.start.row
+            };
+        } else {
+            break;
+        }
+    }
+    base_row
+}
+
 fn added_hunk_color(cx: &AppContext) -> Hsla {
     let mut created_color = cx.theme().status().git().created;
     created_color.fade_out(0.7);
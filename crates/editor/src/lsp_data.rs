@@ -0,0 +1,205 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use collections::{HashMap, HashSet};
+use futures::future::join_all;
+use gpui::{Model, ModelContext, Task, ViewContext};
+use language::{
+    language_settings::{language_settings, LanguageSettings},
+    Buffer, BufferId,
+};
+use project::Project;
+use util::ResultExt;
+
+use crate::{
+    code_lens::CodeLensState, document_links::DocumentLinksState,
+    folding_ranges::FoldingRangesState, semantic_tokens::SemanticTokensState, Editor, EditorMode,
+};
+
+/// Data that an editor fetches from the language servers for each of its buffers.
+pub(crate) trait LspData: Sized + 'static {
+    /// What the settings of a buffer enable fetching. The data is refetched when it changes.
+    type Key: Clone + PartialEq + 'static;
+    type Data: 'static;
+
+    const FETCH_DEBOUNCE: Duration;
+
+    fn fetched(editor: &mut Editor) -> &mut FetchedLspData<Self>;
+
+    /// Returns `None` if the settings of the buffer disable fetching.
+    fn key(settings: &LanguageSettings) -> Option<Self::Key>;
+
+    /// Whether the event means the language servers may now return different data,
+    /// so that the data of every buffer is refetched.
+    fn refetch_on(event: &project::Event) -> bool;
+
+    fn fetch(
+        project: &mut Project,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Project>,
+    ) -> Task<Result<Self::Data>>;
+
+    /// Adjusts the data fetched for a buffer before it replaces the previous data.
+    fn prepare(_data: &mut Self::Data, _previous: Option<&Self::Data>, _buffer: &Buffer) {}
+
+    /// Called when the data of some buffers was fetched or dropped.
+    fn updated(editor: &mut Editor, cx: &mut ViewContext<Editor>);
+}
+
+pub(crate) struct FetchedLspData<D: LspData> {
+    pub buffers: HashMap<BufferId, BufferLspData<D>>,
+    fetch_task: Option<Task<()>>,
+}
+
+impl<D: LspData> Default for FetchedLspData<D> {
+    fn default() -> Self {
+        Self {
+            buffers: HashMap::default(),
+            fetch_task: None,
+        }
+    }
+}
+
+pub(crate) struct BufferLspData<D: LspData> {
+    pub buffer: Model<Buffer>,
+    pub key: D::Key,
+    /// The buffer version the data was fetched for, or `None` if it needs refetching.
+    version: Option<clock::Global>,
+    pub data: D::Data,
+}
+
+/// Which buffers to refetch language server data for.
+pub(crate) enum LspDataRefetch<'a> {
+    /// Only the buffers edited since their data was fetched.
+    Edited,
+    /// Every buffer, even unedited ones.
+    All,
+    /// Every buffer, for the kinds of data the project event makes stale.
+    Requested(&'a project::Event),
+}
+
+impl Editor {
+    pub(crate) fn refresh_lsp_data(
+        &mut self,
+        refetch: LspDataRefetch,
+        debounce: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.refresh_lsp_data_of::<SemanticTokensState>(&refetch, debounce, cx);
+        self.refresh_lsp_data_of::<CodeLensState>(&refetch, debounce, cx);
+        self.refresh_lsp_data_of::<FoldingRangesState>(&refetch, debounce, cx);
+        self.refresh_lsp_data_of::<DocumentLinksState>(&refetch, debounce, cx);
+    }
+
+    fn refresh_lsp_data_of<D: LspData>(
+        &mut self,
+        refetch: &LspDataRefetch,
+        debounce: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if self.mode != EditorMode::Full {
+            return;
+        }
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let invalidate = match refetch {
+            LspDataRefetch::Edited => false,
+            LspDataRefetch::All => true,
+            LspDataRefetch::Requested(event) if D::refetch_on(event) => true,
+            LspDataRefetch::Requested(_) => return,
+        };
+
+        if invalidate {
+            for buffer_data in D::fetched(self).buffers.values_mut() {
+                buffer_data.version = None;
+            }
+        }
+
+        let mut enabled_buffers = HashSet::default();
+        let mut stale_buffers = Vec::new();
+        let buffers = self.buffer.read(cx).all_buffers();
+        for buffer in buffers {
+            let buffer_ref = buffer.read(cx);
+            let settings = language_settings(
+                buffer_ref.language().map(|language| language.name()),
+                buffer_ref.file(),
+                cx,
+            );
+            let Some(key) = D::key(&settings) else {
+                continue;
+            };
+            let buffer_id = buffer_ref.remote_id();
+            enabled_buffers.insert(buffer_id);
+            let version = buffer_ref.version();
+            let is_fetched = D::fetched(self)
+                .buffers
+                .get(&buffer_id)
+                .is_some_and(|fetched| {
+                    fetched.key == key && fetched.version.as_ref() == Some(&version)
+                });
+            if !is_fetched {
+                stale_buffers.push((buffer, key, version));
+            }
+        }
+
+        let fetched = D::fetched(self);
+        let previous_len = fetched.buffers.len();
+        fetched
+            .buffers
+            .retain(|buffer_id, _| enabled_buffers.contains(buffer_id));
+        if fetched.buffers.len() != previous_len {
+            D::updated(self, cx);
+        }
+        if stale_buffers.is_empty() {
+            D::fetched(self).fetch_task = None;
+            return;
+        }
+
+        let fetch_task = cx.spawn(|editor, mut cx| async move {
+            if debounce {
+                cx.background_executor().timer(D::FETCH_DEBOUNCE).await;
+            }
+            let Some(fetches) = project
+                .update(&mut cx, |project, cx| {
+                    stale_buffers
+                        .into_iter()
+                        .map(|(buffer, key, version)| {
+                            let data = D::fetch(project, &buffer, cx);
+                            async move { (buffer, key, version, data.await) }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .log_err()
+            else {
+                return;
+            };
+            let fetched = join_all(fetches).await;
+
+            editor
+                .update(&mut cx, |editor, cx| {
+                    for (buffer, key, version, data) in fetched {
+                        let Some(mut data) = data.log_err() else {
+                            continue;
+                        };
+                        let buffer_id = buffer.read(cx).remote_id();
+                        let buffers = &mut D::fetched(editor).buffers;
+                        let previous = buffers.get(&buffer_id).map(|previous| &previous.data);
+                        D::prepare(&mut data, previous, buffer.read(cx));
+                        buffers.insert(
+                            buffer_id,
+                            BufferLspData {
+                                buffer,
+                                key,
+                                version: Some(version),
+                                data,
+                            },
+                        );
+                    }
+                    D::updated(editor, cx);
+                })
+                .ok();
+        });
+        D::fetched(self).fetch_task = Some(fetch_task);
+    }
+}
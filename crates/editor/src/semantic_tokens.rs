@@ -0,0 +1,300 @@
+use std::{ops::Range, time::Duration};
+
+use anyhow::Result;
+use collections::HashMap;
+use gpui::{Model, ModelContext, Task, ViewContext};
+use language::{
+    language_settings::{LanguageSettings, SemanticTokensSetting},
+    Buffer, HighlightMap,
+};
+use project::{
+    lsp_command::{SemanticToken, SemanticTokens},
+    Project,
+};
+use theme::ActiveTheme;
+
+use crate::{
+    display_map::SemanticTokenHighlights,
+    lsp_data::{FetchedLspData, LspData},
+    Editor,
+};
+
+const FETCH_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// The semantic tokens of the buffers in an editor whose language enables them.
+#[derive(Default)]
+pub(crate) struct SemanticTokensState {
+    fetched: FetchedLspData<Self>,
+}
+
+impl LspData for SemanticTokensState {
+    type Key = SemanticTokensSetting;
+    type Data = SemanticTokens;
+
+    const FETCH_DEBOUNCE: Duration = FETCH_DEBOUNCE;
+
+    fn fetched(editor: &mut Editor) -> &mut FetchedLspData<Self> {
+        &mut editor.semantic_tokens.fetched
+    }
+
+    fn key(settings: &LanguageSettings) -> Option<Self::Key> {
+        settings
+            .semantic_tokens
+            .enabled()
+            .then_some(settings.semantic_tokens)
+    }
+
+    fn refetch_on(event: &project::Event) -> bool {
+        matches!(
+            event,
+            project::Event::RefreshSemanticTokens | project::Event::LanguageServerAdded(..)
+        )
+    }
+
+    fn fetch(
+        project: &mut Project,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Project>,
+    ) -> Task<Result<Self::Data>> {
+        project.semantic_tokens(buffer.clone(), cx)
+    }
+
+    fn updated(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+        editor.update_semantic_token_highlights(cx);
+    }
+}
+
+impl Editor {
+    /// Resolves the tokens against the current theme and passes them to the display map.
+    fn update_semantic_token_highlights(&mut self, cx: &mut ViewContext<Self>) {
+        let theme = cx.theme().syntax().clone();
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let mut highlights = SemanticTokenHighlights::default();
+        let mut highlight_ids = HashMap::default();
+        for (excerpt_id, buffer, excerpt_range) in snapshot.excerpts() {
+            let buffer_id = buffer.remote_id();
+            let Some(buffer_tokens) = self.semantic_tokens.fetched.buffers.get(&buffer_id) else {
+                continue;
+            };
+            let to_multi_buffer = |range: &Range<text::Anchor>| {
+                Some(
+                    snapshot.anchor_in_excerpt(excerpt_id, range.start)?
+                        ..snapshot.anchor_in_excerpt(excerpt_id, range.end)?,
+                )
+            };
+            let context = excerpt_range.context;
+            if buffer_tokens.key == SemanticTokensSetting::Full {
+                highlights.syntax_replaced.extend(to_multi_buffer(&context));
+            }
+
+            let tokens = &buffer_tokens.data;
+            let start_ix = tokens
+                .tokens
+                .partition_point(|token| token.range.end.cmp(&context.start, buffer).is_le());
+            for token in &tokens.tokens[start_ix..] {
+                if token.range.start.cmp(&context.end, buffer).is_ge() {
+                    break;
+                }
+                let highlight_id = *highlight_ids
+                    .entry((buffer_id, token.token_type, token.token_modifiers))
+                    .or_insert_with(|| {
+                        HighlightMap::new(&[capture_name(tokens, token)], &theme).get(0)
+                    });
+                if highlight_id.is_default() {
+                    continue;
+                }
+                if let Some(range) = to_multi_buffer(&token.range) {
+                    highlights.tokens.push((range, highlight_id));
+                }
+            }
+        }
+
+        self.display_map.update(cx, |display_map, _| {
+            display_map.set_semantic_token_highlights(highlights)
+        });
+        cx.notify();
+    }
+}
+
+/// Maps a semantic token to the capture name a highlights query would give it, so that
+/// themes style both the same way. Unknown token types are used as capture names as-is.
+fn capture_name<'a>(tokens: &'a SemanticTokens, token: &SemanticToken) -> &'a str {
+    match tokens.token_type(token).unwrap_or_default() {
+        "type" | "class" | "struct" | "interface" | "typeParameter" => "type",
+        "enumMember" => "variant",
+        "parameter" => "variable.parameter",
+        "event" => "property",
+        "method" => "function.method",
+        "macro" => "function.special",
+        "modifier" => "keyword",
+        "decorator" => "attribute",
+        "regexp" => "string.regex",
+        "comment"
+            if tokens
+                .token_modifiers(token)
+                .any(|modifier| modifier == "documentation") =>
+        {
+            "comment.doc"
+        }
+        token_type => token_type,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::StreamExt as _;
+    use parking_lot::Mutex;
+
+    use super::*;
+    use crate::{
+        display_map::HighlightStyles, editor_tests::init_test, lsp_data::LspDataRefetch,
+        test::editor_lsp_test_context::EditorLspTestContext, DisplayRow, RowExt as _,
+    };
+
+    #[gpui::test]
+    async fn test_semantic_tokens(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |settings| {
+            settings.defaults.semantic_tokens = Some(SemanticTokensSetting::Full)
+        });
+        let mut cx = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                semantic_tokens_provider: Some(
+                    lsp::SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        lsp::SemanticTokensOptions {
+                            legend: lsp::SemanticTokensLegend {
+                                token_types: vec!["function".into(), "type".into()],
+                                token_modifiers: Vec::new(),
+                            },
+                            full: Some(lsp::SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                            ..Default::default()
+                        },
+                    ),
+                ),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+
+        fn token(
+            delta_line: u32,
+            delta_start: u32,
+            length: u32,
+            token_type: u32,
+        ) -> lsp::SemanticToken {
+            lsp::SemanticToken {
+                delta_line,
+                delta_start,
+                length,
+                token_type,
+                token_modifiers_bitset: 0,
+            }
+        }
+        let mut full_requests = cx.handle_request::<lsp::request::SemanticTokensFullRequest, _, _>(
+            |_, _, _| async move {
+                Ok(Some(lsp::SemanticTokensResult::Tokens(
+                    lsp::SemanticTokens {
+                        result_id: Some("1".to_string()),
+                        data: vec![token(0, 3, 4, 0), token(1, 7, 3, 1)],
+                    },
+                )))
+            },
+        );
+        let previous_result_ids = Arc::new(Mutex::new(Vec::new()));
+        let mut delta_requests = cx
+            .handle_request::<lsp::request::SemanticTokensFullDeltaRequest, _, _>({
+                let previous_result_ids = previous_result_ids.clone();
+                move |_, params, _| {
+                    // The first delta adds a token for the function typed after the initial
+                    // response, the ones after that have nothing to add.
+                    let edits = if params.previous_result_id == "1" {
+                        vec![lsp::SemanticTokensEdit {
+                            start: 10,
+                            delete_count: 0,
+                            data: Some(vec![token(1, 3, 3, 0)]),
+                        }]
+                    } else {
+                        Vec::new()
+                    };
+                    let mut previous_result_ids = previous_result_ids.lock();
+                    previous_result_ids.push(params.previous_result_id);
+                    let result_id = (previous_result_ids.len() + 1).to_string();
+                    async move {
+                        Ok(Some(lsp::SemanticTokensFullDeltaResult::TokensDelta(
+                            lsp::SemanticTokensDelta {
+                                result_id: Some(result_id),
+                                edits,
+                            },
+                        )))
+                    }
+                }
+            });
+
+        cx.set_state("fn main() {}\nstruct Foo;\nˇ");
+        cx.executor().advance_clock(FETCH_DEBOUNCE);
+        full_requests.next().await;
+        cx.run_until_parked();
+        assert_eq!(
+            highlighted_text(&mut cx),
+            ["main: function", "Foo: type"],
+            "Tokens should replace tree-sitter highlights"
+        );
+
+        // After an edit, only the changes since the previous response are requested.
+        cx.update_editor(|editor, cx| editor.handle_input("fn bar() {}", cx));
+        cx.executor().advance_clock(FETCH_DEBOUNCE);
+        delta_requests.next().await;
+        cx.run_until_parked();
+        assert_eq!(*previous_result_ids.lock(), ["1"]);
+        assert_eq!(
+            highlighted_text(&mut cx),
+            ["main: function", "Foo: type", "bar: function"]
+        );
+
+        // Refreshing without edits doesn't fetch the tokens again, unless the server asks for it.
+        cx.update_editor(|editor, cx| editor.refresh_lsp_data(LspDataRefetch::Edited, false, cx));
+        cx.run_until_parked();
+        assert!(delta_requests.try_next().is_err());
+        cx.lsp
+            .request::<lsp::request::SemanticTokensRefresh>(())
+            .await
+            .expect("semantic tokens refresh request failed");
+        cx.executor().advance_clock(FETCH_DEBOUNCE);
+        delta_requests.next().await;
+        cx.run_until_parked();
+        assert_eq!(*previous_result_ids.lock(), ["1", "2"]);
+        assert!(full_requests.try_next().is_err());
+        assert_eq!(
+            highlighted_text(&mut cx),
+            ["main: function", "Foo: type", "bar: function"]
+        );
+    }
+
+    /// Returns the highlighted text, each run followed by the name of its highlight.
+    fn highlighted_text(cx: &mut EditorLspTestContext) -> Vec<String> {
+        cx.update_editor(|editor, cx| {
+            let theme = cx.theme().syntax().clone();
+            let snapshot = editor.snapshot(cx);
+            let rows = DisplayRow(0)..snapshot.max_point().row().next_row();
+            let mut runs = Vec::<(String, &str)>::new();
+            let mut previous_highlight = None;
+            for chunk in snapshot.chunks(rows, true, HighlightStyles::default()) {
+                let highlight = chunk.syntax_highlight_id.and_then(|id| id.name(&theme));
+                if let Some(highlight) = highlight {
+                    match runs.last_mut() {
+                        Some((text, _)) if previous_highlight == Some(highlight) => {
+                            text.push_str(chunk.text)
+                        }
+                        _ => runs.push((chunk.text.to_string(), highlight)),
+                    }
+                }
+                previous_highlight = highlight;
+            }
+            runs.into_iter()
+                .map(|(text, highlight)| format!("{text}: {highlight}"))
+                .collect()
+        })
+    }
+}
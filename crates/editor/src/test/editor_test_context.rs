@@ -288,6 +288,30 @@ impl EditorTestContext {
         self.cx.run_until_parked();
     }
 
+    pub fn set_head_text(&mut self, head_text: &str) {
+        self.cx.run_until_parked();
+        let fs = self
+            .update_editor(|editor, cx| editor.project.as_ref().unwrap().read(cx).fs().as_fake());
+        let path = self.update_buffer(|buffer, _| buffer.file().unwrap().path().clone());
+        fs.set_head_for_repo(
+            &Self::root_path().join(".git"),
+            &[(path.as_ref(), head_text.to_string())],
+        );
+        self.cx.run_until_parked();
+    }
+
+    pub fn index_text(&mut self) -> Option<String> {
+        self.cx.run_until_parked();
+        let fs = self
+            .update_editor(|editor, cx| editor.project.as_ref().unwrap().read(cx).fs().as_fake());
+        let path = self.update_buffer(|buffer, _| buffer.file().unwrap().path().clone());
+        let mut index_text = None;
+        fs.with_git_state(&Self::root_path().join(".git"), false, |state| {
+            index_text = state.index_contents.get(path.as_ref()).cloned();
+        });
+        index_text
+    }
+
     /// Change the editor's text and selections using a string containing
     /// embedded range markers that represent the ranges and directions of
     /// each selection.
@@ -43,7 +43,7 @@ use util::ResultExt;
 #[cfg(any(test, feature = "test-support"))]
 use collections::{btree_map, BTreeMap};
 #[cfg(any(test, feature = "test-support"))]
-use git::repository::{FakeGitRepositoryState, GitFileStatus};
+use git::repository::{FakeGitRepositoryState, FakeWorkingCopy, GitFileStatus};
 #[cfg(any(test, feature = "test-support"))]
 use parking_lot::Mutex;
 #[cfg(any(test, feature = "test-support"))]
@@ -1248,6 +1248,27 @@ impl FakeFs {
         .boxed()
     }
 
+    fn new_git_repo_state(
+        &self,
+        dot_git: &Path,
+        git_event_tx: smol::channel::Sender<PathBuf>,
+    ) -> Arc<Mutex<FakeGitRepositoryState>> {
+        let mut repo_state = FakeGitRepositoryState::new(dot_git.to_path_buf(), git_event_tx);
+        let this = self.this.clone();
+        let work_directory = dot_git.parent().unwrap_or(dot_git).to_path_buf();
+        repo_state.working_copy = Some(FakeWorkingCopy::new(move |path| {
+            let this = this.upgrade()?;
+            let state = this.state.lock();
+            let entry = state.read_path(&work_directory.join(path)).ok()?;
+            let entry = entry.lock();
+            match &*entry {
+                FakeFsEntry::File { content, .. } => String::from_utf8(content.clone()).ok(),
+                _ => None,
+            }
+        }));
+        Arc::new(Mutex::new(repo_state))
+    }
+
     pub fn with_git_state<F>(&self, dot_git: &Path, emit_git_event: bool, f: F)
     where
         F: FnOnce(&mut FakeGitRepositoryState),
@@ -1258,10 +1279,7 @@ impl FakeFs {
 
         if let FakeFsEntry::Dir { git_repo_state, .. } = &mut *entry {
             let repo_state = git_repo_state.get_or_insert_with(|| {
-                Arc::new(Mutex::new(FakeGitRepositoryState::new(
-                    dot_git.to_path_buf(),
-                    state.git_event_tx.clone(),
-                )))
+                self.new_git_repo_state(dot_git, state.git_event_tx.clone())
             });
             let mut repo_state = repo_state.lock();
 
@@ -1307,6 +1325,17 @@ impl FakeFs {
         });
     }
 
+    pub fn set_head_for_repo(&self, dot_git: &Path, head_state: &[(&Path, String)]) {
+        self.with_git_state(dot_git, true, |state| {
+            state.head_contents.clear();
+            state.head_contents.extend(
+                head_state
+                    .iter()
+                    .map(|(path, content)| (path.to_path_buf(), content.clone())),
+            );
+        });
+    }
+
     pub fn set_blame_for_repo(&self, dot_git: &Path, blames: Vec<(&Path, git::blame::Blame)>) {
         self.with_git_state(dot_git, true, |state| {
             state.blames.clear();
@@ -1958,10 +1987,7 @@ impl Fs for FakeFs {
         if let FakeFsEntry::Dir { git_repo_state, .. } = &mut *entry {
             let state = git_repo_state
                 .get_or_insert_with(|| {
-                    Arc::new(Mutex::new(FakeGitRepositoryState::new(
-                        abs_dot_git.to_path_buf(),
-                        state.git_event_tx.clone(),
-                    )))
+                    self.new_git_repo_state(abs_dot_git, state.git_event_tx.clone())
                 })
                 .clone();
             Some(git::repository::FakeGitRepository::open(state))
@@ -0,0 +1,242 @@
+use std::ops::Range;
+
+const OURS_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR_MARKER: &str = "=======";
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+/// A region of text delimited by merge conflict markers, as written by git for both merges and
+/// rebases. All ranges are byte offsets into the parsed text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    /// The whole region, from the start of the `<<<<<<<` line to the end of the `>>>>>>>` line.
+    pub range: Range<usize>,
+    /// The lines between the `<<<<<<<` marker and the next marker.
+    pub ours: Range<usize>,
+    /// The lines between the `|||||||` and `=======` markers, only present for diff3-style conflicts.
+    pub base: Option<Range<usize>>,
+    /// The lines between the `=======` and `>>>>>>>` markers.
+    pub theirs: Range<usize>,
+    /// The text after the `<<<<<<<` marker, such as `HEAD`.
+    pub ours_label: String,
+    /// The text after the `>>>>>>>` marker, such as a branch name or a commit being rebased.
+    pub theirs_label: String,
+}
+
+enum Section {
+    Ours,
+    Base,
+    Theirs,
+}
+
+struct PartialConflict<'a> {
+    start: usize,
+    ours_label: &'a str,
+    section: Section,
+    ours: Range<usize>,
+    base: Option<Range<usize>>,
+    theirs: Range<usize>,
+}
+
+/// Finds all well-formed conflict regions in the given text. Unterminated or malformed regions are
+/// skipped.
+pub fn parse_conflicts(text: &str) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let mut current: Option<PartialConflict> = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let line_start = offset;
+        let line_end = offset + line.len();
+        offset = line_end;
+
+        if let Some(label) = marker_label(line, OURS_MARKER) {
+            current = Some(PartialConflict {
+                start: line_start,
+                ours_label: label,
+                section: Section::Ours,
+                ours: line_end..line_end,
+                base: None,
+                theirs: line_end..line_end,
+            });
+            continue;
+        }
+        let Some(conflict) = current.as_mut() else {
+            continue;
+        };
+
+        match conflict.section {
+            Section::Ours | Section::Base if marker_label(line, BASE_MARKER).is_some() => {
+                if matches!(conflict.section, Section::Base) {
+                    current = None;
+                    continue;
+                }
+                conflict.ours.end = line_start;
+                conflict.base = Some(line_end..line_end);
+                conflict.section = Section::Base;
+            }
+            Section::Ours | Section::Base if is_separator(line) => {
+                match &mut conflict.base {
+                    Some(base) if matches!(conflict.section, Section::Base) => {
+                        base.end = line_start
+                    }
+                    _ => conflict.ours.end = line_start,
+                }
+                conflict.theirs = line_end..line_end;
+                conflict.section = Section::Theirs;
+            }
+            Section::Theirs => {
+                if let Some(label) = marker_label(line, THEIRS_MARKER) {
+                    conflicts.push(Conflict {
+                        range: conflict.start..line_end,
+                        ours: conflict.ours.clone(),
+                        base: conflict.base.clone(),
+                        theirs: conflict.theirs.start..line_start,
+                        ours_label: conflict.ours_label.to_string(),
+                        theirs_label: label.to_string(),
+                    });
+                    current = None;
+                } else if is_separator(line) || marker_label(line, BASE_MARKER).is_some() {
+                    current = None;
+                }
+            }
+            _ => {}
+        }
+    }
+    conflicts
+}
+
+/// Returns whether any line of the given text starts with a conflict marker.
+pub fn has_conflict_marker(text: &str) -> bool {
+    text.split_inclusive('\n').any(|line| {
+        [OURS_MARKER, BASE_MARKER, THEIRS_MARKER]
+            .iter()
+            .any(|marker| marker_label(line, marker).is_some())
+            || is_separator(line)
+    })
+}
+
+/// Returns the label following a conflict marker, if the line starts with exactly that marker.
+fn marker_label<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(marker)?;
+    if rest.is_empty() || rest.starts_with([' ', '\t', '\r', '\n']) {
+        Some(rest.trim())
+    } else {
+        None
+    }
+}
+
+fn is_separator(line: &str) -> bool {
+    marker_label(line, SEPARATOR_MARKER).is_some_and(str::is_empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unindent::Unindent as _;
+
+    fn sections(text: &str, conflict: &Conflict) -> (String, Option<String>, String) {
+        (
+            text[conflict.ours.clone()].to_string(),
+            conflict.base.clone().map(|base| text[base].to_string()),
+            text[conflict.theirs.clone()].to_string(),
+        )
+    }
+
+    #[test]
+    fn test_parse_merge_conflict() {
+        let text = "
+            one
+            <<<<<<< HEAD
+            two
+            =======
+            TWO
+            2
+            >>>>>>> feature
+            three
+        "
+        .unindent();
+
+        let conflicts = parse_conflicts(&text);
+        assert_eq!(conflicts.len(), 1);
+        let conflict = &conflicts[0];
+        assert_eq!(conflict.ours_label, "HEAD");
+        assert_eq!(conflict.theirs_label, "feature");
+        assert_eq!(
+            &text[conflict.range.clone()],
+            "<<<<<<< HEAD\ntwo\n=======\nTWO\n2\n>>>>>>> feature\n"
+        );
+        assert_eq!(
+            sections(&text, conflict),
+            ("two\n".into(), None, "TWO\n2\n".into())
+        );
+    }
+
+    #[test]
+    fn test_parse_diff3_rebase_conflict() {
+        let text = "
+            <<<<<<< HEAD
+            =======
+            added
+            >>>>>>> 4b2c8f1 (Add a line)
+            between
+            <<<<<<< HEAD
+            upstream
+            ||||||| parent of 4b2c8f1 (Change a line)
+            original
+            =======
+            mine
+            >>>>>>> 4b2c8f1 (Change a line)"
+            .unindent();
+
+        let conflicts = parse_conflicts(&text);
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(
+            sections(&text, &conflicts[0]),
+            ("".into(), None, "added\n".into())
+        );
+        assert_eq!(conflicts[1].theirs_label, "4b2c8f1 (Change a line)");
+        assert_eq!(
+            sections(&text, &conflicts[1]),
+            (
+                "upstream\n".into(),
+                Some("original\n".into()),
+                "mine\n".into()
+            )
+        );
+        assert_eq!(conflicts[1].range.end, text.len());
+    }
+
+    #[test]
+    fn test_parse_malformed_conflicts() {
+        let text = "
+            <<<<<<< HEAD
+            unterminated
+            =======
+            <<<<<<< HEAD
+            a
+            ========
+            still ours
+            =======
+            b
+            >>>>>>> other
+            <<<<<<<< not a marker
+            =======
+            >>>>>>> dangling
+        "
+        .unindent();
+
+        let conflicts = parse_conflicts(&text);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            sections(&text, &conflicts[0]),
+            ("a\n========\nstill ours\n".into(), None, "b\n".into())
+        );
+    }
+
+    #[test]
+    fn test_has_conflict_marker() {
+        assert!(has_conflict_marker("a\n=======\nb"));
+        assert!(has_conflict_marker(">>>>>>> branch"));
+        assert!(!has_conflict_marker("a <<<<<<< b\n========\n<<<<<<<< c\n"));
+    }
+}
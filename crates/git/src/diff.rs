@@ -83,7 +83,13 @@ impl BufferDiff {
         if let Some(patch) = patch {
             let mut divergence = 0;
             for hunk_index in 0..patch.num_hunks() {
-                let hunk = Self::process_patch_hunk(&patch, hunk_index, buffer, &mut divergence);
+                let hunk = Self::process_patch_hunk(
+                    &patch,
+                    hunk_index,
+                    diff_base,
+                    buffer,
+                    &mut divergence,
+                );
                 tree.push(hunk, buffer);
             }
         }
@@ -222,6 +228,7 @@ impl BufferDiff {
     fn process_patch_hunk(
         patch: &GitPatch<'_>,
         hunk_index: usize,
+        diff_base: &str,
         buffer: &text::BufferSnapshot,
         buffer_row_divergence: &mut i64,
     ) -> InternalDiffHunk {
@@ -273,8 +280,18 @@ impl BufferDiff {
             row..row
         });
 
-        //unwrap_or addition without deletion
-        let diff_base_byte_range = diff_base_byte_range.unwrap_or(0..0);
+        //unwrap_or addition without deletion, which is inserted after the hunk's old start line
+        let diff_base_byte_range = diff_base_byte_range.unwrap_or_else(|| {
+            let (hunk, _) = patch.hunk(hunk_index).unwrap();
+            let offset = match hunk.old_start() as usize {
+                0 => 0,
+                row => diff_base
+                    .match_indices('\n')
+                    .nth(row - 1)
+                    .map_or(diff_base.len(), |(ix, _)| ix + 1),
+            };
+            offset..offset
+        });
 
         let start = Point::new(buffer_row_range.start, 0);
         let end = Point::new(buffer_row_range.end, 0);
@@ -367,6 +384,35 @@ mod tests {
         assert_hunks(diff.hunks(&buffer), &buffer, &diff_base, &[]);
     }
 
+    #[test]
+    fn test_buffer_diff_insertion_base_range() {
+        let diff_base = "
+            one
+            two
+        "
+        .unindent();
+        let diff_base_rope = Rope::from(diff_base.clone());
+
+        let buffer_text = "
+            zero
+            one
+            one and a half
+            two
+            three
+        "
+        .unindent();
+
+        let buffer = Buffer::new(0, BufferId::new(1).unwrap(), buffer_text);
+        let mut diff = BufferDiff::new(&buffer);
+        smol::block_on(diff.update(&diff_base_rope, &buffer));
+        assert_eq!(
+            diff.hunks(&buffer)
+                .map(|hunk| (hunk.row_range, hunk.diff_base_byte_range))
+                .collect::<Vec<_>>(),
+            &[(0..1, 0..0), (2..3, 4..4), (4..5, 8..8)]
+        );
+    }
+
     #[test]
     fn test_buffer_diff_range() {
         let diff_base = "
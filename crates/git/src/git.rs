@@ -1,7 +1,9 @@
 pub mod blame;
 pub mod commit;
+pub mod conflict;
 pub mod diff;
 mod hosting_provider;
+pub mod log;
 mod remote;
 pub mod repository;
 pub mod status;
@@ -0,0 +1,238 @@
+use crate::repository::RepoPath;
+use crate::{parse_git_remote_url, BuildCommitPermalinkParams, GitHostingProviderRegistry, Oid};
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::sync::Arc;
+use time::{OffsetDateTime, UtcOffset};
+use url::Url;
+
+/// A commit returned by [`GitRepository::log`](crate::repository::GitRepository::log).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogEntry {
+    pub sha: Oid,
+    pub parents: Vec<Oid>,
+    pub author_name: String,
+    pub author_email: String,
+    /// The author timestamp, in seconds since the Unix epoch.
+    pub author_time: i64,
+    /// The author's offset from UTC, in minutes.
+    pub author_offset_minutes: i32,
+    pub summary: String,
+    pub message: String,
+    /// The files changed relative to the commit's first parent.
+    pub changed_files: Vec<ChangedFile>,
+}
+
+impl LogEntry {
+    pub fn author_offset_date_time(&self) -> Result<OffsetDateTime> {
+        let timestamp = OffsetDateTime::from_unix_timestamp(self.author_time)?;
+        let offset = UtcOffset::from_whole_seconds(self.author_offset_minutes * 60)?;
+        Ok(timestamp.to_offset(offset))
+    }
+
+    /// Returns whether this commit touched the given path, or any path beneath it.
+    pub fn touches(&self, path: &Path) -> bool {
+        self.changed_files.iter().any(|file| {
+            file.path.starts_with(path)
+                || file
+                    .old_path
+                    .as_ref()
+                    .map_or(false, |old_path| old_path.starts_with(path))
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangedFile {
+    pub path: RepoPath,
+    /// The path the file had before the commit, if it was renamed.
+    pub old_path: Option<RepoPath>,
+    pub kind: ChangeKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+/// The contents of a file before and after a commit. Texts are `None` when the file
+/// didn't exist on that side of the commit, or isn't valid UTF-8.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommitFileDiff {
+    pub path: RepoPath,
+    pub kind: ChangeKind,
+    pub old_text: Option<String>,
+    pub new_text: Option<String>,
+}
+
+/// Builds a link to the given commit on the hosting provider the remote points to.
+pub fn commit_permalink(
+    provider_registry: Arc<GitHostingProviderRegistry>,
+    remote_url: &str,
+    sha: Oid,
+) -> Option<Url> {
+    let (provider, remote) = parse_git_remote_url(provider_registry, remote_url)?;
+    Some(provider.build_commit_permalink(
+        &remote,
+        BuildCommitPermalinkParams {
+            sha: &sha.to_string(),
+        },
+    ))
+}
+
+pub(crate) fn read_log(
+    repo: &git2::Repository,
+    path: Option<&RepoPath>,
+    range: Option<&str>,
+    limit: usize,
+) -> Result<Vec<LogEntry>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    match range {
+        Some(range) if range.contains("..") => revwalk
+            .push_range(range)
+            .with_context(|| format!("invalid revision range {range:?}"))?,
+        Some(revision) => {
+            let commit = repo
+                .revparse_single(revision)
+                .and_then(|object| object.peel_to_commit())
+                .with_context(|| format!("invalid revision {revision:?}"))?;
+            revwalk.push(commit.id())?;
+        }
+        None => match revwalk.push_head() {
+            Ok(()) => {}
+            Err(error) if error.code() == git2::ErrorCode::UnbornBranch => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        },
+    }
+
+    let mut entries = Vec::new();
+    for oid in revwalk {
+        if entries.len() >= limit {
+            break;
+        }
+        let commit = repo.find_commit(oid?)?;
+        if let Some(path) = path {
+            // Diffing just the path first avoids diffing whole trees for commits that don't touch it.
+            if diff_to_first_parent(repo, &commit, Some(path))?
+                .deltas()
+                .len()
+                == 0
+            {
+                continue;
+            }
+        }
+        let mut diff = diff_to_first_parent(repo, &commit, None)?;
+        diff.find_similar(None)?;
+
+        let changed_files = diff
+            .deltas()
+            .filter_map(|delta| {
+                let kind = change_kind(delta.status())?;
+                let old_path = delta.old_file().path().map(RepoPath::from);
+                let path = delta.new_file().path().map(RepoPath::from);
+                Some(match kind {
+                    ChangeKind::Deleted => ChangedFile {
+                        path: old_path?,
+                        old_path: None,
+                        kind,
+                    },
+                    ChangeKind::Renamed => ChangedFile {
+                        path: path?,
+                        old_path,
+                        kind,
+                    },
+                    ChangeKind::Added | ChangeKind::Modified => ChangedFile {
+                        path: path?,
+                        old_path: None,
+                        kind,
+                    },
+                })
+            })
+            .collect();
+
+        let author = commit.author();
+        entries.push(LogEntry {
+            sha: Oid(commit.id()),
+            parents: commit.parent_ids().map(Oid).collect(),
+            author_name: String::from_utf8_lossy(author.name_bytes()).into_owned(),
+            author_email: String::from_utf8_lossy(author.email_bytes()).into_owned(),
+            author_time: author.when().seconds(),
+            author_offset_minutes: author.when().offset_minutes(),
+            summary: String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default())
+                .into_owned(),
+            message: String::from_utf8_lossy(commit.message_bytes()).into_owned(),
+            changed_files,
+        });
+    }
+    Ok(entries)
+}
+
+pub(crate) fn read_commit_diff(repo: &git2::Repository, sha: Oid) -> Result<Vec<CommitFileDiff>> {
+    let commit = repo
+        .find_commit(sha.0)
+        .with_context(|| format!("commit {sha} not found"))?;
+    let mut diff = diff_to_first_parent(repo, &commit, None)?;
+    diff.find_similar(None)?;
+
+    let load_text = |file: git2::DiffFile<'_>| -> Result<Option<String>> {
+        if file.id().is_zero() {
+            return Ok(None);
+        }
+        let blob = repo.find_blob(file.id())?;
+        Ok(String::from_utf8(blob.content().to_owned()).ok())
+    };
+
+    let mut files = Vec::new();
+    for delta in diff.deltas() {
+        let Some(kind) = change_kind(delta.status()) else {
+            continue;
+        };
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .context("diff entry has no path")?;
+        files.push(CommitFileDiff {
+            path: RepoPath::from(path),
+            kind,
+            old_text: load_text(delta.old_file())?,
+            new_text: load_text(delta.new_file())?,
+        });
+    }
+    Ok(files)
+}
+
+/// Diffs a commit against its first parent, limited to `path` and the files beneath it if given.
+fn diff_to_first_parent<'a>(
+    repo: &'a git2::Repository,
+    commit: &git2::Commit,
+    path: Option<&RepoPath>,
+) -> Result<git2::Diff<'a>> {
+    let tree = commit.tree()?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let mut options = path.map(|path| {
+        let mut options = git2::DiffOptions::new();
+        options
+            .pathspec(path.as_path())
+            .disable_pathspec_match(true);
+        options
+    });
+    Ok(repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), options.as_mut())?)
+}
+
+fn change_kind(status: git2::Delta) -> Option<ChangeKind> {
+    match status {
+        git2::Delta::Added | git2::Delta::Copied => Some(ChangeKind::Added),
+        git2::Delta::Modified | git2::Delta::Typechange => Some(ChangeKind::Modified),
+        git2::Delta::Deleted => Some(ChangeKind::Deleted),
+        git2::Delta::Renamed => Some(ChangeKind::Renamed),
+        _ => None,
+    }
+}
@@ -1,4 +1,6 @@
+use crate::log::{CommitFileDiff, LogEntry};
 use crate::GitHostingProviderRegistry;
+use crate::Oid;
 use crate::{blame::Blame, status::GitStatus};
 use anyhow::{Context, Result};
 use collections::{HashMap, HashSet};
@@ -30,6 +32,13 @@ pub trait GitRepository: Send + Sync {
     /// Note that for symlink entries, this will return the contents of the symlink, not the target.
     fn load_index_text(&self, relative_file_path: &Path) -> Option<String>;
 
+    /// Loads a git repository entry's contents at HEAD.
+    fn load_committed_text(&self, path: &RepoPath) -> Option<String>;
+
+    /// Writes the given contents to the index entry for a path, leaving the working copy alone.
+    /// When `content` is `None`, the path is removed from the index.
+    fn set_index_text(&self, path: &RepoPath, content: Option<String>) -> Result<()>;
+
     /// Returns the URL of the remote with the given name.
     fn remote_url(&self, name: &str) -> Option<String>;
     fn branch_name(&self) -> Option<String>;
@@ -48,6 +57,79 @@ pub trait GitRepository: Send + Sync {
 
     /// Returns the path to the repository, typically the `.git` folder.
     fn dot_git_dir(&self) -> PathBuf;
+
+    /// Updates the index to match the working copy at the given paths.
+    /// Paths that no longer exist in the working copy are removed from the index.
+    fn stage_paths(&self, paths: &[RepoPath]) -> Result<()>;
+
+    /// Resets the index entries for the given paths to their contents at HEAD.
+    fn unstage_paths(&self, paths: &[RepoPath]) -> Result<()>;
+
+    /// Creates a commit from the index on top of HEAD.
+    /// When `amend` is true, HEAD is replaced instead.
+    fn commit(&self, message: &str, amend: bool) -> Result<()>;
+
+    /// Resets both the index and the working copy for the given paths to their contents at HEAD.
+    /// Untracked files are left in place.
+    fn discard(&self, paths: &[RepoPath]) -> Result<()>;
+
+    /// Returns up to `limit` commits, newest first, reachable from HEAD or from the given revision
+    /// or `a..b` range. When `path` is given, only commits touching it or anything beneath it are
+    /// returned.
+    fn log(
+        &self,
+        path: Option<&RepoPath>,
+        range: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<LogEntry>>;
+
+    /// Returns the contents of every file changed by a commit, relative to its first parent.
+    fn commit_diff(&self, sha: Oid) -> Result<Vec<CommitFileDiff>>;
+
+    /// Loads a file's contents at the commit the given revision resolves to.
+    fn load_text_at_revision(&self, path: &RepoPath, revision: &str) -> Result<Option<String>>;
+
+    /// Returns the best common ancestor of HEAD and the given revision.
+    fn merge_base_with_head(&self, revision: &str) -> Result<Oid>;
+}
+
+/// What buffers are compared against when computing their git diff hunks.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DiffBase {
+    /// The staged contents of each file, so that only unstaged changes are shown.
+    #[default]
+    Index,
+    /// Any revision git can resolve, such as `HEAD`, a branch, a tag or a commit SHA.
+    Revision(SharedString),
+    /// The merge base of HEAD and the given revision, which shows everything changed on the
+    /// current branch since it diverged from that revision.
+    MergeBase(SharedString),
+}
+
+impl DiffBase {
+    /// Loads the contents this base has for the given path. Files missing from a revision
+    /// are compared against empty text, so that they show up as added.
+    pub fn load_text(&self, repo: &dyn GitRepository, path: &RepoPath) -> Result<Option<String>> {
+        let text = match self {
+            DiffBase::Index => return Ok(repo.load_index_text(path)),
+            DiffBase::Revision(revision) => repo.load_text_at_revision(path, revision)?,
+            DiffBase::MergeBase(revision) => {
+                let merge_base = repo.merge_base_with_head(revision)?;
+                repo.load_text_at_revision(path, &merge_base.to_string())?
+            }
+        };
+        Ok(Some(text.unwrap_or_default()))
+    }
+}
+
+impl std::fmt::Display for DiffBase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffBase::Index => write!(f, "index"),
+            DiffBase::Revision(revision) => write!(f, "{revision}"),
+            DiffBase::MergeBase(revision) => write!(f, "merge base with {revision}"),
+        }
+    }
 }
 
 impl std::fmt::Debug for dyn GitRepository {
@@ -77,7 +159,9 @@ impl RealGitRepository {
 }
 
 // https://git-scm.com/book/en/v2/Git-Internals-Git-Objects
+const GIT_MODE_FILE: u32 = 0o100644;
 const GIT_MODE_SYMLINK: u32 = 0o120000;
+const STAGE_NORMAL: i32 = 0;
 
 impl GitRepository for RealGitRepository {
     fn reload_index(&self) {
@@ -93,7 +177,6 @@ impl GitRepository for RealGitRepository {
 
     fn load_index_text(&self, relative_file_path: &Path) -> Option<String> {
         fn logic(repo: &git2::Repository, relative_file_path: &Path) -> Result<Option<String>> {
-            const STAGE_NORMAL: i32 = 0;
             let index = repo.index()?;
 
             // This check is required because index.get_path() unwraps internally :(
@@ -115,6 +198,55 @@ impl GitRepository for RealGitRepository {
         None
     }
 
+    fn load_committed_text(&self, path: &RepoPath) -> Option<String> {
+        fn logic(repo: &git2::Repository, path: &RepoPath) -> Result<Option<String>> {
+            let Some(head) = head_commit(repo)? else {
+                return Ok(None);
+            };
+            load_text_from_commit(repo, &head, path)
+        }
+
+        match logic(&self.repository.lock(), path) {
+            Ok(value) => return value,
+            Err(err) => log::error!("Error loading committed text: {:?}", err),
+        }
+        None
+    }
+
+    fn set_index_text(&self, path: &RepoPath, content: Option<String>) -> Result<()> {
+        check_path_to_repo_path_errors(path)?;
+        let repo = self.repository.lock();
+        let mut index = repo.index()?;
+        if let Some(content) = content {
+            let mode = index
+                .get_path(path, STAGE_NORMAL)
+                .map_or(GIT_MODE_FILE, |entry| entry.mode);
+            let entry_path = path
+                .to_str()
+                .with_context(|| format!("invalid repo path {path:?}"))?
+                .replace(std::path::MAIN_SEPARATOR, "/");
+            let entry = git2::IndexEntry {
+                ctime: git2::IndexTime::new(0, 0),
+                mtime: git2::IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode,
+                uid: 0,
+                gid: 0,
+                file_size: content.len() as u32,
+                id: git2::Oid::zero(),
+                flags: 0,
+                flags_extended: 0,
+                path: entry_path.into_bytes(),
+            };
+            index.add_frombuffer(&entry, content.as_bytes())?;
+        } else {
+            index.remove_path(path)?;
+        }
+        index.write()?;
+        Ok(())
+    }
+
     fn remote_url(&self, name: &str) -> Option<String> {
         let repo = self.repository.lock();
         let remote = repo.find_remote(name).ok()?;
@@ -225,6 +357,176 @@ impl GitRepository for RealGitRepository {
             self.hosting_provider_registry.clone(),
         )
     }
+
+    fn stage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        let repo = self.repository.lock();
+        let working_directory = repo
+            .workdir()
+            .context("failed to read git work directory")?
+            .to_path_buf();
+        let mut index = repo.index()?;
+        for path in paths {
+            check_path_to_repo_path_errors(path)?;
+            if working_directory.join(path).symlink_metadata().is_ok() {
+                index.add_path(path)?;
+            } else {
+                index.remove_path(path)?;
+            }
+        }
+        index.write()?;
+        Ok(())
+    }
+
+    fn unstage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        let repo = self.repository.lock();
+        reset_index_to_head(&repo, paths)
+    }
+
+    fn commit(&self, message: &str, amend: bool) -> Result<()> {
+        if message.trim().is_empty() {
+            anyhow::bail!("commit message must not be empty");
+        }
+
+        let repo = self.repository.lock();
+        let signature = repo
+            .signature()
+            .context("failed to determine commit author, is user.name and user.email set?")?;
+        let tree = repo.find_tree(repo.index()?.write_tree()?)?;
+        let head = head_commit(&repo)?;
+        if amend {
+            let head = head.context("there is no commit to amend")?;
+            head.amend(
+                Some("HEAD"),
+                None,
+                Some(&signature),
+                None,
+                Some(message),
+                Some(&tree),
+            )?;
+        } else {
+            let unchanged = match &head {
+                Some(head) => head.tree_id() == tree.id(),
+                None => tree.is_empty(),
+            };
+            if unchanged {
+                anyhow::bail!("nothing to commit");
+            }
+            let parents = head.iter().collect::<Vec<_>>();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn discard(&self, paths: &[RepoPath]) -> Result<()> {
+        // An empty pathspec matches every file.
+        if paths.is_empty() {
+            return Ok(());
+        }
+        let repo = self.repository.lock();
+        reset_index_to_head(&repo, paths)?;
+        if head_commit(&repo)?.is_none() {
+            return Ok(());
+        }
+
+        let mut checkout = git2::build::CheckoutBuilder::new();
+        checkout.force().disable_pathspec_match(true);
+        for path in paths {
+            checkout.path(path.as_path());
+        }
+        repo.checkout_head(Some(&mut checkout))?;
+        Ok(())
+    }
+
+    fn log(
+        &self,
+        path: Option<&RepoPath>,
+        range: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<LogEntry>> {
+        let repo = self.repository.lock();
+        crate::log::read_log(&repo, path, range, limit)
+    }
+
+    fn commit_diff(&self, sha: Oid) -> Result<Vec<CommitFileDiff>> {
+        let repo = self.repository.lock();
+        crate::log::read_commit_diff(&repo, sha)
+    }
+
+    fn load_text_at_revision(&self, path: &RepoPath, revision: &str) -> Result<Option<String>> {
+        let repo = self.repository.lock();
+        let commit = resolve_commit(&repo, revision)?;
+        load_text_from_commit(&repo, &commit, path)
+    }
+
+    fn merge_base_with_head(&self, revision: &str) -> Result<Oid> {
+        let repo = self.repository.lock();
+        let head = head_commit(&repo)?.context("HEAD does not point to a commit")?;
+        let commit = resolve_commit(&repo, revision)?;
+        let merge_base = repo
+            .merge_base(head.id(), commit.id())
+            .with_context(|| format!("HEAD and {revision:?} have no common ancestor"))?;
+        Ok(Oid(merge_base))
+    }
+}
+
+fn resolve_commit<'a>(repo: &'a git2::Repository, revision: &str) -> Result<git2::Commit<'a>> {
+    repo.revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .with_context(|| format!("invalid revision {revision:?}"))
+}
+
+/// Loads a file's contents in the given commit, or `None` if it doesn't exist there or is a
+/// symlink.
+fn load_text_from_commit(
+    repo: &git2::Repository,
+    commit: &git2::Commit,
+    path: &RepoPath,
+) -> Result<Option<String>> {
+    check_path_to_repo_path_errors(path)?;
+    let entry = match commit.tree()?.get_path(path) {
+        Ok(entry) => entry,
+        Err(error) if error.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+    if entry.filemode() as u32 == GIT_MODE_SYMLINK {
+        return Ok(None);
+    }
+
+    let content = repo.find_blob(entry.id())?.content().to_owned();
+    Ok(Some(String::from_utf8(content)?))
+}
+
+/// Returns the commit HEAD points to, or `None` if no commit has been made yet.
+fn head_commit(repo: &git2::Repository) -> Result<Option<git2::Commit<'_>>> {
+    match repo.head() {
+        Ok(head) => Ok(Some(head.peel_to_commit()?)),
+        Err(error) if error.code() == git2::ErrorCode::UnbornBranch => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn reset_index_to_head(repo: &git2::Repository, paths: &[RepoPath]) -> Result<()> {
+    // An empty pathspec matches every file.
+    if paths.is_empty() {
+        return Ok(());
+    }
+    for path in paths {
+        check_path_to_repo_path_errors(path)?;
+    }
+    let head = head_commit(repo)?;
+    // Without a HEAD commit, the paths are removed from the index instead.
+    repo.reset_default(
+        head.as_ref().map(|head| head.as_object()),
+        paths.iter().map(|path| path.as_path()),
+    )?;
+    Ok(())
 }
 
 #[derive(Debug, Clone)]
@@ -232,15 +534,47 @@ pub struct FakeGitRepository {
     state: Arc<Mutex<FakeGitRepositoryState>>,
 }
 
+/// Reads files from the working copy of a [`FakeGitRepository`], so that staging them can
+/// update the index.
+#[derive(Clone)]
+pub struct FakeWorkingCopy(Arc<dyn Fn(&Path) -> Option<String> + Send + Sync>);
+
+impl FakeWorkingCopy {
+    pub fn new(load: impl Fn(&Path) -> Option<String> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(load))
+    }
+
+    fn load(&self, path: &Path) -> Option<String> {
+        (self.0)(path)
+    }
+}
+
+impl std::fmt::Debug for FakeWorkingCopy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FakeWorkingCopy")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FakeGitRepositoryState {
     pub dot_git_dir: PathBuf,
     pub event_emitter: smol::channel::Sender<PathBuf>,
     pub index_contents: HashMap<PathBuf, String>,
+    pub head_contents: HashMap<PathBuf, String>,
     pub blames: HashMap<PathBuf, Blame>,
     pub worktree_statuses: HashMap<RepoPath, GitFileStatus>,
     pub current_branch_name: Option<String>,
     pub branches: HashSet<String>,
+    pub staged_paths: HashSet<RepoPath>,
+    pub commit_messages: Vec<String>,
+    /// Commits returned by `log`, newest first.
+    pub log: Vec<LogEntry>,
+    pub commit_diffs: HashMap<Oid, Vec<CommitFileDiff>>,
+    /// File contents at revisions other than HEAD, keyed by revision.
+    pub revision_contents: HashMap<String, HashMap<PathBuf, String>>,
+    /// The merge bases of HEAD with other revisions.
+    pub merge_bases: HashMap<String, Oid>,
+    pub working_copy: Option<FakeWorkingCopy>,
 }
 
 impl FakeGitRepository {
@@ -255,10 +589,30 @@ impl FakeGitRepositoryState {
             dot_git_dir,
             event_emitter,
             index_contents: Default::default(),
+            head_contents: Default::default(),
             blames: Default::default(),
             worktree_statuses: Default::default(),
             current_branch_name: Default::default(),
             branches: Default::default(),
+            staged_paths: Default::default(),
+            commit_messages: Default::default(),
+            log: Default::default(),
+            commit_diffs: Default::default(),
+            revision_contents: Default::default(),
+            merge_bases: Default::default(),
+            working_copy: None,
+        }
+    }
+
+    fn reset_index_to_head(&mut self, path: &RepoPath) {
+        match self.head_contents.get(path.as_path()) {
+            Some(content) => {
+                self.index_contents
+                    .insert(path.to_path_buf(), content.clone());
+            }
+            None => {
+                self.index_contents.remove(path.as_path());
+            }
         }
     }
 }
@@ -271,6 +625,26 @@ impl GitRepository for FakeGitRepository {
         state.index_contents.get(path).cloned()
     }
 
+    fn load_committed_text(&self, path: &RepoPath) -> Option<String> {
+        let state = self.state.lock();
+        state.head_contents.get(path.as_path()).cloned()
+    }
+
+    fn set_index_text(&self, path: &RepoPath, content: Option<String>) -> Result<()> {
+        check_path_to_repo_path_errors(path)?;
+        let mut state = self.state.lock();
+        if let Some(content) = content {
+            state.index_contents.insert(path.to_path_buf(), content);
+        } else {
+            state.index_contents.remove(path.as_path());
+        }
+        state
+            .event_emitter
+            .try_send(state.dot_git_dir.clone())
+            .expect("Dropped repo change event");
+        Ok(())
+    }
+
     fn remote_url(&self, _name: &str) -> Option<String> {
         None
     }
@@ -358,6 +732,150 @@ impl GitRepository for FakeGitRepository {
             .with_context(|| format!("failed to get blame for {:?}", path))
             .cloned()
     }
+
+    fn stage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        for path in paths {
+            check_path_to_repo_path_errors(path)?;
+        }
+        // Read the working copy before locking the state, as it may lock the file system.
+        let working_copy = self.state.lock().working_copy.clone();
+        let contents = working_copy.map(|working_copy| {
+            paths
+                .iter()
+                .map(|path| working_copy.load(path))
+                .collect::<Vec<_>>()
+        });
+
+        let mut state = self.state.lock();
+        for path in paths {
+            if state.worktree_statuses.contains_key(path) {
+                state.staged_paths.insert(path.clone());
+            }
+        }
+        if let Some(contents) = contents {
+            for (path, content) in paths.iter().zip(contents) {
+                match content {
+                    Some(content) => {
+                        state.index_contents.insert(path.to_path_buf(), content);
+                    }
+                    None => {
+                        state.index_contents.remove(path.as_path());
+                    }
+                }
+            }
+        }
+        state
+            .event_emitter
+            .try_send(state.dot_git_dir.clone())
+            .expect("Dropped repo change event");
+        Ok(())
+    }
+
+    fn unstage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        let mut state = self.state.lock();
+        for path in paths {
+            check_path_to_repo_path_errors(path)?;
+            state.staged_paths.remove(path);
+            state.reset_index_to_head(path);
+        }
+        state
+            .event_emitter
+            .try_send(state.dot_git_dir.clone())
+            .expect("Dropped repo change event");
+        Ok(())
+    }
+
+    fn commit(&self, message: &str, amend: bool) -> Result<()> {
+        if message.trim().is_empty() {
+            anyhow::bail!("commit message must not be empty");
+        }
+
+        let mut state = self.state.lock();
+        if amend {
+            let last_message = state
+                .commit_messages
+                .last_mut()
+                .context("there is no commit to amend")?;
+            *last_message = message.to_owned();
+        } else if state.staged_paths.is_empty() {
+            anyhow::bail!("nothing to commit");
+        } else {
+            state.commit_messages.push(message.to_owned());
+        }
+        for path in std::mem::take(&mut state.staged_paths) {
+            state.worktree_statuses.remove(&path);
+        }
+        state
+            .event_emitter
+            .try_send(state.dot_git_dir.clone())
+            .expect("Dropped repo change event");
+        Ok(())
+    }
+
+    fn discard(&self, paths: &[RepoPath]) -> Result<()> {
+        let mut state = self.state.lock();
+        for path in paths {
+            check_path_to_repo_path_errors(path)?;
+            state.staged_paths.remove(path);
+            state.reset_index_to_head(path);
+            // Untracked files are left in place, like in a real repository.
+            if state.worktree_statuses.get(path) != Some(&GitFileStatus::Added) {
+                state.worktree_statuses.remove(path);
+            }
+        }
+        state
+            .event_emitter
+            .try_send(state.dot_git_dir.clone())
+            .expect("Dropped repo change event");
+        Ok(())
+    }
+
+    fn log(
+        &self,
+        path: Option<&RepoPath>,
+        _range: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<LogEntry>> {
+        let state = self.state.lock();
+        Ok(state
+            .log
+            .iter()
+            .filter(|entry| path.map_or(true, |path| entry.touches(path)))
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    fn commit_diff(&self, sha: Oid) -> Result<Vec<CommitFileDiff>> {
+        let state = self.state.lock();
+        state
+            .commit_diffs
+            .get(&sha)
+            .cloned()
+            .with_context(|| format!("commit {sha} not found"))
+    }
+
+    fn load_text_at_revision(&self, path: &RepoPath, revision: &str) -> Result<Option<String>> {
+        let state = self.state.lock();
+        let contents = if revision == "HEAD" {
+            &state.head_contents
+        } else {
+            state
+                .revision_contents
+                .get(revision)
+                .with_context(|| format!("invalid revision {revision:?}"))?
+        };
+        Ok(contents.get(path.as_path()).cloned())
+    }
+
+    fn merge_base_with_head(&self, revision: &str) -> Result<Oid> {
+        let state = self.state.lock();
+        state
+            .merge_bases
+            .get(revision)
+            .copied()
+            .with_context(|| format!("HEAD and {revision:?} have no common ancestor"))
+    }
 }
 
 fn check_path_to_repo_path_errors(relative_file_path: &Path) -> Result<()> {
@@ -476,3 +994,297 @@ impl<'a> MapSeekTarget<RepoPath> for RepoPathDescendants<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn init_repository(path: &Path) -> RealGitRepository {
+        let repository = git2::Repository::init(path).unwrap();
+        let mut config = repository.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        RealGitRepository::new(repository, None, Default::default())
+    }
+
+    fn head_message(repo: &RealGitRepository) -> String {
+        let repo = repo.repository.lock();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        head.message().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_stage_and_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repository(dir.path());
+        let a = RepoPath::new("a.txt".into());
+        let b = RepoPath::new("b.txt".into());
+        fs::write(dir.path().join("a.txt"), "a\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "b\n").unwrap();
+
+        assert!(repo.commit("empty", false).is_err());
+        assert!(repo.commit("nothing yet", true).is_err());
+
+        repo.stage_paths(&[a.clone(), b.clone()]).unwrap();
+        assert_eq!(repo.load_index_text(&a).as_deref(), Some("a\n"));
+        repo.unstage_paths(&[b.clone()]).unwrap();
+        assert_eq!(repo.load_index_text(&b), None);
+
+        repo.commit("Add a", false).unwrap();
+        assert_eq!(head_message(&repo), "Add a");
+        assert!(repo.commit("Nothing changed", false).is_err());
+
+        fs::write(dir.path().join("a.txt"), "a\na\n").unwrap();
+        repo.stage_paths(&[a.clone(), b.clone()]).unwrap();
+        repo.unstage_paths(&[a.clone()]).unwrap();
+        assert_eq!(repo.load_index_text(&a).as_deref(), Some("a\n"));
+        repo.commit("Add a and b", true).unwrap();
+        assert_eq!(head_message(&repo), "Add a and b");
+        assert_eq!(repo.load_index_text(&b).as_deref(), Some("b\n"));
+
+        fs::remove_file(dir.path().join("b.txt")).unwrap();
+        repo.stage_paths(&[b.clone()]).unwrap();
+        assert_eq!(repo.load_index_text(&b), None);
+    }
+
+    #[test]
+    fn test_discard() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repository(dir.path());
+        let a = RepoPath::new("a.txt".into());
+        let c = RepoPath::new("c.txt".into());
+        fs::write(dir.path().join("a.txt"), "a\n").unwrap();
+        repo.stage_paths(&[a.clone()]).unwrap();
+        repo.commit("Add a", false).unwrap();
+
+        fs::write(dir.path().join("a.txt"), "staged\n").unwrap();
+        repo.stage_paths(&[a.clone()]).unwrap();
+        fs::write(dir.path().join("a.txt"), "unstaged\n").unwrap();
+        fs::write(dir.path().join("c.txt"), "c\n").unwrap();
+        repo.stage_paths(&[c.clone()]).unwrap();
+
+        repo.discard(&[a.clone(), c.clone()]).unwrap();
+        assert_eq!(repo.load_index_text(&a).as_deref(), Some("a\n"));
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "a\n");
+        assert_eq!(repo.load_index_text(&c), None);
+        assert!(dir.path().join("c.txt").exists());
+    }
+
+    #[test]
+    fn test_discard_no_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repository(dir.path());
+        let a = RepoPath::new("a.txt".into());
+        fs::write(dir.path().join("a.txt"), "a\n").unwrap();
+        repo.stage_paths(&[a.clone()]).unwrap();
+        repo.commit("Add a", false).unwrap();
+
+        // An empty pathspec would match every file, so nothing must be reset.
+        fs::write(dir.path().join("a.txt"), "staged\n").unwrap();
+        repo.stage_paths(&[a.clone()]).unwrap();
+        fs::write(dir.path().join("a.txt"), "unstaged\n").unwrap();
+        repo.discard(&[]).unwrap();
+        repo.unstage_paths(&[]).unwrap();
+        assert_eq!(repo.load_index_text(&a).as_deref(), Some("staged\n"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "unstaged\n"
+        );
+    }
+
+    #[test]
+    fn test_set_index_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repository(dir.path());
+        let a = RepoPath::new("dir/a.txt".into());
+        fs::create_dir(dir.path().join("dir")).unwrap();
+        fs::write(dir.path().join("dir/a.txt"), "one\ntwo\n").unwrap();
+        repo.stage_paths(&[a.clone()]).unwrap();
+        repo.commit("Add a", false).unwrap();
+
+        repo.set_index_text(&a, Some("one\n".into())).unwrap();
+        assert_eq!(repo.load_index_text(&a).as_deref(), Some("one\n"));
+        assert_eq!(repo.load_committed_text(&a).as_deref(), Some("one\ntwo\n"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("dir/a.txt")).unwrap(),
+            "one\ntwo\n"
+        );
+
+        repo.set_index_text(&a, None).unwrap();
+        assert_eq!(repo.load_index_text(&a), None);
+        assert_eq!(
+            repo.load_committed_text(&RepoPath::new("b.txt".into())),
+            None
+        );
+    }
+
+    #[test]
+    fn test_log_and_commit_diff() {
+        use crate::log::ChangeKind;
+
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repository(dir.path());
+        assert_eq!(repo.log(None, None, 10).unwrap(), Vec::new());
+
+        let a = RepoPath::new("src/a.txt".into());
+        let b = RepoPath::new("b.txt".into());
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/a.txt"), "a\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "b\n").unwrap();
+        repo.stage_paths(&[a.clone(), b.clone()]).unwrap();
+        repo.commit("Add a and b", false).unwrap();
+
+        fs::write(dir.path().join("b.txt"), "b\nb\n").unwrap();
+        repo.stage_paths(&[b.clone()]).unwrap();
+        repo.commit("Change b\n\nWith a body.", false).unwrap();
+
+        fs::remove_file(dir.path().join("src/a.txt")).unwrap();
+        repo.stage_paths(&[a.clone()]).unwrap();
+        repo.commit("Remove a", false).unwrap();
+
+        let log = repo.log(None, None, 10).unwrap();
+        assert_eq!(
+            log.iter()
+                .map(|entry| entry.summary.as_str())
+                .collect::<Vec<_>>(),
+            ["Remove a", "Change b", "Add a and b"]
+        );
+        assert_eq!(log[1].message, "Change b\n\nWith a body.");
+        assert_eq!(log[1].author_name, "Test");
+        assert_eq!(log[1].parents, vec![log[2].sha]);
+        assert!(log[2].parents.is_empty());
+        assert_eq!(log[0].changed_files[0].path, a);
+        assert_eq!(log[0].changed_files[0].kind, ChangeKind::Deleted);
+
+        let history = repo
+            .log(Some(&RepoPath::new("src".into())), None, 10)
+            .unwrap();
+        assert_eq!(
+            history.iter().map(|entry| entry.sha).collect::<Vec<_>>(),
+            vec![log[0].sha, log[2].sha]
+        );
+        assert_eq!(repo.log(Some(&b), None, 1).unwrap(), vec![log[1].clone()]);
+        let range = format!("{}..HEAD", log[2].sha);
+        assert_eq!(repo.log(None, Some(&range), 10).unwrap(), log[..2].to_vec());
+
+        let diff = repo.commit_diff(log[1].sha).unwrap();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].path, b);
+        assert_eq!(diff[0].kind, ChangeKind::Modified);
+        assert_eq!(diff[0].old_text.as_deref(), Some("b\n"));
+        assert_eq!(diff[0].new_text.as_deref(), Some("b\nb\n"));
+
+        let diff = repo.commit_diff(log[0].sha).unwrap();
+        assert_eq!(diff[0].old_text.as_deref(), Some("a\n"));
+        assert_eq!(diff[0].new_text, None);
+    }
+
+    #[test]
+    fn test_diff_base_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repository(dir.path());
+        let a = RepoPath::new("a.txt".into());
+
+        fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+        repo.stage_paths(&[a.clone()]).unwrap();
+        repo.commit("Add a", false).unwrap();
+        let first_sha = repo.head_sha().unwrap();
+        repo.create_branch("base").unwrap();
+
+        fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+        repo.stage_paths(&[a.clone()]).unwrap();
+        repo.commit("Change a", false).unwrap();
+        fs::write(dir.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        repo.stage_paths(&[a.clone()]).unwrap();
+
+        let load = |base: DiffBase| base.load_text(&repo, &a);
+        assert_eq!(
+            load(DiffBase::Index).unwrap().as_deref(),
+            Some("one\ntwo\nthree\n")
+        );
+        assert_eq!(
+            load(DiffBase::Revision("HEAD".into())).unwrap().as_deref(),
+            Some("one\ntwo\n")
+        );
+        assert_eq!(
+            load(DiffBase::Revision(first_sha.clone().into()))
+                .unwrap()
+                .as_deref(),
+            Some("one\n")
+        );
+        assert_eq!(
+            load(DiffBase::MergeBase("base".into())).unwrap().as_deref(),
+            Some("one\n")
+        );
+        assert_eq!(
+            repo.merge_base_with_head("base").unwrap().to_string(),
+            first_sha
+        );
+        assert!(load(DiffBase::Revision("missing".into())).is_err());
+        let b = RepoPath::new("b.txt".into());
+        assert_eq!(
+            DiffBase::Revision("HEAD".into())
+                .load_text(&repo, &b)
+                .unwrap()
+                .as_deref(),
+            Some("")
+        );
+        assert_eq!(DiffBase::Index.load_text(&repo, &b).unwrap(), None);
+        assert_eq!(
+            repo.load_text_at_revision(&RepoPath::new("b.txt".into()), "HEAD")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fake_stage_and_commit() {
+        let (tx, rx) = smol::channel::unbounded();
+        let a = RepoPath::new("a.txt".into());
+        let b = RepoPath::new("b.txt".into());
+        let c = RepoPath::new("c.txt".into());
+        let mut state = FakeGitRepositoryState::new(PathBuf::from("/.git"), tx);
+        state.worktree_statuses.extend([
+            (a.clone(), GitFileStatus::Modified),
+            (b.clone(), GitFileStatus::Modified),
+            (c.clone(), GitFileStatus::Added),
+        ]);
+        state.head_contents.extend([
+            (PathBuf::from("a.txt"), "a\n".to_string()),
+            (PathBuf::from("b.txt"), "b\n".to_string()),
+        ]);
+        state.index_contents = state.head_contents.clone();
+        state.working_copy = Some(FakeWorkingCopy::new(|path| {
+            Some(format!("new {}\n", path.display()))
+        }));
+        let state = Arc::new(Mutex::new(state));
+        let repo = FakeGitRepository::open(state.clone());
+
+        assert!(repo.commit("Nothing staged", false).is_err());
+        repo.stage_paths(&[a.clone(), b.clone(), c.clone()])
+            .unwrap();
+        assert_eq!(repo.load_index_text(&b).as_deref(), Some("new b.txt\n"));
+        repo.unstage_paths(&[b.clone()]).unwrap();
+        assert_eq!(repo.load_index_text(&b).as_deref(), Some("b\n"));
+        repo.commit("Change a", false).unwrap();
+        repo.commit("Change a only", true).unwrap();
+        repo.discard(&[b.clone(), c.clone()]).unwrap();
+
+        let state = state.lock();
+        assert_eq!(state.commit_messages, vec!["Change a only".to_string()]);
+        assert!(state.staged_paths.is_empty());
+        assert_eq!(
+            state.worktree_statuses,
+            HashMap::from_iter([(c, GitFileStatus::Added)])
+        );
+        assert_eq!(
+            state.index_contents,
+            HashMap::from_iter([
+                (PathBuf::from("a.txt"), "new a.txt\n".to_string()),
+                (PathBuf::from("b.txt"), "b\n".to_string()),
+            ])
+        );
+        assert_eq!(rx.len(), 5);
+    }
+}
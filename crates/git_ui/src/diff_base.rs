@@ -0,0 +1,254 @@
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use fuzzy::{StringMatch, StringMatchCandidate};
+use git::repository::DiffBase;
+use gpui::{
+    actions, rems, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Render,
+    Subscription, Task, View, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use project::ProjectPath;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{notifications::DetachAndPromptErr, ModalView, Workspace};
+
+actions!(git_ui, [SelectDiffBase]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(DiffBaseSelector::open);
+    })
+    .detach();
+}
+
+/// A modal for choosing what the editor's git hunks are computed against.
+pub struct DiffBaseSelector {
+    picker: View<Picker<DiffBaseSelectorDelegate>>,
+    _subscription: Subscription,
+}
+
+impl DiffBaseSelector {
+    fn open(workspace: &mut Workspace, _: &SelectDiffBase, cx: &mut ViewContext<Workspace>) {
+        let project = workspace.project().read(cx);
+        let current = project.diff_base(cx).clone();
+        let branches = project
+            .visible_worktrees(cx)
+            .next()
+            .context("No worktrees found")
+            .map(|worktree| project.branches(ProjectPath::root_path(worktree.read(cx).id()), cx));
+        cx.spawn(|workspace, mut cx| async move {
+            let branches = branches?.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let mut entries = vec![
+                    DiffBaseEntry::new(DiffBase::Index, "Unstaged changes".into()),
+                    DiffBaseEntry::new(
+                        DiffBase::Revision("HEAD".into()),
+                        "Uncommitted changes".into(),
+                    ),
+                ];
+                for branch in branches.iter().filter(|branch| !branch.is_head) {
+                    entries.push(DiffBaseEntry::new(
+                        DiffBase::MergeBase(branch.name.clone()),
+                        format!("Changes since branching from {}", branch.name),
+                    ));
+                    entries.push(DiffBaseEntry::new(
+                        DiffBase::Revision(branch.name.clone()),
+                        format!("Differences from {}", branch.name),
+                    ));
+                }
+                let delegate = DiffBaseSelectorDelegate {
+                    workspace: cx.view().downgrade(),
+                    entries,
+                    current,
+                    matches: Vec::new(),
+                    selected_index: 0,
+                };
+                workspace.toggle_modal(cx, |cx| DiffBaseSelector::new(delegate, cx));
+            })
+        })
+        .detach_and_prompt_err("Failed to read branches", cx, |_, _| None);
+    }
+
+    fn new(delegate: DiffBaseSelectorDelegate, cx: &mut ViewContext<Self>) -> Self {
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        let _subscription = cx.subscribe(&picker, |_, _, _, cx| cx.emit(DismissEvent));
+        Self {
+            picker,
+            _subscription,
+        }
+    }
+}
+
+impl ModalView for DiffBaseSelector {}
+impl EventEmitter<DismissEvent> for DiffBaseSelector {}
+
+impl FocusableView for DiffBaseSelector {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for DiffBaseSelector {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct DiffBaseEntry {
+    base: DiffBase,
+    label: String,
+}
+
+impl DiffBaseEntry {
+    fn new(base: DiffBase, label: String) -> Self {
+        Self { base, label }
+    }
+}
+
+pub struct DiffBaseSelectorDelegate {
+    workspace: WeakView<Workspace>,
+    entries: Vec<DiffBaseEntry>,
+    current: DiffBase,
+    /// The entries matching the query, followed by any typed revision.
+    matches: Vec<(DiffBaseEntry, StringMatch)>,
+    selected_index: usize,
+}
+
+impl PickerDelegate for DiffBaseSelectorDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Diff against a branch, tag or commit...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let entries = self.entries.clone();
+        cx.spawn(move |picker, mut cx| async move {
+            let candidates = entries
+                .iter()
+                .enumerate()
+                .map(|(ix, entry)| StringMatchCandidate::new(ix, &entry.label))
+                .collect::<Vec<_>>();
+            let query = query.trim();
+            let hits = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect::<Vec<_>>()
+            } else {
+                fuzzy::match_strings(
+                    &candidates,
+                    query,
+                    true,
+                    entries.len(),
+                    &Default::default(),
+                    cx.background_executor().clone(),
+                )
+                .await
+            };
+            let mut matches = hits
+                .into_iter()
+                .map(|hit| (entries[hit.candidate_id].clone(), hit))
+                .collect::<Vec<_>>();
+
+            // Anything typed may be a revision that isn't listed, such as a tag or a commit SHA.
+            if !query.is_empty() {
+                for entry in [
+                    DiffBaseEntry::new(
+                        DiffBase::Revision(query.to_string().into()),
+                        format!("Differences from {query}"),
+                    ),
+                    DiffBaseEntry::new(
+                        DiffBase::MergeBase(query.to_string().into()),
+                        format!("Changes since branching from {query}"),
+                    ),
+                ] {
+                    if matches
+                        .iter()
+                        .all(|(existing, _)| existing.base != entry.base)
+                    {
+                        let hit = StringMatch {
+                            candidate_id: usize::MAX,
+                            string: entry.label.clone(),
+                            positions: Vec::new(),
+                            score: 0.0,
+                        };
+                        matches.push((entry, hit));
+                    }
+                }
+            }
+
+            picker
+                .update(&mut cx, |picker, _| {
+                    let delegate = &mut picker.delegate;
+                    delegate.matches = matches;
+                    delegate.selected_index = delegate
+                        .selected_index
+                        .min(delegate.matches.len().saturating_sub(1));
+                })
+                .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _secondary: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let Some((entry, _)) = self.matches.get(self.selected_index) else {
+            return;
+        };
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let diff_base = entry.base.clone();
+        let project = workspace.read(cx).project().clone();
+        project
+            .update(cx, |project, cx| project.set_diff_base(diff_base, cx))
+            .detach_and_prompt_err("Failed to change the diff base", cx, |_, _| None);
+        cx.emit(DismissEvent);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let (entry, hit) = &self.matches[ix];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .toggle_state(selected)
+                .child(HighlightedLabel::new(
+                    entry.label.clone(),
+                    hit.positions.clone(),
+                ))
+                .end_slot::<Icon>((entry.base == self.current).then(|| {
+                    Icon::new(IconName::Check)
+                        .size(IconSize::Small)
+                        .color(Color::Accent)
+                })),
+        )
+    }
+}
@@ -4,7 +4,9 @@ use gpui::{actions, AppContext, Hsla};
 use settings::GitPanelSettings;
 use ui::{Color, Icon, IconName, IntoElement};
 
+pub mod diff_base;
 pub mod git_panel;
+pub mod history;
 mod settings;
 
 actions!(
@@ -20,6 +22,8 @@ actions!(
 
 pub fn init(cx: &mut AppContext) {
     GitPanelSettings::register(cx);
+    diff_base::init(cx);
+    history::init(cx);
 }
 
 const ADDED_COLOR: Hsla = Hsla {
@@ -0,0 +1,621 @@
+use std::{
+    any::Any,
+    ffi::OsStr,
+    ops::Not,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context as _;
+use editor::{Editor, DEFAULT_MULTIBUFFER_CONTEXT};
+use fuzzy::{StringMatch, StringMatchCandidate};
+use git::log::{CommitFileDiff, LogEntry};
+use gpui::{
+    actions, rems, AnyElement, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView,
+    Model, Render, Subscription, Task, View, WeakView,
+};
+use language::{Buffer, Capability, DiskState, File, LocalFile};
+use multi_buffer::MultiBuffer;
+use picker::{Picker, PickerDelegate};
+use project::{buffer_store::BufferChangeSet, Project, ProjectPath, WorktreeId};
+use time::{OffsetDateTime, UtcOffset};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{notifications::DetachAndPromptErr, ModalView, Workspace};
+
+actions!(git_history, [FileHistory, RepositoryHistory]);
+
+/// The maximum number of commits listed in the history view.
+const LOG_LIMIT: usize = 1000;
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(|workspace, _: &FileHistory, cx| {
+            let project_path = workspace.active_item_as::<Editor>(cx).and_then(|editor| {
+                let buffer = editor.read(cx).buffer().read(cx).as_singleton()?;
+                let file = buffer.read(cx).file()?;
+                Some(ProjectPath {
+                    worktree_id: file.worktree_id(cx),
+                    path: file.path().clone(),
+                })
+            });
+            if let Some(project_path) = project_path {
+                CommitHistory::open(workspace, project_path, cx);
+            }
+        });
+        workspace.register_action(|workspace, _: &RepositoryHistory, cx| {
+            let worktree = workspace.project().read(cx).visible_worktrees(cx).next();
+            if let Some(worktree) = worktree {
+                let project_path = ProjectPath::root_path(worktree.read(cx).id());
+                CommitHistory::open(workspace, project_path, cx);
+            }
+        });
+    })
+    .detach();
+}
+
+/// A modal listing the commits of a repository, or of a single file within it.
+pub struct CommitHistory {
+    picker: View<Picker<CommitHistoryDelegate>>,
+    _subscription: Subscription,
+}
+
+impl CommitHistory {
+    fn open(workspace: &mut Workspace, project_path: ProjectPath, cx: &mut ViewContext<Workspace>) {
+        let log = workspace
+            .project()
+            .read(cx)
+            .git_log(&project_path, None, LOG_LIMIT, cx);
+        cx.spawn(|workspace, mut cx| async move {
+            let entries = log.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let delegate =
+                    CommitHistoryDelegate::new(cx.view().downgrade(), project_path, entries);
+                workspace.toggle_modal(cx, |cx| CommitHistory::new(delegate, cx));
+            })
+        })
+        .detach_and_prompt_err("Failed to read git history", cx, |_, _| None);
+    }
+
+    fn new(delegate: CommitHistoryDelegate, cx: &mut ViewContext<Self>) -> Self {
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        let _subscription = cx.subscribe(&picker, |_, _, _, cx| cx.emit(DismissEvent));
+        Self {
+            picker,
+            _subscription,
+        }
+    }
+}
+
+impl ModalView for CommitHistory {}
+impl EventEmitter<DismissEvent> for CommitHistory {}
+
+impl FocusableView for CommitHistory {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for CommitHistory {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(40.)).child(self.picker.clone())
+    }
+}
+
+pub struct CommitHistoryDelegate {
+    workspace: WeakView<Workspace>,
+    project_path: ProjectPath,
+    entries: Vec<LogEntry>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+    local_offset: UtcOffset,
+}
+
+impl CommitHistoryDelegate {
+    fn new(
+        workspace: WeakView<Workspace>,
+        project_path: ProjectPath,
+        entries: Vec<LogEntry>,
+    ) -> Self {
+        let local_offset = chrono::Local::now().offset().local_minus_utc();
+        Self {
+            workspace,
+            project_path,
+            entries,
+            matches: Vec::new(),
+            selected_index: 0,
+            local_offset: UtcOffset::from_whole_seconds(local_offset).unwrap_or(UtcOffset::UTC),
+        }
+    }
+}
+
+impl PickerDelegate for CommitHistoryDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Search commits...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let candidates = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(ix, entry)| StringMatchCandidate::new(ix, &entry.summary))
+            .collect::<Vec<_>>();
+        cx.spawn(move |picker, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                let mut matches = fuzzy::match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    LOG_LIMIT,
+                    &Default::default(),
+                    cx.background_executor().clone(),
+                )
+                .await;
+                // Keep the history in chronological order rather than by score.
+                matches.sort_unstable_by_key(|hit| hit.candidate_id);
+                matches
+            };
+            picker
+                .update(&mut cx, |picker, _| {
+                    let delegate = &mut picker.delegate;
+                    delegate.matches = matches;
+                    delegate.selected_index = delegate
+                        .selected_index
+                        .min(delegate.matches.len().saturating_sub(1));
+                })
+                .log_err();
+        })
+    }
+
+    fn confirm(&mut self, secondary: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let Some(hit) = self.matches.get(self.selected_index) else {
+            return;
+        };
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let entry = self.entries[hit.candidate_id].clone();
+        let project_path = self.project_path.clone();
+
+        if secondary {
+            let permalink = workspace.read(cx).project().read(cx).commit_permalink(
+                &project_path,
+                entry.sha,
+                cx,
+            );
+            cx.spawn(|_, mut cx| async move {
+                let permalink = permalink.await?;
+                cx.update(|cx| cx.open_url(permalink.as_str()))
+            })
+            .detach_and_prompt_err("Failed to open commit link", cx, |_, _| None);
+        } else {
+            workspace.update(cx, |workspace, cx| {
+                open_commit_diff(workspace, project_path, entry, cx);
+            });
+        }
+        cx.emit(DismissEvent);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let hit = &self.matches[ix];
+        let entry = &self.entries[hit.candidate_id];
+        let timestamp = entry
+            .author_offset_date_time()
+            .map(|timestamp| {
+                time_format::format_localized_timestamp(
+                    timestamp,
+                    OffsetDateTime::now_utc(),
+                    self.local_offset,
+                    time_format::TimestampFormat::Relative,
+                )
+            })
+            .unwrap_or_default();
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .toggle_state(selected)
+                .start_slot(
+                    Label::new(entry.sha.display_short())
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .child(HighlightedLabel::new(
+                    entry.summary.clone(),
+                    hit.positions.clone(),
+                ))
+                .end_slot(
+                    Label::new(format!("{}, {}", entry.author_name, timestamp))
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                ),
+        )
+    }
+
+    fn render_header(&self, _: &mut ViewContext<Picker<Self>>) -> Option<AnyElement> {
+        let title = if self.project_path.path.as_os_str().is_empty() {
+            "Repository History".to_string()
+        } else {
+            format!("History of {}", self.project_path.path.display())
+        };
+        let commit_count = self.matches.is_empty().not().then(|| {
+            let suffix = if self.matches.len() == 1 { "" } else { "s" };
+            Label::new(format!("{} commit{}", self.matches.len(), suffix))
+                .color(Color::Muted)
+                .size(LabelSize::Small)
+        });
+        Some(
+            h_flex()
+                .mt_1()
+                .px_3()
+                .justify_between()
+                .child(Label::new(title).size(LabelSize::Small))
+                .children(commit_count)
+                .into_any_element(),
+        )
+    }
+}
+
+/// Opens the changes made by a commit in a multibuffer, with every hunk expanded.
+pub fn open_commit_diff(
+    workspace: &mut Workspace,
+    project_path: ProjectPath,
+    entry: LogEntry,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let project = workspace.project().clone();
+    let repository = project
+        .read(cx)
+        .worktree_for_id(project_path.worktree_id, cx)
+        .and_then(|worktree| {
+            let snapshot = worktree.read(cx).snapshot();
+            let repository = snapshot.repository_for_path(&project_path.path)?;
+            Some((snapshot, repository))
+        });
+    let commit_diff = project
+        .read(cx)
+        .load_commit_diff(&project_path, entry.sha, cx);
+    let languages = project.read(cx).languages().clone();
+    cx.spawn(|workspace, mut cx| async move {
+        let files = commit_diff.await?;
+        let (snapshot, repository) =
+            repository.with_context(|| format!("{project_path:?} is not in a git repository"))?;
+        // Changes outside of the worktree can't be opened in the project, so they're left out.
+        let files = files
+            .into_iter()
+            .filter_map(|file| {
+                let path = repository.unrelativize(&snapshot, &file.path)?;
+                Some((path, file))
+            })
+            .collect::<Vec<_>>();
+        let mut file_languages = Vec::with_capacity(files.len());
+        for (path, _) in &files {
+            file_languages.push(languages.language_for_file_path(path).await.ok());
+        }
+
+        let mut buffers = Vec::with_capacity(files.len());
+        let mut diffs_updated = Vec::with_capacity(files.len());
+        for ((path, file), language) in files.into_iter().zip(file_languages) {
+            let (buffer, change_set, diff_updated) = cx.update(|cx| {
+                create_commit_buffer(file, path, language, project_path.worktree_id, cx)
+            })?;
+            buffers.push((buffer, change_set));
+            diffs_updated.push(diff_updated);
+        }
+        futures::future::join_all(diffs_updated).await;
+
+        workspace.update(&mut cx, |workspace, cx| {
+            let title = format!("{} {}", entry.sha.display_short(), entry.summary);
+            let multibuffer =
+                cx.new_model(|_| MultiBuffer::new(Capability::ReadOnly).with_title(title));
+            let editor = cx.new_view(|cx| {
+                let mut editor =
+                    Editor::for_multibuffer(multibuffer.clone(), Some(project.clone()), true, cx);
+                editor.set_expand_all_diff_hunks();
+                for (_, change_set) in &buffers {
+                    editor.add_change_set(change_set.clone(), cx);
+                }
+                editor
+            });
+            multibuffer.update(cx, |multibuffer, cx| {
+                for (buffer, change_set) in buffers {
+                    let snapshot = buffer.read(cx).text_snapshot();
+                    let hunk_ranges = change_set
+                        .read(cx)
+                        .diff_hunks_intersecting_range(
+                            text::Anchor::MIN..text::Anchor::MAX,
+                            &snapshot,
+                        )
+                        .map(|hunk| hunk.buffer_range)
+                        .collect::<Vec<_>>();
+                    multibuffer.push_excerpts_with_context_lines(
+                        buffer,
+                        hunk_ranges,
+                        DEFAULT_MULTIBUFFER_CONTEXT,
+                        cx,
+                    );
+                }
+            });
+            workspace.add_item_to_active_pane(Box::new(editor), None, true, cx);
+        })
+    })
+    .detach_and_prompt_err("Failed to open commit", cx, |_, _| None);
+}
+
+fn create_commit_buffer(
+    file: CommitFileDiff,
+    path: Arc<Path>,
+    language: Option<Arc<language::Language>>,
+    worktree_id: WorktreeId,
+    cx: &mut AppContext,
+) -> (
+    Model<Buffer>,
+    Model<BufferChangeSet>,
+    futures::channel::oneshot::Receiver<()>,
+) {
+    let commit_file = Arc::new(CommitFile { path, worktree_id });
+    let buffer = cx.new_model(|cx| {
+        let text = text::Buffer::new(
+            0,
+            cx.entity_id().as_non_zero_u64().into(),
+            file.new_text.unwrap_or_default(),
+        );
+        let mut buffer = Buffer::build(text, Some(commit_file), Capability::ReadOnly);
+        buffer.set_language(language, cx);
+        buffer
+    });
+    let snapshot = buffer.read(cx).text_snapshot();
+    let mut diff_updated = None;
+    let change_set = cx.new_model(|cx| {
+        let mut change_set = BufferChangeSet::new(&snapshot);
+        diff_updated =
+            Some(change_set.set_base_text(file.old_text.unwrap_or_default(), snapshot, cx));
+        change_set
+    });
+    (buffer, change_set, diff_updated.unwrap())
+}
+
+/// A file as it was in a commit, which doesn't correspond to anything on disk.
+struct CommitFile {
+    path: Arc<Path>,
+    worktree_id: WorktreeId,
+}
+
+impl File for CommitFile {
+    fn as_local(&self) -> Option<&dyn LocalFile> {
+        None
+    }
+
+    fn disk_state(&self) -> DiskState {
+        DiskState::New
+    }
+
+    fn path(&self) -> &Arc<Path> {
+        &self.path
+    }
+
+    fn full_path(&self, _: &AppContext) -> PathBuf {
+        self.path.to_path_buf()
+    }
+
+    fn file_name<'a>(&'a self, _: &'a AppContext) -> &'a OsStr {
+        self.path.file_name().unwrap_or_default()
+    }
+
+    fn worktree_id(&self, _: &AppContext) -> WorktreeId {
+        self.worktree_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_proto(&self, _: &AppContext) -> rpc::proto::File {
+        rpc::proto::File {
+            worktree_id: self.worktree_id.to_proto(),
+            entry_id: None,
+            path: self.path.to_string_lossy().into(),
+            mtime: None,
+            is_deleted: false,
+        }
+    }
+
+    fn is_private(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use git::{
+        log::{ChangeKind, ChangedFile},
+        repository::RepoPath,
+        Oid,
+    };
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::FakeFs;
+    use serde_json::json;
+    use workspace::AppState;
+
+    use super::*;
+
+    fn init_test(cx: &mut TestAppContext) -> Arc<AppState> {
+        cx.update(|cx| {
+            let state = AppState::test(cx);
+            language::init(cx);
+            editor::init(cx);
+            Project::init_settings(cx);
+            super::init(cx);
+            state
+        })
+    }
+
+    fn log_entry(sha: Oid, summary: &str, changed_files: &[&str]) -> LogEntry {
+        LogEntry {
+            sha,
+            parents: Vec::new(),
+            author_name: "Test".into(),
+            author_email: "test@example.com".into(),
+            author_time: 0,
+            author_offset_minutes: 0,
+            summary: summary.into(),
+            message: summary.into(),
+            changed_files: changed_files
+                .iter()
+                .map(|path| ChangedFile {
+                    path: RepoPath::new(PathBuf::from(path)),
+                    old_path: None,
+                    kind: ChangeKind::Modified,
+                })
+                .collect(),
+        }
+    }
+
+    fn open_history(
+        workspace: &View<Workspace>,
+        cx: &mut VisualTestContext,
+    ) -> View<Picker<CommitHistoryDelegate>> {
+        cx.dispatch_action(FileHistory);
+        cx.run_until_parked();
+        workspace.update(cx, |workspace, cx| {
+            workspace
+                .active_modal::<CommitHistory>(cx)
+                .expect("no history modal after `FileHistory` was dispatched")
+                .read(cx)
+                .picker
+                .clone()
+        })
+    }
+
+    fn summaries(
+        picker: &View<Picker<CommitHistoryDelegate>>,
+        cx: &mut VisualTestContext,
+    ) -> Vec<String> {
+        picker.update(cx, |picker, _| {
+            picker
+                .delegate
+                .matches
+                .iter()
+                .map(|hit| hit.string.clone())
+                .collect()
+        })
+    }
+
+    #[gpui::test]
+    async fn test_file_history(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        // The worktree is a folder within the repository.
+        fs.insert_tree(
+            "/repo",
+            json!({
+                ".git": {},
+                "project": {
+                    "src": { "a.rs": "fn a() {}\n" },
+                },
+                "b.txt": "b\n",
+            }),
+        )
+        .await;
+        let add_a = Oid::from_bytes(&[1; 20]).unwrap();
+        let change_a = Oid::from_bytes(&[2; 20]).unwrap();
+        let change_b = Oid::from_bytes(&[3; 20]).unwrap();
+        fs.with_git_state(Path::new("/repo/.git"), false, |state| {
+            state.log = vec![
+                log_entry(change_b, "Change b", &["b.txt"]),
+                log_entry(change_a, "Change a and b", &["project/src/a.rs", "b.txt"]),
+                log_entry(add_a, "Add a", &["project/src/a.rs"]),
+            ];
+            state.commit_diffs.insert(
+                change_a,
+                vec![
+                    CommitFileDiff {
+                        path: RepoPath::new("project/src/a.rs".into()),
+                        kind: ChangeKind::Modified,
+                        old_text: Some("fn a() {\n}\n".into()),
+                        new_text: Some("fn a() {}\n".into()),
+                    },
+                    CommitFileDiff {
+                        path: RepoPath::new("b.txt".into()),
+                        kind: ChangeKind::Modified,
+                        old_text: Some("".into()),
+                        new_text: Some("b\n".into()),
+                    },
+                ],
+            );
+        });
+
+        let project = Project::test(fs, ["/repo/project".as_ref()], cx).await;
+        let worktree_id = project.update(cx, |project, cx| {
+            project.worktrees(cx).next().unwrap().read(cx).id()
+        });
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_abs_path(PathBuf::from("/repo/project/src/a.rs"), true, cx)
+            })
+            .await
+            .unwrap();
+
+        let picker = open_history(&workspace, cx);
+        assert_eq!(summaries(&picker, cx), ["Change a and b", "Add a"]);
+
+        cx.simulate_input("chng");
+        assert_eq!(summaries(&picker, cx), ["Change a and b"]);
+
+        picker.update(cx, |picker, cx| picker.delegate.confirm(false, cx));
+        cx.run_until_parked();
+        assert!(workspace.update(cx, |workspace, cx| workspace
+            .active_modal::<CommitHistory>(cx)
+            .is_none()));
+
+        // Only the file within the worktree is shown, at its path in the worktree.
+        let editor = workspace
+            .update(cx, |workspace, cx| workspace.active_item_as::<Editor>(cx))
+            .unwrap();
+        editor.update(cx, |editor, cx| {
+            let buffers = editor.buffer().read(cx).all_buffers();
+            assert_eq!(buffers.len(), 1);
+            let buffer = buffers.into_iter().next().unwrap();
+            let file = buffer.read(cx).file().unwrap().clone();
+            assert_eq!(file.path().as_ref(), Path::new("src/a.rs"));
+            assert_eq!(file.worktree_id(cx), worktree_id);
+            assert_eq!(buffer.read(cx).text(), "fn a() {}\n");
+        });
+    }
+}
@@ -0,0 +1,918 @@
+mod hierarchy_panel_settings;
+
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use collections::HashMap;
+use db::kvp::KEY_VALUE_STORE;
+use editor::{scroll::Autoscroll, Editor};
+use gpui::{
+    actions, px, uniform_list, Action, AppContext, AsyncWindowContext, ClickEvent, EventEmitter,
+    FocusHandle, FocusableView, KeyContext, Model, MouseButton, Pixels, Render, ScrollStrategy,
+    Task, UniformListScrollHandle, View, ViewContext, WeakView,
+};
+use hierarchy_panel_settings::{HierarchyPanelDockPosition, HierarchyPanelSettings};
+use language::OffsetRangeExt as _;
+use menu::{Confirm, SelectFirst, SelectLast, SelectNext, SelectPrev};
+use project::{CallHierarchyItem, Fs, Location, Project, TypeHierarchyItem};
+use serde::{Deserialize, Serialize};
+use settings::Settings;
+use ui::{prelude::*, ListItem};
+use util::ResultExt;
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+actions!(
+    hierarchy_panel,
+    [
+        CollapseSelectedEntry,
+        ExpandSelectedEntry,
+        ShowIncomingCalls,
+        ShowOutgoingCalls,
+        ShowSubtypes,
+        ShowSupertypes,
+        ToggleFocus,
+    ]
+);
+
+const HIERARCHY_PANEL_KEY: &str = "HierarchyPanel";
+
+pub fn init(cx: &mut AppContext) {
+    HierarchyPanelSettings::register(cx);
+
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(|workspace, _: &ToggleFocus, cx| {
+            workspace.toggle_panel_focus::<HierarchyPanel>(cx);
+        });
+        workspace.register_action(|workspace, _: &ShowIncomingCalls, cx| {
+            show_hierarchy(workspace, HierarchyKind::IncomingCalls, cx);
+        });
+        workspace.register_action(|workspace, _: &ShowOutgoingCalls, cx| {
+            show_hierarchy(workspace, HierarchyKind::OutgoingCalls, cx);
+        });
+        workspace.register_action(|workspace, _: &ShowSupertypes, cx| {
+            show_hierarchy(workspace, HierarchyKind::Supertypes, cx);
+        });
+        workspace.register_action(|workspace, _: &ShowSubtypes, cx| {
+            show_hierarchy(workspace, HierarchyKind::Subtypes, cx);
+        });
+    })
+    .detach();
+}
+
+fn show_hierarchy(workspace: &mut Workspace, kind: HierarchyKind, cx: &mut ViewContext<Workspace>) {
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some((buffer, position)) = editor.update(cx, |editor, cx| {
+        let head = editor.selections.newest_anchor().head();
+        editor.buffer().read(cx).text_anchor_for_position(head, cx)
+    }) else {
+        return;
+    };
+    let items = match kind {
+        HierarchyKind::IncomingCalls | HierarchyKind::OutgoingCalls => {
+            let items = workspace.project().update(cx, |project, cx| {
+                project.prepare_call_hierarchy(&buffer, position, cx)
+            });
+            cx.spawn(|_, _| async move {
+                Ok(items.await?.into_iter().map(HierarchyItem::Call).collect())
+            })
+        }
+        HierarchyKind::Supertypes | HierarchyKind::Subtypes => {
+            let items = workspace.project().update(cx, |project, cx| {
+                project.prepare_type_hierarchy(&buffer, position, cx)
+            });
+            cx.spawn(|_, _| async move {
+                Ok(items.await?.into_iter().map(HierarchyItem::Type).collect())
+            })
+        }
+    };
+    if let Some(panel) = workspace.focus_panel::<HierarchyPanel>(cx) {
+        panel.update(cx, |panel, cx| panel.show(kind, items, cx));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HierarchyKind {
+    IncomingCalls,
+    OutgoingCalls,
+    Supertypes,
+    Subtypes,
+}
+
+impl HierarchyKind {
+    fn title(&self) -> &'static str {
+        match self {
+            HierarchyKind::IncomingCalls => "Incoming Calls",
+            HierarchyKind::OutgoingCalls => "Outgoing Calls",
+            HierarchyKind::Supertypes => "Supertypes",
+            HierarchyKind::Subtypes => "Subtypes",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum HierarchyItem {
+    Call(CallHierarchyItem),
+    Type(TypeHierarchyItem),
+}
+
+impl HierarchyItem {
+    fn name(&self) -> &str {
+        match self {
+            HierarchyItem::Call(item) => &item.lsp_item.name,
+            HierarchyItem::Type(item) => &item.lsp_item.name,
+        }
+    }
+
+    fn detail(&self) -> Option<&str> {
+        match self {
+            HierarchyItem::Call(item) => item.lsp_item.detail.as_deref(),
+            HierarchyItem::Type(item) => item.lsp_item.detail.as_deref(),
+        }
+    }
+
+    fn location(&self) -> &Location {
+        match self {
+            HierarchyItem::Call(item) => &item.location,
+            HierarchyItem::Type(item) => &item.location,
+        }
+    }
+}
+
+struct HierarchyEntry {
+    item: HierarchyItem,
+    /// The calls between this entry's item and its parent's, if this is a call hierarchy.
+    call_sites: Vec<Location>,
+    parent: Option<usize>,
+    depth: usize,
+    /// The entries nested under this one, or `None` if they haven't been fetched yet.
+    children: Option<Vec<usize>>,
+    expanded: bool,
+}
+
+/// A panel showing the callers or callees of a symbol, or the supertypes or subtypes of
+/// a type, as a tree whose levels are fetched from the language server as they're expanded.
+pub struct HierarchyPanel {
+    fs: Arc<dyn Fs>,
+    width: Option<Pixels>,
+    project: Model<Project>,
+    workspace: WeakView<Workspace>,
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    pending_serialization: Task<Option<()>>,
+    /// The kind of hierarchy shown, or `None` until one is first requested.
+    kind: Option<HierarchyKind>,
+    entries: Vec<HierarchyEntry>,
+    roots: Vec<usize>,
+    /// Indices into `entries`, in the order they're displayed.
+    visible_entries: Vec<usize>,
+    /// An index into `visible_entries`.
+    selected_entry: Option<usize>,
+    loading_roots: bool,
+    roots_task: Task<()>,
+    fetch_tasks: HashMap<usize, Task<()>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedHierarchyPanel {
+    width: Option<Pixels>,
+}
+
+impl HierarchyPanel {
+    pub async fn load(
+        workspace: WeakView<Workspace>,
+        mut cx: AsyncWindowContext,
+    ) -> anyhow::Result<View<Self>> {
+        let serialized_panel = cx
+            .background_executor()
+            .spawn(async move { KEY_VALUE_STORE.read_kvp(HIERARCHY_PANEL_KEY) })
+            .await
+            .context("loading hierarchy panel")
+            .log_err()
+            .flatten()
+            .map(|panel| serde_json::from_str::<SerializedHierarchyPanel>(&panel))
+            .transpose()
+            .log_err()
+            .flatten();
+
+        workspace.update(&mut cx, |workspace, cx| {
+            let panel = Self::new(workspace, cx);
+            if let Some(serialized_panel) = serialized_panel {
+                panel.update(cx, |panel, cx| {
+                    panel.width = serialized_panel.width.map(|px| px.round());
+                    cx.notify();
+                });
+            }
+            panel
+        })
+    }
+
+    fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let project = workspace.project().clone();
+        let fs = workspace.app_state().fs.clone();
+        let workspace = cx.view().downgrade();
+        cx.new_view(|cx| Self {
+            fs,
+            width: None,
+            project,
+            workspace,
+            focus_handle: cx.focus_handle(),
+            scroll_handle: UniformListScrollHandle::new(),
+            pending_serialization: Task::ready(None),
+            kind: None,
+            entries: Vec::new(),
+            roots: Vec::new(),
+            visible_entries: Vec::new(),
+            selected_entry: None,
+            loading_roots: false,
+            roots_task: Task::ready(()),
+            fetch_tasks: HashMap::default(),
+        })
+    }
+
+    fn serialize(&mut self, cx: &mut ViewContext<Self>) {
+        let width = self.width;
+        self.pending_serialization = cx.background_executor().spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(
+                        HIERARCHY_PANEL_KEY.into(),
+                        serde_json::to_string(&SerializedHierarchyPanel { width })?,
+                    )
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    fn dispatch_context(&self) -> KeyContext {
+        let mut dispatch_context = KeyContext::new_with_defaults();
+        dispatch_context.add("HierarchyPanel");
+        dispatch_context.add("menu");
+        dispatch_context
+    }
+
+    fn show(
+        &mut self,
+        kind: HierarchyKind,
+        items: Task<anyhow::Result<Vec<HierarchyItem>>>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.kind = Some(kind);
+        self.entries.clear();
+        self.roots.clear();
+        self.visible_entries.clear();
+        self.selected_entry = None;
+        self.fetch_tasks.clear();
+        self.loading_roots = true;
+        self.roots_task = cx.spawn(|panel, mut cx| async move {
+            let items = items.await.log_err().unwrap_or_default();
+            panel
+                .update(&mut cx, |panel, cx| {
+                    panel.loading_roots = false;
+                    for item in items {
+                        panel.roots.push(panel.entries.len());
+                        panel.entries.push(HierarchyEntry {
+                            item,
+                            call_sites: Vec::new(),
+                            parent: None,
+                            depth: 0,
+                            children: None,
+                            expanded: false,
+                        });
+                    }
+                    // Usually there's a single symbol under the cursor, so expand it right away.
+                    if let [root] = panel.roots[..] {
+                        panel.expand_entry(root, cx);
+                    }
+                    panel.update_visible_entries(cx);
+                    if !panel.visible_entries.is_empty() {
+                        panel.selected_entry = Some(0);
+                    }
+                })
+                .ok();
+        });
+        cx.notify();
+    }
+
+    fn expand_entry(&mut self, entry_ix: usize, cx: &mut ViewContext<Self>) {
+        let entry = &mut self.entries[entry_ix];
+        entry.expanded = true;
+        if entry.children.is_some() || self.fetch_tasks.contains_key(&entry_ix) {
+            return;
+        }
+
+        let Some(kind) = self.kind else {
+            return;
+        };
+        let item = entry.item.clone();
+        let children = self.fetch_children(kind, &item, cx);
+        let fetch_task = cx.spawn(|panel, mut cx| async move {
+            let children = children.await.log_err().unwrap_or_default();
+            panel
+                .update(&mut cx, |panel, cx| {
+                    let depth = panel.entries[entry_ix].depth + 1;
+                    let mut child_ixs = Vec::with_capacity(children.len());
+                    for (item, call_sites) in children {
+                        child_ixs.push(panel.entries.len());
+                        panel.entries.push(HierarchyEntry {
+                            item,
+                            call_sites,
+                            parent: Some(entry_ix),
+                            depth,
+                            children: None,
+                            expanded: false,
+                        });
+                    }
+                    panel.entries[entry_ix].children = Some(child_ixs);
+                    panel.update_visible_entries(cx);
+                })
+                .ok();
+        });
+        self.fetch_tasks.insert(entry_ix, fetch_task);
+    }
+
+    /// Fetches the items nested under the given one, along with the call sites linking them.
+    fn fetch_children(
+        &self,
+        kind: HierarchyKind,
+        item: &HierarchyItem,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<anyhow::Result<Vec<(HierarchyItem, Vec<Location>)>>> {
+        match item {
+            HierarchyItem::Call(item) => {
+                let calls = self.project.update(cx, |project, cx| match kind {
+                    HierarchyKind::OutgoingCalls => project.outgoing_calls(item, cx),
+                    _ => project.incoming_calls(item, cx),
+                });
+                cx.spawn(|_, _| async move {
+                    Ok(calls
+                        .await?
+                        .into_iter()
+                        .map(|call| (HierarchyItem::Call(call.item), call.call_sites))
+                        .collect())
+                })
+            }
+            HierarchyItem::Type(item) => {
+                let types = self.project.update(cx, |project, cx| match kind {
+                    HierarchyKind::Subtypes => project.subtypes(item, cx),
+                    _ => project.supertypes(item, cx),
+                });
+                cx.spawn(|_, _| async move {
+                    Ok(types
+                        .await?
+                        .into_iter()
+                        .map(|item| (HierarchyItem::Type(item), Vec::new()))
+                        .collect())
+                })
+            }
+        }
+    }
+
+    fn toggle_expanded(&mut self, entry_ix: usize, cx: &mut ViewContext<Self>) {
+        if self.entries[entry_ix].expanded {
+            self.entries[entry_ix].expanded = false;
+        } else {
+            self.expand_entry(entry_ix, cx);
+        }
+        self.update_visible_entries(cx);
+    }
+
+    fn update_visible_entries(&mut self, cx: &mut ViewContext<Self>) {
+        let selected_entry = self.selected_entry_ix();
+        self.visible_entries.clear();
+        let mut stack = self.roots.iter().rev().copied().collect::<Vec<_>>();
+        while let Some(entry_ix) = stack.pop() {
+            self.visible_entries.push(entry_ix);
+            let entry = &self.entries[entry_ix];
+            if let Some(children) = entry.children.as_ref().filter(|_| entry.expanded) {
+                stack.extend(children.iter().rev().copied());
+            }
+        }
+        self.selected_entry = selected_entry.and_then(|selected_entry| {
+            self.visible_entries
+                .iter()
+                .position(|&entry_ix| entry_ix == selected_entry)
+        });
+        cx.notify();
+    }
+
+    fn selected_entry_ix(&self) -> Option<usize> {
+        self.visible_entries.get(self.selected_entry?).copied()
+    }
+
+    fn select_visible_entry(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        self.selected_entry = Some(ix);
+        self.scroll_handle
+            .scroll_to_item(ix, ScrollStrategy::Center);
+        cx.notify();
+    }
+
+    fn select_next(&mut self, _: &SelectNext, cx: &mut ViewContext<Self>) {
+        let ix = self.selected_entry.map_or(0, |ix| ix + 1);
+        if ix < self.visible_entries.len() {
+            self.select_visible_entry(ix, cx);
+        }
+    }
+
+    fn select_prev(&mut self, _: &SelectPrev, cx: &mut ViewContext<Self>) {
+        let ix = self.selected_entry.map_or(0, |ix| ix.saturating_sub(1));
+        if ix < self.visible_entries.len() {
+            self.select_visible_entry(ix, cx);
+        }
+    }
+
+    fn select_first(&mut self, _: &SelectFirst, cx: &mut ViewContext<Self>) {
+        if !self.visible_entries.is_empty() {
+            self.select_visible_entry(0, cx);
+        }
+    }
+
+    fn select_last(&mut self, _: &SelectLast, cx: &mut ViewContext<Self>) {
+        if let Some(ix) = self.visible_entries.len().checked_sub(1) {
+            self.select_visible_entry(ix, cx);
+        }
+    }
+
+    fn expand_selected_entry(&mut self, _: &ExpandSelectedEntry, cx: &mut ViewContext<Self>) {
+        let Some(entry_ix) = self.selected_entry_ix() else {
+            return;
+        };
+        if self.entries[entry_ix].expanded {
+            self.select_next(&SelectNext, cx);
+        } else {
+            self.expand_entry(entry_ix, cx);
+            self.update_visible_entries(cx);
+        }
+    }
+
+    fn collapse_selected_entry(&mut self, _: &CollapseSelectedEntry, cx: &mut ViewContext<Self>) {
+        let Some(entry_ix) = self.selected_entry_ix() else {
+            return;
+        };
+        let entry = &mut self.entries[entry_ix];
+        if entry.expanded {
+            entry.expanded = false;
+            self.update_visible_entries(cx);
+        } else if let Some(parent_ix) = entry.parent {
+            if let Some(ix) = self.visible_entries.iter().position(|&ix| ix == parent_ix) {
+                self.select_visible_entry(ix, cx);
+            }
+        }
+    }
+
+    fn confirm(&mut self, _: &Confirm, cx: &mut ViewContext<Self>) {
+        if let Some(entry_ix) = self.selected_entry_ix() {
+            self.open_entry(entry_ix, true, cx);
+        }
+    }
+
+    /// Opens the first call site of the entry, or its definition if there are none.
+    fn open_entry(&mut self, entry_ix: usize, focus: bool, cx: &mut ViewContext<Self>) {
+        let entry = &self.entries[entry_ix];
+        let location = entry
+            .call_sites
+            .first()
+            .unwrap_or(entry.item.location())
+            .clone();
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        workspace.update(cx, |workspace, cx| {
+            let pane = workspace.active_pane().clone();
+            let editor = workspace.open_project_item::<Editor>(
+                pane,
+                location.buffer.clone(),
+                true,
+                focus,
+                cx,
+            );
+            editor.update(cx, |editor, cx| {
+                let range = location.range.to_offset(location.buffer.read(cx));
+                editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                    s.select_ranges([range]);
+                });
+            });
+        });
+    }
+
+    fn render_entry(&self, ix: usize, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let entry_ix = self.visible_entries[ix];
+        let entry = &self.entries[entry_ix];
+        let settings = HierarchyPanelSettings::get_global(cx);
+        let toggle = match &entry.children {
+            Some(children) if children.is_empty() => None,
+            _ => Some(entry.expanded),
+        };
+        let is_loading = entry.expanded && entry.children.is_none();
+        let file_name = entry
+            .item
+            .location()
+            .buffer
+            .read(cx)
+            .file()
+            .map(|file| file.file_name(cx).to_string_lossy().into_owned());
+
+        ListItem::new(ix)
+            .indent_level(entry.depth)
+            .indent_step_size(px(settings.indent_size))
+            .toggle(toggle)
+            .toggle_state(self.selected_entry == Some(ix))
+            .on_toggle(cx.listener(move |panel, _, cx| {
+                panel.toggle_expanded(entry_ix, cx);
+            }))
+            .on_click(cx.listener(move |panel, event: &ClickEvent, cx| {
+                if event.down.button == MouseButton::Right {
+                    return;
+                }
+                panel.select_visible_entry(ix, cx);
+                panel.open_entry(entry_ix, event.down.click_count > 1, cx);
+            }))
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(entry.item.name().to_string()))
+                    .when_some(entry.item.detail(), |this, detail| {
+                        this.child(
+                            Label::new(detail.to_string())
+                                .size(LabelSize::Small)
+                                .color(Color::Muted)
+                                .single_line(),
+                        )
+                    })
+                    .when_some(file_name, |this, file_name| {
+                        this.child(
+                            Label::new(file_name)
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                    })
+                    .when(entry.call_sites.len() > 1, |this| {
+                        this.child(
+                            Label::new(format!("({})", entry.call_sites.len()))
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                    })
+                    .when(is_loading, |this| {
+                        this.child(
+                            Label::new("Loading...")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                    }),
+            )
+    }
+
+    fn render_header(&self) -> impl IntoElement {
+        let title = self.kind.map_or("", |kind| kind.title());
+        h_flex()
+            .px_2()
+            .py_1()
+            .child(Label::new(title).size(LabelSize::Small).color(Color::Muted))
+    }
+
+    fn render_empty_state(&self) -> impl IntoElement {
+        let message = match self.kind {
+            _ if self.loading_roots => "Loading...",
+            Some(HierarchyKind::IncomingCalls | HierarchyKind::OutgoingCalls) => {
+                "No calls found for the symbol at the cursor"
+            }
+            Some(HierarchyKind::Supertypes | HierarchyKind::Subtypes) => {
+                "No type hierarchy found for the symbol at the cursor"
+            }
+            None => "Show the calls or type hierarchy of a symbol from an editor to see them here",
+        };
+        v_flex()
+            .flex_1()
+            .size_full()
+            .justify_center()
+            .p_4()
+            .child(h_flex().justify_center().child(Label::new(message)))
+    }
+}
+
+impl Panel for HierarchyPanel {
+    fn persistent_name() -> &'static str {
+        "Hierarchy Panel"
+    }
+
+    fn position(&self, cx: &WindowContext) -> DockPosition {
+        match HierarchyPanelSettings::get_global(cx).dock {
+            HierarchyPanelDockPosition::Left => DockPosition::Left,
+            HierarchyPanelDockPosition::Right => DockPosition::Right,
+        }
+    }
+
+    fn position_is_valid(&self, position: DockPosition) -> bool {
+        matches!(position, DockPosition::Left | DockPosition::Right)
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        settings::update_settings_file::<HierarchyPanelSettings>(
+            self.fs.clone(),
+            cx,
+            move |settings, _| {
+                let dock = match position {
+                    DockPosition::Left | DockPosition::Bottom => HierarchyPanelDockPosition::Left,
+                    DockPosition::Right => HierarchyPanelDockPosition::Right,
+                };
+                settings.dock = Some(dock);
+            },
+        );
+    }
+
+    fn size(&self, cx: &WindowContext) -> Pixels {
+        self.width
+            .unwrap_or_else(|| HierarchyPanelSettings::get_global(cx).default_width)
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.width = size;
+        self.serialize(cx);
+        cx.notify();
+    }
+
+    fn icon(&self, cx: &WindowContext) -> Option<IconName> {
+        HierarchyPanelSettings::get_global(cx)
+            .button
+            .then_some(IconName::Route)
+    }
+
+    fn icon_tooltip(&self, _: &WindowContext) -> Option<&'static str> {
+        Some("Hierarchy Panel")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+}
+
+impl FocusableView for HierarchyPanel {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<PanelEvent> for HierarchyPanel {}
+
+impl Render for HierarchyPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .id("hierarchy-panel")
+            .size_full()
+            .overflow_hidden()
+            .key_context(self.dispatch_context())
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_prev))
+            .on_action(cx.listener(Self::select_first))
+            .on_action(cx.listener(Self::select_last))
+            .on_action(cx.listener(Self::expand_selected_entry))
+            .on_action(cx.listener(Self::collapse_selected_entry))
+            .on_action(cx.listener(Self::confirm))
+            .map(|this| {
+                if self.visible_entries.is_empty() {
+                    this.child(self.render_empty_state())
+                } else {
+                    this.child(self.render_header()).child(
+                        uniform_list(
+                            cx.view().clone(),
+                            "hierarchy-entries",
+                            self.visible_entries.len(),
+                            |panel, range, cx| range.map(|ix| panel.render_entry(ix, cx)).collect(),
+                        )
+                        .size_full()
+                        .track_scroll(self.scroll_handle.clone()),
+                    )
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{self, AtomicUsize};
+
+    use futures::StreamExt as _;
+    use gpui::{TestAppContext, VisualTestContext};
+    use language::{FakeLspAdapter, Language, LanguageConfig, LanguageMatcher, Point};
+    use project::FakeFs;
+    use serde_json::json;
+    use workspace::AppState;
+
+    use super::*;
+
+    const SELECTED_MARKER: &str = "  <==== selected";
+
+    #[gpui::test]
+    async fn test_expanding_and_collapsing_entries(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "a.rs": "fn a() {\n    b();\n}\nfn b() {\n    c();\n}\nfn c() {}\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+        language_registry.add(Arc::new(Language::new(
+            LanguageConfig {
+                name: "Rust".into(),
+                matcher: LanguageMatcher {
+                    path_suffixes: vec!["rs".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            None,
+        )));
+        let mut fake_servers = language_registry.register_fake_lsp(
+            "Rust",
+            FakeLspAdapter {
+                capabilities: lsp::ServerCapabilities {
+                    call_hierarchy_provider: Some(lsp::CallHierarchyServerCapability::Simple(true)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let (buffer, _handle) = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer_with_lsp("/dir/a.rs", cx)
+            })
+            .await
+            .unwrap();
+
+        fn function_item(name: &str, row: u32) -> lsp::CallHierarchyItem {
+            lsp::CallHierarchyItem {
+                name: name.to_string(),
+                kind: lsp::SymbolKind::FUNCTION,
+                tags: None,
+                detail: None,
+                uri: lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+                range: lsp::Range::new(lsp::Position::new(row, 0), lsp::Position::new(row, 9)),
+                selection_range: lsp::Range::new(
+                    lsp::Position::new(row, 3),
+                    lsp::Position::new(row, 4),
+                ),
+                data: None,
+            }
+        }
+        fn call_site(row: u32) -> lsp::Range {
+            lsp::Range::new(lsp::Position::new(row, 4), lsp::Position::new(row, 5))
+        }
+
+        let fake_server = fake_servers.next().await.unwrap();
+        fake_server.handle_request::<lsp::request::CallHierarchyPrepare, _, _>(|_, _| async move {
+            Ok(Some(vec![function_item("c", 6)]))
+        });
+        let incoming_call_requests = Arc::new(AtomicUsize::new(0));
+        fake_server.handle_request::<lsp::request::CallHierarchyIncomingCalls, _, _>({
+            let incoming_call_requests = incoming_call_requests.clone();
+            move |params, _| {
+                incoming_call_requests.fetch_add(1, atomic::Ordering::SeqCst);
+                async move {
+                    let call = match params.item.name.as_str() {
+                        "c" => lsp::CallHierarchyIncomingCall {
+                            from: function_item("b", 3),
+                            from_ranges: vec![call_site(4)],
+                        },
+                        "b" => lsp::CallHierarchyIncomingCall {
+                            from: function_item("a", 0),
+                            from_ranges: vec![call_site(1)],
+                        },
+                        _ => return Ok(Some(Vec::new())),
+                    };
+                    Ok(Some(vec![call]))
+                }
+            }
+        });
+
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| HierarchyPanel::new(workspace, cx))
+            .unwrap();
+
+        panel.update(cx, |panel, cx| {
+            let items = panel.project.update(cx, |project, cx| {
+                project.prepare_call_hierarchy(&buffer, Point::new(6, 3), cx)
+            });
+            let items = cx.spawn(|_, _| async move {
+                Ok(items.await?.into_iter().map(HierarchyItem::Call).collect())
+            });
+            panel.show(HierarchyKind::IncomingCalls, items, cx);
+        });
+        cx.run_until_parked();
+        assert_eq!(
+            display_entries(&panel, cx),
+            [format!("c{SELECTED_MARKER}"), "  b".to_string()],
+            "A single root should be expanded right away"
+        );
+
+        panel.update(cx, |panel, cx| {
+            panel.select_next(&SelectNext, cx);
+            panel.expand_selected_entry(&ExpandSelectedEntry, cx);
+        });
+        cx.run_until_parked();
+        assert_eq!(
+            display_entries(&panel, cx),
+            [
+                "c".to_string(),
+                format!("  b{SELECTED_MARKER}"),
+                "    a".to_string()
+            ]
+        );
+
+        panel.update(cx, |panel, cx| {
+            panel.expand_selected_entry(&ExpandSelectedEntry, cx);
+        });
+        assert_eq!(
+            display_entries(&panel, cx),
+            [
+                "c".to_string(),
+                "  b".to_string(),
+                format!("    a{SELECTED_MARKER}")
+            ],
+            "Expanding an expanded entry should select its first child"
+        );
+
+        panel.update(cx, |panel, cx| {
+            panel.collapse_selected_entry(&CollapseSelectedEntry, cx);
+        });
+        assert_eq!(
+            display_entries(&panel, cx),
+            [
+                "c".to_string(),
+                format!("  b{SELECTED_MARKER}"),
+                "    a".to_string()
+            ],
+            "Collapsing a collapsed entry should select its parent"
+        );
+
+        panel.update(cx, |panel, cx| {
+            panel.collapse_selected_entry(&CollapseSelectedEntry, cx);
+        });
+        assert_eq!(
+            display_entries(&panel, cx),
+            ["c".to_string(), format!("  b{SELECTED_MARKER}")]
+        );
+
+        let requests_before_reexpanding = incoming_call_requests.load(atomic::Ordering::SeqCst);
+        panel.update(cx, |panel, cx| {
+            panel.expand_selected_entry(&ExpandSelectedEntry, cx);
+        });
+        assert_eq!(
+            display_entries(&panel, cx),
+            [
+                "c".to_string(),
+                format!("  b{SELECTED_MARKER}"),
+                "    a".to_string()
+            ],
+            "Fetched children should be shown again without waiting for the server"
+        );
+        cx.run_until_parked();
+        assert_eq!(
+            incoming_call_requests.load(atomic::Ordering::SeqCst),
+            requests_before_reexpanding
+        );
+    }
+
+    fn display_entries(panel: &View<HierarchyPanel>, cx: &mut VisualTestContext) -> Vec<String> {
+        panel.update(cx, |panel, _| {
+            panel
+                .visible_entries
+                .iter()
+                .enumerate()
+                .map(|(ix, &entry_ix)| {
+                    let entry = &panel.entries[entry_ix];
+                    let mut line = format!("{}{}", "  ".repeat(entry.depth), entry.item.name());
+                    if panel.selected_entry == Some(ix) {
+                        line.push_str(SELECTED_MARKER);
+                    }
+                    line
+                })
+                .collect()
+        })
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            AppState::test(cx);
+            language::init(cx);
+            editor::init(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+            super::init(cx);
+        });
+    }
+}
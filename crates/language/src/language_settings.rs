@@ -136,6 +136,14 @@ pub struct LanguageSettings {
     pub code_actions_on_format: HashMap<String, bool>,
     /// Whether to perform linked edits
     pub linked_edits: bool,
+    /// How semantic tokens from language servers are used for highlighting.
+    pub semantic_tokens: SemanticTokensSetting,
+    /// Whether to show code lenses from language servers above the items they refer to.
+    pub code_lens: bool,
+    /// Where folding ranges come from.
+    pub folding_ranges: FoldingRangesSource,
+    /// Where the ranges used to expand and shrink selections come from.
+    pub selection_ranges: SelectionRangesSource,
     /// Task configuration for this language.
     pub tasks: LanguageTaskConfig,
     /// Whether to pop the completions menu while typing in an editor without
@@ -380,6 +388,24 @@ pub struct LanguageSettingsContent {
     ///
     /// Default: true
     pub linked_edits: Option<bool>,
+    /// How to use semantic tokens from language servers for syntax highlighting.
+    ///
+    /// Default: off
+    pub semantic_tokens: Option<SemanticTokensSetting>,
+    /// Whether to show code lenses provided by language servers, such as reference
+    /// counts or "Run" commands, above the items they refer to.
+    ///
+    /// Default: true
+    pub code_lens: Option<bool>,
+    /// Where the editor gets the foldable ranges of a buffer from.
+    ///
+    /// Default: indentation
+    pub folding_ranges: Option<FoldingRangesSource>,
+    /// Where "select larger syntax node" and "select smaller syntax node" get the
+    /// ranges to expand and shrink selections to from.
+    ///
+    /// Default: tree_sitter
+    pub selection_ranges: Option<SelectionRangesSource>,
     /// Whether indentation of pasted content should be adjusted based on the context.
     ///
     /// Default: true
@@ -568,6 +594,49 @@ pub enum ShowWhitespaceSetting {
     Boundary,
 }
 
+/// Controls how semantic tokens from language servers are used for highlighting.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SemanticTokensSetting {
+    /// Do not request semantic tokens; highlight with tree-sitter only.
+    #[default]
+    Off,
+    /// Layer semantic tokens on top of tree-sitter highlights.
+    Combined,
+    /// Highlight with semantic tokens only, ignoring tree-sitter highlights.
+    Full,
+}
+
+impl SemanticTokensSetting {
+    /// Returns whether semantic tokens should be requested at all.
+    pub fn enabled(&self) -> bool {
+        *self != Self::Off
+    }
+}
+
+/// Controls where foldable ranges come from.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FoldingRangesSource {
+    /// Fold lines that are indented further than the line before them.
+    #[default]
+    Indentation,
+    /// Fold the ranges reported by the language server, such as regions, import
+    /// blocks and comment blocks.
+    LanguageServer,
+}
+
+/// Controls where the ranges used to expand and shrink selections come from.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionRangesSource {
+    /// Expand selections to the enclosing tree-sitter syntax node.
+    #[default]
+    TreeSitter,
+    /// Expand selections to the enclosing selection range reported by the language server.
+    LanguageServer,
+}
+
 /// Controls which formatter should be used when formatting code.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub enum SelectedFormatter {
@@ -1166,6 +1235,10 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
         src.code_actions_on_format.clone(),
     );
     merge(&mut settings.linked_edits, src.linked_edits);
+    merge(&mut settings.semantic_tokens, src.semantic_tokens);
+    merge(&mut settings.code_lens, src.code_lens);
+    merge(&mut settings.folding_ranges, src.folding_ranges);
+    merge(&mut settings.selection_ranges, src.selection_ranges);
     merge(&mut settings.tasks, src.tasks.clone());
 
     merge(
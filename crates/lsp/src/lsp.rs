@@ -83,6 +83,7 @@ pub struct LanguageServer {
     name: LanguageServerName,
     process_name: Arc<str>,
     capabilities: RwLock<ServerCapabilities>,
+    extra_capabilities: ExtraServerCapabilities,
     code_action_kinds: Option<Vec<CodeActionKind>>,
     notification_handlers: Arc<Mutex<HashMap<&'static str, NotificationHandler>>>,
     response_handlers: Arc<Mutex<Option<HashMap<RequestId, ResponseHandler>>>>,
@@ -287,10 +288,40 @@ impl<F: Future> LspRequestFuture<F::Output> for LspRequest<F> {
 pub struct AdapterServerCapabilities {
     // Reported capabilities by the server
     pub server_capabilities: ServerCapabilities,
+    // Reported capabilities by the server that `ServerCapabilities` has no fields for
+    pub extra_capabilities: ExtraServerCapabilities,
     // List of code actions supported by the LspAdapter matching the server
     pub code_action_kinds: Option<Vec<CodeActionKind>>,
 }
 
+/// Capabilities from the server's `initialize` response that [`ServerCapabilities`] doesn't model.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtraServerCapabilities {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub type_hierarchy_provider: Option<OneOf<bool, TypeHierarchyOptions>>,
+}
+
+impl ExtraServerCapabilities {
+    pub fn type_hierarchy_supported(&self) -> bool {
+        match &self.type_hierarchy_provider {
+            Some(OneOf::Left(supported)) => *supported,
+            Some(OneOf::Right(_)) => true,
+            None => false,
+        }
+    }
+}
+
+/// The `initialize` request, with the response kept as JSON so that the capabilities
+/// missing from [`ServerCapabilities`] can be read from it as well.
+enum InitializeWithExtraCapabilities {}
+
+impl request::Request for InitializeWithExtraCapabilities {
+    type Params = InitializeParams;
+    type Result = Value;
+    const METHOD: &'static str = request::Initialize::METHOD;
+}
+
 /// Experimental: Informs the end user about the state of the server
 ///
 /// [Rust Analyzer Specification](https://github.com/rust-lang/rust-analyzer/blob/master/docs/dev/lsp-extensions.md#server-status)
@@ -467,6 +498,7 @@ impl LanguageServer {
             name: server_name,
             process_name: Arc::default(),
             capabilities: Default::default(),
+            extra_capabilities: Default::default(),
             code_action_kinds,
             next_id: Default::default(),
             outbound_tx,
@@ -629,6 +661,12 @@ impl LanguageServer {
                     inlay_hint: Some(InlayHintWorkspaceClientCapabilities {
                         refresh_support: Some(true),
                     }),
+                    semantic_tokens: Some(SemanticTokensWorkspaceClientCapabilities {
+                        refresh_support: Some(true),
+                    }),
+                    code_lens: Some(CodeLensWorkspaceClientCapabilities {
+                        refresh_support: Some(true),
+                    }),
                     diagnostic: Some(DiagnosticWorkspaceClientCapabilities {
                         refresh_support: None,
                     }),
@@ -757,6 +795,76 @@ impl LanguageServer {
                         did_save: Some(true),
                         ..TextDocumentSyncClientCapabilities::default()
                     }),
+                    call_hierarchy: Some(DynamicRegistrationClientCapabilities {
+                        dynamic_registration: Some(false),
+                    }),
+                    type_hierarchy: Some(DynamicRegistrationClientCapabilities {
+                        dynamic_registration: Some(false),
+                    }),
+                    code_lens: Some(CodeLensClientCapabilities {
+                        dynamic_registration: Some(false),
+                    }),
+                    semantic_tokens: Some(SemanticTokensClientCapabilities {
+                        dynamic_registration: Some(false),
+                        requests: SemanticTokensClientCapabilitiesRequests {
+                            range: Some(false),
+                            full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                        },
+                        token_types: vec![
+                            SemanticTokenType::NAMESPACE,
+                            SemanticTokenType::TYPE,
+                            SemanticTokenType::CLASS,
+                            SemanticTokenType::ENUM,
+                            SemanticTokenType::INTERFACE,
+                            SemanticTokenType::STRUCT,
+                            SemanticTokenType::TYPE_PARAMETER,
+                            SemanticTokenType::PARAMETER,
+                            SemanticTokenType::VARIABLE,
+                            SemanticTokenType::PROPERTY,
+                            SemanticTokenType::ENUM_MEMBER,
+                            SemanticTokenType::EVENT,
+                            SemanticTokenType::FUNCTION,
+                            SemanticTokenType::METHOD,
+                            SemanticTokenType::MACRO,
+                            SemanticTokenType::KEYWORD,
+                            SemanticTokenType::MODIFIER,
+                            SemanticTokenType::COMMENT,
+                            SemanticTokenType::STRING,
+                            SemanticTokenType::NUMBER,
+                            SemanticTokenType::REGEXP,
+                            SemanticTokenType::OPERATOR,
+                            SemanticTokenType::DECORATOR,
+                        ],
+                        token_modifiers: vec![
+                            SemanticTokenModifier::DECLARATION,
+                            SemanticTokenModifier::DEFINITION,
+                            SemanticTokenModifier::READONLY,
+                            SemanticTokenModifier::STATIC,
+                            SemanticTokenModifier::DEPRECATED,
+                            SemanticTokenModifier::ABSTRACT,
+                            SemanticTokenModifier::ASYNC,
+                            SemanticTokenModifier::MODIFICATION,
+                            SemanticTokenModifier::DOCUMENTATION,
+                            SemanticTokenModifier::DEFAULT_LIBRARY,
+                        ],
+                        formats: vec![TokenFormat::RELATIVE],
+                        overlapping_token_support: Some(false),
+                        multiline_token_support: Some(false),
+                        server_cancel_support: None,
+                        augments_syntax_tokens: Some(true),
+                    }),
+                    folding_range: Some(FoldingRangeClientCapabilities {
+                        dynamic_registration: Some(false),
+                        line_folding_only: Some(true),
+                        ..FoldingRangeClientCapabilities::default()
+                    }),
+                    selection_range: Some(SelectionRangeClientCapabilities {
+                        dynamic_registration: Some(false),
+                    }),
+                    document_link: Some(DocumentLinkClientCapabilities {
+                        dynamic_registration: Some(false),
+                        tooltip_support: Some(false),
+                    }),
                     ..TextDocumentClientCapabilities::default()
                 }),
                 experimental: Some(json!({
@@ -804,7 +912,16 @@ impl LanguageServer {
         };
 
         cx.spawn(|_| async move {
-            let response = self.request::<request::Initialize>(params).await?;
+            let response = self
+                .request::<InitializeWithExtraCapabilities>(params)
+                .await?;
+            if let Some(capabilities) = response.get("capabilities") {
+                self.extra_capabilities = serde_json::from_value(capabilities.clone())
+                    .context("parsing server capabilities")
+                    .log_err()
+                    .unwrap_or_default();
+            }
+            let response: InitializeResult = serde_json::from_value(response)?;
             if let Some(info) = response.server_info {
                 self.process_name = info.name.into();
             }
@@ -1036,6 +1153,7 @@ impl LanguageServer {
     pub fn adapter_server_capabilities(&self) -> AdapterServerCapabilities {
         AdapterServerCapabilities {
             server_capabilities: self.capabilities(),
+            extra_capabilities: self.extra_capabilities.clone(),
             code_action_kinds: self.code_action_kinds(),
         }
     }
@@ -1246,6 +1364,7 @@ pub struct FakeLanguageServer {
     pub binary: LanguageServerBinary,
     pub server: Arc<LanguageServer>,
     notifications_rx: channel::Receiver<(String, String)>,
+    extra_capabilities: Arc<Mutex<ExtraServerCapabilities>>,
 }
 
 #[cfg(any(test, feature = "test-support"))]
@@ -1309,20 +1428,30 @@ impl FakeLanguageServer {
                 server
             }),
             notifications_rx,
+            extra_capabilities: Default::default(),
         };
-        fake.handle_request::<request::Initialize, _, _>({
+        fake.handle_request::<InitializeWithExtraCapabilities, _, _>({
             let capabilities = capabilities;
+            let extra_capabilities = fake.extra_capabilities.clone();
             move |_, _| {
                 let capabilities = capabilities.clone();
+                let extra_capabilities = extra_capabilities.lock().clone();
                 let name = name.clone();
                 async move {
-                    Ok(InitializeResult {
+                    let mut response = serde_json::to_value(InitializeResult {
                         capabilities,
                         server_info: Some(ServerInfo {
                             name,
                             ..Default::default()
                         }),
-                    })
+                    })?;
+                    if let (Some(Value::Object(capabilities)), Value::Object(extra_capabilities)) = (
+                        response.get_mut("capabilities"),
+                        serde_json::to_value(extra_capabilities)?,
+                    ) {
+                        capabilities.extend(extra_capabilities);
+                    }
+                    Ok(response)
                 }
             }
         });
@@ -1330,6 +1459,11 @@ impl FakeLanguageServer {
         (server, fake)
     }
 
+    /// Sets the capabilities outside of [`ServerCapabilities`] that the server reports once initialized.
+    pub fn set_extra_capabilities(&self, extra_capabilities: ExtraServerCapabilities) {
+        *self.extra_capabilities.lock() = extra_capabilities;
+    }
+
     #[cfg(target_os = "windows")]
     fn root_path() -> &'static Path {
         Path::new("C:\\")
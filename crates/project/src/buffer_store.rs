@@ -10,7 +10,7 @@ use client::Client;
 use collections::{hash_map, HashMap, HashSet};
 use fs::Fs;
 use futures::{channel::oneshot, future::Shared, Future, FutureExt as _, StreamExt};
-use git::{blame::Blame, diff::BufferDiff};
+use git::{blame::Blame, diff::BufferDiff, repository::DiffBase};
 use gpui::{
     AppContext, AsyncAppContext, Context as _, EventEmitter, Model, ModelContext, Subscription,
     Task, WeakModel,
@@ -42,6 +42,8 @@ pub struct BufferStore {
     opened_buffers: HashMap<BufferId, OpenBuffer>,
     downstream_client: Option<(AnyProtoClient, u64)>,
     shared_buffers: HashMap<proto::PeerId, HashMap<BufferId, SharedBuffer>>,
+    /// What the buffers' change sets are diffed against.
+    diff_base: DiffBase,
 }
 
 #[derive(Hash, Eq, PartialEq, Clone)]
@@ -390,9 +392,10 @@ impl RemoteBufferStore {
 }
 
 impl LocalBufferStore {
-    fn load_staged_text(
+    fn load_diff_base_text(
         &self,
         buffer: &Model<Buffer>,
+        diff_base: DiffBase,
         cx: &AppContext,
     ) -> Task<Result<Option<String>>> {
         let Some(file) = buffer.read(cx).file() else {
@@ -408,7 +411,9 @@ impl LocalBufferStore {
             return Task::ready(Err(anyhow!("no such worktree")));
         };
 
-        worktree.read(cx).load_staged_file(path.as_ref(), cx)
+        worktree
+            .read(cx)
+            .load_diff_base_file(path.as_ref(), diff_base, cx)
     }
 
     fn save_local_buffer(
@@ -552,6 +557,7 @@ impl LocalBufferStore {
             return;
         }
 
+        let diff_base = this.diff_base.clone();
         cx.spawn(move |this, mut cx| async move {
             let snapshot =
                 worktree_handle.update(&mut cx, |tree, _| tree.as_local().unwrap().snapshot())?;
@@ -563,7 +569,9 @@ impl LocalBufferStore {
                         .filter_map(|(change_set, buffer_snapshot, path)| {
                             let (repo_entry, local_repo_entry) = snapshot.repo_for_path(&path)?;
                             let relative_path = repo_entry.relativize(&snapshot, &path).ok()?;
-                            let base_text = local_repo_entry.repo().load_index_text(&relative_path);
+                            let base_text = diff_base
+                                .load_text(local_repo_entry.repo().as_ref(), &relative_path)
+                                .log_err()?;
                             Some((change_set, buffer_snapshot, base_text))
                         })
                         .collect::<Vec<_>>()
@@ -583,11 +591,12 @@ impl LocalBufferStore {
 
                     if let Some((client, project_id)) = &this.downstream_client.clone() {
                         client
-                            .send(proto::UpdateDiffBase {
-                                project_id: *project_id,
-                                buffer_id: buffer_snapshot.remote_id().to_proto(),
+                            .send(update_diff_base_message(
+                                *project_id,
+                                buffer_snapshot.remote_id(),
+                                &this.diff_base,
                                 staged_text,
-                            })
+                            ))
                             .log_err();
                     }
                 }
@@ -919,6 +928,7 @@ impl BufferStore {
             loading_buffers: Default::default(),
             loading_change_sets: Default::default(),
             worktree_store,
+            diff_base: DiffBase::Index,
         }
     }
 
@@ -943,6 +953,7 @@ impl BufferStore {
             loading_change_sets: Default::default(),
             shared_buffers: Default::default(),
             worktree_store,
+            diff_base: DiffBase::Index,
         }
     }
 
@@ -1027,7 +1038,9 @@ impl BufferStore {
             hash_map::Entry::Occupied(e) => e.get().clone(),
             hash_map::Entry::Vacant(entry) => {
                 let load = match &self.state {
-                    BufferStoreState::Local(this) => this.load_staged_text(&buffer, cx),
+                    BufferStoreState::Local(this) => {
+                        this.load_diff_base_text(&buffer, self.diff_base.clone(), cx)
+                    }
                     BufferStoreState::Remote(this) => this.load_staged_text(buffer_id, cx),
                 };
 
@@ -1048,6 +1061,151 @@ impl BufferStore {
             .spawn(async move { task.await.map_err(|e| anyhow!("{e}")) })
     }
 
+    pub fn diff_base(&self) -> &DiffBase {
+        &self.diff_base
+    }
+
+    /// Changes what every buffer's change set is diffed against, reloading the base text of
+    /// those already open.
+    pub fn set_diff_base(
+        &mut self,
+        diff_base: DiffBase,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let BufferStoreState::Local(local) = &self.state else {
+            return Task::ready(Err(anyhow!(
+                "the diff base can only be changed in local projects"
+            )));
+        };
+
+        let loads = self
+            .opened_buffers
+            .values()
+            .filter_map(|buffer| {
+                if let OpenBuffer::Complete {
+                    buffer,
+                    unstaged_changes,
+                } = buffer
+                {
+                    let buffer = buffer.upgrade()?;
+                    let change_set = unstaged_changes.as_ref()?.upgrade()?;
+                    let load = local.load_diff_base_text(&buffer, diff_base.clone(), cx);
+                    Some((buffer, change_set, load))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        cx.spawn(move |this, mut cx| async move {
+            // A buffer whose base can't be loaded keeps its previous one rather than
+            // preventing the others from switching. The switch is only abandoned when no
+            // base could be loaded at all, such as for a revision that doesn't exist.
+            let mut base_texts = Vec::with_capacity(loads.len());
+            let mut error = None;
+            for (buffer, change_set, load) in loads {
+                match load.await {
+                    Ok(base_text) => base_texts.push((buffer, change_set, base_text)),
+                    Err(load_error) => {
+                        log::error!("failed to load diff base for buffer: {load_error:?}");
+                        error = Some(load_error);
+                    }
+                }
+            }
+            if let Some(error) = error.filter(|_| base_texts.is_empty()) {
+                return Err(error);
+            }
+
+            this.update(&mut cx, |this, cx| {
+                this.diff_base = diff_base;
+                for (buffer, change_set, base_text) in base_texts {
+                    let buffer_snapshot = buffer.read(cx).text_snapshot();
+                    let buffer_id = buffer_snapshot.remote_id();
+                    change_set.update(cx, |change_set, cx| {
+                        if let Some(base_text) = base_text.clone() {
+                            let _ = change_set.set_base_text(base_text, buffer_snapshot, cx);
+                        } else {
+                            change_set.unset_base_text(buffer_snapshot, cx);
+                        }
+                    });
+
+                    if let Some((client, project_id)) = &this.downstream_client {
+                        client
+                            .send(update_diff_base_message(
+                                *project_id,
+                                buffer_id,
+                                &this.diff_base,
+                                base_text,
+                            ))
+                            .log_err();
+                    }
+                }
+            })
+        })
+    }
+
+    /// Loads the text of the buffer's file at the repository's HEAD commit.
+    pub fn load_committed_text(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Task<Result<Option<String>>> {
+        let Some(file) = File::from_dyn(buffer.read(cx).file()) else {
+            return Task::ready(Err(anyhow!("buffer has no file")));
+        };
+        file.worktree.read(cx).load_committed_file(&file.path, cx)
+    }
+
+    /// Writes new staged text for the buffer's file to the index, updating the buffer's
+    /// unstaged changes right away rather than waiting for the repository to be rescanned.
+    pub fn set_index_text(
+        &mut self,
+        buffer: &Model<Buffer>,
+        index_text: Option<String>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let Some(file) = File::from_dyn(buffer.read(cx).file()) else {
+            return Task::ready(Err(anyhow!("buffer has no file")));
+        };
+        let write = file
+            .worktree
+            .read(cx)
+            .write_staged_file(&file.path, index_text.clone(), cx);
+        let buffer = buffer.clone();
+        cx.spawn(move |this, mut cx| async move {
+            write.await?;
+            this.update(&mut cx, |this, cx| {
+                // Change sets diffed against a revision aren't affected by the index.
+                if this.diff_base != DiffBase::Index {
+                    return;
+                }
+
+                let buffer_snapshot = buffer.read(cx).text_snapshot();
+                let buffer_id = buffer_snapshot.remote_id();
+                if let Some(change_set) = this.get_unstaged_changes(buffer_id) {
+                    change_set.update(cx, |change_set, cx| {
+                        if let Some(index_text) = index_text.clone() {
+                            let _ = change_set.set_base_text(index_text, buffer_snapshot, cx);
+                        } else {
+                            change_set.unset_base_text(buffer_snapshot, cx);
+                        }
+                    });
+                }
+
+                if let Some((client, project_id)) = &this.downstream_client {
+                    client
+                        .send(update_diff_base_message(
+                            *project_id,
+                            buffer_id,
+                            &DiffBase::Index,
+                            index_text,
+                        ))
+                        .log_err();
+                }
+            })
+        })
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     pub fn set_change_set(&mut self, buffer_id: BufferId, change_set: Model<BufferChangeSet>) {
         self.loading_change_sets
@@ -1970,9 +2128,10 @@ impl BufferStore {
         else {
             return Ok(());
         };
+        let base_text = request.payload.base_text.or(request.payload.staged_text);
         change_set.update(&mut cx, |change_set, cx| {
-            if let Some(staged_text) = request.payload.staged_text {
-                let _ = change_set.set_base_text(staged_text, buffer.read(cx).text_snapshot(), cx);
+            if let Some(base_text) = base_text {
+                let _ = change_set.set_base_text(base_text, buffer.read(cx).text_snapshot(), cx);
             } else {
                 change_set.unset_base_text(buffer.read(cx).text_snapshot(), cx)
             }
@@ -2317,6 +2476,25 @@ impl OpenBuffer {
     }
 }
 
+/// Builds the message telling downstream clients about a buffer's new diff base text.
+fn update_diff_base_message(
+    project_id: u64,
+    buffer_id: BufferId,
+    diff_base: &DiffBase,
+    text: Option<String>,
+) -> proto::UpdateDiffBase {
+    let (staged_text, base_text) = match diff_base {
+        DiffBase::Index => (text, None),
+        DiffBase::Revision(_) | DiffBase::MergeBase(_) => (None, text),
+    };
+    proto::UpdateDiffBase {
+        project_id,
+        buffer_id: buffer_id.to_proto(),
+        staged_text,
+        base_text,
+    }
+}
+
 fn is_not_found_error(error: &anyhow::Error) -> bool {
     error
         .root_cause()
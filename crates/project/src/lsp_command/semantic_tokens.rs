@@ -0,0 +1,246 @@
+use std::ops::Range;
+
+use anyhow::{Context as _, Result};
+use language::{
+    proto::{deserialize_anchor, serialize_anchor, serialize_version},
+    Anchor, Bias, BufferSnapshot, PointUtf16, Unclipped,
+};
+use rpc::proto;
+
+/// The semantic tokens of a buffer, as reported by its primary language server.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SemanticTokens {
+    /// The token types from the server's legend, such as `function` or `parameter`.
+    pub token_types: Vec<String>,
+    /// The token modifiers from the server's legend, such as `readonly` or `static`.
+    pub token_modifiers: Vec<String>,
+    /// The tokens, ordered by their position in the buffer.
+    pub tokens: Vec<SemanticToken>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SemanticToken {
+    pub range: Range<Anchor>,
+    /// An index into [`SemanticTokens::token_types`].
+    pub token_type: u32,
+    /// A bit set of indices into [`SemanticTokens::token_modifiers`].
+    pub token_modifiers: u32,
+}
+
+impl SemanticTokens {
+    /// Returns the name of the given token's type, if the legend has one.
+    pub fn token_type(&self, token: &SemanticToken) -> Option<&str> {
+        self.token_types
+            .get(token.token_type as usize)
+            .map(String::as_str)
+    }
+
+    /// Returns the names of the given token's modifiers.
+    pub fn token_modifiers<'a>(
+        &'a self,
+        token: &'a SemanticToken,
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        self.token_modifiers
+            .iter()
+            .enumerate()
+            .filter(|(ix, _)| *ix < 32 && token.token_modifiers & (1 << ix) != 0)
+            .map(|(_, modifier)| modifier.as_str())
+    }
+
+    pub(crate) fn from_lsp(
+        legend: &lsp::SemanticTokensLegend,
+        data: &[u32],
+        snapshot: &BufferSnapshot,
+    ) -> Self {
+        let mut tokens = Vec::with_capacity(data.len() / 5);
+        let mut line = 0;
+        let mut start = 0;
+        for token in data.chunks_exact(5) {
+            let [delta_line, delta_start, length, token_type, token_modifiers] = *token else {
+                continue;
+            };
+            if delta_line > 0 {
+                line += delta_line;
+                start = delta_start;
+            } else {
+                start += delta_start;
+            }
+            let token_start =
+                snapshot.clip_point_utf16(Unclipped(PointUtf16::new(line, start)), Bias::Left);
+            let token_end = snapshot.clip_point_utf16(
+                Unclipped(PointUtf16::new(line, start + length)),
+                Bias::Right,
+            );
+            if token_start < token_end {
+                tokens.push(SemanticToken {
+                    range: snapshot.anchor_after(token_start)..snapshot.anchor_before(token_end),
+                    token_type,
+                    token_modifiers,
+                });
+            }
+        }
+
+        Self {
+            token_types: legend
+                .token_types
+                .iter()
+                .map(|token_type| token_type.as_str().to_string())
+                .collect(),
+            token_modifiers: legend
+                .token_modifiers
+                .iter()
+                .map(|modifier| modifier.as_str().to_string())
+                .collect(),
+            tokens,
+        }
+    }
+
+    pub(crate) fn to_proto(&self, version: &clock::Global) -> proto::GetSemanticTokensResponse {
+        proto::GetSemanticTokensResponse {
+            token_types: self.token_types.clone(),
+            token_modifiers: self.token_modifiers.clone(),
+            tokens: self
+                .tokens
+                .iter()
+                .map(|token| proto::SemanticToken {
+                    start: Some(serialize_anchor(&token.range.start)),
+                    end: Some(serialize_anchor(&token.range.end)),
+                    token_type: token.token_type,
+                    token_modifiers: token.token_modifiers,
+                })
+                .collect(),
+            version: serialize_version(version),
+        }
+    }
+
+    pub(crate) fn from_proto(message: proto::GetSemanticTokensResponse) -> Result<Self> {
+        let tokens = message
+            .tokens
+            .into_iter()
+            .map(|token| {
+                let start = token
+                    .start
+                    .and_then(deserialize_anchor)
+                    .context("missing semantic token start")?;
+                let end = token
+                    .end
+                    .and_then(deserialize_anchor)
+                    .context("missing semantic token end")?;
+                Ok(SemanticToken {
+                    range: start..end,
+                    token_type: token.token_type,
+                    token_modifiers: token.token_modifiers,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            token_types: message.token_types,
+            token_modifiers: message.token_modifiers,
+            tokens,
+        })
+    }
+}
+
+/// Returns the server's semantic token legend and whether it can send deltas
+/// between full responses, or `None` if it can't provide full semantic tokens.
+pub(crate) fn semantic_tokens_legend(
+    capabilities: &lsp::ServerCapabilities,
+) -> Option<(&lsp::SemanticTokensLegend, bool)> {
+    let options = match capabilities.semantic_tokens_provider.as_ref()? {
+        lsp::SemanticTokensServerCapabilities::SemanticTokensOptions(options) => options,
+        lsp::SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(options) => {
+            &options.semantic_tokens_options
+        }
+    };
+    let supports_delta = match options.full.as_ref()? {
+        lsp::SemanticTokensFullOptions::Bool(false) => return None,
+        lsp::SemanticTokensFullOptions::Bool(true) => false,
+        lsp::SemanticTokensFullOptions::Delta { delta } => delta.unwrap_or(false),
+    };
+    Some((&options.legend, supports_delta))
+}
+
+/// Flattens tokens into the integer encoding that semantic token edits refer to.
+pub(crate) fn semantic_tokens_data(tokens: Vec<lsp::SemanticToken>) -> Vec<u32> {
+    tokens
+        .into_iter()
+        .flat_map(|token| {
+            [
+                token.delta_line,
+                token.delta_start,
+                token.length,
+                token.token_type,
+                token.token_modifiers_bitset,
+            ]
+        })
+        .collect()
+}
+
+/// Applies the edits of a semantic tokens delta response to the previous response's data.
+pub(crate) fn apply_semantic_tokens_edits(
+    data: &mut Vec<u32>,
+    mut edits: Vec<lsp::SemanticTokensEdit>,
+) -> Result<()> {
+    // Edits are relative to the previous data, so apply them back to front.
+    edits.sort_by_key(|edit| edit.start);
+    for edit in edits.into_iter().rev() {
+        let start = edit.start as usize;
+        let end = start + edit.delete_count as usize;
+        anyhow::ensure!(
+            end <= data.len(),
+            "semantic tokens edit {start}..{end} is out of bounds for {} integers",
+            data.len()
+        );
+        data.splice(
+            start..end,
+            semantic_tokens_data(edit.data.unwrap_or_default()),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_semantic_tokens_edits() {
+        let token = |delta_line, delta_start, length| lsp::SemanticToken {
+            delta_line,
+            delta_start,
+            length,
+            token_type: 0,
+            token_modifiers_bitset: 0,
+        };
+        let mut data = semantic_tokens_data(vec![token(0, 0, 3), token(1, 4, 5), token(0, 6, 2)]);
+        apply_semantic_tokens_edits(
+            &mut data,
+            vec![
+                lsp::SemanticTokensEdit {
+                    start: 10,
+                    delete_count: 5,
+                    data: None,
+                },
+                lsp::SemanticTokensEdit {
+                    start: 0,
+                    delete_count: 0,
+                    data: Some(vec![token(0, 1, 1)]),
+                },
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            data,
+            semantic_tokens_data(vec![token(0, 1, 1), token(0, 0, 3), token(1, 4, 5)])
+        );
+        assert!(apply_semantic_tokens_edits(
+            &mut data,
+            vec![lsp::SemanticTokensEdit {
+                start: 10,
+                delete_count: 10,
+                data: None,
+            }],
+        )
+        .is_err());
+    }
+}
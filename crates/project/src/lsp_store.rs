@@ -10,8 +10,9 @@ use crate::{
     toolchain_store::{EmptyToolchainStore, ToolchainStoreEvent},
     worktree_store::{WorktreeStore, WorktreeStoreEvent},
     yarn::YarnPathStore,
-    CodeAction, Completion, CoreCompletion, Hover, InlayHint, ProjectItem as _, ProjectPath,
-    ProjectTransaction, ResolveState, Symbol, ToolchainStore,
+    CallHierarchyCall, CallHierarchyItem, CodeAction, CodeLens, Completion, CoreCompletion,
+    DocumentLink, Hover, InlayHint, ProjectItem as _, ProjectPath, ProjectTransaction,
+    ResolveState, Symbol, ToolchainStore, TypeHierarchyItem,
 };
 use anyhow::{anyhow, Context as _, Result};
 use async_trait::async_trait;
@@ -160,6 +161,9 @@ pub struct LocalLspStore {
     prettier_store: Model<PrettierStore>,
     current_lsp_settings: HashMap<LanguageServerName, LspSettings>,
     next_diagnostic_group_id: usize,
+    /// The pseudo language server ids that task problem matchers report diagnostics under,
+    /// along with the paths they last reported, keyed by the matcher's source.
+    task_diagnostics: HashMap<String, (LanguageServerId, HashSet<PathBuf>)>,
     diagnostics: HashMap<
         WorktreeId,
         HashMap<
@@ -171,6 +175,8 @@ pub struct LocalLspStore {
         >,
     >,
     buffer_snapshots: HashMap<BufferId, HashMap<LanguageServerId, Vec<LspBufferSnapshot>>>, // buffer_id -> server_id -> vec of snapshots
+    /// The last semantic tokens response for each buffer, which delta requests are relative to.
+    semantic_tokens: HashMap<BufferId, LspSemanticTokens>,
     _subscription: gpui::Subscription,
     registered_buffers: HashMap<BufferId, usize>,
 }
@@ -819,6 +825,48 @@ impl LocalLspStore {
             })
             .detach();
 
+        language_server
+            .on_request::<lsp::request::SemanticTokensRefresh, _, _>({
+                let this = this.clone();
+                move |(), mut cx| {
+                    let this = this.clone();
+                    async move {
+                        this.update(&mut cx, |this, cx| {
+                            cx.emit(LspStoreEvent::RefreshSemanticTokens);
+                            this.downstream_client.as_ref().map(|(client, project_id)| {
+                                client.send(proto::RefreshSemanticTokens {
+                                    project_id: *project_id,
+                                })
+                            })
+                        })?
+                        .transpose()?;
+                        Ok(())
+                    }
+                }
+            })
+            .detach();
+
+        language_server
+            .on_request::<lsp::request::CodeLensRefresh, _, _>({
+                let this = this.clone();
+                move |(), mut cx| {
+                    let this = this.clone();
+                    async move {
+                        this.update(&mut cx, |this, cx| {
+                            cx.emit(LspStoreEvent::RefreshCodeLens);
+                            this.downstream_client.as_ref().map(|(client, project_id)| {
+                                client.send(proto::RefreshCodeLens {
+                                    project_id: *project_id,
+                                })
+                            })
+                        })?
+                        .transpose()?;
+                        Ok(())
+                    }
+                }
+            })
+            .detach();
+
         language_server
             .on_request::<lsp::request::ShowMessageRequest, _, _>({
                 let this = this.clone();
@@ -1957,6 +2005,7 @@ impl LocalLspStore {
     ) {
         buffer.update(cx, |buffer, cx| {
             self.buffer_snapshots.remove(&buffer.remote_id());
+            self.semantic_tokens.remove(&buffer.remote_id());
             for (_, language_server) in self.language_servers_for_buffer(buffer, cx) {
                 language_server
                     .notify::<lsp::notification::DidCloseTextDocument>(
@@ -2778,6 +2827,8 @@ pub enum LspStoreEvent {
     },
     Notification(String),
     RefreshInlayHints,
+    RefreshSemanticTokens,
+    RefreshCodeLens,
     DiagnosticsUpdated {
         language_server_id: LanguageServerId,
         path: ProjectPath,
@@ -2827,10 +2878,13 @@ impl LspStore {
         client.add_model_request_handler(Self::handle_resolve_completion_documentation);
         client.add_model_request_handler(Self::handle_apply_code_action);
         client.add_model_request_handler(Self::handle_inlay_hints);
+        client.add_model_request_handler(Self::handle_get_semantic_tokens);
         client.add_model_request_handler(Self::handle_get_project_symbols);
         client.add_model_request_handler(Self::handle_resolve_inlay_hint);
         client.add_model_request_handler(Self::handle_open_buffer_for_symbol);
         client.add_model_request_handler(Self::handle_refresh_inlay_hints);
+        client.add_model_request_handler(Self::handle_refresh_semantic_tokens);
+        client.add_model_request_handler(Self::handle_refresh_code_lens);
         client.add_model_request_handler(Self::handle_on_type_formatting);
         client.add_model_request_handler(Self::handle_apply_additional_edits_for_completion);
         client.add_model_request_handler(Self::handle_register_buffer_with_language_servers);
@@ -2846,6 +2900,18 @@ impl LspStore {
         client.add_model_request_handler(Self::handle_lsp_command::<PerformRename>);
         client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::ExpandMacro>);
         client.add_model_request_handler(Self::handle_lsp_command::<LinkedEditingRange>);
+        client.add_model_request_handler(Self::handle_lsp_command::<PrepareCallHierarchy>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetIncomingCalls>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetOutgoingCalls>);
+        client.add_model_request_handler(Self::handle_lsp_command::<PrepareTypeHierarchy>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetSupertypes>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetSubtypes>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetCodeLens>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetFoldingRanges>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetSelectionRanges>);
+        client.add_model_request_handler(Self::handle_resolve_code_lens);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetDocumentLinks>);
+        client.add_model_request_handler(Self::handle_resolve_document_link);
     }
 
     pub fn as_remote(&self) -> Option<&RemoteLspStore> {
@@ -2951,11 +3017,13 @@ impl LspStore {
                 fs,
                 yarn,
                 next_diagnostic_group_id: Default::default(),
+                task_diagnostics: Default::default(),
                 diagnostics: Default::default(),
                 _subscription: cx.on_app_quit(|this, cx| {
                     this.as_local_mut().unwrap().shutdown_language_servers(cx)
                 }),
                 registered_buffers: HashMap::default(),
+                semantic_tokens: HashMap::default(),
             }),
             last_formatting_failure: None,
             downstream_client: None,
@@ -3855,6 +3923,265 @@ impl LspStore {
         )
     }
 
+    pub fn code_lens(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CodeLens>>> {
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetCodeLens,
+            cx,
+        )
+    }
+
+    pub fn folding_ranges(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<Range<Anchor>>>> {
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetFoldingRanges,
+            cx,
+        )
+    }
+
+    pub fn selection_ranges(
+        &mut self,
+        buffer: &Model<Buffer>,
+        positions: Vec<Anchor>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<Vec<Range<Anchor>>>>> {
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetSelectionRanges { positions },
+            cx,
+        )
+    }
+
+    pub fn resolve_code_lens(
+        &self,
+        buffer_handle: &Model<Buffer>,
+        lens: CodeLens,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<CodeLens>> {
+        if lens.lsp_lens.command.is_some() {
+            return Task::ready(Ok(lens));
+        }
+
+        if let Some((upstream_client, project_id)) = self.upstream_client() {
+            let request = proto::ResolveCodeLens {
+                project_id,
+                buffer_id: buffer_handle.read(cx).remote_id().into(),
+                lens: Some(GetCodeLens::project_to_proto_lens(&lens)),
+            };
+            cx.spawn(move |_, _| async move {
+                let response = upstream_client
+                    .request(request)
+                    .await
+                    .context("code lens resolve proto request")?;
+                GetCodeLens::proto_to_project_lens(
+                    response.lens.context("missing resolved code lens")?,
+                )
+            })
+        } else {
+            let buffer = buffer_handle.read(cx);
+            let Some((_, lang_server)) =
+                self.language_server_for_local_buffer(buffer, lens.server_id, cx)
+            else {
+                return Task::ready(Ok(lens));
+            };
+            if !GetCodeLens::can_resolve_lens(&lang_server.capabilities()) {
+                return Task::ready(Ok(lens));
+            }
+
+            let lang_server = lang_server.clone();
+            cx.spawn(move |_, _| async move {
+                let lsp_lens = lang_server
+                    .request::<lsp::request::CodeLensResolve>(lens.lsp_lens.clone())
+                    .await
+                    .context("code lens resolve LSP request")?;
+                Ok(CodeLens { lsp_lens, ..lens })
+            })
+        }
+    }
+
+    pub fn document_links(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<DocumentLink>>> {
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetDocumentLinks,
+            cx,
+        )
+    }
+
+    pub fn resolve_document_link(
+        &self,
+        buffer_handle: &Model<Buffer>,
+        link: DocumentLink,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<DocumentLink>> {
+        if link.lsp_link.target.is_some() {
+            return Task::ready(Ok(link));
+        }
+
+        if let Some((upstream_client, project_id)) = self.upstream_client() {
+            let request = proto::ResolveDocumentLink {
+                project_id,
+                buffer_id: buffer_handle.read(cx).remote_id().into(),
+                link: Some(GetDocumentLinks::project_to_proto_link(&link)),
+            };
+            cx.spawn(move |_, _| async move {
+                let response = upstream_client
+                    .request(request)
+                    .await
+                    .context("document link resolve proto request")?;
+                GetDocumentLinks::proto_to_project_link(
+                    response.link.context("missing resolved document link")?,
+                )
+            })
+        } else {
+            let buffer = buffer_handle.read(cx);
+            let Some((_, lang_server)) =
+                self.language_server_for_local_buffer(buffer, link.server_id, cx)
+            else {
+                return Task::ready(Ok(link));
+            };
+            if !GetDocumentLinks::can_resolve_link(&lang_server.capabilities()) {
+                return Task::ready(Ok(link));
+            }
+
+            let lang_server = lang_server.clone();
+            cx.spawn(move |_, _| async move {
+                let lsp_link = lang_server
+                    .request::<lsp::request::DocumentLinkResolve>(link.lsp_link.clone())
+                    .await
+                    .context("document link resolve LSP request")?;
+                Ok(DocumentLink { lsp_link, ..link })
+            })
+        }
+    }
+
+    /// Runs the command of a resolved code lens. This goes through the same path as
+    /// code action commands, so edits the server applies while running it are
+    /// returned as a transaction.
+    pub fn execute_code_lens(
+        &self,
+        buffer: Model<Buffer>,
+        lens: CodeLens,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<ProjectTransaction>> {
+        let Some(command) = lens.lsp_lens.command else {
+            return Task::ready(Err(anyhow!("code lens has no command to run")));
+        };
+        let action = CodeAction {
+            server_id: lens.server_id,
+            range: lens.range,
+            lsp_action: lsp::CodeAction {
+                title: command.title.clone(),
+                command: Some(command),
+                ..Default::default()
+            },
+        };
+        self.apply_code_action(buffer, action, true, cx)
+    }
+
+    pub fn prepare_call_hierarchy(
+        &mut self,
+        buffer: &Model<Buffer>,
+        position: PointUtf16,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyItem>>> {
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            PrepareCallHierarchy { position },
+            cx,
+        )
+    }
+
+    pub fn incoming_calls(
+        &mut self,
+        item: &CallHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyCall>>> {
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetIncomingCalls {
+                item: item.lsp_item.clone(),
+            },
+            cx,
+        )
+    }
+
+    pub fn outgoing_calls(
+        &mut self,
+        item: &CallHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyCall>>> {
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetOutgoingCalls {
+                item: item.lsp_item.clone(),
+            },
+            cx,
+        )
+    }
+
+    pub fn prepare_type_hierarchy(
+        &mut self,
+        buffer: &Model<Buffer>,
+        position: PointUtf16,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<TypeHierarchyItem>>> {
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            PrepareTypeHierarchy { position },
+            cx,
+        )
+    }
+
+    pub fn supertypes(
+        &mut self,
+        item: &TypeHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<TypeHierarchyItem>>> {
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetSupertypes {
+                item: item.lsp_item.clone(),
+            },
+            cx,
+        )
+    }
+
+    pub fn subtypes(
+        &mut self,
+        item: &TypeHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<TypeHierarchyItem>>> {
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetSubtypes {
+                item: item.lsp_item.clone(),
+            },
+            cx,
+        )
+    }
+
     fn apply_on_type_formatting(
         &mut self,
         buffer: Model<Buffer>,
@@ -4546,6 +4873,154 @@ impl LspStore {
         }
     }
 
+    /// Requests the semantic tokens of the whole buffer from its primary language server,
+    /// asking only for the changes since the previous response when the server supports it.
+    pub fn semantic_tokens(
+        &mut self,
+        buffer_handle: Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<anyhow::Result<SemanticTokens>> {
+        let buffer = buffer_handle.read(cx);
+        let buffer_id = buffer.remote_id();
+
+        if let Some((client, project_id)) = self.upstream_client() {
+            let request = proto::GetSemanticTokens {
+                project_id,
+                buffer_id: buffer_id.into(),
+                version: serialize_version(&buffer.version()),
+            };
+            return cx.spawn(move |_, mut cx| async move {
+                let response = client
+                    .request(request)
+                    .await
+                    .context("semantic tokens proto request")?;
+                buffer_handle
+                    .update(&mut cx, |buffer, _| {
+                        buffer.wait_for_version(deserialize_version(&response.version))
+                    })?
+                    .await?;
+                SemanticTokens::from_proto(response)
+            });
+        }
+
+        let Some(language_server) = self.as_local().and_then(|local| {
+            local
+                .primary_language_server_for_buffer(buffer, cx)
+                .map(|(_, server)| server.clone())
+        }) else {
+            return Task::ready(Ok(SemanticTokens::default()));
+        };
+        let Some(file) = File::from_dyn(buffer.file()).and_then(File::as_local) else {
+            return Task::ready(Ok(SemanticTokens::default()));
+        };
+        let capabilities = language_server.capabilities();
+        let Some((legend, supports_delta)) = semantic_tokens_legend(&capabilities) else {
+            return Task::ready(Ok(SemanticTokens::default()));
+        };
+        let legend = legend.clone();
+        let text_document =
+            lsp::TextDocumentIdentifier::new(lsp::Url::from_file_path(file.abs_path(cx)).unwrap());
+        let snapshot = buffer.snapshot();
+        let server_id = language_server.server_id();
+        // The previous result stays cached until the response arrives, so that a request
+        // dropped before then doesn't make the next one fetch all of the tokens again.
+        let previous = self
+            .as_local()
+            .and_then(|local| local.semantic_tokens.get(&buffer_id))
+            .filter(|previous| supports_delta && previous.server_id == server_id)
+            .cloned();
+
+        cx.spawn(move |this, mut cx| async move {
+            let response = async {
+                anyhow::Ok(match previous {
+                    Some(previous) => {
+                        let response = language_server
+                            .request::<lsp::request::SemanticTokensFullDeltaRequest>(
+                                lsp::SemanticTokensDeltaParams {
+                                    text_document,
+                                    previous_result_id: previous.result_id,
+                                    work_done_progress_params: Default::default(),
+                                    partial_result_params: Default::default(),
+                                },
+                            )
+                            .await
+                            .context("semantic tokens delta LSP request")?;
+                        match response {
+                            Some(lsp::SemanticTokensFullDeltaResult::Tokens(tokens)) => {
+                                (tokens.result_id, semantic_tokens_data(tokens.data))
+                            }
+                            Some(lsp::SemanticTokensFullDeltaResult::TokensDelta(delta)) => {
+                                let mut data = previous.data;
+                                apply_semantic_tokens_edits(&mut data, delta.edits)?;
+                                (delta.result_id, data)
+                            }
+                            Some(lsp::SemanticTokensFullDeltaResult::PartialTokensDelta {
+                                ..
+                            })
+                            | None => (None, Vec::new()),
+                        }
+                    }
+                    None => {
+                        let response = language_server
+                            .request::<lsp::request::SemanticTokensFullRequest>(
+                                lsp::SemanticTokensParams {
+                                    text_document,
+                                    work_done_progress_params: Default::default(),
+                                    partial_result_params: Default::default(),
+                                },
+                            )
+                            .await
+                            .context("semantic tokens LSP request")?;
+                        match response {
+                            Some(lsp::SemanticTokensResult::Tokens(tokens)) => {
+                                (tokens.result_id, semantic_tokens_data(tokens.data))
+                            }
+                            Some(lsp::SemanticTokensResult::Partial(_)) | None => {
+                                (None, Vec::new())
+                            }
+                        }
+                    }
+                })
+            }
+            .await;
+            let (result_id, data) = match response {
+                Ok(response) => response,
+                Err(error) => {
+                    // The server may no longer know the previous result, so the next
+                    // request asks for all of the tokens again.
+                    this.update(&mut cx, |this, _| {
+                        if let Some(local) = this.as_local_mut() {
+                            local.semantic_tokens.remove(&buffer_id);
+                        }
+                    })?;
+                    return Err(error);
+                }
+            };
+
+            let tokens = SemanticTokens::from_lsp(&legend, &data, &snapshot);
+            this.update(&mut cx, |this, _| {
+                if let Some(local) = this.as_local_mut() {
+                    match result_id {
+                        Some(result_id) => {
+                            local.semantic_tokens.insert(
+                                buffer_id,
+                                LspSemanticTokens {
+                                    server_id,
+                                    result_id,
+                                    data,
+                                },
+                            );
+                        }
+                        None => {
+                            local.semantic_tokens.remove(&buffer_id);
+                        }
+                    }
+                }
+            })?;
+            Ok(tokens)
+        })
+    }
+
     pub fn signature_help<T: ToPointUtf16>(
         &mut self,
         buffer: &Model<Buffer>,
@@ -5279,6 +5754,83 @@ impl LspStore {
             .insert((worktree_id, language_server_name), language_server_id);
     }
 
+    /// Replaces the diagnostics reported by task problem matchers with the given source.
+    /// Problem paths must be absolute; passing no problems clears the source's diagnostics.
+    pub fn update_task_diagnostics(
+        &mut self,
+        source: &str,
+        problems: Vec<task::Problem>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let Some(local) = self.as_local_mut() else {
+            anyhow::bail!("task diagnostics can only be reported in local projects");
+        };
+        let languages = local.languages.clone();
+        let (server_id, paths) = local
+            .task_diagnostics
+            .entry(source.to_string())
+            .or_insert_with(|| (languages.next_language_server_id(), HashSet::default()));
+        let server_id = *server_id;
+        let old_paths = paths.clone();
+
+        let mut diagnostics_by_path = HashMap::<PathBuf, Vec<_>>::default();
+        for problem in problems {
+            let start = PointUtf16::new(
+                problem.line.saturating_sub(1),
+                problem.column.unwrap_or(1).saturating_sub(1),
+            );
+            let end = PointUtf16::new(
+                problem.end_line.unwrap_or(problem.line).saturating_sub(1),
+                problem
+                    .end_column
+                    .or(problem.column)
+                    .unwrap_or(1)
+                    .saturating_sub(1),
+            );
+            let severity = match problem.severity {
+                task::ProblemSeverity::Error => DiagnosticSeverity::ERROR,
+                task::ProblemSeverity::Warning => DiagnosticSeverity::WARNING,
+                task::ProblemSeverity::Info => DiagnosticSeverity::INFORMATION,
+            };
+            diagnostics_by_path
+                .entry(problem.path)
+                .or_default()
+                .push(DiagnosticEntry {
+                    range: Unclipped(start)..Unclipped(end.max(start)),
+                    diagnostic: Diagnostic {
+                        source: Some(problem.source),
+                        code: problem.code,
+                        severity,
+                        message: problem.message,
+                        group_id: post_inc(&mut local.next_diagnostic_group_id),
+                        is_primary: true,
+                        is_disk_based: true,
+                        is_unnecessary: false,
+                        data: None,
+                    },
+                });
+        }
+
+        let new_paths = diagnostics_by_path.keys().cloned().collect::<HashSet<_>>();
+        // Until every path has been updated, any of them may still have diagnostics from this
+        // source, so keep them all around to be cleared by the next update if this one fails.
+        paths.extend(new_paths.iter().cloned());
+        for path in old_paths.difference(&new_paths) {
+            self.update_diagnostic_entries(server_id, path.clone(), None, Vec::new(), cx)?;
+        }
+        for (path, mut diagnostics) in diagnostics_by_path {
+            diagnostics.sort_by_key(|entry| (entry.range.start.0, entry.range.end.0));
+            self.update_diagnostic_entries(server_id, path, None, diagnostics, cx)?;
+        }
+        if let Some((_, paths)) = self
+            .as_local_mut()
+            .and_then(|local| local.task_diagnostics.get_mut(source))
+        {
+            *paths = new_paths;
+        }
+        Ok(())
+    }
+
     pub fn update_diagnostic_entries(
         &mut self,
         server_id: LanguageServerId,
@@ -6570,6 +7122,28 @@ impl LspStore {
         Ok(proto::Ack {})
     }
 
+    async fn handle_refresh_semantic_tokens(
+        this: Model<Self>,
+        _: TypedEnvelope<proto::RefreshSemanticTokens>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        this.update(&mut cx, |_, cx| {
+            cx.emit(LspStoreEvent::RefreshSemanticTokens);
+        })?;
+        Ok(proto::Ack {})
+    }
+
+    async fn handle_refresh_code_lens(
+        this: Model<Self>,
+        _: TypedEnvelope<proto::RefreshCodeLens>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        this.update(&mut cx, |_, cx| {
+            cx.emit(LspStoreEvent::RefreshCodeLens);
+        })?;
+        Ok(proto::Ack {})
+    }
+
     async fn handle_inlay_hints(
         this: Model<Self>,
         envelope: TypedEnvelope<proto::InlayHints>,
@@ -6615,6 +7189,78 @@ impl LspStore {
         })
     }
 
+    async fn handle_get_semantic_tokens(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GetSemanticTokens>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::GetSemanticTokensResponse> {
+        let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+        let buffer = this.update(&mut cx, |this, cx| {
+            this.buffer_store.read(cx).get_existing(buffer_id)
+        })??;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&envelope.payload.version))
+            })?
+            .await
+            .with_context(|| format!("waiting for version for buffer {}", buffer.entity_id()))?;
+
+        let tokens = this
+            .update(&mut cx, |lsp_store, cx| {
+                lsp_store.semantic_tokens(buffer.clone(), cx)
+            })?
+            .await
+            .context("semantic tokens fetch")?;
+
+        buffer.update(&mut cx, |buffer, _| tokens.to_proto(&buffer.version()))
+    }
+
+    async fn handle_resolve_code_lens(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::ResolveCodeLens>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::ResolveCodeLensResponse> {
+        let lens = GetCodeLens::proto_to_project_lens(
+            envelope.payload.lens.context("missing code lens")?,
+        )?;
+        let buffer = this.update(&mut cx, |this, cx| {
+            let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+            this.buffer_store.read(cx).get_existing(buffer_id)
+        })??;
+        let lens = this
+            .update(&mut cx, |this, cx| {
+                this.resolve_code_lens(&buffer, lens, cx)
+            })?
+            .await
+            .context("code lens resolve")?;
+        Ok(proto::ResolveCodeLensResponse {
+            lens: Some(GetCodeLens::project_to_proto_lens(&lens)),
+        })
+    }
+
+    async fn handle_resolve_document_link(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::ResolveDocumentLink>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::ResolveDocumentLinkResponse> {
+        let link = GetDocumentLinks::proto_to_project_link(
+            envelope.payload.link.context("missing document link")?,
+        )?;
+        let buffer = this.update(&mut cx, |this, cx| {
+            let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+            this.buffer_store.read(cx).get_existing(buffer_id)
+        })??;
+        let link = this
+            .update(&mut cx, |this, cx| {
+                this.resolve_document_link(&buffer, link, cx)
+            })?
+            .await
+            .context("document link resolve")?;
+        Ok(proto::ResolveDocumentLinkResponse {
+            link: Some(GetDocumentLinks::project_to_proto_link(&link)),
+        })
+    }
+
     async fn handle_resolve_inlay_hint(
         this: Model<Self>,
         envelope: TypedEnvelope<proto::ResolveInlayHint>,
@@ -8079,6 +8725,13 @@ struct LspBufferSnapshot {
     snapshot: TextBufferSnapshot,
 }
 
+#[derive(Clone)]
+struct LspSemanticTokens {
+    server_id: LanguageServerId,
+    result_id: String,
+    data: Vec<u32>,
+}
+
 /// A prompt requested by LSP server.
 #[derive(Clone, Debug)]
 pub struct LanguageServerPromptRequest {
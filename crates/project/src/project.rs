@@ -39,7 +39,12 @@ use futures::{
 pub use image_store::{ImageItem, ImageStore};
 use image_store::{ImageItemEvent, ImageStoreEvent};
 
-use git::{blame::Blame, repository::GitRepository};
+use git::{
+    blame::Blame,
+    log::{CommitFileDiff, LogEntry},
+    repository::{DiffBase, GitRepository},
+    GitHostingProviderRegistry, Oid,
+};
 use gpui::{
     AnyModel, AppContext, AsyncAppContext, BorrowAppContext, Context as _, EventEmitter, Hsla,
     Model, ModelContext, SharedString, Task, WeakModel, WindowContext,
@@ -272,6 +277,8 @@ pub enum Event {
     Reshared,
     Rejoined,
     RefreshInlayHints,
+    RefreshSemanticTokens,
+    RefreshCodeLens,
     RevealInProjectPanel(ProjectEntryId),
     SnippetEdit(BufferId, Vec<(lsp::Range, Snippet)>),
 }
@@ -393,6 +400,30 @@ pub struct CodeAction {
     pub lsp_action: lsp::CodeAction,
 }
 
+/// A command shown above a range of a buffer, such as "Run" or "3 references".
+#[derive(Clone, Debug)]
+pub struct CodeLens {
+    /// The id of the language server that produced this code lens.
+    pub server_id: LanguageServerId,
+    /// The range of the buffer that the code lens applies to.
+    pub range: Range<Anchor>,
+    /// The raw code lens provided by the language server. Its command may be
+    /// missing until the code lens is resolved.
+    pub lsp_lens: lsp::CodeLens,
+}
+
+/// A range of a buffer that links to a file or URL, such as an `#include` path.
+#[derive(Clone, Debug)]
+pub struct DocumentLink {
+    /// The id of the language server that produced this link.
+    pub server_id: LanguageServerId,
+    /// The range of the buffer that the link covers.
+    pub range: Range<Anchor>,
+    /// The raw document link provided by the language server. Its target may be
+    /// missing until the link is resolved.
+    pub lsp_link: lsp::DocumentLink,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ResolveState {
     Resolved,
@@ -490,6 +521,35 @@ impl Hover {
     }
 }
 
+/// A function-like symbol whose callers and callees can be queried.
+#[derive(Clone, Debug)]
+pub struct CallHierarchyItem {
+    /// The range of the symbol's name.
+    pub location: Location,
+    /// The item as reported by the language server, which has to be sent back unchanged
+    /// when querying its calls.
+    pub lsp_item: lsp::CallHierarchyItem,
+}
+
+/// A call into or out of a [`CallHierarchyItem`].
+#[derive(Clone, Debug)]
+pub struct CallHierarchyCall {
+    /// The caller for incoming calls, or the callee for outgoing ones.
+    pub item: CallHierarchyItem,
+    /// The ranges of the calls, all of which lie within the calling item.
+    pub call_sites: Vec<Location>,
+}
+
+/// A type whose supertypes and subtypes can be queried.
+#[derive(Clone, Debug)]
+pub struct TypeHierarchyItem {
+    /// The range of the type's name.
+    pub location: Location,
+    /// The item as reported by the language server, which has to be sent back unchanged
+    /// when querying its supertypes or subtypes.
+    pub lsp_item: lsp::TypeHierarchyItem,
+}
+
 enum EntitySubscription {
     Project(PendingEntitySubscription<Project>),
     BufferStore(PendingEntitySubscription<BufferStore>),
@@ -1907,6 +1967,103 @@ impl Project {
         })
     }
 
+    pub fn load_committed_text(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Task<Result<Option<String>>> {
+        self.buffer_store.read(cx).load_committed_text(buffer, cx)
+    }
+
+    pub fn set_index_text(
+        &mut self,
+        buffer: &Model<Buffer>,
+        index_text: Option<String>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        self.buffer_store.update(cx, |buffer_store, cx| {
+            buffer_store.set_index_text(buffer, index_text, cx)
+        })
+    }
+
+    /// Returns what buffers are currently diffed against.
+    pub fn diff_base<'a>(&'a self, cx: &'a AppContext) -> &'a DiffBase {
+        self.buffer_store.read(cx).diff_base()
+    }
+
+    /// Diffs every buffer against the given base instead, such as a branch under review.
+    pub fn set_diff_base(
+        &mut self,
+        diff_base: DiffBase,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        self.buffer_store.update(cx, |buffer_store, cx| {
+            buffer_store.set_diff_base(diff_base, cx)
+        })
+    }
+
+    /// Reads the history of the repository containing the given path, limited to commits
+    /// touching it unless it's the repository's root.
+    pub fn git_log(
+        &self,
+        project_path: &ProjectPath,
+        range: Option<String>,
+        limit: usize,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<LogEntry>>> {
+        let Some(worktree) = self.worktree_for_id(project_path.worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("no such worktree")));
+        };
+        worktree
+            .read(cx)
+            .git_log(&project_path.path, range, limit, cx)
+    }
+
+    pub fn load_commit_diff(
+        &self,
+        project_path: &ProjectPath,
+        sha: Oid,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<CommitFileDiff>>> {
+        let Some(worktree) = self.worktree_for_id(project_path.worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("no such worktree")));
+        };
+        worktree
+            .read(cx)
+            .load_commit_diff(&project_path.path, sha, cx)
+    }
+
+    /// Returns a link to the given commit on the hosting provider of the "origin" remote of the
+    /// repository containing the given path.
+    pub fn commit_permalink(
+        &self,
+        project_path: &ProjectPath,
+        sha: Oid,
+        cx: &AppContext,
+    ) -> Task<Result<url::Url>> {
+        let repo = self
+            .worktree_for_id(project_path.worktree_id, cx)
+            .and_then(|worktree| {
+                worktree
+                    .read(cx)
+                    .as_local()?
+                    .local_git_repo(&project_path.path)
+            });
+        let Some(repo) = repo else {
+            return Task::ready(Err(anyhow!("no repository found for {project_path:?}")));
+        };
+
+        cx.spawn(|cx| async move {
+            const REMOTE_NAME: &str = "origin";
+            let origin_url = repo
+                .remote_url(REMOTE_NAME)
+                .ok_or_else(|| anyhow!("remote \"{REMOTE_NAME}\" not found"))?;
+            let provider_registry = cx.update(GitHostingProviderRegistry::default_global)?;
+            git::log::commit_permalink(provider_registry, &origin_url, sha)
+                .ok_or_else(|| anyhow!("failed to parse Git remote URL"))
+        })
+    }
+
     pub fn open_buffer_by_id(
         &mut self,
         id: BufferId,
@@ -2191,6 +2348,8 @@ impl Project {
                 };
             }
             LspStoreEvent::RefreshInlayHints => cx.emit(Event::RefreshInlayHints),
+            LspStoreEvent::RefreshSemanticTokens => cx.emit(Event::RefreshSemanticTokens),
+            LspStoreEvent::RefreshCodeLens => cx.emit(Event::RefreshCodeLens),
             LspStoreEvent::LanguageServerPrompt(prompt) => {
                 cx.emit(Event::LanguageServerPrompt(prompt.clone()))
             }
@@ -2739,6 +2898,66 @@ impl Project {
         )
     }
 
+    pub fn prepare_call_hierarchy<T: ToPointUtf16>(
+        &mut self,
+        buffer: &Model<Buffer>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyItem>>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store.prepare_call_hierarchy(buffer, position, cx)
+        })
+    }
+
+    pub fn incoming_calls(
+        &mut self,
+        item: &CallHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyCall>>> {
+        self.lsp_store
+            .update(cx, |lsp_store, cx| lsp_store.incoming_calls(item, cx))
+    }
+
+    pub fn outgoing_calls(
+        &mut self,
+        item: &CallHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyCall>>> {
+        self.lsp_store
+            .update(cx, |lsp_store, cx| lsp_store.outgoing_calls(item, cx))
+    }
+
+    pub fn prepare_type_hierarchy<T: ToPointUtf16>(
+        &mut self,
+        buffer: &Model<Buffer>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<TypeHierarchyItem>>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store.prepare_type_hierarchy(buffer, position, cx)
+        })
+    }
+
+    pub fn supertypes(
+        &mut self,
+        item: &TypeHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<TypeHierarchyItem>>> {
+        self.lsp_store
+            .update(cx, |lsp_store, cx| lsp_store.supertypes(item, cx))
+    }
+
+    pub fn subtypes(
+        &mut self,
+        item: &TypeHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<TypeHierarchyItem>>> {
+        self.lsp_store
+            .update(cx, |lsp_store, cx| lsp_store.subtypes(item, cx))
+    }
+
     fn document_highlights_impl(
         &mut self,
         buffer: &Model<Buffer>,
@@ -2925,6 +3144,80 @@ impl Project {
         })
     }
 
+    pub fn code_lens(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CodeLens>>> {
+        self.lsp_store
+            .update(cx, |lsp_store, cx| lsp_store.code_lens(buffer, cx))
+    }
+
+    /// Returns the foldable ranges of the buffer reported by its primary language server.
+    pub fn folding_ranges(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<Range<Anchor>>>> {
+        self.lsp_store
+            .update(cx, |lsp_store, cx| lsp_store.folding_ranges(buffer, cx))
+    }
+
+    /// Returns, for each position, the ranges containing it from innermost to outermost,
+    /// as reported by the buffer's primary language server.
+    pub fn selection_ranges(
+        &mut self,
+        buffer: &Model<Buffer>,
+        positions: Vec<Anchor>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<Vec<Range<Anchor>>>>> {
+        self.lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store.selection_ranges(buffer, positions, cx)
+        })
+    }
+
+    pub fn resolve_code_lens(
+        &self,
+        buffer: &Model<Buffer>,
+        lens: CodeLens,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<CodeLens>> {
+        self.lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store.resolve_code_lens(buffer, lens, cx)
+        })
+    }
+
+    pub fn execute_code_lens(
+        &self,
+        buffer: Model<Buffer>,
+        lens: CodeLens,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<ProjectTransaction>> {
+        self.lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store.execute_code_lens(buffer, lens, cx)
+        })
+    }
+
+    pub fn document_links(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<DocumentLink>>> {
+        self.lsp_store
+            .update(cx, |lsp_store, cx| lsp_store.document_links(buffer, cx))
+    }
+
+    pub fn resolve_document_link(
+        &self,
+        buffer: &Model<Buffer>,
+        link: DocumentLink,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<DocumentLink>> {
+        self.lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store.resolve_document_link(buffer, link, cx)
+        })
+    }
+
     fn prepare_rename_impl(
         &mut self,
         buffer: Model<Buffer>,
@@ -3006,6 +3299,16 @@ impl Project {
         })
     }
 
+    pub fn semantic_tokens(
+        &mut self,
+        buffer_handle: Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<anyhow::Result<SemanticTokens>> {
+        self.lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store.semantic_tokens(buffer_handle, cx)
+        })
+    }
+
     pub fn resolve_inlay_hint(
         &self,
         hint: InlayHint,
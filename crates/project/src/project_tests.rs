@@ -1,7 +1,7 @@
-use crate::{Event, *};
+use crate::{terminals::TerminalKind, Event, *};
 use fs::FakeFs;
 use futures::{future, StreamExt};
-use git::diff::assert_hunks;
+use git::{diff::assert_hunks, repository::DiffBase, Oid};
 use gpui::{AppContext, SemanticVersion, UpdateGlobal};
 use http_client::Url;
 use language::{
@@ -22,7 +22,10 @@ use std::os;
 use std::{str::FromStr, sync::OnceLock};
 
 use std::{mem, num::NonZeroU32, ops::Range, task::Poll};
-use task::{ResolvedTask, TaskContext};
+use task::{
+    BackgroundPatterns, ProblemMatcher, ProblemMatcherDefinition, ResolvedTask, TaskContext,
+    TaskTemplate,
+};
 use unindent::Unindent as _;
 use util::{assert_set_eq, paths::PathMatcher, test::temp_tree, TryFutureExt as _};
 
@@ -1207,6 +1210,122 @@ async fn test_omitted_diagnostics(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[cfg(not(windows))]
+#[gpui::test]
+async fn test_task_problem_diagnostics(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+    cx.update(terminal::init);
+
+    let dir = temp_tree(json!({
+        "src": {
+            "a.c": "int main() { return 0 }\n",
+            "b.c": "int x;\nint y;\n",
+        }
+    }));
+    let root = dir.path().canonicalize().unwrap();
+    let project = Project::test(Arc::new(RealFs::default()), [root.as_ref()], cx).await;
+    let window = cx.add_window(|_| gpui::EmptyView);
+
+    let run_task =
+        |script: &str, problem_matcher: ProblemMatcher, cx: &mut gpui::TestAppContext| {
+            let spawn_task = TaskTemplate {
+                label: "build".to_string(),
+                command: "sh".to_string(),
+                args: vec!["-c".to_string(), script.to_string()],
+                cwd: Some(root.to_string_lossy().into_owned()),
+                problem_matchers: vec![problem_matcher],
+                ..TaskTemplate::default()
+            }
+            .resolve_task("test", &TaskContext::default())
+            .unwrap()
+            .resolved
+            .unwrap();
+            project.update(cx, |project, cx| {
+                project.create_terminal(TerminalKind::Task(spawn_task), window.into(), cx)
+            })
+        };
+    let gcc = || ProblemMatcher::Builtin("$gcc".to_string());
+
+    // Each terminal is kept until its task's problems are reported from its output.
+    let _first_run = run_task(
+        "echo 'src/a.c:1:23: error: expected semicolon'; \
+         echo 'src/b.c:2:5: warning: unused variable y'",
+        gcc(),
+        cx,
+    )
+    .await
+    .unwrap();
+    wait_for_task_diagnostics(&project, &[("src/a.c", 1, 0), ("src/b.c", 0, 1)], cx);
+
+    // Rerunning the task replaces its diagnostics, clearing those of fixed files.
+    let _second_run = run_task("echo 'src/b.c:2:5: warning: unused variable y'", gcc(), cx)
+        .await
+        .unwrap();
+    wait_for_task_diagnostics(&project, &[("src/b.c", 0, 1)], cx);
+    let _third_run = run_task("true", gcc(), cx).await.unwrap();
+    wait_for_task_diagnostics(&project, &[], cx);
+
+    // Tasks in watch mode report the problems of each run as it ends, while they keep running.
+    let watch = ProblemMatcher::Custom(ProblemMatcherDefinition {
+        background: Some(BackgroundPatterns {
+            begins_pattern: "^begin$".to_string(),
+            ends_pattern: "^end$".to_string(),
+        }),
+        ..gcc().definition().unwrap().into_owned()
+    });
+    let terminal = run_task(
+        "echo begin; echo 'src/a.c:1:23: error: expected semicolon'; echo end; \
+         read line; echo begin; echo end; sleep 60",
+        watch,
+        cx,
+    )
+    .await
+    .unwrap();
+    wait_for_task_diagnostics(&project, &[("src/a.c", 1, 0)], cx);
+    terminal.update(cx, |terminal, _| terminal.input("\n".to_string()));
+    wait_for_task_diagnostics(&project, &[], cx);
+    drop(terminal);
+}
+
+#[cfg(not(windows))]
+fn wait_for_task_diagnostics(
+    project: &Model<Project>,
+    expected: &[(&str, usize, usize)],
+    cx: &mut gpui::TestAppContext,
+) {
+    let summaries = |cx: &mut gpui::TestAppContext| {
+        project.read_with(cx, |project, cx| {
+            let mut summaries = project
+                .diagnostic_summaries(false, cx)
+                .map(|(path, _, summary)| {
+                    (
+                        path.path.to_string_lossy().into_owned(),
+                        summary.error_count,
+                        summary.warning_count,
+                    )
+                })
+                .collect::<Vec<_>>();
+            summaries.sort();
+            summaries
+        })
+    };
+    let expected = expected
+        .iter()
+        .map(|(path, errors, warnings)| (path.to_string(), *errors, *warnings))
+        .collect::<Vec<_>>();
+    // The task runs in a real process, so give it time to print its output.
+    for _ in 0..500 {
+        cx.executor().advance_clock(Duration::from_millis(500));
+        cx.run_until_parked();
+        if summaries(cx) == expected {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(summaries(cx), expected);
+}
+
 #[gpui::test]
 async fn test_disk_based_diagnostics_progress(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -2632,6 +2751,292 @@ async fn test_definition(cx: &mut gpui::TestAppContext) {
     }
 }
 
+#[gpui::test]
+async fn test_call_hierarchy(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "fn a() {\n    b();\n}\nfn b() {\n    c();\n}\nfn c() {}\n",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                call_hierarchy_provider: Some(lsp::CallHierarchyServerCapability::Simple(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let (buffer, _handle) = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp("/dir/a.rs", cx)
+        })
+        .await
+        .unwrap();
+
+    fn function_item(name: &str, row: u32) -> lsp::CallHierarchyItem {
+        lsp::CallHierarchyItem {
+            name: name.to_string(),
+            kind: lsp::SymbolKind::FUNCTION,
+            tags: None,
+            detail: None,
+            uri: lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+            range: lsp::Range::new(lsp::Position::new(row, 0), lsp::Position::new(row, 9)),
+            selection_range: lsp::Range::new(
+                lsp::Position::new(row, 3),
+                lsp::Position::new(row, 4),
+            ),
+            data: None,
+        }
+    }
+    let call_of_c = lsp::Range::new(lsp::Position::new(4, 4), lsp::Position::new(4, 5));
+
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server.handle_request::<lsp::request::CallHierarchyPrepare, _, _>(
+        |params, _| async move {
+            assert_eq!(
+                params.text_document_position_params.position,
+                lsp::Position::new(4, 4)
+            );
+            Ok(Some(vec![function_item("c", 6)]))
+        },
+    );
+    fake_server.handle_request::<lsp::request::CallHierarchyIncomingCalls, _, _>(
+        move |params, _| async move {
+            assert_eq!(params.item.name, "c");
+            Ok(Some(vec![lsp::CallHierarchyIncomingCall {
+                from: function_item("b", 3),
+                from_ranges: vec![call_of_c],
+            }]))
+        },
+    );
+    fake_server.handle_request::<lsp::request::CallHierarchyOutgoingCalls, _, _>(
+        move |params, _| async move {
+            assert_eq!(params.item.name, "b");
+            Ok(Some(vec![lsp::CallHierarchyOutgoingCall {
+                to: function_item("c", 6),
+                from_ranges: vec![call_of_c],
+            }]))
+        },
+    );
+
+    let items = project
+        .update(cx, |project, cx| {
+            project.prepare_call_hierarchy(&buffer, Point::new(4, 4), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    let c = &items[0];
+    assert_eq!(c.lsp_item.name, "c");
+    cx.update(|cx| {
+        assert_eq!(c.location.buffer, buffer);
+        assert_eq!(
+            c.location.range.to_point(buffer.read(cx)),
+            Point::new(6, 3)..Point::new(6, 4)
+        );
+    });
+
+    let incoming_calls = project
+        .update(cx, |project, cx| project.incoming_calls(c, cx))
+        .await
+        .unwrap();
+    assert_eq!(incoming_calls.len(), 1);
+    let b = &incoming_calls[0].item;
+    assert_eq!(b.lsp_item.name, "b");
+    cx.update(|cx| {
+        let buffer = buffer.read(cx);
+        assert_eq!(
+            b.location.range.to_point(buffer),
+            Point::new(3, 3)..Point::new(3, 4)
+        );
+        assert_eq!(
+            incoming_calls[0]
+                .call_sites
+                .iter()
+                .map(|call_site| call_site.range.to_point(buffer))
+                .collect::<Vec<_>>(),
+            [Point::new(4, 4)..Point::new(4, 5)]
+        );
+    });
+
+    let outgoing_calls = project
+        .update(cx, |project, cx| project.outgoing_calls(b, cx))
+        .await
+        .unwrap();
+    assert_eq!(outgoing_calls.len(), 1);
+    assert_eq!(outgoing_calls[0].item.lsp_item.name, "c");
+    cx.update(|cx| {
+        let buffer = buffer.read(cx);
+        assert_eq!(
+            outgoing_calls[0].item.location.range.to_point(buffer),
+            Point::new(6, 3)..Point::new(6, 4)
+        );
+        assert_eq!(
+            outgoing_calls[0]
+                .call_sites
+                .iter()
+                .map(|call_site| call_site.range.to_point(buffer))
+                .collect::<Vec<_>>(),
+            [Point::new(4, 4)..Point::new(4, 5)]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_type_hierarchy(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "trait A {}\nstruct B;\nimpl A for B {}\n",
+            "c.ts": "class C {}\n",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    language_registry.add(typescript_lang());
+    let mut fake_rust_servers = language_registry.register_fake_lsp(
+        "Rust",
+        FakeLspAdapter {
+            initializer: Some(Box::new(|fake_server| {
+                fake_server.set_extra_capabilities(lsp::ExtraServerCapabilities {
+                    type_hierarchy_provider: Some(lsp::OneOf::Left(true)),
+                })
+            })),
+            ..Default::default()
+        },
+    );
+    let mut fake_typescript_servers =
+        language_registry.register_fake_lsp("TypeScript", FakeLspAdapter::default());
+
+    let (rust_buffer, _rust_handle) = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp("/dir/a.rs", cx)
+        })
+        .await
+        .unwrap();
+    let (typescript_buffer, _typescript_handle) = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp("/dir/c.ts", cx)
+        })
+        .await
+        .unwrap();
+
+    fn type_item(
+        name: &str,
+        kind: lsp::SymbolKind,
+        row: u32,
+        column: u32,
+    ) -> lsp::TypeHierarchyItem {
+        lsp::TypeHierarchyItem {
+            name: name.to_string(),
+            kind,
+            tags: None,
+            detail: None,
+            uri: lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+            range: lsp::Range::new(lsp::Position::new(row, 0), lsp::Position::new(row, 9)),
+            selection_range: lsp::Range::new(
+                lsp::Position::new(row, column),
+                lsp::Position::new(row, column + 1),
+            ),
+            data: None,
+        }
+    }
+    let trait_a = || type_item("A", lsp::SymbolKind::INTERFACE, 0, 6);
+    let struct_b = || type_item("B", lsp::SymbolKind::STRUCT, 1, 7);
+
+    let fake_rust_server = fake_rust_servers.next().await.unwrap();
+    fake_rust_server.handle_request::<lsp::request::TypeHierarchyPrepare, _, _>(
+        move |params, _| async move {
+            assert_eq!(
+                params.text_document_position_params.position,
+                lsp::Position::new(0, 6)
+            );
+            Ok(Some(vec![trait_a()]))
+        },
+    );
+    fake_rust_server.handle_request::<lsp::request::TypeHierarchySubtypes, _, _>(
+        move |params, _| async move {
+            assert_eq!(params.item, trait_a());
+            Ok(Some(vec![struct_b()]))
+        },
+    );
+    fake_rust_server.handle_request::<lsp::request::TypeHierarchySupertypes, _, _>(
+        move |params, _| async move {
+            assert_eq!(params.item, struct_b());
+            Ok(Some(vec![trait_a()]))
+        },
+    );
+
+    let items = project
+        .update(cx, |project, cx| {
+            project.prepare_type_hierarchy(&rust_buffer, Point::new(0, 6), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].lsp_item, trait_a());
+    cx.update(|cx| {
+        assert_eq!(
+            items[0].location.range.to_point(rust_buffer.read(cx)),
+            Point::new(0, 6)..Point::new(0, 7)
+        );
+    });
+
+    let subtypes = project
+        .update(cx, |project, cx| project.subtypes(&items[0], cx))
+        .await
+        .unwrap();
+    assert_eq!(subtypes.len(), 1);
+    assert_eq!(subtypes[0].lsp_item, struct_b());
+    cx.update(|cx| {
+        assert_eq!(
+            subtypes[0].location.range.to_point(rust_buffer.read(cx)),
+            Point::new(1, 7)..Point::new(1, 8)
+        );
+    });
+
+    let supertypes = project
+        .update(cx, |project, cx| project.supertypes(&subtypes[0], cx))
+        .await
+        .unwrap();
+    assert_eq!(supertypes.len(), 1);
+    assert_eq!(supertypes[0].lsp_item, trait_a());
+
+    // Servers that don't support type hierarchies aren't asked for them.
+    let fake_typescript_server = fake_typescript_servers.next().await.unwrap();
+    let mut typescript_responses = fake_typescript_server
+        .handle_request::<lsp::request::TypeHierarchyPrepare, _, _>(|_, _| async move { Ok(None) });
+    let items = project
+        .update(cx, |project, cx| {
+            project.prepare_type_hierarchy(&typescript_buffer, Point::new(0, 6), cx)
+        })
+        .await
+        .unwrap();
+    assert!(items.is_empty());
+    cx.executor().run_until_parked();
+    assert!(typescript_responses.try_next().is_err());
+}
+
 #[gpui::test]
 async fn test_completions_without_edit_ranges(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -5652,6 +6057,125 @@ async fn test_unstaged_changes_for_buffer(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_diff_base_for_buffer(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".git": {},
+            "a.txt": "one\ntwo\nthree\n",
+            "b.txt": "new\n",
+        }),
+    )
+    .await;
+    let merge_base = Oid::from_bytes(&[1; 20]).unwrap();
+    fs.with_git_state(Path::new("/dir/.git"), true, |state| {
+        state
+            .index_contents
+            .insert("a.txt".into(), "one\ntwo\nthree\n".into());
+        state
+            .revision_contents
+            .entry("main".into())
+            .or_default()
+            .insert("a.txt".into(), "one\n".into());
+        state
+            .revision_contents
+            .entry(merge_base.to_string())
+            .or_default()
+            .insert("a.txt".into(), "one\ntwo\n".into());
+        state.merge_bases.insert("main".into(), merge_base);
+    });
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/a.txt", cx)
+        })
+        .await
+        .unwrap();
+    let change_set = project
+        .update(cx, |project, cx| {
+            project.open_unstaged_changes(buffer.clone(), cx)
+        })
+        .await
+        .unwrap();
+
+    let assert_change_set_hunks =
+        |expected: &[(Range<u32>, &str, &str)], cx: &mut gpui::TestAppContext| {
+            cx.run_until_parked();
+            change_set.update(cx, |change_set, cx| {
+                let snapshot = buffer.read(cx).snapshot();
+                assert_hunks(
+                    change_set.diff_hunks_intersecting_range(Anchor::MIN..Anchor::MAX, &snapshot),
+                    &snapshot,
+                    &change_set.base_text.as_ref().unwrap().read(cx).text(),
+                    expected,
+                );
+            });
+        };
+    assert_change_set_hunks(&[], cx);
+
+    let new_buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/b.txt", cx)
+        })
+        .await
+        .unwrap();
+    let new_change_set = project
+        .update(cx, |project, cx| {
+            project.open_unstaged_changes(new_buffer.clone(), cx)
+        })
+        .await
+        .unwrap();
+
+    project
+        .update(cx, |project, cx| {
+            project.set_diff_base(DiffBase::Revision("main".into()), cx)
+        })
+        .await
+        .unwrap();
+    assert_change_set_hunks(&[(1..3, "", "two\nthree\n")], cx);
+
+    // Files missing from the revision show up as added.
+    new_change_set.update(cx, |change_set, cx| {
+        let snapshot = new_buffer.read(cx).snapshot();
+        assert_hunks(
+            change_set.diff_hunks_intersecting_range(Anchor::MIN..Anchor::MAX, &snapshot),
+            &snapshot,
+            &change_set.base_text.as_ref().unwrap().read(cx).text(),
+            &[(0..1, "", "new\n")],
+        );
+    });
+
+    project
+        .update(cx, |project, cx| {
+            project.set_diff_base(DiffBase::Revision("missing".into()), cx)
+        })
+        .await
+        .unwrap_err();
+    project.read_with(cx, |project, cx| {
+        assert_eq!(*project.diff_base(cx), DiffBase::Revision("main".into()));
+    });
+    assert_change_set_hunks(&[(1..3, "", "two\nthree\n")], cx);
+
+    project
+        .update(cx, |project, cx| {
+            project.set_diff_base(DiffBase::MergeBase("main".into()), cx)
+        })
+        .await
+        .unwrap();
+    assert_change_set_hunks(&[(2..3, "", "three\n")], cx);
+
+    project
+        .update(cx, |project, cx| project.set_diff_base(DiffBase::Index, cx))
+        .await
+        .unwrap();
+    assert_change_set_hunks(&[], cx);
+}
+
 async fn search(
     project: &Model<Project>,
     query: SearchQuery,
@@ -15,7 +15,8 @@ use itertools::Itertools;
 use language::{ContextProvider, File, Language, LanguageToolchainStore, Location};
 use settings::{parse_json_with_comments, SettingsLocation};
 use task::{
-    ResolvedTask, TaskContext, TaskId, TaskTemplate, TaskTemplates, TaskVariables, VariableName,
+    ResolvedTask, SpawnInTerminal, TaskContext, TaskId, TaskInput, TaskTemplate, TaskTemplates,
+    TaskVariables, VariableName,
 };
 use text::{Point, ToPoint};
 use util::{post_inc, NumericPrefixWithSuffix, ResultExt as _};
@@ -182,11 +183,14 @@ impl Inventory {
         let new_resolved_tasks = worktree_tasks
             .filter_map(|(kind, task)| {
                 let id_base = kind.to_id_base();
-                Some((
-                    kind,
-                    task.resolve_task(&id_base, task_context)?,
-                    not_used_score,
-                ))
+                let mut resolved_task = task.resolve_task(&id_base, task_context)?;
+                // Listed anyway, so that the error shows up when the task is picked.
+                if let Err(error) =
+                    self.resolve_dependencies(worktree, &mut resolved_task, task_context)
+                {
+                    resolved_task.dependency_error = Some(format!("{error:#}"));
+                }
+                Some((kind, resolved_task, not_used_score))
             })
             .filter(|(_, resolved_task, _)| {
                 match task_labels_to_ids.entry(resolved_task.resolved_label.clone()) {
@@ -207,6 +211,59 @@ impl Inventory {
         (previously_spawned_tasks, new_resolved_tasks)
     }
 
+    /// Resolves the tasks that the given task depends on, and the ones those depend on in turn,
+    /// looking them up by label among the worktree and global task templates.
+    /// Fails if a dependency is not found, cannot be resolved or depends on itself.
+    pub fn resolve_dependencies(
+        &self,
+        worktree: Option<WorktreeId>,
+        resolved_task: &mut ResolvedTask,
+        task_context: &TaskContext,
+    ) -> Result<()> {
+        if resolved_task.original_task().depends_on.is_empty() {
+            return Ok(());
+        }
+        let templates = self
+            .worktree_templates_from_settings(worktree)
+            .chain(self.global_templates_from_settings())
+            .collect::<Vec<_>>();
+        let template = resolved_task.original_task().clone();
+        let dependencies = resolve_dependencies(
+            &template,
+            &templates,
+            task_context,
+            &mut vec![template.label.clone()],
+        )?;
+        if let Some(spawn_in_terminal) = resolved_task.resolved.as_mut() {
+            spawn_in_terminal.dependencies = dependencies;
+        }
+        Ok(())
+    }
+
+    /// Returns the inputs that the template and the tasks it depends on reference, and the
+    /// [`TaskContext`] has no values for, each input id once.
+    /// Dependencies share the task's context, so the user only has to enter each value once.
+    pub fn unresolved_inputs(
+        &self,
+        worktree: Option<WorktreeId>,
+        template: &TaskTemplate,
+        task_context: &TaskContext,
+    ) -> Vec<TaskInput> {
+        let templates = self
+            .worktree_templates_from_settings(worktree)
+            .chain(self.global_templates_from_settings())
+            .collect::<Vec<_>>();
+        let mut inputs = Vec::new();
+        collect_unresolved_inputs(
+            template,
+            &templates,
+            task_context,
+            &mut HashSet::default(),
+            &mut inputs,
+        );
+        inputs
+    }
+
     /// Returns the last scheduled task by task_id if provided.
     /// Otherwise, returns the last scheduled task.
     pub fn last_scheduled_task(
@@ -328,6 +385,69 @@ impl Inventory {
     }
 }
 
+fn resolve_dependencies(
+    template: &TaskTemplate,
+    templates: &[(TaskSourceKind, TaskTemplate)],
+    task_context: &TaskContext,
+    dependents: &mut Vec<String>,
+) -> Result<Vec<SpawnInTerminal>> {
+    template
+        .depends_on
+        .iter()
+        .map(|label| {
+            if dependents.contains(label) {
+                anyhow::bail!(
+                    "task dependency cycle: {} -> {label}",
+                    dependents.join(" -> ")
+                );
+            }
+            let (kind, dependency) = templates
+                .iter()
+                .find(|(_, template)| &template.label == label)
+                .with_context(|| {
+                    format!(
+                        "task {:?} depends on unknown task {label:?}",
+                        template.label
+                    )
+                })?;
+            let mut spawn_in_terminal = dependency
+                .resolve_task(&kind.to_id_base(), task_context)
+                .and_then(|resolved_task| resolved_task.resolved)
+                .with_context(|| format!("resolving task {label:?}"))?;
+            dependents.push(label.clone());
+            spawn_in_terminal.dependencies =
+                resolve_dependencies(dependency, templates, task_context, dependents)?;
+            dependents.pop();
+            Ok(spawn_in_terminal)
+        })
+        .collect()
+}
+
+fn collect_unresolved_inputs(
+    template: &TaskTemplate,
+    templates: &[(TaskSourceKind, TaskTemplate)],
+    task_context: &TaskContext,
+    visited: &mut HashSet<String>,
+    inputs: &mut Vec<TaskInput>,
+) {
+    if !visited.insert(template.label.clone()) {
+        return;
+    }
+    for input in template.unresolved_inputs(task_context) {
+        if !inputs.iter().any(|known_input| known_input.id == input.id) {
+            inputs.push(input.clone());
+        }
+    }
+    for label in &template.depends_on {
+        if let Some((_, dependency)) = templates
+            .iter()
+            .find(|(_, template)| &template.label == label)
+        {
+            collect_unresolved_inputs(dependency, templates, task_context, visited, inputs);
+        }
+    }
+}
+
 fn task_lru_comparator(
     (kind_a, task_a, lru_score_a): &(TaskSourceKind, ResolvedTask, u32),
     (kind_b, task_b, lru_score_b): &(TaskSourceKind, ResolvedTask, u32),
@@ -564,6 +684,7 @@ mod tests {
     use gpui::TestAppContext;
     use pretty_assertions::assert_eq;
     use serde_json::json;
+    use task::DependsOrder;
 
     use crate::task_store::TaskStore;
 
@@ -851,6 +972,158 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_task_dependencies(cx: &mut TestAppContext) {
+        init_test(cx);
+        let inventory = cx.update(Inventory::new);
+        inventory.update(cx, |inventory, _| {
+            inventory
+                .update_file_based_tasks(
+                    None,
+                    Some(
+                        &json!([
+                            { "label": "build", "command": "cargo", "args": ["build"] },
+                            { "label": "test", "command": "cargo", "args": ["test"], "depends_on": ["build"] },
+                            { "label": "lint", "command": "cargo", "args": ["clippy"] },
+                            {
+                                "label": "check",
+                                "depends_on": ["test", "lint"],
+                                "depends_order": "sequence"
+                            },
+                            { "label": "cycle a", "command": "echo", "depends_on": ["cycle b"] },
+                            { "label": "cycle b", "command": "echo", "depends_on": ["cycle a"] },
+                            { "label": "broken", "command": "echo", "depends_on": ["missing"] },
+                        ])
+                        .to_string(),
+                    ),
+                )
+                .unwrap();
+        });
+        cx.run_until_parked();
+
+        let (_, current) = inventory.update(cx, |inventory, cx| {
+            inventory.used_and_current_resolved_tasks(None, None, &TaskContext::default(), cx)
+        });
+        let check = current
+            .iter()
+            .find(|(_, task)| task.resolved_label == "check")
+            .and_then(|(_, task)| task.resolved.clone())
+            .expect("compound task should be resolved");
+        assert_eq!(check.command, "");
+        assert_eq!(check.depends_order, DependsOrder::Sequence);
+        assert_eq!(
+            check
+                .dependencies
+                .iter()
+                .map(|dependency| dependency.label.as_str())
+                .collect::<Vec<_>>(),
+            vec!["test", "lint"],
+        );
+        assert_eq!(
+            check.dependencies[0]
+                .dependencies
+                .iter()
+                .map(|dependency| dependency.command_label.as_str())
+                .collect::<Vec<_>>(),
+            vec!["cargo build"],
+        );
+
+        let dependency_error = |label: &str| {
+            current
+                .iter()
+                .find(|(_, task)| task.resolved_label == label)
+                .unwrap_or_else(|| panic!("task {label:?} should be listed"))
+                .1
+                .dependency_error
+                .clone()
+        };
+        assert_eq!(dependency_error("check"), None);
+        assert_eq!(
+            dependency_error("cycle a").as_deref(),
+            Some("task dependency cycle: cycle a -> cycle b -> cycle a")
+        );
+        assert_eq!(
+            dependency_error("cycle b").as_deref(),
+            Some("task dependency cycle: cycle b -> cycle a -> cycle b")
+        );
+        assert_eq!(
+            dependency_error("broken").as_deref(),
+            Some("task \"broken\" depends on unknown task \"missing\"")
+        );
+    }
+
+    #[gpui::test]
+    async fn test_unresolved_inputs_of_dependencies(cx: &mut TestAppContext) {
+        init_test(cx);
+        let inventory = cx.update(Inventory::new);
+        inventory.update(cx, |inventory, _| {
+            inventory
+                .update_file_based_tasks(
+                    None,
+                    Some(
+                        &json!([
+                            {
+                                "label": "build",
+                                "command": "make",
+                                "args": ["${input:target}", "${input:jobs}"],
+                                "inputs": [
+                                    { "id": "target", "options": ["debug", "release"] },
+                                    { "id": "jobs", "default": "4" },
+                                ]
+                            },
+                            {
+                                "label": "deploy",
+                                "command": "deploy",
+                                "args": ["${input:host}", "${input:target}"],
+                                "depends_on": ["build"],
+                                "inputs": [
+                                    { "id": "host" },
+                                    { "id": "target", "description": "Target to deploy" },
+                                ]
+                            },
+                        ])
+                        .to_string(),
+                    ),
+                )
+                .unwrap();
+        });
+        cx.run_until_parked();
+
+        let unresolved_inputs = |task_context: &TaskContext, cx: &mut TestAppContext| {
+            inventory.read_with(cx, |inventory, _| {
+                let (_, deploy) = inventory
+                    .global_templates_from_settings()
+                    .find(|(_, template)| template.label == "deploy")
+                    .unwrap();
+                inventory
+                    .unresolved_inputs(None, &deploy, task_context)
+                    .into_iter()
+                    .map(|input| (input.id, input.description))
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(
+            unresolved_inputs(&TaskContext::default(), cx),
+            vec![
+                ("host".to_string(), String::new()),
+                ("target".to_string(), "Target to deploy".to_string()),
+                ("jobs".to_string(), String::new()),
+            ],
+            "inputs of dependencies should be asked for after the task's own, each once"
+        );
+        let task_context = TaskContext {
+            input_values: HashMap::from_iter([("target".to_string(), "release".to_string())]),
+            ..TaskContext::default()
+        };
+        assert_eq!(
+            unresolved_inputs(&task_context, cx),
+            vec![
+                ("host".to_string(), String::new()),
+                ("jobs".to_string(), String::new()),
+            ],
+        );
+    }
+
     fn init_test(_cx: &mut TestAppContext) {
         if std::env::var("RUST_LOG").is_ok() {
             env_logger::try_init().ok();
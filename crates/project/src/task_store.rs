@@ -363,6 +363,7 @@ fn local_task_context_for_location(
             project_env: project_env.unwrap_or_default(),
             cwd: worktree_abs_path.map(|p| p.to_path_buf()),
             task_variables,
+            input_values: HashMap::default(),
         })
     })
 }
@@ -426,6 +427,7 @@ fn remote_task_context_for_location(
                 )
                 .collect(),
             project_env: task_context.project_env.into_iter().collect(),
+            input_values: HashMap::default(),
         })
     })
 }
@@ -1,7 +1,11 @@
 use crate::Project;
 use anyhow::{Context as _, Result};
 use collections::HashMap;
-use gpui::{AnyWindowHandle, AppContext, Context, Entity, Model, ModelContext, Task, WeakModel};
+use futures::{channel::mpsc, FutureExt as _, StreamExt as _};
+use gpui::{
+    AnyWindowHandle, AppContext, AsyncAppContext, Context, Entity, Model, ModelContext, Task,
+    WeakModel,
+};
 use itertools::Itertools;
 use language::LanguageName;
 use settings::{Settings, SettingsLocation};
@@ -12,8 +16,9 @@ use std::{
     iter,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
-use task::{Shell, SpawnInTerminal};
+use task::{Problem, ProblemMatcher, Shell, SpawnInTerminal};
 use terminal::{
     terminal_settings::{self, TerminalSettings, VenvSettings},
     TaskState, TaskStatus, Terminal, TerminalBuilder,
@@ -23,6 +28,9 @@ use util::ResultExt;
 // #[cfg(target_os = "macos")]
 // use std::os::unix::ffi::OsStrExt;
 
+/// How often the output of a task in watch mode is matched for problems, while it prints.
+const TASK_PROBLEMS_THROTTLE: Duration = Duration::from_millis(500);
+
 pub struct Terminals {
     pub(crate) local_handles: Vec<WeakModel<terminal::Terminal>>,
 }
@@ -83,6 +91,18 @@ impl Project {
         kind: TerminalKind,
         window: AnyWindowHandle,
         cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<Terminal>>> {
+        self.create_restored_terminal(kind, None, window, cx)
+    }
+
+    /// Creates a terminal that shows the given output from a previous session above the
+    /// new shell's prompt.
+    pub fn create_restored_terminal(
+        &mut self,
+        kind: TerminalKind,
+        restored_scrollback: Option<String>,
+        window: AnyWindowHandle,
+        cx: &mut ModelContext<Self>,
     ) -> Task<Result<Model<Terminal>>> {
         let path: Option<Arc<Path>> = match &kind {
             TerminalKind::Shell(path) => path.as_ref().map(|path| Arc::from(path.as_ref())),
@@ -117,7 +137,13 @@ impl Project {
                 None
             };
             project.update(&mut cx, |project, cx| {
-                project.create_terminal_with_venv(kind, python_venv_directory, window, cx)
+                project.create_terminal_with_venv(
+                    kind,
+                    python_venv_directory,
+                    restored_scrollback,
+                    window,
+                    cx,
+                )
             })?
         })
     }
@@ -126,6 +152,7 @@ impl Project {
         &mut self,
         kind: TerminalKind,
         python_venv_directory: Option<PathBuf>,
+        restored_scrollback: Option<String>,
         window: AnyWindowHandle,
         cx: &mut ModelContext<Self>,
     ) -> Result<Model<Terminal>> {
@@ -173,6 +200,14 @@ impl Project {
 
         let mut python_venv_activate_command = None;
 
+        // Task output is only matched locally, where diagnostics can be reported.
+        let problem_matchers = match &kind {
+            TerminalKind::Task(spawn_task) if ssh_details.is_none() => {
+                spawn_task.problem_matchers.clone()
+            }
+            _ => Vec::new(),
+        };
+
         let (spawn_task, shell) = match kind {
             TerminalKind::Shell(_) => {
                 if let Some(python_venv_directory) = &python_venv_directory {
@@ -276,6 +311,7 @@ impl Project {
             settings.cursor_shape.unwrap_or_default(),
             settings.alternate_scroll,
             settings.max_scroll_history_lines,
+            restored_scrollback,
             ssh_details.is_some(),
             window,
             completion_tx,
@@ -305,10 +341,115 @@ impl Project {
             if let Some(activate_command) = python_venv_activate_command {
                 this.activate_python_virtual_environment(activate_command, &terminal_handle, cx);
             }
+            if !problem_matchers.is_empty() {
+                this.report_task_problems(problem_matchers, path, &terminal_handle, cx);
+            }
             terminal_handle
         })
     }
 
+    /// Clears the diagnostics left by the matchers' previous run and reports the problems they find
+    /// in the task's output once it completes. Matchers of tasks in watch mode also report the
+    /// problems of each run as it ends. Relative paths are resolved against `cwd`.
+    fn report_task_problems(
+        &mut self,
+        problem_matchers: Vec<ProblemMatcher>,
+        cwd: Option<Arc<Path>>,
+        terminal: &Model<Terminal>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let sources = problem_matchers
+            .iter()
+            .filter_map(ProblemMatcher::source)
+            .unique()
+            .collect::<Vec<_>>();
+        self.lsp_store.update(cx, |lsp_store, cx| {
+            for source in &sources {
+                lsp_store
+                    .update_task_diagnostics(source, Vec::new(), cx)
+                    .log_err();
+            }
+        });
+
+        let watches = problem_matchers.iter().any(|matcher| {
+            matcher
+                .definition()
+                .is_some_and(|definition| definition.background.is_some())
+        });
+        let (wakeup_tx, mut wakeup_rx) = mpsc::unbounded();
+        let wakeups = watches.then(|| {
+            cx.subscribe(terminal, move |_, _, event, _| {
+                if let terminal::Event::Wakeup = event {
+                    wakeup_tx.unbounded_send(()).ok();
+                }
+            })
+        });
+        let mut completion = terminal.read(cx).wait_for_completed_task(cx).fuse();
+        let terminal = terminal.downgrade();
+        cx.spawn(|project, mut cx| async move {
+            let _wakeups = wakeups;
+            let mut reported = HashMap::default();
+            loop {
+                futures::select_biased! {
+                    _ = completion => break,
+                    wakeup = wakeup_rx.next() => {
+                        if wakeup.is_none() {
+                            (&mut completion).await;
+                            break;
+                        }
+                    }
+                }
+                cx.background_executor().timer(TASK_PROBLEMS_THROTTLE).await;
+                while let Ok(Some(())) = wakeup_rx.try_next() {}
+
+                let Some(output) =
+                    terminal.update(&mut cx, |terminal, _| terminal.scrollback_text(usize::MAX))?
+                else {
+                    continue;
+                };
+                let matchers = problem_matchers.clone();
+                let problems_by_source = cx
+                    .background_executor()
+                    .spawn(async move { task::match_background_problems(&matchers, &output) })
+                    .await;
+                let changed = problems_by_source
+                    .into_iter()
+                    .filter(|(source, problems)| reported.get(source) != Some(problems))
+                    .collect::<Vec<_>>();
+                for (source, problems) in changed {
+                    reported.insert(source.clone(), problems.clone());
+                    update_task_diagnostics(&project, source, problems, cwd.as_deref(), &mut cx)?;
+                }
+            }
+
+            let Some(output) =
+                terminal.update(&mut cx, |terminal, _| terminal.scrollback_text(usize::MAX))?
+            else {
+                return Ok(());
+            };
+            let problems = cx
+                .background_executor()
+                .spawn(async move { task::match_problems(&problem_matchers, &output) })
+                .await;
+
+            let mut problems_by_source = sources
+                .into_iter()
+                .map(|source| (source, Vec::new()))
+                .collect::<HashMap<_, _>>();
+            for problem in problems {
+                problems_by_source
+                    .entry(problem.source.clone())
+                    .or_default()
+                    .push(problem);
+            }
+            for (source, problems) in problems_by_source {
+                update_task_diagnostics(&project, source, problems, cwd.as_deref(), &mut cx)?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
     fn python_venv_directory(
         &self,
         abs_path: Arc<Path>,
@@ -505,6 +646,33 @@ pub fn wrap_for_ssh(
     (program, args)
 }
 
+/// Replaces the task diagnostics of `source` with `problems`, dropping those with a relative path
+/// when the task had no working directory to resolve it against.
+fn update_task_diagnostics(
+    project: &WeakModel<Project>,
+    source: String,
+    mut problems: Vec<Problem>,
+    cwd: Option<&Path>,
+    cx: &mut AsyncAppContext,
+) -> Result<()> {
+    problems.retain_mut(|problem| {
+        if problem.path.is_relative() {
+            let Some(cwd) = cwd else {
+                return false;
+            };
+            problem.path = cwd.join(&problem.path);
+        }
+        true
+    });
+    project.update(cx, |project, cx| {
+        project.lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store
+                .update_task_diagnostics(&source, problems, cx)
+                .log_err();
+        })
+    })
+}
+
 fn add_environment_path(env: &mut HashMap<String, String>, new_path: &Path) -> Result<()> {
     let mut env_paths = vec![new_path.to_path_buf()];
     if let Some(path) = env.get("PATH").or(env::var("PATH").ok().as_ref()) {
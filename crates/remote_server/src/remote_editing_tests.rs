@@ -8,6 +8,7 @@ use http_client::{BlockedHttpClient, FakeHttpClient};
 use language::{
     language_settings::{language_settings, AllLanguageSettings},
     Buffer, FakeLspAdapter, LanguageConfig, LanguageMatcher, LanguageRegistry, LineEnding,
+    OffsetRangeExt as _, Point,
 };
 use lsp::{CompletionContext, CompletionResponse, CompletionTriggerKind, LanguageServerName};
 use node_runtime::NodeRuntime;
@@ -537,6 +538,354 @@ async fn test_remote_lsp(cx: &mut TestAppContext, server_cx: &mut TestAppContext
     })
 }
 
+#[gpui::test]
+async fn test_remote_call_hierarchy(cx: &mut TestAppContext, server_cx: &mut TestAppContext) {
+    let fs = FakeFs::new(server_cx.executor());
+    fs.insert_tree(
+        "/code",
+        json!({
+            "project1": {
+                ".git": {},
+                "src": {
+                    "lib.rs": "fn one() -> usize { two() }\nfn two() -> usize { 2 }"
+                }
+            },
+        }),
+    )
+    .await;
+
+    let (project, headless) = init_test(&fs, cx, server_cx).await;
+
+    fs.insert_tree(
+        "/code/project1/.zed",
+        json!({
+            "settings.json": r#"
+          {
+            "languages": {"Rust":{"language_servers":["rust-analyzer"]}},
+            "lsp": {
+              "rust-analyzer": {
+                "binary": {
+                  "path": "~/.cargo/bin/rust-analyzer"
+                }
+              }
+            }
+          }"#
+        }),
+    )
+    .await;
+
+    cx.update_model(&project, |project, _| {
+        project.languages().register_test_language(LanguageConfig {
+            name: "Rust".into(),
+            matcher: LanguageMatcher {
+                path_suffixes: vec!["rs".into()],
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        project.languages().register_fake_lsp_adapter(
+            "Rust",
+            FakeLspAdapter {
+                name: "rust-analyzer",
+                ..Default::default()
+            },
+        )
+    });
+
+    let mut fake_lsp = server_cx.update(|cx| {
+        headless.read(cx).languages.register_fake_language_server(
+            LanguageServerName("rust-analyzer".into()),
+            lsp::ServerCapabilities {
+                call_hierarchy_provider: Some(lsp::CallHierarchyServerCapability::Simple(true)),
+                ..Default::default()
+            },
+            None,
+        )
+    });
+
+    cx.run_until_parked();
+
+    let worktree_id = project
+        .update(cx, |project, cx| {
+            project.find_or_create_worktree("/code/project1", true, cx)
+        })
+        .await
+        .unwrap()
+        .0
+        .read_with(cx, |worktree, _| worktree.id());
+    cx.run_until_parked();
+
+    let (buffer, _handle) = project
+        .update(cx, |project, cx| {
+            project.open_buffer_with_lsp((worktree_id, Path::new("src/lib.rs")), cx)
+        })
+        .await
+        .unwrap();
+    cx.run_until_parked();
+
+    let fake_lsp = fake_lsp.next().await.unwrap();
+
+    fn function_item(name: &str, row: u32) -> lsp::CallHierarchyItem {
+        lsp::CallHierarchyItem {
+            name: name.to_string(),
+            kind: lsp::SymbolKind::FUNCTION,
+            tags: None,
+            detail: Some("fn() -> usize".to_string()),
+            uri: lsp::Url::from_file_path("/code/project1/src/lib.rs").unwrap(),
+            range: lsp::Range::new(lsp::Position::new(row, 0), lsp::Position::new(row, 7)),
+            selection_range: lsp::Range::new(
+                lsp::Position::new(row, 3),
+                lsp::Position::new(row, 6),
+            ),
+            data: Some(json!({ "id": name })),
+        }
+    }
+    let call_of_two = lsp::Range::new(lsp::Position::new(0, 20), lsp::Position::new(0, 23));
+
+    fake_lsp.handle_request::<lsp::request::CallHierarchyPrepare, _, _>(|_, _| async move {
+        Ok(Some(vec![function_item("two", 1)]))
+    });
+    fake_lsp.handle_request::<lsp::request::CallHierarchyIncomingCalls, _, _>(
+        move |params, _| async move {
+            // The item is passed back to the server as it sent it.
+            assert_eq!(params.item, function_item("two", 1));
+            Ok(Some(vec![lsp::CallHierarchyIncomingCall {
+                from: function_item("one", 0),
+                from_ranges: vec![call_of_two],
+            }]))
+        },
+    );
+    fake_lsp.handle_request::<lsp::request::CallHierarchyOutgoingCalls, _, _>(
+        move |params, _| async move {
+            assert_eq!(params.item, function_item("one", 0));
+            Ok(Some(vec![lsp::CallHierarchyOutgoingCall {
+                to: function_item("two", 1),
+                from_ranges: vec![call_of_two],
+            }]))
+        },
+    );
+
+    let items = project
+        .update(cx, |project, cx| {
+            project.prepare_call_hierarchy(&buffer, Point::new(0, 21), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].lsp_item, function_item("two", 1));
+    cx.update(|cx| {
+        assert_eq!(
+            items[0].location.range.to_point(buffer.read(cx)),
+            Point::new(1, 3)..Point::new(1, 6)
+        );
+    });
+
+    let incoming_calls = project
+        .update(cx, |project, cx| project.incoming_calls(&items[0], cx))
+        .await
+        .unwrap();
+    assert_eq!(incoming_calls.len(), 1);
+    assert_eq!(incoming_calls[0].item.lsp_item, function_item("one", 0));
+    cx.update(|cx| {
+        let buffer = buffer.read(cx);
+        assert_eq!(
+            incoming_calls[0].item.location.range.to_point(buffer),
+            Point::new(0, 3)..Point::new(0, 6)
+        );
+        assert_eq!(
+            incoming_calls[0]
+                .call_sites
+                .iter()
+                .map(|call_site| call_site.range.to_point(buffer))
+                .collect::<Vec<_>>(),
+            [Point::new(0, 20)..Point::new(0, 23)]
+        );
+    });
+
+    let outgoing_calls = project
+        .update(cx, |project, cx| {
+            project.outgoing_calls(&incoming_calls[0].item, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(outgoing_calls.len(), 1);
+    assert_eq!(outgoing_calls[0].item.lsp_item, function_item("two", 1));
+    cx.update(|cx| {
+        assert_eq!(
+            outgoing_calls[0]
+                .call_sites
+                .iter()
+                .map(|call_site| call_site.range.to_point(buffer.read(cx)))
+                .collect::<Vec<_>>(),
+            [Point::new(0, 20)..Point::new(0, 23)]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_remote_type_hierarchy(cx: &mut TestAppContext, server_cx: &mut TestAppContext) {
+    let fs = FakeFs::new(server_cx.executor());
+    fs.insert_tree(
+        "/code",
+        json!({
+            "project1": {
+                ".git": {},
+                "src": {
+                    "lib.rs": "trait Shape {}\nstruct Circle;\nimpl Shape for Circle {}"
+                }
+            },
+        }),
+    )
+    .await;
+
+    let (project, headless) = init_test(&fs, cx, server_cx).await;
+
+    fs.insert_tree(
+        "/code/project1/.zed",
+        json!({
+            "settings.json": r#"
+          {
+            "languages": {"Rust":{"language_servers":["rust-analyzer"]}},
+            "lsp": {
+              "rust-analyzer": {
+                "binary": {
+                  "path": "~/.cargo/bin/rust-analyzer"
+                }
+              }
+            }
+          }"#
+        }),
+    )
+    .await;
+
+    cx.update_model(&project, |project, _| {
+        project.languages().register_test_language(LanguageConfig {
+            name: "Rust".into(),
+            matcher: LanguageMatcher {
+                path_suffixes: vec!["rs".into()],
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        project.languages().register_fake_lsp_adapter(
+            "Rust",
+            FakeLspAdapter {
+                name: "rust-analyzer",
+                ..Default::default()
+            },
+        )
+    });
+
+    let mut fake_lsp = server_cx.update(|cx| {
+        headless.read(cx).languages.register_fake_language_server(
+            LanguageServerName("rust-analyzer".into()),
+            Default::default(),
+            Some(Box::new(|fake_lsp| {
+                fake_lsp.set_extra_capabilities(lsp::ExtraServerCapabilities {
+                    type_hierarchy_provider: Some(lsp::OneOf::Left(true)),
+                })
+            })),
+        )
+    });
+
+    cx.run_until_parked();
+
+    let worktree_id = project
+        .update(cx, |project, cx| {
+            project.find_or_create_worktree("/code/project1", true, cx)
+        })
+        .await
+        .unwrap()
+        .0
+        .read_with(cx, |worktree, _| worktree.id());
+    cx.run_until_parked();
+
+    let (buffer, _handle) = project
+        .update(cx, |project, cx| {
+            project.open_buffer_with_lsp((worktree_id, Path::new("src/lib.rs")), cx)
+        })
+        .await
+        .unwrap();
+    cx.run_until_parked();
+
+    let fake_lsp = fake_lsp.next().await.unwrap();
+
+    fn type_item(
+        name: &str,
+        kind: lsp::SymbolKind,
+        row: u32,
+        column: u32,
+    ) -> lsp::TypeHierarchyItem {
+        lsp::TypeHierarchyItem {
+            name: name.to_string(),
+            kind,
+            tags: None,
+            detail: None,
+            uri: lsp::Url::from_file_path("/code/project1/src/lib.rs").unwrap(),
+            range: lsp::Range::new(lsp::Position::new(row, 0), lsp::Position::new(row, 14)),
+            selection_range: lsp::Range::new(
+                lsp::Position::new(row, column),
+                lsp::Position::new(row, column + name.len() as u32),
+            ),
+            data: Some(json!({ "id": name })),
+        }
+    }
+    let shape = || type_item("Shape", lsp::SymbolKind::INTERFACE, 0, 6);
+    let circle = || type_item("Circle", lsp::SymbolKind::STRUCT, 1, 7);
+
+    fake_lsp.handle_request::<lsp::request::TypeHierarchyPrepare, _, _>(move |_, _| async move {
+        Ok(Some(vec![circle()]))
+    });
+    fake_lsp.handle_request::<lsp::request::TypeHierarchySupertypes, _, _>(
+        move |params, _| async move {
+            // The item is passed back to the server as it sent it.
+            assert_eq!(params.item, circle());
+            Ok(Some(vec![shape()]))
+        },
+    );
+    fake_lsp.handle_request::<lsp::request::TypeHierarchySubtypes, _, _>(
+        move |params, _| async move {
+            assert_eq!(params.item, shape());
+            Ok(Some(vec![circle()]))
+        },
+    );
+
+    let items = project
+        .update(cx, |project, cx| {
+            project.prepare_type_hierarchy(&buffer, Point::new(1, 8), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].lsp_item, circle());
+
+    let supertypes = project
+        .update(cx, |project, cx| project.supertypes(&items[0], cx))
+        .await
+        .unwrap();
+    assert_eq!(supertypes.len(), 1);
+    assert_eq!(supertypes[0].lsp_item, shape());
+    cx.update(|cx| {
+        assert_eq!(
+            supertypes[0].location.range.to_point(buffer.read(cx)),
+            Point::new(0, 6)..Point::new(0, 11)
+        );
+    });
+
+    let subtypes = project
+        .update(cx, |project, cx| project.subtypes(&supertypes[0], cx))
+        .await
+        .unwrap();
+    assert_eq!(subtypes.len(), 1);
+    assert_eq!(subtypes[0].lsp_item, circle());
+    cx.update(|cx| {
+        assert_eq!(
+            subtypes[0].location.range.to_point(buffer.read(cx)),
+            Point::new(1, 7)..Point::new(1, 13)
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_remote_cancel_language_server_work(
     cx: &mut TestAppContext,
@@ -7,6 +7,7 @@ use gpui::{prelude::*, AppContext, Hsla, Task, TextStyleRefinement, View};
 use language::{Buffer, Language, LanguageRegistry};
 use markdown_preview::{markdown_parser::parse_markdown, markdown_renderer::render_markdown_block};
 use nbformat::v4::{CellId, CellMetadata, CellType};
+use runtimelib::{JupyterMessage, JupyterMessageContent, MimeBundle, Stdio};
 use settings::Settings as _;
 use theme::ThemeSettings;
 use ui::{prelude::*, IconButtonShape};
@@ -14,7 +15,10 @@ use util::ResultExt;
 
 use crate::{
     notebook::{CODE_BLOCK_INSET, GUTTER_WIDTH},
-    outputs::{plain::TerminalOutput, user_error::ErrorView, Output},
+    outputs::{
+        plain::TerminalOutput, push_output_message, update_display_data, user_error::ErrorView,
+        Output,
+    },
 };
 
 #[derive(Copy, Clone, PartialEq, PartialOrd)]
@@ -102,6 +106,164 @@ fn convert_outputs(outputs: &Vec<nbformat::v4::Output>, cx: &mut WindowContext)
         .collect()
 }
 
+/// Converts a Jupyter message into the output it should be saved as, if any.
+fn nbformat_output(message: &JupyterMessageContent) -> Option<nbformat::v4::Output> {
+    match message {
+        JupyterMessageContent::StreamContent(stream) => Some(nbformat::v4::Output::Stream {
+            name: match stream.name {
+                Stdio::Stdout => "stdout",
+                Stdio::Stderr => "stderr",
+            }
+            .to_string(),
+            text: nbformat::v4::MultilineString(stream.text.clone()),
+        }),
+        JupyterMessageContent::DisplayData(display_data) => {
+            Some(nbformat::v4::Output::DisplayData(display_data.clone()))
+        }
+        JupyterMessageContent::ExecuteResult(execute_result) => {
+            Some(nbformat::v4::Output::ExecuteResult(execute_result.clone()))
+        }
+        JupyterMessageContent::ErrorOutput(error) => {
+            Some(nbformat::v4::Output::Error(error.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// Splits cell source into lines that keep their line endings, the way nbformat stores it.
+fn split_source(source: &str) -> Vec<String> {
+    source.split_inclusive('\n').map(String::from).collect()
+}
+
+pub(crate) fn new_cell_id() -> CellId {
+    CellId::from(uuid::Uuid::new_v4())
+}
+
+fn nbformat_source(cell: &nbformat::v4::Cell) -> String {
+    match cell {
+        nbformat::v4::Cell::Markdown { source, .. }
+        | nbformat::v4::Cell::Code { source, .. }
+        | nbformat::v4::Cell::Raw { source, .. } => source.join(""),
+    }
+}
+
+fn nbformat_metadata(cell: &nbformat::v4::Cell) -> &CellMetadata {
+    match cell {
+        nbformat::v4::Cell::Markdown { metadata, .. }
+        | nbformat::v4::Cell::Code { metadata, .. }
+        | nbformat::v4::Cell::Raw { metadata, .. } => metadata,
+    }
+}
+
+fn nbformat_cell_type(cell: &nbformat::v4::Cell) -> CellType {
+    match cell {
+        nbformat::v4::Cell::Markdown { .. } => CellType::Markdown,
+        nbformat::v4::Cell::Code { .. } => CellType::Code,
+        nbformat::v4::Cell::Raw { .. } => CellType::Raw,
+    }
+}
+
+/// Creates a cell without outputs or attachments.
+pub(crate) fn new_nbformat_cell(
+    cell_type: CellType,
+    id: CellId,
+    metadata: CellMetadata,
+    source: &str,
+) -> nbformat::v4::Cell {
+    let source = split_source(source);
+    match cell_type {
+        CellType::Code => nbformat::v4::Cell::Code {
+            id,
+            metadata,
+            execution_count: None,
+            source,
+            outputs: Vec::new(),
+        },
+        CellType::Markdown => nbformat::v4::Cell::Markdown {
+            id,
+            metadata,
+            source,
+            attachments: None,
+        },
+        CellType::Raw => nbformat::v4::Cell::Raw {
+            id,
+            metadata,
+            source,
+        },
+    }
+}
+
+/// Changes the type of a cell, keeping its id, metadata and source. Outputs and
+/// attachments only apply to one type of cell, so they are dropped.
+pub(crate) fn convert_nbformat_cell(
+    cell: nbformat::v4::Cell,
+    cell_type: CellType,
+) -> nbformat::v4::Cell {
+    match (&cell, &cell_type) {
+        (nbformat::v4::Cell::Code { .. }, CellType::Code)
+        | (nbformat::v4::Cell::Markdown { .. }, CellType::Markdown)
+        | (nbformat::v4::Cell::Raw { .. }, CellType::Raw) => return cell,
+        _ => {}
+    }
+    let source = nbformat_source(&cell);
+    new_nbformat_cell(
+        cell_type,
+        cell.id().clone(),
+        nbformat_metadata(&cell).clone(),
+        &source,
+    )
+}
+
+/// Splits a cell at the given source offset, dropping the line break the split happens at.
+/// The first cell keeps the original id and metadata.
+pub(crate) fn split_nbformat_cell(
+    cell: nbformat::v4::Cell,
+    offset: usize,
+) -> (nbformat::v4::Cell, nbformat::v4::Cell) {
+    let source = nbformat_source(&cell);
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let (before, after) = source.split_at(offset);
+    let before = before.strip_suffix('\n').unwrap_or(before);
+    let after = after.strip_prefix('\n').unwrap_or(after);
+
+    let first = new_nbformat_cell(
+        nbformat_cell_type(&cell),
+        cell.id().clone(),
+        nbformat_metadata(&cell).clone(),
+        before,
+    );
+    let second = new_nbformat_cell(
+        nbformat_cell_type(&cell),
+        new_cell_id(),
+        CellMetadata::default(),
+        after,
+    );
+    (first, second)
+}
+
+/// Joins a cell with the one after it into a cell of the first one's type.
+pub(crate) fn merge_nbformat_cells(
+    first: nbformat::v4::Cell,
+    second: &nbformat::v4::Cell,
+) -> nbformat::v4::Cell {
+    let first_source = nbformat_source(&first);
+    let second_source = nbformat_source(second);
+    let source = match (first_source.is_empty(), second_source.is_empty()) {
+        (_, true) => first_source,
+        (true, false) => second_source,
+        (false, false) => format!("{first_source}\n\n{second_source}"),
+    };
+    new_nbformat_cell(
+        nbformat_cell_type(&first),
+        first.id().clone(),
+        nbformat_metadata(&first).clone(),
+        &source,
+    )
+}
+
 impl Cell {
     pub fn load(
         cell: &nbformat::v4::Cell,
@@ -114,7 +276,7 @@ impl Cell {
                 id,
                 metadata,
                 source,
-                ..
+                attachments,
             } => {
                 let source = source.join("");
 
@@ -144,6 +306,7 @@ impl Cell {
                         id: id.clone(),
                         metadata: metadata.clone(),
                         source: source.clone(),
+                        attachments: attachments.clone(),
                         parsed_markdown: None,
                         selected: false,
                         cell_position: None,
@@ -191,6 +354,10 @@ impl Cell {
                     editor
                 });
 
+                buffer.update(cx, |buffer, cx| {
+                    buffer.did_save(buffer.version(), None, cx);
+                });
+
                 let buffer = buffer.clone();
                 let language_task = cx.spawn(|this, mut cx| async move {
                     let language = notebook_language.await;
@@ -207,6 +374,9 @@ impl Cell {
                     source: source.join(""),
                     editor: editor_view,
                     outputs: convert_outputs(outputs, cx),
+                    nbformat_outputs: outputs.clone(),
+                    clear_on_next_output: false,
+                    outputs_changed: false,
                     selected: false,
                     language_task,
                     cell_position: None,
@@ -225,6 +395,44 @@ impl Cell {
             })),
         }
     }
+
+    pub fn id(&self, cx: &AppContext) -> CellId {
+        match self {
+            Cell::Code(cell) => cell.read(cx).id().clone(),
+            Cell::Markdown(cell) => cell.read(cx).id().clone(),
+            Cell::Raw(cell) => cell.read(cx).id().clone(),
+        }
+    }
+
+    /// Serializes the cell with its current source and outputs.
+    pub fn to_nbformat(&self, cx: &AppContext) -> nbformat::v4::Cell {
+        match self {
+            Cell::Code(cell) => cell.read(cx).to_nbformat(cx),
+            Cell::Markdown(cell) => cell.read(cx).to_nbformat(),
+            Cell::Raw(cell) => cell.read(cx).to_nbformat(),
+        }
+    }
+
+    pub fn cell_type(&self) -> CellType {
+        match self {
+            Cell::Code(_) => CellType::Code,
+            Cell::Markdown(_) => CellType::Markdown,
+            Cell::Raw(_) => CellType::Raw,
+        }
+    }
+
+    pub fn is_dirty(&self, cx: &AppContext) -> bool {
+        match self {
+            Cell::Code(cell) => cell.read(cx).is_dirty(cx),
+            Cell::Markdown(_) | Cell::Raw(_) => false,
+        }
+    }
+
+    pub fn mark_saved(&self, cx: &mut WindowContext) {
+        if let Cell::Code(cell) = self {
+            cell.update(cx, |cell, cx| cell.mark_saved(cx));
+        }
+    }
 }
 
 pub trait RenderableCell: Render {
@@ -321,6 +529,7 @@ pub struct MarkdownCell {
     id: CellId,
     metadata: CellMetadata,
     source: String,
+    attachments: Option<serde_json::Value>,
     parsed_markdown: Option<markdown_preview::markdown_elements::ParsedMarkdown>,
     markdown_parsing_task: Task<()>,
     selected: bool,
@@ -328,6 +537,17 @@ pub struct MarkdownCell {
     languages: Arc<LanguageRegistry>,
 }
 
+impl MarkdownCell {
+    pub fn to_nbformat(&self) -> nbformat::v4::Cell {
+        nbformat::v4::Cell::Markdown {
+            id: self.id.clone(),
+            metadata: self.metadata.clone(),
+            source: split_source(&self.source),
+            attachments: self.attachments.clone(),
+        }
+    }
+}
+
 impl RenderableCell for MarkdownCell {
     const CELL_TYPE: CellType = CellType::Markdown;
 
@@ -419,6 +639,10 @@ pub struct CodeCell {
     source: String,
     editor: View<editor::Editor>,
     outputs: Vec<Output>,
+    /// The outputs as they are saved, which keep every media type the kernel sent.
+    nbformat_outputs: Vec<nbformat::v4::Output>,
+    clear_on_next_output: bool,
+    outputs_changed: bool,
     selected: bool,
     cell_position: Option<CellPosition>,
     language_task: Task<()>,
@@ -426,14 +650,144 @@ pub struct CodeCell {
 
 impl CodeCell {
     pub fn is_dirty(&self, cx: &AppContext) -> bool {
-        self.editor.read(cx).buffer().read(cx).is_dirty(cx)
+        self.outputs_changed || self.editor.read(cx).buffer().read(cx).is_dirty(cx)
     }
     pub fn has_outputs(&self) -> bool {
         !self.outputs.is_empty()
     }
 
     pub fn clear_outputs(&mut self) {
+        if self.has_outputs() || self.execution_count.is_some() {
+            self.outputs_changed = true;
+        }
         self.outputs.clear();
+        self.nbformat_outputs.clear();
+        self.clear_on_next_output = false;
+        self.execution_count = None;
+    }
+
+    pub fn editor(&self) -> &View<editor::Editor> {
+        &self.editor
+    }
+
+    pub fn source_text(&self, cx: &AppContext) -> String {
+        self.editor.read(cx).text(cx)
+    }
+
+    /// Accepts a Jupyter message belonging to an execution of this cell.
+    pub fn handle_message(&mut self, message: &JupyterMessage, cx: &mut ViewContext<Self>) {
+        match &message.content {
+            JupyterMessageContent::ExecuteInput(input) => {
+                self.execution_count = Some(input.execution_count.0 as i32);
+            }
+            JupyterMessageContent::ClearOutput(options) => {
+                if options.wait {
+                    self.clear_on_next_output = true;
+                } else {
+                    self.nbformat_outputs.clear();
+                }
+            }
+            content => {
+                if let Some(output) = nbformat_output(content) {
+                    if std::mem::take(&mut self.clear_on_next_output) {
+                        self.nbformat_outputs.clear();
+                    }
+                    self.push_nbformat_output(output);
+                }
+            }
+        }
+
+        push_output_message(&mut self.outputs, &message.content, cx);
+        self.outputs_changed = true;
+        cx.notify();
+    }
+
+    fn push_nbformat_output(&mut self, output: nbformat::v4::Output) {
+        // Consecutive writes to the same stream are saved as one output.
+        if let (
+            Some(nbformat::v4::Output::Stream {
+                name: last_name,
+                text: last_text,
+            }),
+            nbformat::v4::Output::Stream { name, text },
+        ) = (self.nbformat_outputs.last_mut(), &output)
+        {
+            if last_name == name {
+                last_text.0.push_str(&text.0);
+                return;
+            }
+        }
+        self.nbformat_outputs.push(output);
+    }
+
+    pub fn update_display_data(
+        &mut self,
+        data: &MimeBundle,
+        display_id: &str,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let mut any = false;
+        for output in &mut self.nbformat_outputs {
+            let (output_data, transient) = match output {
+                nbformat::v4::Output::DisplayData(display_data) => {
+                    (&mut display_data.data, &display_data.transient)
+                }
+                nbformat::v4::Output::ExecuteResult(execute_result) => {
+                    (&mut execute_result.data, &execute_result.transient)
+                }
+                _ => continue,
+            };
+            let output_display_id = transient.as_ref().and_then(|t| t.display_id.as_deref());
+            if output_display_id == Some(display_id) {
+                *output_data = data.clone();
+                any = true;
+            }
+        }
+
+        if update_display_data(&mut self.outputs, data, display_id, cx) || any {
+            self.outputs_changed = true;
+            cx.notify();
+        }
+    }
+
+    pub fn to_nbformat(&self, cx: &AppContext) -> nbformat::v4::Cell {
+        let outputs = self
+            .nbformat_outputs
+            .iter()
+            .cloned()
+            .map(|mut output| {
+                // Transient data only applies to the live session and isn't saved.
+                match &mut output {
+                    nbformat::v4::Output::DisplayData(display_data) => {
+                        display_data.transient = None;
+                    }
+                    nbformat::v4::Output::ExecuteResult(execute_result) => {
+                        execute_result.transient = None;
+                    }
+                    _ => {}
+                }
+                output
+            })
+            .collect();
+
+        nbformat::v4::Cell::Code {
+            id: self.id.clone(),
+            metadata: self.metadata.clone(),
+            execution_count: self.execution_count,
+            source: split_source(&self.source_text(cx)),
+            outputs,
+        }
+    }
+
+    pub fn mark_saved(&mut self, cx: &mut ViewContext<Self>) {
+        self.outputs_changed = false;
+        self.source = self.source_text(cx);
+        if let Some(buffer) = self.editor.read(cx).buffer().read(cx).as_singleton() {
+            buffer.update(cx, |buffer, cx| {
+                buffer.did_save(buffer.version(), None, cx);
+            });
+        }
+        cx.notify();
     }
 
     fn output_control(&self) -> Option<CellControlType> {
@@ -537,7 +891,7 @@ impl RenderableCell for CodeCell {
 
 impl RunnableCell for CodeCell {
     fn run(&mut self, cx: &mut ViewContext<Self>) {
-        println!("Running code cell: {}", self.id);
+        log::debug!("running code cell: {}", self.id);
     }
 
     fn execution_count(&self) -> Option<i32> {
@@ -660,6 +1014,16 @@ pub struct RawCell {
     cell_position: Option<CellPosition>,
 }
 
+impl RawCell {
+    pub fn to_nbformat(&self) -> nbformat::v4::Cell {
+        nbformat::v4::Cell::Raw {
+            id: self.id.clone(),
+            metadata: self.metadata.clone(),
+            source: split_source(&self.source),
+        }
+    }
+}
+
 impl RenderableCell for RawCell {
     const CELL_TYPE: CellType = CellType::Raw;
 
@@ -728,3 +1092,49 @@ impl Render for RawCell {
             .children(self.cell_position_spacer(false, cx))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_edits() {
+        let id = new_cell_id();
+        let cell = new_nbformat_cell(
+            CellType::Code,
+            id.clone(),
+            CellMetadata::default(),
+            "import os\nprint(os.getcwd())\n",
+        );
+
+        let (first, second) = split_nbformat_cell(cell, "import os\n".len());
+        assert_eq!(first.id(), &id);
+        assert_ne!(second.id(), &id);
+        assert_eq!(nbformat_source(&first), "import os");
+        assert_eq!(nbformat_source(&second), "print(os.getcwd())\n");
+        assert!(matches!(second, nbformat::v4::Cell::Code { .. }));
+
+        let merged = merge_nbformat_cells(first, &second);
+        assert_eq!(merged.id(), &id);
+        assert_eq!(
+            nbformat_source(&merged),
+            "import os\n\nprint(os.getcwd())\n"
+        );
+
+        let markdown = convert_nbformat_cell(merged, CellType::Markdown);
+        assert_eq!(markdown.id(), &id);
+        assert!(matches!(markdown, nbformat::v4::Cell::Markdown { .. }));
+        assert_eq!(
+            nbformat_source(&markdown),
+            "import os\n\nprint(os.getcwd())\n"
+        );
+
+        let (first, second) = split_nbformat_cell(markdown, 0);
+        assert_eq!(nbformat_source(&first), "");
+        assert!(matches!(second, nbformat::v4::Cell::Markdown { .. }));
+        assert_eq!(
+            nbformat_source(&merge_nbformat_cells(first, &second)),
+            "import os\n\nprint(os.getcwd())\n"
+        );
+    }
+}
@@ -1,29 +1,41 @@
 #![allow(unused, dead_code)]
+use std::env::temp_dir;
 use std::future::Future;
-use std::{path::PathBuf, sync::Arc};
+use std::ops::Range;
+use std::{mem, path::PathBuf, sync::Arc};
 
 use anyhow::{Context as _, Result};
 use client::proto::ViewId;
 use collections::HashMap;
 use feature_flags::{FeatureFlagAppExt as _, NotebookFeatureFlag};
 use futures::future::Shared;
-use futures::FutureExt;
+use futures::{FutureExt, SinkExt as _};
 use gpui::{
     actions, list, prelude::*, AnyElement, AppContext, EventEmitter, FocusHandle, FocusableView,
-    ListScrollEvent, ListState, Model, Point, Task, View,
+    KeyContext, ListScrollEvent, ListState, Model, Point, Task, View,
 };
 use language::{Language, LanguageRegistry};
 use project::{Project, ProjectEntryId, ProjectPath};
+use runtimelib::{ExecuteRequest, JupyterMessage, JupyterMessageContent};
+use serde::Serialize as _;
 use ui::{prelude::*, Tooltip};
+use util::ResultExt as _;
 use workspace::item::{ItemEvent, TabContentParams};
 use workspace::searchable::SearchableItemHandle;
 use workspace::{Item, ItemHandle, ProjectItem, ToolbarItemLocation};
 use workspace::{ToolbarItemEvent, ToolbarItemView};
 
-use super::{Cell, CellPosition, RenderableCell};
+use super::{
+    convert_nbformat_cell, merge_nbformat_cells, new_cell_id, new_nbformat_cell,
+    split_nbformat_cell, Cell, CellPosition, RenderableCell,
+};
+use crate::kernels::{
+    Kernel, KernelSession, KernelSpecification, NativeRunningKernel, RemoteRunningKernel,
+};
+use crate::repl_store::ReplStore;
 
-use nbformat::v4::CellId;
 use nbformat::v4::Metadata as NotebookMetadata;
+use nbformat::v4::{CellId, CellMetadata, CellType};
 
 actions!(
     notebook,
@@ -35,6 +47,17 @@ actions!(
         MoveCellDown,
         AddMarkdownBlock,
         AddCodeBlock,
+        InsertCellAbove,
+        InsertCellBelow,
+        DeleteCell,
+        SplitCell,
+        MergeCellBelow,
+        ChangeCellToCode,
+        ChangeCellToMarkdown,
+        ChangeCellToRaw,
+        UndoCellEdit,
+        RedoCellEdit,
+        EnterEditMode,
     ]
 );
 
@@ -70,6 +93,7 @@ pub struct NotebookEditor {
 
     focus_handle: FocusHandle,
     notebook_item: Model<NotebookItem>,
+    notebook_language: Shared<Task<Option<Arc<Language>>>>,
 
     remote_id: Option<ViewId>,
     cell_list: ListState,
@@ -77,6 +101,28 @@ pub struct NotebookEditor {
     selected_cell_index: usize,
     cell_order: Vec<CellId>,
     cell_map: HashMap<CellId, Cell>,
+
+    kernel: Kernel,
+    kernel_specification: Option<KernelSpecification>,
+    /// Whether to run the cells once the kernel is done restarting or shutting down.
+    run_cells_when_ready: bool,
+    /// The cell that each execute request was sent for, keyed by message id.
+    executions: HashMap<String, CellId>,
+
+    undo_stack: Vec<CellsSnapshot>,
+    redo_stack: Vec<CellsSnapshot>,
+    /// Whether cells were added, removed or rearranged since the notebook was last saved.
+    cells_changed: bool,
+}
+
+/// The cells of a notebook before a structural edit, restored when the edit is undone.
+///
+/// Edits never change a cell in place. They replace it with new cells instead, so that
+/// a snapshot keeps every cell as it was.
+struct CellsSnapshot {
+    cell_order: Vec<CellId>,
+    cell_map: HashMap<CellId, Cell>,
+    selected_cell_index: usize,
 }
 
 impl NotebookEditor {
@@ -93,24 +139,8 @@ impl NotebookEditor {
         let notebook_language = notebook_item.read(cx).notebook_language();
         let notebook_language = cx.spawn(|_, _| notebook_language).shared();
 
-        let mut cell_order = vec![]; // Vec<CellId>
-        let mut cell_map = HashMap::default(); // HashMap<CellId, Cell>
-
-        for (index, cell) in notebook_item
-            .read(cx)
-            .notebook
-            .clone()
-            .cells
-            .iter()
-            .enumerate()
-        {
-            let cell_id = cell.id();
-            cell_order.push(cell_id.clone());
-            cell_map.insert(
-                cell_id.clone(),
-                Cell::load(cell, &languages, notebook_language.clone(), cx),
-            );
-        }
+        let (cell_order, cell_map) =
+            Self::load_cells(&notebook_item, &languages, notebook_language.clone(), cx);
 
         let view = cx.view().downgrade();
         let cell_count = cell_order.len();
@@ -140,12 +170,102 @@ impl NotebookEditor {
             languages: languages.clone(),
             focus_handle,
             notebook_item,
+            notebook_language,
             remote_id: None,
             cell_list,
             selected_cell_index: 0,
             cell_order: cell_order.clone(),
             cell_map: cell_map.clone(),
+            kernel: Kernel::Shutdown,
+            kernel_specification: None,
+            run_cells_when_ready: false,
+            executions: HashMap::default(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            cells_changed: false,
+        }
+    }
+
+    fn load_cells(
+        notebook_item: &Model<NotebookItem>,
+        languages: &Arc<LanguageRegistry>,
+        notebook_language: Shared<Task<Option<Arc<Language>>>>,
+        cx: &mut WindowContext,
+    ) -> (Vec<CellId>, HashMap<CellId, Cell>) {
+        let mut cell_order = vec![]; // Vec<CellId>
+        let mut cell_map = HashMap::default(); // HashMap<CellId, Cell>
+
+        for cell in notebook_item.read(cx).notebook.clone().cells.iter() {
+            let cell_id = cell.id();
+            cell_order.push(cell_id.clone());
+            cell_map.insert(
+                cell_id.clone(),
+                Cell::load(cell, languages, notebook_language.clone(), cx),
+            );
         }
+
+        (cell_order, cell_map)
+    }
+
+    /// Replaces the cells with the ones in the notebook item, such as after a reload.
+    fn reload_cells(&mut self, cx: &mut ViewContext<Self>) {
+        let (cell_order, cell_map) = Self::load_cells(
+            &self.notebook_item,
+            &self.languages,
+            self.notebook_language.clone(),
+            cx,
+        );
+        self.cell_order = cell_order;
+        self.cell_map = cell_map;
+        self.executions.clear();
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.cells_changed = false;
+        self.cell_list.reset(self.cell_order.len());
+        self.selected_cell_index = self
+            .selected_cell_index
+            .min(self.cell_order.len().saturating_sub(1));
+        cx.notify();
+    }
+
+    /// The notebook as it would be saved, with the current source and outputs of every cell.
+    fn to_notebook(&self, cx: &AppContext) -> nbformat::v4::Notebook {
+        let mut notebook = self.notebook_item.read(cx).notebook.clone();
+        notebook.cells = self
+            .cell_order
+            .iter()
+            .filter_map(|cell_id| self.cell_map.get(cell_id))
+            .map(|cell| cell.to_nbformat(cx))
+            .collect();
+        notebook
+    }
+
+    fn write_notebook(
+        &mut self,
+        abs_path: PathBuf,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        let notebook = self.to_notebook(cx);
+        let fs = project.read(cx).fs().clone();
+        let notebook_item = self.notebook_item.clone();
+
+        cx.spawn(|this, mut cx| async move {
+            let content = serialize_notebook(&notebook)?;
+            fs.atomic_write(abs_path, content).await?;
+
+            notebook_item.update(&mut cx, |notebook_item, cx| {
+                notebook_item.notebook = notebook;
+                cx.notify();
+            })?;
+            this.update(&mut cx, |this, cx| {
+                for cell in this.cell_map.values() {
+                    cell.mark_saved(cx);
+                }
+                this.cells_changed = false;
+                cx.notify();
+            })
+        })
     }
 
     fn has_outputs(&self, cx: &ViewContext<Self>) -> bool {
@@ -169,27 +289,421 @@ impl NotebookEditor {
     }
 
     fn run_cells(&mut self, cx: &mut ViewContext<Self>) {
-        println!("Cells would all run here, if that was implemented!");
+        match &self.kernel {
+            Kernel::RunningKernel(_) | Kernel::StartingKernel(_) => self.execute_cells(cx),
+            Kernel::ShuttingDown | Kernel::Restarting => {
+                self.run_cells_when_ready = true;
+                cx.notify();
+            }
+            Kernel::Shutdown | Kernel::ErroredLaunch(_) => {
+                let notebook_language = self.notebook_language.clone();
+                cx.spawn(|this, mut cx| async move {
+                    let language = notebook_language.await;
+                    this.update(&mut cx, |this, cx| {
+                        this.start_kernel(language, cx)?;
+                        this.execute_cells(cx);
+                        anyhow::Ok(())
+                    })?
+                })
+                .detach_and_log_err(cx);
+            }
+        }
+    }
+
+    fn set_kernel(&mut self, kernel: Kernel, cx: &mut ViewContext<Self>) {
+        self.kernel = kernel;
+        match self.kernel {
+            Kernel::ShuttingDown | Kernel::Restarting => {}
+            Kernel::ErroredLaunch(_) => self.run_cells_when_ready = false,
+            Kernel::RunningKernel(_) | Kernel::StartingKernel(_) | Kernel::Shutdown => {
+                if mem::take(&mut self.run_cells_when_ready) {
+                    self.run_cells(cx);
+                }
+            }
+        }
+        cx.notify();
+    }
+
+    /// Picks the kernel the notebook's metadata asks for, falling back to the active kernel
+    /// for the notebook's language.
+    fn kernel_specification_for_notebook(
+        &self,
+        language: Option<Arc<Language>>,
+        cx: &AppContext,
+    ) -> Option<KernelSpecification> {
+        let notebook_item = self.notebook_item.read(cx);
+        let worktree_id = notebook_item.project_path.worktree_id;
+        let store = ReplStore::global(cx);
+        let store = store.read(cx);
+
+        let kernelspec_name = notebook_item
+            .notebook
+            .metadata
+            .kernelspec
+            .as_ref()
+            .map(|kernelspec| kernelspec.name.clone());
+        kernelspec_name
+            .and_then(|name| {
+                store
+                    .kernel_specifications_for_worktree(worktree_id)
+                    .find(|specification| specification.name().as_ref() == name)
+                    .cloned()
+            })
+            .or_else(|| store.active_kernelspec(worktree_id, language, cx))
+    }
+
+    fn start_kernel(
+        &mut self,
+        language: Option<Arc<Language>>,
+        cx: &mut ViewContext<Self>,
+    ) -> Result<()> {
+        let kernel_specification = self
+            .kernel_specification_for_notebook(language, cx)
+            .with_context(|| {
+                format!(
+                    "No kernel found for notebook language: {}",
+                    self.notebook_item
+                        .read(cx)
+                        .language_name()
+                        .unwrap_or_else(|| "unknown".to_string())
+                )
+            })?;
+
+        let fs = ReplStore::global(cx).read(cx).fs().clone();
+        let working_directory = self
+            .notebook_item
+            .read(cx)
+            .path
+            .parent()
+            .map(|directory| directory.to_path_buf())
+            .unwrap_or_else(temp_dir);
+        let entity_id = cx.entity_id();
+        let notebook_view = cx.view().clone();
+
+        let kernel = match kernel_specification.clone() {
+            KernelSpecification::Jupyter(kernel_specification)
+            | KernelSpecification::PythonEnv(kernel_specification) => NativeRunningKernel::new(
+                kernel_specification,
+                entity_id,
+                working_directory,
+                fs,
+                notebook_view,
+                cx,
+            ),
+            KernelSpecification::Remote(remote_kernel_specification) => RemoteRunningKernel::new(
+                remote_kernel_specification,
+                working_directory,
+                notebook_view,
+                cx,
+            ),
+        };
+
+        let pending_kernel = cx
+            .spawn(|this, mut cx| async move {
+                let kernel = kernel.await;
+                this.update(&mut cx, |this, cx| match kernel {
+                    Ok(kernel) => this.set_kernel(Kernel::RunningKernel(kernel), cx),
+                    Err(err) => this.kernel_errored(err.to_string(), cx),
+                })
+                .ok();
+            })
+            .shared();
+
+        self.kernel_specification = Some(kernel_specification);
+        self.set_kernel(Kernel::StartingKernel(pending_kernel), cx);
+        Ok(())
+    }
+
+    /// Sends an execute request for every code cell, in notebook order. The kernel runs
+    /// them one after another and skips the rest once one of them fails.
+    fn execute_cells(&mut self, cx: &mut ViewContext<Self>) {
+        let mut messages = Vec::new();
+        for cell_id in &self.cell_order {
+            let Some(Cell::Code(cell)) = self.cell_map.get(cell_id) else {
+                continue;
+            };
+            let code = cell.update(cx, |cell, cx| {
+                cell.clear_outputs();
+                cx.notify();
+                cell.source_text(cx)
+            });
+            if code.trim().is_empty() {
+                continue;
+            }
+
+            let message: JupyterMessage = ExecuteRequest {
+                code,
+                stop_on_error: true,
+                ..ExecuteRequest::default()
+            }
+            .into();
+            self.executions
+                .retain(|_, executed_cell_id| executed_cell_id != cell_id);
+            self.executions
+                .insert(message.header.msg_id.clone(), cell_id.clone());
+            messages.push(message);
+        }
+        cx.notify();
+
+        match &self.kernel {
+            Kernel::RunningKernel(_) => self.send(messages, cx),
+            Kernel::StartingKernel(task) => {
+                // Queue up the executions to run after the kernel starts
+                let task = task.clone();
+                cx.spawn(|this, mut cx| async move {
+                    task.await;
+                    this.update(&mut cx, |this, cx| this.send(messages, cx))
+                        .ok();
+                })
+                .detach();
+            }
+            _ => {}
+        }
+    }
+
+    /// Sends the messages to the kernel in order, waiting for room in its request channel
+    /// rather than dropping the ones that don't fit.
+    fn send(&mut self, messages: Vec<JupyterMessage>, cx: &mut ViewContext<Self>) {
+        if let Kernel::RunningKernel(kernel) = &mut self.kernel {
+            let mut request_tx = kernel.request_tx();
+            cx.background_executor()
+                .spawn(async move {
+                    for message in messages {
+                        request_tx.send(message).await?;
+                    }
+                    anyhow::Ok(())
+                })
+                .detach_and_log_err(cx);
+        }
     }
 
     fn open_notebook(&mut self, _: &OpenNotebook, _cx: &mut ViewContext<Self>) {
-        println!("Open notebook triggered");
+        log::debug!("open notebook triggered");
     }
 
     fn move_cell_up(&mut self, cx: &mut ViewContext<Self>) {
-        println!("Move cell up triggered");
+        let index = self.selected_cell_index;
+        if index > 0 && index < self.cell_order.len() {
+            self.swap_cells(index - 1, cx);
+            self.set_selected_index(index - 1, true, cx);
+        }
     }
 
     fn move_cell_down(&mut self, cx: &mut ViewContext<Self>) {
-        println!("Move cell down triggered");
+        let index = self.selected_cell_index;
+        if index + 1 < self.cell_order.len() {
+            self.swap_cells(index, cx);
+            self.set_selected_index(index + 1, true, cx);
+        }
+    }
+
+    /// Swaps the cell at the given index with the one after it.
+    fn swap_cells(&mut self, index: usize, cx: &mut ViewContext<Self>) {
+        self.push_undo_snapshot();
+        self.cell_order.swap(index, index + 1);
+        self.cell_list.splice(index..index + 2, 2);
+        cx.notify();
     }
 
     fn add_markdown_block(&mut self, cx: &mut ViewContext<Self>) {
-        println!("Add markdown block triggered");
+        self.insert_cell(false, CellType::Markdown, cx);
     }
 
     fn add_code_block(&mut self, cx: &mut ViewContext<Self>) {
-        println!("Add code block triggered");
+        self.insert_cell(false, CellType::Code, cx);
+    }
+
+    fn insert_cell(&mut self, above: bool, cell_type: CellType, cx: &mut ViewContext<Self>) {
+        let index = if self.cell_order.is_empty() {
+            0
+        } else if above {
+            self.selected_cell_index
+        } else {
+            self.selected_cell_index + 1
+        };
+        let cell = new_nbformat_cell(cell_type, new_cell_id(), CellMetadata::default(), "");
+        self.replace_cells(index..index, vec![cell], index, cx);
+    }
+
+    fn delete_cell(&mut self, cx: &mut ViewContext<Self>) {
+        let index = self.selected_cell_index;
+        if index < self.cell_order.len() {
+            self.replace_cells(index..index + 1, Vec::new(), index, cx);
+        }
+    }
+
+    /// Splits the cell being edited, or the selected one, at its newest cursor.
+    fn split_cell(&mut self, cx: &mut ViewContext<Self>) {
+        let focused_index = self.focused_cell_index(cx);
+        let index = focused_index.unwrap_or(self.selected_cell_index);
+        let Some(Cell::Code(cell)) = self.cell_at(index) else {
+            return;
+        };
+        let cell = cell.clone();
+        let editor = cell.read(cx).editor().clone();
+        let offset = editor.update(cx, |editor, cx| {
+            editor.selections.newest::<usize>(cx).head()
+        });
+        let (first, second) = split_nbformat_cell(cell.read(cx).to_nbformat(cx), offset);
+        self.replace_cells(index..index + 1, vec![first, second], index + 1, cx);
+        if focused_index.is_some() {
+            self.enter_edit_mode(cx);
+        }
+    }
+
+    fn merge_cell_below(&mut self, cx: &mut ViewContext<Self>) {
+        let index = self.selected_cell_index;
+        let (Some(first), Some(second)) = (self.cell_at(index), self.cell_at(index + 1)) else {
+            return;
+        };
+        let merged = merge_nbformat_cells(first.to_nbformat(cx), &second.to_nbformat(cx));
+        self.replace_cells(index..index + 2, vec![merged], index, cx);
+    }
+
+    fn change_cell_type(&mut self, cell_type: CellType, cx: &mut ViewContext<Self>) {
+        let index = self.selected_cell_index;
+        let Some(cell) = self.cell_at(index) else {
+            return;
+        };
+        if matches!(
+            (cell, &cell_type),
+            (Cell::Code(_), CellType::Code)
+                | (Cell::Markdown(_), CellType::Markdown)
+                | (Cell::Raw(_), CellType::Raw)
+        ) {
+            return;
+        }
+        let converted = convert_nbformat_cell(cell.to_nbformat(cx), cell_type);
+        self.replace_cells(index..index + 1, vec![converted], index, cx);
+    }
+
+    fn cell_at(&self, index: usize) -> Option<&Cell> {
+        self.cell_order
+            .get(index)
+            .and_then(|cell_id| self.cell_map.get(cell_id))
+    }
+
+    /// Returns the index of the code cell whose editor has focus.
+    fn focused_cell_index(&self, cx: &WindowContext) -> Option<usize> {
+        self.cell_order.iter().position(|cell_id| {
+            if let Some(Cell::Code(cell)) = self.cell_map.get(cell_id) {
+                cell.read(cx).editor().focus_handle(cx).is_focused(cx)
+            } else {
+                false
+            }
+        })
+    }
+
+    /// Replaces a range of cells with new ones, recording the change so it can be undone.
+    fn replace_cells(
+        &mut self,
+        range: Range<usize>,
+        new_cells: Vec<nbformat::v4::Cell>,
+        selected_index: usize,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.push_undo_snapshot();
+
+        let new_cells = new_cells
+            .iter()
+            .map(|cell| {
+                let cell_view =
+                    Cell::load(cell, &self.languages, self.notebook_language.clone(), cx);
+                (cell.id().clone(), cell_view)
+            })
+            .collect::<Vec<_>>();
+        let new_cell_count = new_cells.len();
+
+        let removed_cell_ids = self
+            .cell_order
+            .splice(
+                range.clone(),
+                new_cells.iter().map(|(cell_id, _)| cell_id.clone()),
+            )
+            .collect::<Vec<_>>();
+        for cell_id in removed_cell_ids {
+            self.cell_map.remove(&cell_id);
+        }
+        self.cell_map.extend(new_cells);
+        self.cell_list.splice(range, new_cell_count);
+
+        let selected_index = selected_index.min(self.cell_order.len().saturating_sub(1));
+        self.set_selected_index(selected_index, true, cx);
+        cx.notify();
+    }
+
+    fn snapshot(&self) -> CellsSnapshot {
+        CellsSnapshot {
+            cell_order: self.cell_order.clone(),
+            cell_map: self.cell_map.clone(),
+            selected_cell_index: self.selected_cell_index,
+        }
+    }
+
+    fn push_undo_snapshot(&mut self) {
+        self.undo_stack.push(self.snapshot());
+        self.redo_stack.clear();
+        self.cells_changed = true;
+    }
+
+    fn undo_cell_edit(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(snapshot) = self.undo_stack.pop() {
+            let current = self.restore_snapshot(snapshot, cx);
+            self.redo_stack.push(current);
+        }
+    }
+
+    fn redo_cell_edit(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(snapshot) = self.redo_stack.pop() {
+            let current = self.restore_snapshot(snapshot, cx);
+            self.undo_stack.push(current);
+        }
+    }
+
+    /// Restores the given cells, returning the ones they replaced.
+    fn restore_snapshot(
+        &mut self,
+        snapshot: CellsSnapshot,
+        cx: &mut ViewContext<Self>,
+    ) -> CellsSnapshot {
+        let current = self.snapshot();
+        self.cell_order = snapshot.cell_order;
+        self.cell_map = snapshot.cell_map;
+        self.cells_changed = true;
+        self.cell_list.reset(self.cell_order.len());
+        self.set_selected_index(snapshot.selected_cell_index, true, cx);
+        cx.notify();
+        current
+    }
+
+    fn enter_edit_mode(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(Cell::Code(cell)) = self.cell_at(self.selected_cell_index) {
+            let editor = cell.read(cx).editor().clone();
+            cx.focus_view(&editor);
+            cx.notify();
+        }
+    }
+
+    fn enter_command_mode(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(index) = self.focused_cell_index(cx) {
+            self.set_selected_index(index, false, cx);
+        }
+        cx.focus(&self.focus_handle);
+        cx.notify();
+    }
+
+    fn dispatch_context(&self, cx: &ViewContext<Self>) -> KeyContext {
+        let mut dispatch_context = KeyContext::new_with_defaults();
+        dispatch_context.add("notebook");
+
+        // Like Jupyter, single key bindings only apply while no cell is being edited.
+        let mode = if self.focus_handle.is_focused(cx) {
+            "command_mode"
+        } else {
+            "edit_mode"
+        };
+        dispatch_context.add(mode);
+        dispatch_context
     }
 
     fn cell_count(&self) -> usize {
@@ -387,13 +901,29 @@ impl NotebookEditor {
                         IconName::Ellipsis,
                         cx,
                     ))
-                    .child(
-                        Self::button_group(cx)
-                            .child(IconButton::new("repl", IconName::ReplNeutral)),
-                    ),
+                    .child(Self::button_group(cx).child(
+                        IconButton::new("repl", IconName::ReplNeutral).tooltip({
+                            let kernel_status = self.kernel_status_text();
+                            move |cx| Tooltip::text(kernel_status.clone(), cx)
+                        }),
+                    )),
             )
     }
 
+    fn kernel_status_text(&self) -> SharedString {
+        let Some(kernel_specification) = self.kernel_specification.as_ref() else {
+            return "No kernel running".into();
+        };
+        let status = match &self.kernel {
+            Kernel::ErroredLaunch(error) => format!("Error: {error}"),
+            kernel if self.run_cells_when_ready => {
+                format!("{}, cells run when ready", kernel.status())
+            }
+            kernel => kernel.status().to_string(),
+        };
+        format!("{} ({status})", kernel_specification.name()).into()
+    }
+
     fn cell_position(&self, index: usize) -> CellPosition {
         match index {
             0 => CellPosition::First,
@@ -441,7 +971,7 @@ impl NotebookEditor {
 impl Render for NotebookEditor {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         div()
-            .key_context("notebook")
+            .key_context(self.dispatch_context(cx))
             .track_focus(&self.focus_handle)
             .on_action(cx.listener(|this, &OpenNotebook, cx| this.open_notebook(&OpenNotebook, cx)))
             .on_action(cx.listener(|this, &ClearOutputs, cx| this.clear_outputs(cx)))
@@ -450,6 +980,36 @@ impl Render for NotebookEditor {
             .on_action(cx.listener(|this, &MoveCellDown, cx| this.move_cell_down(cx)))
             .on_action(cx.listener(|this, &AddMarkdownBlock, cx| this.add_markdown_block(cx)))
             .on_action(cx.listener(|this, &AddCodeBlock, cx| this.add_code_block(cx)))
+            .on_action(
+                cx.listener(|this, &InsertCellAbove, cx| {
+                    this.insert_cell(true, CellType::Code, cx)
+                }),
+            )
+            .on_action(
+                cx.listener(|this, &InsertCellBelow, cx| {
+                    this.insert_cell(false, CellType::Code, cx)
+                }),
+            )
+            .on_action(cx.listener(|this, &DeleteCell, cx| this.delete_cell(cx)))
+            .on_action(cx.listener(|this, &SplitCell, cx| this.split_cell(cx)))
+            .on_action(cx.listener(|this, &MergeCellBelow, cx| this.merge_cell_below(cx)))
+            .on_action(
+                cx.listener(|this, &ChangeCellToCode, cx| {
+                    this.change_cell_type(CellType::Code, cx)
+                }),
+            )
+            .on_action(cx.listener(|this, &ChangeCellToMarkdown, cx| {
+                this.change_cell_type(CellType::Markdown, cx)
+            }))
+            .on_action(
+                cx.listener(|this, &ChangeCellToRaw, cx| this.change_cell_type(CellType::Raw, cx)),
+            )
+            .on_action(cx.listener(|this, &UndoCellEdit, cx| this.undo_cell_edit(cx)))
+            .on_action(cx.listener(|this, &RedoCellEdit, cx| this.redo_cell_edit(cx)))
+            .on_action(cx.listener(|this, &EnterEditMode, cx| this.enter_edit_mode(cx)))
+            .on_action(
+                cx.listener(|this, _: &editor::actions::Cancel, cx| this.enter_command_mode(cx)),
+            )
             .on_action(cx.listener(Self::select_next))
             .on_action(cx.listener(Self::select_previous))
             .on_action(cx.listener(Self::select_first))
@@ -473,6 +1033,53 @@ impl Render for NotebookEditor {
     }
 }
 
+impl KernelSession for NotebookEditor {
+    fn route(&mut self, message: &JupyterMessage, cx: &mut ViewContext<Self>) {
+        match &message.content {
+            JupyterMessageContent::Status(status) => {
+                self.kernel.set_execution_state(&status.execution_state);
+                cx.notify();
+            }
+            JupyterMessageContent::KernelInfoReply(reply) => {
+                self.kernel.set_kernel_info(reply);
+                cx.notify();
+            }
+            JupyterMessageContent::UpdateDisplayData(update) => {
+                let Some(display_id) = update.transient.display_id.as_deref() else {
+                    return;
+                };
+                for cell in self.cell_map.values() {
+                    if let Cell::Code(cell) = cell {
+                        cell.update(cx, |cell, cx| {
+                            cell.update_display_data(&update.data, display_id, cx);
+                        });
+                    }
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        let Some(parent_header) = message.parent_header.as_ref() else {
+            return;
+        };
+        let cell = self
+            .executions
+            .get(&parent_header.msg_id)
+            .and_then(|cell_id| self.cell_map.get(cell_id));
+        if let Some(Cell::Code(cell)) = cell {
+            cell.update(cx, |cell, cx| cell.handle_message(message, cx));
+            cx.notify();
+        }
+    }
+
+    fn kernel_errored(&mut self, error_message: String, cx: &mut ViewContext<Self>) {
+        log::error!("notebook kernel errored: {error_message}");
+        self.executions.clear();
+        self.set_kernel(Kernel::ErroredLaunch(error_message), cx);
+    }
+}
+
 impl FocusableView for NotebookEditor {
     fn focus_handle(&self, _: &AppContext) -> FocusHandle {
         self.focus_handle.clone()
@@ -509,22 +1116,7 @@ impl project::ProjectItem for NotebookItem {
 
                 // todo: watch for changes to the file
                 let file_content = fs.load(&abs_path.as_path()).await?;
-                let notebook = nbformat::parse_notebook(&file_content);
-
-                let notebook = match notebook {
-                    Ok(nbformat::Notebook::V4(notebook)) => notebook,
-                    // 4.1 - 4.4 are converted to 4.5
-                    Ok(nbformat::Notebook::Legacy(legacy_notebook)) => {
-                        // todo!(): Decide if we want to mutate the notebook by including Cell IDs
-                        // and any other conversions
-                        let notebook = nbformat::upgrade_legacy_notebook(legacy_notebook)?;
-                        notebook
-                    }
-                    // Bad notebooks and notebooks v4.0 and below are not supported
-                    Err(e) => {
-                        anyhow::bail!("Failed to parse notebook: {:?}", e);
-                    }
-                };
+                let notebook = deserialize_notebook(&file_content)?;
 
                 let id = project
                     .update(&mut cx, |project, cx| project.entry_for_path(&path, cx))?
@@ -557,6 +1149,33 @@ impl project::ProjectItem for NotebookItem {
     }
 }
 
+fn deserialize_notebook(content: &str) -> Result<nbformat::v4::Notebook> {
+    match nbformat::parse_notebook(content) {
+        Ok(nbformat::Notebook::V4(notebook)) => Ok(notebook),
+        // 4.1 - 4.4 are converted to 4.5
+        Ok(nbformat::Notebook::Legacy(legacy_notebook)) => {
+            // todo!(): Decide if we want to mutate the notebook by including Cell IDs
+            // and any other conversions
+            let notebook = nbformat::upgrade_legacy_notebook(legacy_notebook)?;
+            Ok(notebook)
+        }
+        // Bad notebooks and notebooks v4.0 and below are not supported
+        Err(e) => {
+            anyhow::bail!("Failed to parse notebook: {:?}", e);
+        }
+    }
+}
+
+/// Serializes a notebook the way Jupyter writes it: indented by one space, with a trailing newline.
+fn serialize_notebook(notebook: &nbformat::v4::Notebook) -> Result<String> {
+    let mut content = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut content, formatter);
+    notebook.serialize(&mut serializer)?;
+    content.push(b'\n');
+    Ok(String::from_utf8(content)?)
+}
+
 impl NotebookItem {
     pub fn language_name(&self) -> Option<String> {
         self.notebook
@@ -695,46 +1314,67 @@ impl Item for NotebookEditor {
         // TODO
     }
 
-    // TODO
     fn can_save(&self, _cx: &AppContext) -> bool {
-        false
+        true
     }
-    // TODO
+
     fn save(
         &mut self,
         _format: bool,
-        _project: Model<Project>,
-        _cx: &mut ViewContext<Self>,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
     ) -> Task<Result<()>> {
-        unimplemented!("save() must be implemented if can_save() returns true")
+        let abs_path = self.notebook_item.read(cx).path.clone();
+        self.write_notebook(abs_path, project, cx)
     }
 
-    // TODO
     fn save_as(
         &mut self,
-        _project: Model<Project>,
-        _path: ProjectPath,
-        _cx: &mut ViewContext<Self>,
+        project: Model<Project>,
+        path: ProjectPath,
+        cx: &mut ViewContext<Self>,
     ) -> Task<Result<()>> {
-        unimplemented!("save_as() must be implemented if can_save() returns true")
+        let Some(abs_path) = project.read(cx).absolute_path(&path, cx) else {
+            return Task::ready(Err(anyhow::anyhow!("Failed to find the absolute path")));
+        };
+        let write = self.write_notebook(abs_path.clone(), project.clone(), cx);
+        let notebook_item = self.notebook_item.clone();
+
+        cx.spawn(|this, mut cx| async move {
+            write.await?;
+            let entry_id = project
+                .update(&mut cx, |project, cx| project.entry_for_path(&path, cx))?
+                .map(|entry| entry.id);
+            notebook_item.update(&mut cx, |notebook_item, cx| {
+                notebook_item.path = abs_path;
+                notebook_item.project_path = path;
+                if let Some(entry_id) = entry_id {
+                    notebook_item.id = entry_id;
+                }
+                cx.notify();
+            })?;
+            this.update(&mut cx, |_, cx| cx.notify())
+        })
     }
-    // TODO
-    fn reload(
-        &mut self,
-        _project: Model<Project>,
-        _cx: &mut ViewContext<Self>,
-    ) -> Task<Result<()>> {
-        unimplemented!("reload() must be implemented if can_save() returns true")
+
+    fn reload(&mut self, project: Model<Project>, cx: &mut ViewContext<Self>) -> Task<Result<()>> {
+        let fs = project.read(cx).fs().clone();
+        let abs_path = self.notebook_item.read(cx).path.clone();
+        let notebook_item = self.notebook_item.clone();
+
+        cx.spawn(|this, mut cx| async move {
+            let file_content = fs.load(&abs_path).await?;
+            let notebook = deserialize_notebook(&file_content)?;
+            notebook_item.update(&mut cx, |notebook_item, cx| {
+                notebook_item.notebook = notebook;
+                cx.notify();
+            })?;
+            this.update(&mut cx, |this, cx| this.reload_cells(cx))
+        })
     }
 
     fn is_dirty(&self, cx: &AppContext) -> bool {
-        self.cell_map.values().any(|cell| {
-            if let Cell::Code(code_cell) = cell {
-                code_cell.read(cx).is_dirty(cx)
-            } else {
-                false
-            }
-        })
+        self.cells_changed || self.cell_map.values().any(|cell| cell.is_dirty(cx))
     }
 }
 
@@ -755,3 +1395,466 @@ impl ProjectItem for NotebookEditor {
         Self::new(project, item, cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use futures::{channel::mpsc, StreamExt as _};
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::FakeFs;
+    use runtimelib::{
+        ClearOutput, ExecuteInput, ExecutionCount, ExecutionState, KernelInfoReply, StreamContent,
+    };
+    use serde_json::json;
+    use settings::SettingsStore;
+
+    use super::*;
+    use crate::kernels::RunningKernel;
+
+    const NOTEBOOK: &str = r##"{
+ "cells": [
+  {"cell_type": "code", "execution_count": null, "id": "first", "metadata": {}, "outputs": [], "source": ["x = 1"]},
+  {"cell_type": "markdown", "id": "notes", "metadata": {}, "source": ["Some notes"]},
+  {"cell_type": "code", "execution_count": null, "id": "second", "metadata": {}, "outputs": [], "source": ["print(x)"]},
+  {"cell_type": "code", "execution_count": null, "id": "third", "metadata": {}, "outputs": [], "source": ["x"]}
+ ],
+ "metadata": {},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"##;
+
+    #[derive(Debug)]
+    struct FakeKernel {
+        request_tx: mpsc::Sender<JupyterMessage>,
+        working_directory: PathBuf,
+        execution_state: ExecutionState,
+        kernel_info: Option<KernelInfoReply>,
+    }
+
+    impl FakeKernel {
+        fn new(request_tx: mpsc::Sender<JupyterMessage>) -> Self {
+            Self {
+                request_tx,
+                working_directory: PathBuf::from("/dir"),
+                execution_state: ExecutionState::Idle,
+                kernel_info: None,
+            }
+        }
+    }
+
+    impl RunningKernel for FakeKernel {
+        fn request_tx(&self) -> mpsc::Sender<JupyterMessage> {
+            self.request_tx.clone()
+        }
+
+        fn working_directory(&self) -> &PathBuf {
+            &self.working_directory
+        }
+
+        fn execution_state(&self) -> &ExecutionState {
+            &self.execution_state
+        }
+
+        fn set_execution_state(&mut self, state: ExecutionState) {
+            self.execution_state = state;
+        }
+
+        fn kernel_info(&self) -> Option<&KernelInfoReply> {
+            self.kernel_info.as_ref()
+        }
+
+        fn set_kernel_info(&mut self, info: KernelInfoReply) {
+            self.kernel_info = Some(info);
+        }
+
+        fn force_shutdown(&mut self, _: &mut WindowContext) -> Task<anyhow::Result<()>> {
+            Task::ready(Ok(()))
+        }
+    }
+
+    #[gpui::test]
+    async fn test_executing_cells(cx: &mut TestAppContext) {
+        init_test(cx);
+        let (notebook, cx) = open_notebook(NOTEBOOK, cx).await;
+
+        // The request channel only has room for one message, so every execution only
+        // reaches the kernel if they are sent one after another.
+        let (request_tx, mut request_rx) = mpsc::channel(0);
+        notebook.update(cx, |notebook, cx| {
+            notebook.kernel = Kernel::RunningKernel(Box::new(FakeKernel::new(request_tx)));
+            notebook.execute_cells(cx);
+        });
+        let mut requests = Vec::new();
+        for _ in 0..3 {
+            requests.push(request_rx.next().await.unwrap());
+        }
+        let codes = requests
+            .iter()
+            .map(|request| match &request.content {
+                JupyterMessageContent::ExecuteRequest(request) => request.code.clone(),
+                content => panic!("unexpected request {content:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(codes, ["x = 1", "print(x)", "x"]);
+
+        // Consecutive writes to the same stream are saved as one output.
+        let request = &requests[1];
+        route_messages(
+            &notebook,
+            [
+                ExecuteInput {
+                    code: "print(x)".into(),
+                    execution_count: ExecutionCount::new(4),
+                }
+                .as_child_of(request),
+                StreamContent::stdout("one\n").as_child_of(request),
+                StreamContent::stdout("two\n").as_child_of(request),
+                StreamContent::stderr("oops\n").as_child_of(request),
+            ],
+            cx,
+        );
+        assert_eq!(
+            code_cell_outputs(&notebook, cx),
+            [
+                (None, vec![]),
+                (
+                    Some(4),
+                    vec!["stdout: one\ntwo\n".into(), "stderr: oops\n".into()]
+                ),
+                (None, vec![]),
+            ]
+        );
+
+        // Clearing the outputs with `wait` keeps them until the next output arrives.
+        route_messages(
+            &notebook,
+            [ClearOutput { wait: true }.as_child_of(request)],
+            cx,
+        );
+        assert_eq!(
+            code_cell_outputs(&notebook, cx)[1],
+            (
+                Some(4),
+                vec!["stdout: one\ntwo\n".into(), "stderr: oops\n".into()]
+            )
+        );
+        route_messages(
+            &notebook,
+            [StreamContent::stdout("three\n").as_child_of(request)],
+            cx,
+        );
+        assert_eq!(
+            code_cell_outputs(&notebook, cx)[1],
+            (Some(4), vec!["stdout: three\n".into()])
+        );
+
+        // Messages for other executions don't reach the cell.
+        let other_request: JupyterMessage = ExecuteRequest {
+            code: "y".into(),
+            ..ExecuteRequest::default()
+        }
+        .into();
+        route_messages(
+            &notebook,
+            [StreamContent::stdout("four\n").as_child_of(&other_request)],
+            cx,
+        );
+        assert_eq!(
+            code_cell_outputs(&notebook, cx)[1],
+            (Some(4), vec!["stdout: three\n".into()])
+        );
+    }
+
+    #[gpui::test]
+    async fn test_running_cells_while_kernel_restarts(cx: &mut TestAppContext) {
+        init_test(cx);
+        let (notebook, cx) = open_notebook(NOTEBOOK, cx).await;
+
+        let (request_tx, mut request_rx) = mpsc::channel(0);
+        notebook.update(cx, |notebook, cx| {
+            notebook.kernel = Kernel::Restarting;
+            notebook.run_cells(cx);
+        });
+        cx.run_until_parked();
+        assert!(request_rx.try_next().is_err());
+
+        // The cells run once the kernel is back.
+        notebook.update(cx, |notebook, cx| {
+            notebook.set_kernel(
+                Kernel::RunningKernel(Box::new(FakeKernel::new(request_tx))),
+                cx,
+            );
+        });
+        let mut codes = Vec::new();
+        for _ in 0..3 {
+            match request_rx.next().await.unwrap().content {
+                JupyterMessageContent::ExecuteRequest(request) => codes.push(request.code),
+                content => panic!("unexpected request {content:?}"),
+            }
+        }
+        assert_eq!(codes, ["x = 1", "print(x)", "x"]);
+    }
+
+    #[gpui::test]
+    async fn test_editing_cells(cx: &mut TestAppContext) {
+        init_test(cx);
+        let (notebook, cx) = open_notebook(NOTEBOOK, cx).await;
+        let mut states = vec![cell_summaries(&notebook, cx)];
+        assert_eq!(
+            states[0],
+            [
+                "code: x = 1",
+                "markdown: Some notes",
+                "code: print(x)",
+                "code: x"
+            ]
+        );
+
+        notebook.update(cx, |notebook, cx| {
+            notebook.set_selected_index(0, false, cx);
+            notebook.insert_cell(false, CellType::Code, cx);
+        });
+        states.push(cell_summaries(&notebook, cx));
+        assert_eq!(
+            states[1],
+            [
+                "code: x = 1",
+                "code: ",
+                "markdown: Some notes",
+                "code: print(x)",
+                "code: x"
+            ]
+        );
+
+        notebook.update(cx, |notebook, cx| notebook.delete_cell(cx));
+        states.push(cell_summaries(&notebook, cx));
+        assert_eq!(states[2], states[0]);
+
+        notebook.update(cx, |notebook, cx| {
+            notebook.set_selected_index(2, false, cx);
+            let Some(Cell::Code(cell)) = notebook.cell_at(2).cloned() else {
+                panic!("expected a code cell");
+            };
+            let editor = cell.read(cx).editor().clone();
+            editor.update(cx, |editor, cx| {
+                editor.change_selections(None, cx, |s| s.select_ranges([5..5]))
+            });
+            notebook.split_cell(cx);
+        });
+        states.push(cell_summaries(&notebook, cx));
+        assert_eq!(
+            states[3],
+            [
+                "code: x = 1",
+                "markdown: Some notes",
+                "code: print",
+                "code: (x)",
+                "code: x"
+            ]
+        );
+
+        notebook.update(cx, |notebook, cx| {
+            notebook.set_selected_index(2, false, cx);
+            notebook.merge_cell_below(cx);
+        });
+        states.push(cell_summaries(&notebook, cx));
+        assert_eq!(
+            states[4],
+            [
+                "code: x = 1",
+                "markdown: Some notes",
+                "code: print\n\n(x)",
+                "code: x"
+            ]
+        );
+
+        notebook.update(cx, |notebook, cx| {
+            notebook.set_selected_index(0, false, cx);
+            notebook.change_cell_type(CellType::Markdown, cx);
+        });
+        states.push(cell_summaries(&notebook, cx));
+        assert_eq!(
+            states[5],
+            [
+                "markdown: x = 1",
+                "markdown: Some notes",
+                "code: print\n\n(x)",
+                "code: x"
+            ]
+        );
+
+        // Undoing each edit restores the cells from before it, until there is nothing left
+        // to undo.
+        for state in states.iter().rev().skip(1) {
+            notebook.update(cx, |notebook, cx| notebook.undo_cell_edit(cx));
+            assert_eq!(&cell_summaries(&notebook, cx), state);
+        }
+        notebook.update(cx, |notebook, cx| notebook.undo_cell_edit(cx));
+        assert_eq!(cell_summaries(&notebook, cx), states[0]);
+
+        for state in states.iter().skip(1) {
+            notebook.update(cx, |notebook, cx| notebook.redo_cell_edit(cx));
+            assert_eq!(&cell_summaries(&notebook, cx), state);
+        }
+        notebook.update(cx, |notebook, cx| notebook.redo_cell_edit(cx));
+        assert_eq!(cell_summaries(&notebook, cx), states[5]);
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings = SettingsStore::test(cx);
+            cx.set_global(settings);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            editor::init(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+        });
+    }
+
+    async fn open_notebook(
+        content: &str,
+        cx: &mut TestAppContext,
+    ) -> (View<NotebookEditor>, &mut VisualTestContext) {
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/dir", json!({ "notebook.ipynb": content }))
+            .await;
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let worktree_id = project.read_with(cx, |project, cx| {
+            project.worktrees(cx).next().unwrap().read(cx).id()
+        });
+        let project_path = ProjectPath {
+            worktree_id,
+            path: Path::new("notebook.ipynb").into(),
+        };
+        let notebook_item = cx
+            .update(|cx| {
+                <NotebookItem as project::ProjectItem>::try_open(&project, &project_path, cx)
+            })
+            .unwrap()
+            .await
+            .unwrap();
+        cx.add_window_view(|cx| NotebookEditor::new(project, notebook_item, cx))
+    }
+
+    fn route_messages(
+        notebook: &View<NotebookEditor>,
+        messages: impl IntoIterator<Item = JupyterMessage>,
+        cx: &mut VisualTestContext,
+    ) {
+        notebook.update(cx, |notebook, cx| {
+            for message in messages {
+                notebook.route(&message, cx);
+            }
+        });
+    }
+
+    /// Returns the type and source of each cell, as they would be saved.
+    fn cell_summaries(notebook: &View<NotebookEditor>, cx: &mut VisualTestContext) -> Vec<String> {
+        notebook.update(cx, |notebook, cx| {
+            notebook
+                .to_notebook(cx)
+                .cells
+                .iter()
+                .map(|cell| match cell {
+                    nbformat::v4::Cell::Code { source, .. } => format!("code: {}", source.join("")),
+                    nbformat::v4::Cell::Markdown { source, .. } => {
+                        format!("markdown: {}", source.join(""))
+                    }
+                    nbformat::v4::Cell::Raw { source, .. } => format!("raw: {}", source.join("")),
+                })
+                .collect()
+        })
+    }
+
+    /// Returns the execution count and saved outputs of each code cell.
+    fn code_cell_outputs(
+        notebook: &View<NotebookEditor>,
+        cx: &mut VisualTestContext,
+    ) -> Vec<(Option<i32>, Vec<String>)> {
+        notebook.update(cx, |notebook, cx| {
+            notebook
+                .to_notebook(cx)
+                .cells
+                .into_iter()
+                .filter_map(|cell| match cell {
+                    nbformat::v4::Cell::Code {
+                        execution_count,
+                        outputs,
+                        ..
+                    } => {
+                        let outputs = outputs
+                            .iter()
+                            .map(|output| match output {
+                                nbformat::v4::Output::Stream { name, text } => {
+                                    format!("{name}: {}", text.0)
+                                }
+                                output => panic!("unexpected output {output:?}"),
+                            })
+                            .collect();
+                        Some((execution_count, outputs))
+                    }
+                    _ => None,
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn test_notebook_serialization_round_trip() {
+        let content = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "id": "intro",
+   "metadata": {},
+   "source": ["# Title\n", "Some text"]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 3,
+   "id": "compute",
+   "metadata": {"tags": ["keep"]},
+   "outputs": [
+    {"name": "stdout", "output_type": "stream", "text": "hello\n"},
+    {
+     "data": {"text/plain": ["2"]},
+     "execution_count": 3,
+     "metadata": {},
+     "output_type": "execute_result"
+    }
+   ],
+   "source": ["print('hello')\n", "1 + 1"]
+  }
+ ],
+ "metadata": {
+  "kernelspec": {"display_name": "Python 3", "language": "python", "name": "python3"},
+  "custom": {"author": "someone"}
+ },
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"##;
+
+        let notebook = deserialize_notebook(content).unwrap();
+        let serialized = serialize_notebook(&notebook).unwrap();
+        assert!(serialized.starts_with("{\n \""));
+        assert!(serialized.ends_with("}\n"));
+
+        let value: serde_json::Value = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(value["metadata"]["custom"]["author"], "someone");
+        assert_eq!(value["metadata"]["kernelspec"]["name"], "python3");
+        assert_eq!(value["cells"][1]["metadata"]["tags"][0], "keep");
+        assert_eq!(value["cells"][1]["execution_count"], 3);
+        assert_eq!(value["cells"][1]["outputs"][0]["output_type"], "stream");
+        assert_eq!(
+            value["cells"][1]["outputs"][1]["output_type"],
+            "execute_result"
+        );
+
+        let reserialized = serialize_notebook(&deserialize_notebook(&serialized).unwrap()).unwrap();
+        assert_eq!(reserialized, serialized);
+    }
+}
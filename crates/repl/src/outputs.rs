@@ -328,80 +328,20 @@ impl ExecutionView {
 
     /// Accept a Jupyter message belonging to this execution
     pub fn push_message(&mut self, message: &JupyterMessageContent, cx: &mut ViewContext<Self>) {
-        let output: Output = match message {
-            JupyterMessageContent::ExecuteResult(result) => Output::new(
-                &result.data,
-                result.transient.as_ref().and_then(|t| t.display_id.clone()),
-                cx,
-            ),
-            JupyterMessageContent::DisplayData(result) => Output::new(
-                &result.data,
-                result.transient.as_ref().and_then(|t| t.display_id.clone()),
-                cx,
-            ),
-            JupyterMessageContent::StreamContent(result) => {
-                // Previous stream data will combine together, handling colors, carriage returns, etc
-                if let Some(new_terminal) = self.apply_terminal_text(&result.text, cx) {
-                    new_terminal
-                } else {
-                    return;
+        if let JupyterMessageContent::Status(status) = message {
+            match status.execution_state {
+                ExecutionState::Busy => {
+                    self.status = ExecutionStatus::Executing;
                 }
+                ExecutionState::Idle => self.status = ExecutionStatus::Finished,
             }
-            JupyterMessageContent::ErrorOutput(result) => {
-                let terminal =
-                    cx.new_view(|cx| TerminalOutput::from(&result.traceback.join("\n"), cx));
-
-                Output::ErrorOutput(ErrorView {
-                    ename: result.ename.clone(),
-                    evalue: result.evalue.clone(),
-                    traceback: terminal,
-                })
-            }
-            JupyterMessageContent::ExecuteReply(reply) => {
-                for payload in reply.payload.iter() {
-                    if let runtimelib::Payload::Page { data, .. } = payload {
-                        let output = Output::new(data, None, cx);
-                        self.outputs.push(output);
-                    }
-                }
-                cx.notify();
-                return;
-            }
-            JupyterMessageContent::ClearOutput(options) => {
-                if !options.wait {
-                    self.outputs.clear();
-                    cx.notify();
-                    return;
-                }
-
-                // Create a marker to clear the output after we get in a new output
-                Output::ClearOutputWaitMarker
-            }
-            JupyterMessageContent::Status(status) => {
-                match status.execution_state {
-                    ExecutionState::Busy => {
-                        self.status = ExecutionStatus::Executing;
-                    }
-                    ExecutionState::Idle => self.status = ExecutionStatus::Finished,
-                }
-                cx.notify();
-                return;
-            }
-            _msg => {
-                return;
-            }
-        };
-
-        // Check for a clear output marker as the previous output, so we can clear it out
-        if let Some(output) = self.outputs.last() {
-            if let Output::ClearOutputWaitMarker = output {
-                self.outputs.clear();
-            }
+            cx.notify();
+            return;
         }
 
-        self.outputs.push(output);
-
-        cx.notify();
+        if push_output_message(&mut self.outputs, message, cx) {
+            cx.notify();
+        }
     }
 
     pub fn update_display_data(
@@ -410,42 +350,124 @@ impl ExecutionView {
         display_id: &str,
         cx: &mut ViewContext<Self>,
     ) {
-        let mut any = false;
+        if update_display_data(&mut self.outputs, data, display_id, cx) {
+            cx.notify();
+        }
+    }
+}
+
+/// Applies a Jupyter message to the outputs of an execution, returning whether they changed.
+pub(crate) fn push_output_message(
+    outputs: &mut Vec<Output>,
+    message: &JupyterMessageContent,
+    cx: &mut WindowContext,
+) -> bool {
+    let output: Output = match message {
+        JupyterMessageContent::ExecuteResult(result) => Output::new(
+            &result.data,
+            result.transient.as_ref().and_then(|t| t.display_id.clone()),
+            cx,
+        ),
+        JupyterMessageContent::DisplayData(result) => Output::new(
+            &result.data,
+            result.transient.as_ref().and_then(|t| t.display_id.clone()),
+            cx,
+        ),
+        JupyterMessageContent::StreamContent(result) => {
+            // Previous stream data will combine together, handling colors, carriage returns, etc
+            if let Some(new_terminal) = apply_terminal_text(outputs, &result.text, cx) {
+                new_terminal
+            } else {
+                return true;
+            }
+        }
+        JupyterMessageContent::ErrorOutput(result) => {
+            let terminal = cx.new_view(|cx| TerminalOutput::from(&result.traceback.join("\n"), cx));
 
-        self.outputs.iter_mut().for_each(|output| {
-            if let Some(other_display_id) = output.display_id().as_ref() {
-                if other_display_id == display_id {
-                    *output = Output::new(data, Some(display_id.to_owned()), cx);
-                    any = true;
+            Output::ErrorOutput(ErrorView {
+                ename: result.ename.clone(),
+                evalue: result.evalue.clone(),
+                traceback: terminal,
+            })
+        }
+        JupyterMessageContent::ExecuteReply(reply) => {
+            for payload in reply.payload.iter() {
+                if let runtimelib::Payload::Page { data, .. } = payload {
+                    let output = Output::new(data, None, cx);
+                    outputs.push(output);
                 }
             }
-        });
+            return true;
+        }
+        JupyterMessageContent::ClearOutput(options) => {
+            if !options.wait {
+                outputs.clear();
+                return true;
+            }
 
-        if any {
-            cx.notify();
+            // Create a marker to clear the output after we get in a new output
+            Output::ClearOutputWaitMarker
+        }
+        _msg => {
+            return false;
+        }
+    };
+
+    // Check for a clear output marker as the previous output, so we can clear it out
+    if let Some(output) = outputs.last() {
+        if let Output::ClearOutputWaitMarker = output {
+            outputs.clear();
         }
     }
 
-    fn apply_terminal_text(&mut self, text: &str, cx: &mut ViewContext<Self>) -> Option<Output> {
-        if let Some(last_output) = self.outputs.last_mut() {
-            if let Output::Stream {
-                content: last_stream,
-            } = last_output
-            {
-                // Don't need to add a new output, we already have a terminal output
-                // and can just update the most recent terminal output
-                last_stream.update(cx, |last_stream, cx| {
-                    last_stream.append_text(text, cx);
-                    cx.notify();
-                });
-                return None;
+    outputs.push(output);
+    true
+}
+
+/// Replaces the outputs with the given display id, returning whether any were found.
+pub(crate) fn update_display_data(
+    outputs: &mut [Output],
+    data: &MimeBundle,
+    display_id: &str,
+    cx: &mut WindowContext,
+) -> bool {
+    let mut any = false;
+
+    outputs.iter_mut().for_each(|output| {
+        if let Some(other_display_id) = output.display_id().as_ref() {
+            if other_display_id == display_id {
+                *output = Output::new(data, Some(display_id.to_owned()), cx);
+                any = true;
             }
         }
+    });
+
+    any
+}
 
-        Some(Output::Stream {
-            content: cx.new_view(|cx| TerminalOutput::from(text, cx)),
-        })
+fn apply_terminal_text(
+    outputs: &mut [Output],
+    text: &str,
+    cx: &mut WindowContext,
+) -> Option<Output> {
+    if let Some(last_output) = outputs.last_mut() {
+        if let Output::Stream {
+            content: last_stream,
+        } = last_output
+        {
+            // Don't need to add a new output, we already have a terminal output
+            // and can just update the most recent terminal output
+            last_stream.update(cx, |last_stream, cx| {
+                last_stream.append_text(text, cx);
+                cx.notify();
+            });
+            return None;
+        }
     }
+
+    Some(Output::Stream {
+        content: cx.new_view(|cx| TerminalOutput::from(text, cx)),
+    })
 }
 
 impl Render for ExecutionView {
@@ -738,6 +738,18 @@ impl BufferSearchBar {
         });
     }
 
+    /// Fills an empty search history, e.g. with queries from a previous session.
+    pub fn restore_search_history(&mut self, queries: impl IntoIterator<Item = String>) {
+        if !self.search_history.is_empty() {
+            return;
+        }
+        for query in queries {
+            self.search_history
+                .add(&mut self.search_history_cursor, query);
+        }
+        self.search_history_cursor.reset();
+    }
+
     pub fn query(&self, cx: &WindowContext) -> String {
         self.query_editor.read(cx).text(cx)
     }
@@ -0,0 +1,78 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use futures::executor::block_on;
+use heed::types::{SerdeBincode, Str};
+use semantic_index::{AnnIndex, Embedding, EmbeddingProvider, FakeEmbeddingProvider, TextToEmbed};
+use std::{path::Path, sync::Arc};
+
+const CHUNKS_PER_FILE: usize = 16;
+
+fn fake_embeddings(texts: &[String]) -> Vec<Embedding> {
+    let texts = texts
+        .iter()
+        .map(|text| TextToEmbed::new(text))
+        .collect::<Vec<_>>();
+    block_on(FakeEmbeddingProvider.embed(&texts)).unwrap()
+}
+
+fn search_benchmarks(c: &mut Criterion) {
+    let sizes = [1024, 4096, 16384];
+    let query = fake_embeddings(&["garbage in, garbage out".to_string()]).remove(0);
+
+    let mut group = c.benchmark_group("search");
+    for size in sizes {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let env = unsafe {
+            heed::EnvOpenOptions::new()
+                .map_size(4 * 1024 * 1024 * 1024)
+                .max_dbs(8)
+                .open(temp_dir.path())
+                .unwrap()
+        };
+
+        // Store every embedding both in the index and in a database that is scanned in full,
+        // like the search did before the index existed.
+        let mut txn = env.write_txn().unwrap();
+        let index = AnnIndex::create(&env, &mut txn, "bench").unwrap();
+        let embeddings_db: heed::Database<Str, SerdeBincode<Vec<Embedding>>> =
+            env.create_database(&mut txn, Some("embeddings")).unwrap();
+        for file_ix in 0..size / CHUNKS_PER_FILE {
+            let path: Arc<Path> = Path::new(&format!("file{file_ix}.rs")).into();
+            let texts = (0..CHUNKS_PER_FILE)
+                .map(|chunk_ix| format!("file {file_ix}, chunk {chunk_ix}"))
+                .collect::<Vec<_>>();
+            let embeddings = fake_embeddings(&texts);
+            embeddings_db
+                .put(&mut txn, &path.to_string_lossy(), &embeddings)
+                .unwrap();
+            let chunks = embeddings
+                .into_iter()
+                .enumerate()
+                .map(|(chunk_ix, embedding)| (chunk_ix..chunk_ix + 1, embedding));
+            index.insert_file(&mut txn, &path, chunks).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let txn = env.read_txn().unwrap();
+        group.bench_with_input(BenchmarkId::new("ann", size), &size, |b, _| {
+            b.iter(|| black_box(index.search(&txn, &query, 16).unwrap()));
+        });
+        group.bench_with_input(BenchmarkId::new("brute_force", size), &size, |b, _| {
+            b.iter(|| {
+                let mut scores = Vec::with_capacity(size);
+                for entry in embeddings_db.iter(&txn).unwrap() {
+                    let (_, embeddings) = entry.unwrap();
+                    for embedding in embeddings {
+                        scores.push(embedding.similarity(&[query.clone()]).0);
+                    }
+                }
+                scores.sort_unstable_by(|a, b| b.total_cmp(a));
+                scores.truncate(16);
+                black_box(scores)
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, search_benchmarks);
+criterion_main!(benches);
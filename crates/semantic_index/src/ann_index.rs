@@ -0,0 +1,797 @@
+use crate::embedding::Embedding;
+use crate::embedding_index::{db_key_for_path, EmbeddedFile};
+use anyhow::{Context as _, Result};
+use collections::{Bound, HashSet};
+use heed::types::{Bytes, DecodeIgnore, SerdeBincode, Str};
+use heed::{RoTxn, RwTxn};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    ops::Range,
+    path::Path,
+    sync::Arc,
+};
+
+/// The maximum number of neighbors a node links to in the layers above the base layer.
+const MAX_CONNECTIONS: usize = 16;
+/// The maximum number of neighbors a node links to in the base layer, which contains every node.
+const MAX_BASE_CONNECTIONS: usize = 2 * MAX_CONNECTIONS;
+/// How many candidates are kept while looking for a new node's neighbors.
+const CONSTRUCTION_CANDIDATES: usize = 64;
+/// The minimum number of candidates kept while searching the base layer.
+const MIN_SEARCH_CANDIDATES: usize = 64;
+const MAX_LEVEL: usize = 16;
+/// How many files are added per transaction when indexing files that are missing from the
+/// index, so that writers re-embedding files don't wait for the whole index to be built.
+const MISSING_FILES_BATCH_SIZE: usize = 64;
+
+// The index's tables share one database, with each key prefixed by its table's tag.
+const NODES: u8 = 0;
+/// Each node's embedding as little-endian floats, so it can be scored without decoding.
+const VECTORS: u8 = 1;
+/// Each node's neighbors in each layer it appears in, keyed by [`link_key`].
+const LINKS: u8 = 2;
+/// The nodes of each file, keyed like the embeddings database.
+const FILES: u8 = 3;
+const METADATA: u8 = 4;
+const METADATA_KEY: [u8; 1] = [METADATA];
+
+type NodeId = u64;
+
+/// An approximate nearest neighbor index over the embedded chunks of a worktree.
+///
+/// The index is a hierarchical navigable small world (HNSW) graph stored in the same heed
+/// environment as the embeddings, so that it can be updated in the same transaction whenever
+/// a file is re-embedded or deleted, and searched without reading every embedding.
+#[derive(Clone, Copy)]
+pub struct AnnIndex {
+    db: heed::Database<Bytes, Bytes>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AnnNode {
+    path: Arc<Path>,
+    range: Range<usize>,
+    level: usize,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct AnnMetadata {
+    entry_point: Option<EntryPoint>,
+    next_id: NodeId,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct EntryPoint {
+    id: NodeId,
+    level: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnSearchResult {
+    pub path: Arc<Path>,
+    pub range: Range<usize>,
+    pub score: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Candidate {
+    score: f32,
+    id: NodeId,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| self.id.cmp(&other.id))
+    }
+}
+
+impl AnnIndex {
+    pub fn create(env: &heed::Env, txn: &mut RwTxn, name: &str) -> Result<Self> {
+        Ok(Self {
+            db: env.create_database(txn, Some(&format!("ann-{name}")))?,
+        })
+    }
+
+    /// Returns the number of chunks in the index.
+    pub fn len(&self, txn: &RoTxn) -> Result<u64> {
+        let mut len = 0;
+        for entry in self
+            .db
+            .remap_data_type::<DecodeIgnore>()
+            .prefix_iter(txn, &[NODES])?
+        {
+            entry?;
+            len += 1;
+        }
+        Ok(len)
+    }
+
+    /// Replaces the chunks of the file at the given path with the given ones.
+    pub fn insert_file(
+        &self,
+        txn: &mut RwTxn,
+        path: &Arc<Path>,
+        chunks: impl IntoIterator<Item = (Range<usize>, Embedding)>,
+    ) -> Result<()> {
+        let key = db_key_for_path(path);
+        self.remove_file(txn, &key)?;
+        let key = file_key(&key);
+
+        let mut node_ids = Vec::new();
+        for (range, embedding) in chunks {
+            let mut metadata = self.metadata().get(txn, &METADATA_KEY)?.unwrap_or_default();
+            let id = metadata.next_id;
+            metadata.next_id += 1;
+            self.metadata().put(txn, &METADATA_KEY, &metadata)?;
+
+            let node = AnnNode {
+                path: path.clone(),
+                range,
+                level: node_level(id),
+            };
+            self.insert_node(txn, id, node, embedding.values())?;
+            node_ids.push(id);
+        }
+        self.files().put(txn, &key, &node_ids)?;
+        Ok(())
+    }
+
+    /// Removes the chunks of the file with the given embeddings database key.
+    pub fn remove_file(&self, txn: &mut RwTxn, key: &str) -> Result<()> {
+        let key = file_key(key);
+        if let Some(node_ids) = self.files().get(txn, &key)? {
+            for id in node_ids {
+                self.remove_node(txn, id)?;
+            }
+            self.db.delete(txn, &key)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the chunks of the files whose embeddings database keys are in the given range.
+    pub fn remove_range(&self, txn: &mut RwTxn, range: &(Bound<&str>, Bound<&str>)) -> Result<()> {
+        // An unbounded range must still stop at the end of the files table.
+        let start = match range.0 {
+            Bound::Unbounded => Bound::Included(vec![FILES]),
+            bound => bound.map(file_key),
+        };
+        let end = match range.1 {
+            Bound::Unbounded => Bound::Excluded(vec![FILES + 1]),
+            bound => bound.map(file_key),
+        };
+        let range = (
+            start.as_ref().map(Vec::as_slice),
+            end.as_ref().map(Vec::as_slice),
+        );
+
+        let node_ids = self
+            .files()
+            .range(txn, &range)?
+            .map(|entry| Ok(entry?.1))
+            .collect::<Result<Vec<_>>>()?;
+        for id in node_ids.into_iter().flatten() {
+            self.remove_node(txn, id)?;
+        }
+        self.db.delete_range(txn, &range)?;
+        Ok(())
+    }
+
+    /// Adds the files in the given embeddings database that aren't in the index yet, such as
+    /// those that were embedded before the index existed. The files are added in batches,
+    /// each in its own transaction.
+    pub(crate) fn index_missing_files(
+        &self,
+        env: &heed::Env,
+        embeddings: &heed::Database<Str, SerdeBincode<EmbeddedFile>>,
+    ) -> Result<()> {
+        let mut missing_keys = Vec::new();
+        {
+            let txn = env.read_txn()?;
+            for entry in embeddings.remap_data_type::<DecodeIgnore>().iter(&txn)? {
+                let (key, ()) = entry?;
+                if self.files().get(&txn, &file_key(key))?.is_none() {
+                    missing_keys.push(key.to_string());
+                }
+            }
+        }
+
+        if !missing_keys.is_empty() {
+            log::info!("adding {} files to the ANN index", missing_keys.len());
+        }
+        for keys in missing_keys.chunks(MISSING_FILES_BATCH_SIZE) {
+            let mut txn = env.write_txn()?;
+            for key in keys {
+                // The file may have been re-embedded or deleted since it was found missing.
+                if self.files().get(&txn, &file_key(key))?.is_some() {
+                    continue;
+                }
+                if let Some(file) = embeddings.get(&txn, key)? {
+                    let chunks = file
+                        .chunks
+                        .into_iter()
+                        .map(|chunk| (chunk.chunk.range, chunk.embedding));
+                    self.insert_file(&mut txn, &file.path, chunks)?;
+                }
+            }
+            txn.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the chunks most similar to the query, most similar first.
+    pub fn search(
+        &self,
+        txn: &RoTxn,
+        query: &Embedding,
+        limit: usize,
+    ) -> Result<Vec<AnnSearchResult>> {
+        let Some(entry_point) = self
+            .metadata()
+            .get(txn, &METADATA_KEY)?
+            .and_then(|metadata| metadata.entry_point)
+        else {
+            return Ok(Vec::new());
+        };
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let query = query.values();
+        let score = self
+            .score(txn, query, entry_point.id)?
+            .context("missing ANN entry point")?;
+        let mut nearest = vec![Candidate {
+            score,
+            id: entry_point.id,
+        }];
+        for layer in (1..=entry_point.level).rev() {
+            nearest = self.search_layer(txn, query, &nearest, 1, layer)?;
+        }
+        nearest = self.search_layer(txn, query, &nearest, limit.max(MIN_SEARCH_CANDIDATES), 0)?;
+
+        let mut results = Vec::with_capacity(limit);
+        for candidate in nearest.into_iter().take(limit) {
+            if let Some(node) = self.nodes().get(txn, &node_key(NODES, candidate.id))? {
+                results.push(AnnSearchResult {
+                    path: node.path,
+                    range: node.range,
+                    score: candidate.score,
+                });
+            }
+        }
+        Ok(results)
+    }
+
+    fn insert_node(
+        &self,
+        txn: &mut RwTxn,
+        id: NodeId,
+        node: AnnNode,
+        embedding: &[f32],
+    ) -> Result<()> {
+        let level = node.level;
+        self.nodes().put(txn, &node_key(NODES, id), &node)?;
+        self.db
+            .put(txn, &node_key(VECTORS, id), &vector_bytes(embedding))?;
+
+        let mut metadata = self.metadata().get(txn, &METADATA_KEY)?.unwrap_or_default();
+        let Some(entry_point) = metadata.entry_point else {
+            for layer in 0..=level {
+                self.links().put(txn, &link_key(id, layer), &Vec::new())?;
+            }
+            metadata.entry_point = Some(EntryPoint { id, level });
+            self.metadata().put(txn, &METADATA_KEY, &metadata)?;
+            return Ok(());
+        };
+
+        let score = self
+            .score(txn, embedding, entry_point.id)?
+            .context("missing ANN entry point")?;
+        let mut nearest = vec![Candidate {
+            score,
+            id: entry_point.id,
+        }];
+        for layer in (level + 1..=entry_point.level).rev() {
+            nearest = self.search_layer(txn, embedding, &nearest, 1, layer)?;
+        }
+        for layer in (0..=level).rev() {
+            if layer > entry_point.level {
+                self.links().put(txn, &link_key(id, layer), &Vec::new())?;
+                continue;
+            }
+
+            nearest =
+                self.search_layer(txn, embedding, &nearest, CONSTRUCTION_CANDIDATES, layer)?;
+            let neighbors = nearest
+                .iter()
+                .filter(|candidate| candidate.id != id)
+                .take(max_connections(layer))
+                .map(|candidate| candidate.id)
+                .collect::<Vec<_>>();
+            self.links().put(txn, &link_key(id, layer), &neighbors)?;
+            for neighbor_id in neighbors {
+                self.connect(txn, neighbor_id, &[id], layer)?;
+            }
+        }
+
+        if level > entry_point.level {
+            metadata.entry_point = Some(EntryPoint { id, level });
+            self.metadata().put(txn, &METADATA_KEY, &metadata)?;
+        }
+        Ok(())
+    }
+
+    fn remove_node(&self, txn: &mut RwTxn, id: NodeId) -> Result<()> {
+        let Some(node) = self.nodes().get(txn, &node_key(NODES, id))? else {
+            return Ok(());
+        };
+        self.db.delete(txn, &node_key(NODES, id))?;
+        self.db.delete(txn, &node_key(VECTORS, id))?;
+
+        for layer in 0..=node.level {
+            let key = link_key(id, layer);
+            let neighbors = self.links().get(txn, &key)?.unwrap_or_default();
+            self.db.delete(txn, &key)?;
+
+            // Link the removed node's neighbors to each other, so that the nodes that were
+            // only reachable through it stay reachable. Links to the removed node from nodes
+            // it didn't link back to are skipped when searching and dropped when pruning.
+            for &neighbor_id in &neighbors {
+                self.disconnect(txn, neighbor_id, id, layer)?;
+                let others = neighbors
+                    .iter()
+                    .copied()
+                    .filter(|other_id| *other_id != neighbor_id)
+                    .collect::<Vec<_>>();
+                self.connect(txn, neighbor_id, &others, layer)?;
+            }
+        }
+
+        let mut metadata = self.metadata().get(txn, &METADATA_KEY)?.unwrap_or_default();
+        if metadata
+            .entry_point
+            .is_some_and(|entry_point| entry_point.id == id)
+        {
+            metadata.entry_point = self.highest_node(txn)?;
+            self.metadata().put(txn, &METADATA_KEY, &metadata)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the node that appears in the most layers, to use as the new entry point.
+    fn highest_node(&self, txn: &RoTxn) -> Result<Option<EntryPoint>> {
+        let mut highest: Option<EntryPoint> = None;
+        for entry in self
+            .db
+            .remap_data_type::<DecodeIgnore>()
+            .prefix_iter(txn, &[LINKS])?
+        {
+            let (key, ()) = entry?;
+            let key = u64::from_be_bytes(key[1..].try_into()?);
+            let (id, level) = (key >> 8, (key & 0xff) as usize);
+            if highest.map_or(true, |highest| level > highest.level) {
+                highest = Some(EntryPoint { id, level });
+            }
+        }
+        Ok(highest)
+    }
+
+    /// Adds links from the given node to the given nodes in a layer, keeping only the
+    /// closest ones if that exceeds the maximum number of connections.
+    fn connect(
+        &self,
+        txn: &mut RwTxn,
+        id: NodeId,
+        new_neighbors: &[NodeId],
+        layer: usize,
+    ) -> Result<()> {
+        let key = link_key(id, layer);
+        let Some(mut neighbors) = self.links().get(txn, &key)? else {
+            return Ok(());
+        };
+        for new_neighbor in new_neighbors {
+            if !neighbors.contains(new_neighbor) {
+                neighbors.push(*new_neighbor);
+            }
+        }
+
+        if neighbors.len() > max_connections(layer) {
+            let Some(embedding) = self.vector(txn, id)? else {
+                return Ok(());
+            };
+            let mut candidates = Vec::with_capacity(neighbors.len());
+            for neighbor_id in neighbors {
+                if let Some(score) = self.score(txn, &embedding, neighbor_id)? {
+                    candidates.push(Candidate {
+                        score,
+                        id: neighbor_id,
+                    });
+                }
+            }
+            candidates.sort_unstable_by(|a, b| b.cmp(a));
+            neighbors = candidates
+                .into_iter()
+                .take(max_connections(layer))
+                .map(|candidate| candidate.id)
+                .collect();
+        }
+        self.links().put(txn, &key, &neighbors)?;
+        Ok(())
+    }
+
+    fn disconnect(
+        &self,
+        txn: &mut RwTxn,
+        id: NodeId,
+        removed_id: NodeId,
+        layer: usize,
+    ) -> Result<()> {
+        let key = link_key(id, layer);
+        if let Some(mut neighbors) = self.links().get(txn, &key)? {
+            neighbors.retain(|neighbor_id| *neighbor_id != removed_id);
+            self.links().put(txn, &key, &neighbors)?;
+        }
+        Ok(())
+    }
+
+    /// Greedily walks a layer of the graph from the given entry points, returning the
+    /// `candidate_count` nodes closest to the query that it finds, closest first.
+    fn search_layer(
+        &self,
+        txn: &RoTxn,
+        query: &[f32],
+        entry_points: &[Candidate],
+        candidate_count: usize,
+        layer: usize,
+    ) -> Result<Vec<Candidate>> {
+        let mut visited = entry_points
+            .iter()
+            .map(|candidate| candidate.id)
+            .collect::<HashSet<_>>();
+        let mut candidates = entry_points.iter().copied().collect::<BinaryHeap<_>>();
+        let mut nearest = entry_points
+            .iter()
+            .copied()
+            .map(Reverse)
+            .collect::<BinaryHeap<_>>();
+
+        while let Some(candidate) = candidates.pop() {
+            if let Some(Reverse(furthest)) = nearest.peek() {
+                if nearest.len() >= candidate_count && candidate.score < furthest.score {
+                    break;
+                }
+            }
+
+            let neighbors = self
+                .links()
+                .get(txn, &link_key(candidate.id, layer))?
+                .unwrap_or_default();
+            for neighbor_id in neighbors {
+                if !visited.insert(neighbor_id) {
+                    continue;
+                }
+                let Some(score) = self.score(txn, query, neighbor_id)? else {
+                    continue;
+                };
+                let is_closer = nearest
+                    .peek()
+                    .map_or(true, |Reverse(furthest)| score > furthest.score);
+                if nearest.len() < candidate_count || is_closer {
+                    let neighbor = Candidate {
+                        score,
+                        id: neighbor_id,
+                    };
+                    candidates.push(neighbor);
+                    nearest.push(Reverse(neighbor));
+                    if nearest.len() > candidate_count {
+                        nearest.pop();
+                    }
+                }
+            }
+        }
+
+        let mut nearest = nearest
+            .into_iter()
+            .map(|Reverse(candidate)| candidate)
+            .collect::<Vec<_>>();
+        nearest.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(nearest)
+    }
+
+    /// Returns the similarity between the query and the given node, reading the node's
+    /// embedding in place.
+    fn score(&self, txn: &RoTxn, query: &[f32], id: NodeId) -> Result<Option<f32>> {
+        Ok(self.db.get(txn, &node_key(VECTORS, id))?.map(|bytes| {
+            bytes
+                .chunks_exact(4)
+                .zip(query)
+                .map(|(value, query)| f32::from_le_bytes(value.try_into().unwrap()) * query)
+                .sum()
+        }))
+    }
+
+    fn vector(&self, txn: &RoTxn, id: NodeId) -> Result<Option<Vec<f32>>> {
+        Ok(self.db.get(txn, &node_key(VECTORS, id))?.map(|bytes| {
+            bytes
+                .chunks_exact(4)
+                .map(|value| f32::from_le_bytes(value.try_into().unwrap()))
+                .collect()
+        }))
+    }
+
+    fn nodes(&self) -> heed::Database<Bytes, SerdeBincode<AnnNode>> {
+        self.db.remap_data_type()
+    }
+
+    fn links(&self) -> heed::Database<Bytes, SerdeBincode<Vec<NodeId>>> {
+        self.db.remap_data_type()
+    }
+
+    fn files(&self) -> heed::Database<Bytes, SerdeBincode<Vec<NodeId>>> {
+        self.db.remap_data_type()
+    }
+
+    fn metadata(&self) -> heed::Database<Bytes, SerdeBincode<AnnMetadata>> {
+        self.db.remap_data_type()
+    }
+}
+
+fn node_key(table: u8, id: NodeId) -> [u8; 9] {
+    let mut key = [table; 9];
+    key[1..].copy_from_slice(&id.to_be_bytes());
+    key
+}
+
+fn file_key(key: &str) -> Vec<u8> {
+    let mut file_key = Vec::with_capacity(key.len() + 1);
+    file_key.push(FILES);
+    file_key.extend_from_slice(key.as_bytes());
+    file_key
+}
+
+fn vector_bytes(embedding: &[f32]) -> Vec<u8> {
+    embedding
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+fn link_key(id: NodeId, layer: usize) -> [u8; 9] {
+    node_key(LINKS, (id << 8) | layer as u64)
+}
+
+fn max_connections(layer: usize) -> usize {
+    if layer == 0 {
+        MAX_BASE_CONNECTIONS
+    } else {
+        MAX_CONNECTIONS
+    }
+}
+
+/// Picks the highest layer a node appears in, so that each layer has about
+/// `MAX_CONNECTIONS` times fewer nodes than the one below it. The level is derived
+/// from a hash of the node's id rather than a random number to keep the graph deterministic.
+fn node_level(id: NodeId) -> usize {
+    let mut hash = id.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^= hash >> 31;
+    let uniform = (hash >> 11) as f64 / (1u64 << 53) as f64;
+    let level = -(1. - uniform).ln() / (MAX_CONNECTIONS as f64).ln();
+    (level as usize).min(MAX_LEVEL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chunking::Chunk, embedding_index::EmbeddedChunk};
+    use sha2::{Digest, Sha256};
+
+    fn test_embedding(seed: usize) -> Embedding {
+        let digest = Sha256::digest(seed.to_le_bytes());
+        Embedding::new(
+            digest
+                .chunks_exact(4)
+                .map(|bytes| i32::from_le_bytes(bytes.try_into().unwrap()) as f32)
+                .collect(),
+        )
+    }
+
+    fn test_path(file_ix: usize) -> Arc<Path> {
+        Path::new(&format!("file{file_ix:02}.rs")).into()
+    }
+
+    fn insert_test_files(index: &AnnIndex, txn: &mut RwTxn, file_ixs: Range<usize>) {
+        for file_ix in file_ixs {
+            let chunks = (0..10).map(|chunk_ix| {
+                (
+                    chunk_ix..chunk_ix + 1,
+                    test_embedding(file_ix * 10 + chunk_ix),
+                )
+            });
+            index.insert_file(txn, &test_path(file_ix), chunks).unwrap();
+        }
+    }
+
+    fn brute_force_search(
+        chunks: &[(Arc<Path>, Range<usize>, Embedding)],
+        query: &Embedding,
+        limit: usize,
+    ) -> Vec<(Arc<Path>, Range<usize>)> {
+        let mut scored = chunks
+            .iter()
+            .map(|(path, range, embedding)| (embedding.similarity(&[query.clone()]).0, path, range))
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, path, range)| (path.clone(), range.clone()))
+            .collect()
+    }
+
+    fn open_test_env(dir: &Path) -> heed::Env {
+        unsafe {
+            heed::EnvOpenOptions::new()
+                .map_size(64 * 1024 * 1024)
+                .max_dbs(8)
+                .open(dir)
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let env = open_test_env(temp_dir.path());
+        let mut txn = env.write_txn().unwrap();
+        let index = AnnIndex::create(&env, &mut txn, "test").unwrap();
+        insert_test_files(&index, &mut txn, 0..50);
+        txn.commit().unwrap();
+
+        let txn = env.read_txn().unwrap();
+        assert_eq!(index.len(&txn).unwrap(), 500);
+
+        let results = index.search(&txn, &test_embedding(123), 1).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, test_path(12));
+        assert_eq!(results[0].range, 3..4);
+        assert!(results[0].score > 0.99);
+
+        let chunks = (0..500)
+            .map(|seed| {
+                (
+                    test_path(seed / 10),
+                    seed % 10..seed % 10 + 1,
+                    test_embedding(seed),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut found = 0;
+        for query_seed in 1000..1020 {
+            let query = test_embedding(query_seed);
+            let expected = brute_force_search(&chunks, &query, 10);
+            let results = index.search(&txn, &query, 10).unwrap();
+            assert_eq!(results.len(), 10);
+            found += results
+                .iter()
+                .filter(|result| expected.contains(&(result.path.clone(), result.range.clone())))
+                .count();
+        }
+        assert!(found >= 190, "only found {found} of the 200 nearest chunks");
+    }
+
+    #[test]
+    fn test_update_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let env = open_test_env(temp_dir.path());
+        let mut txn = env.write_txn().unwrap();
+        let index = AnnIndex::create(&env, &mut txn, "test").unwrap();
+        insert_test_files(&index, &mut txn, 0..50);
+
+        let start = db_key_for_path(&test_path(10));
+        let end = db_key_for_path(&test_path(30));
+        index
+            .remove_range(
+                &mut txn,
+                &(
+                    Bound::Included(start.as_str()),
+                    Bound::Excluded(end.as_str()),
+                ),
+            )
+            .unwrap();
+        index
+            .remove_file(&mut txn, &db_key_for_path(&test_path(40)))
+            .unwrap();
+        // Re-embedding a file replaces its chunks.
+        index
+            .insert_file(
+                &mut txn,
+                &test_path(45),
+                [(0..5, test_embedding(2000)), (5..10, test_embedding(2001))],
+            )
+            .unwrap();
+        txn.commit().unwrap();
+
+        let txn = env.read_txn().unwrap();
+        assert_eq!(index.len(&txn).unwrap(), 500 - 210 - 8);
+
+        let removed_paths = (10..30).chain([40]).map(test_path).collect::<Vec<_>>();
+        for seed in (0..500).step_by(7) {
+            let results = index.search(&txn, &test_embedding(seed), 5).unwrap();
+            assert!(results
+                .iter()
+                .all(|result| !removed_paths.contains(&result.path)));
+
+            let path = test_path(seed / 10);
+            if !removed_paths.contains(&path) && seed / 10 != 45 {
+                assert_eq!(results[0].path, path);
+                assert_eq!(results[0].range, seed % 10..seed % 10 + 1);
+            }
+        }
+
+        let results = index.search(&txn, &test_embedding(2001), 1).unwrap();
+        assert_eq!(results[0].path, test_path(45));
+        assert_eq!(results[0].range, 5..10);
+    }
+
+    #[test]
+    fn test_index_missing_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let env = open_test_env(temp_dir.path());
+        let mut txn = env.write_txn().unwrap();
+        let embeddings = env
+            .create_database::<Str, SerdeBincode<EmbeddedFile>>(&mut txn, Some("embeddings"))
+            .unwrap();
+        let index = AnnIndex::create(&env, &mut txn, "test").unwrap();
+        // More files than fit in one batch, one of which is already indexed.
+        for file_ix in 0..100 {
+            let path = test_path(file_ix);
+            let file = EmbeddedFile {
+                path: path.clone(),
+                mtime: None,
+                chunks: (0..10)
+                    .map(|chunk_ix| EmbeddedChunk {
+                        chunk: Chunk {
+                            range: chunk_ix..chunk_ix + 1,
+                            digest: [0; 32],
+                        },
+                        embedding: test_embedding(file_ix * 10 + chunk_ix),
+                    })
+                    .collect(),
+            };
+            embeddings
+                .put(&mut txn, &db_key_for_path(&path), &file)
+                .unwrap();
+        }
+        insert_test_files(&index, &mut txn, 50..51);
+        txn.commit().unwrap();
+
+        index.index_missing_files(&env, &embeddings).unwrap();
+        let txn = env.read_txn().unwrap();
+        assert_eq!(index.len(&txn).unwrap(), 1000);
+        for seed in (0..1000).step_by(37) {
+            let results = index.search(&txn, &test_embedding(seed), 1).unwrap();
+            assert_eq!(results[0].path, test_path(seed / 10));
+            assert_eq!(results[0].range, seed % 10..seed % 10 + 1);
+        }
+    }
+}
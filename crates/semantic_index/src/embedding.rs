@@ -42,6 +42,10 @@ impl Embedding {
         self.0.len()
     }
 
+    pub fn values(&self) -> &[f32] {
+        &self.0
+    }
+
     pub fn similarity(&self, others: &[Embedding]) -> (f32, usize) {
         debug_assert!(others.iter().all(|other| self.0.len() == other.0.len()));
         others
@@ -105,10 +109,16 @@ impl EmbeddingProvider for FakeEmbeddingProvider {
     fn embed<'a>(&'a self, texts: &'a [TextToEmbed<'a>]) -> BoxFuture<'a, Result<Vec<Embedding>>> {
         let embeddings = texts
             .iter()
-            .map(|_text| {
-                let mut embedding = vec![0f32; 1536];
-                for i in 0..embedding.len() {
-                    embedding[i] = i as f32;
+            .map(|text| {
+                // Expand the text's digest into a pseudo-random vector, so that different
+                // texts get different embeddings.
+                let mut embedding = Vec::with_capacity(1536);
+                let mut block = text.digest;
+                while embedding.len() < 1536 {
+                    block = Sha256::digest(block).into();
+                    embedding.extend(block.chunks_exact(4).map(|bytes| {
+                        i32::from_le_bytes(bytes.try_into().unwrap()) as f32 / i32::MAX as f32
+                    }));
                 }
                 Embedding::new(embedding)
             })
@@ -1,4 +1,5 @@
 use crate::{
+    ann_index::AnnIndex,
     chunking::{self, Chunk},
     embedding::{Embedding, EmbeddingProvider, TextToEmbed},
     indexing::{IndexingEntryHandle, IndexingEntrySet},
@@ -26,6 +27,7 @@ pub struct EmbeddingIndex {
     worktree: Model<Worktree>,
     db_connection: heed::Env,
     db: heed::Database<Str, SerdeBincode<EmbeddedFile>>,
+    ann_index: AnnIndex,
     fs: Arc<dyn Fs>,
     language_registry: Arc<LanguageRegistry>,
     embedding_provider: Arc<dyn EmbeddingProvider>,
@@ -38,6 +40,7 @@ impl EmbeddingIndex {
         fs: Arc<dyn Fs>,
         db_connection: heed::Env,
         embedding_db: heed::Database<Str, SerdeBincode<EmbeddedFile>>,
+        ann_index: AnnIndex,
         language_registry: Arc<LanguageRegistry>,
         embedding_provider: Arc<dyn EmbeddingProvider>,
         entry_ids_being_indexed: Arc<IndexingEntrySet>,
@@ -47,6 +50,7 @@ impl EmbeddingIndex {
             fs,
             db_connection,
             db: embedding_db,
+            ann_index,
             language_registry,
             embedding_provider,
             entry_ids_being_indexed,
@@ -57,6 +61,10 @@ impl EmbeddingIndex {
         &self.db
     }
 
+    pub fn ann_index(&self) -> &AnnIndex {
+        &self.ann_index
+    }
+
     pub fn index_entries_changed_on_disk(
         &self,
         cx: &AppContext,
@@ -364,6 +372,7 @@ impl EmbeddingIndex {
     ) -> Task<Result<()>> {
         let db_connection = self.db_connection.clone();
         let db = self.db;
+        let ann_index = self.ann_index;
 
         cx.background_executor().spawn(async move {
             loop {
@@ -375,6 +384,7 @@ impl EmbeddingIndex {
                             let start = deletion_range.0.as_ref().map(|start| start.as_str());
                             let end = deletion_range.1.as_ref().map(|end| end.as_str());
                             log::debug!("deleting embeddings in range {:?}", &(start, end));
+                            ann_index.remove_range(&mut txn, &(start, end))?;
                             db.delete_range(&mut txn, &(start, end))?;
                             txn.commit()?;
                         }
@@ -385,6 +395,11 @@ impl EmbeddingIndex {
                             log::debug!("saving embedding for file {:?}", file.path);
                             let key = db_key_for_path(&file.path);
                             db.put(&mut txn, &key, &file)?;
+                            let chunks = file
+                                .chunks
+                                .into_iter()
+                                .map(|chunk| (chunk.chunk.range, chunk.embedding));
+                            ann_index.insert_file(&mut txn, &file.path, chunks)?;
                             txn.commit()?;
                         }
                     },
@@ -469,6 +484,6 @@ pub struct EmbeddedChunk {
     pub embedding: Embedding,
 }
 
-fn db_key_for_path(path: &Arc<Path>) -> String {
+pub(crate) fn db_key_for_path(path: &Arc<Path>) -> String {
     path.to_string_lossy().replace('/', "\0")
 }
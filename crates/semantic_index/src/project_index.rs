@@ -234,45 +234,7 @@ impl ProjectIndex {
         limit: usize,
         cx: &AppContext,
     ) -> Task<Result<Vec<SearchResult>>> {
-        let (chunks_tx, chunks_rx) = channel::bounded(1024);
-        let mut worktree_scan_tasks = Vec::new();
-        for worktree_index in self.worktree_indices.values() {
-            let worktree_index = worktree_index.clone();
-            let chunks_tx = chunks_tx.clone();
-            worktree_scan_tasks.push(cx.spawn(|cx| async move {
-                let index = match worktree_index {
-                    WorktreeIndexHandle::Loading { index } => {
-                        index.clone().await.map_err(|error| anyhow!(error))?
-                    }
-                    WorktreeIndexHandle::Loaded { index } => index.clone(),
-                };
-
-                index
-                    .read_with(&cx, |index, cx| {
-                        let worktree_id = index.worktree().read(cx).id();
-                        let db_connection = index.db_connection().clone();
-                        let db = *index.embedding_index().db();
-                        cx.background_executor().spawn(async move {
-                            let txn = db_connection
-                                .read_txn()
-                                .context("failed to create read transaction")?;
-                            let db_entries = db.iter(&txn).context("failed to iterate database")?;
-                            for db_entry in db_entries {
-                                let (_key, db_embedded_file) = db_entry?;
-                                for chunk in db_embedded_file.chunks {
-                                    chunks_tx
-                                        .send((worktree_id, db_embedded_file.path.clone(), chunk))
-                                        .await?;
-                                }
-                            }
-                            anyhow::Ok(())
-                        })
-                    })?
-                    .await
-            }));
-        }
-        drop(chunks_tx);
-
+        let worktree_indices = self.worktree_indices.values().cloned().collect::<Vec<_>>();
         let project = self.project.clone();
         let embedding_provider = self.embedding_provider.clone();
         cx.spawn(|cx| async move {
@@ -290,56 +252,72 @@ impl ProjectIndex {
                     "The number of query embeddings does not match the number of queries"
                 ));
             }
-
-            let mut results_by_worker = Vec::new();
-            for _ in 0..cx.background_executor().num_cpus() {
-                results_by_worker.push(Vec::<WorktreeSearchResult>::new());
-            }
+            let query_embeddings = Arc::new(query_embeddings);
 
             #[cfg(debug_assertions)]
             let search_start = std::time::Instant::now();
-            cx.background_executor()
-                .scoped(|cx| {
-                    for results in results_by_worker.iter_mut() {
-                        cx.spawn(async {
-                            while let Ok((worktree_id, path, chunk)) = chunks_rx.recv().await {
-                                let (score, query_index) =
-                                    chunk.embedding.similarity(&query_embeddings);
-
-                                let ix = match results.binary_search_by(|probe| {
-                                    score.partial_cmp(&probe.score).unwrap_or(Ordering::Equal)
-                                }) {
-                                    Ok(ix) | Err(ix) => ix,
-                                };
-                                if ix < limit {
-                                    results.insert(
-                                        ix,
-                                        WorktreeSearchResult {
+            let worktree_searches = worktree_indices.into_iter().map(|worktree_index| {
+                let query_embeddings = query_embeddings.clone();
+                let cx = cx.clone();
+                async move {
+                    let index = match worktree_index {
+                        WorktreeIndexHandle::Loading { index } => {
+                            index.clone().await.map_err(|error| anyhow!(error))?
+                        }
+                        WorktreeIndexHandle::Loaded { index } => index.clone(),
+                    };
+
+                    index
+                        .read_with(&cx, |index, cx| {
+                            let worktree_id = index.worktree().read(cx).id();
+                            let db_connection = index.db_connection().clone();
+                            let ann_index = *index.embedding_index().ann_index();
+                            cx.background_executor().spawn(async move {
+                                let txn = db_connection
+                                    .read_txn()
+                                    .context("failed to create read transaction")?;
+                                let mut results = Vec::new();
+                                for (query_index, query_embedding) in
+                                    query_embeddings.iter().enumerate()
+                                {
+                                    for result in ann_index.search(&txn, query_embedding, limit)? {
+                                        results.push(WorktreeSearchResult {
                                             worktree_id,
-                                            path: path.clone(),
-                                            range: chunk.chunk.range.clone(),
+                                            path: result.path,
+                                            range: result.range,
                                             query_index,
-                                            score,
-                                        },
-                                    );
-                                    if results.len() > limit {
-                                        results.pop();
+                                            score: result.score,
+                                        });
                                     }
                                 }
-                            }
-                        });
-                    }
-                })
-                .await;
+                                anyhow::Ok(results)
+                            })
+                        })?
+                        .await
+                }
+            });
 
-            for scan_task in futures::future::join_all(worktree_scan_tasks).await {
-                scan_task.log_err();
+            // A chunk can be among the nearest neighbors of several queries, so only keep
+            // its score for the query it is most similar to.
+            let mut results_by_chunk = HashMap::default();
+            for worktree_results in futures::future::join_all(worktree_searches).await {
+                for result in worktree_results.log_err().into_iter().flatten() {
+                    let key = (result.worktree_id, result.path.clone(), result.range.start);
+                    if results_by_chunk
+                        .get(&key)
+                        .map_or(true, |existing: &WorktreeSearchResult| {
+                            result.score > existing.score
+                        })
+                    {
+                        results_by_chunk.insert(key, result);
+                    }
+                }
             }
 
             project.read_with(&cx, |project, cx| {
-                let mut search_results = Vec::with_capacity(results_by_worker.len() * limit);
-                for worker_results in results_by_worker {
-                    search_results.extend(worker_results.into_iter().filter_map(|result| {
+                let mut search_results = results_by_chunk
+                    .into_values()
+                    .filter_map(|result| {
                         Some(SearchResult {
                             worktree: project.worktree_for_id(result.worktree_id, cx)?,
                             path: result.path,
@@ -347,8 +325,8 @@ impl ProjectIndex {
                             score: result.score,
                             query_index: result.query_index,
                         })
-                    }));
-                }
+                    })
+                    .collect::<Vec<_>>();
                 search_results.sort_unstable_by(|a, b| {
                     b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal)
                 });
@@ -1,3 +1,4 @@
+mod ann_index;
 mod chunking;
 mod embedding;
 mod embedding_index;
@@ -23,6 +24,7 @@ use ui::ViewContext;
 use util::ResultExt as _;
 use workspace::Workspace;
 
+pub use ann_index::{AnnIndex, AnnSearchResult};
 pub use embedding::*;
 pub use project_index::{LoadedSearchResult, ProjectIndex, SearchResult, Status};
 pub use project_index_debug_view::ProjectIndexDebugView;
@@ -47,9 +49,13 @@ impl SemanticDb {
             .spawn(async move {
                 std::fs::create_dir_all(&db_path)?;
                 unsafe {
+                    // Each worktree uses four databases: embeddings, the ANN index over
+                    // them, file digests and summaries. The ANN index stores every embedding
+                    // again along with its links, so the map is sized for large worktrees.
+                    // It only reserves address space, the file grows as data is written.
                     heed::EnvOpenOptions::new()
-                        .map_size(1024 * 1024 * 1024)
-                        .max_dbs(3000)
+                        .map_size(16 * 1024 * 1024 * 1024)
+                        .max_dbs(4000)
                         .open(db_path)
                 }
             })
@@ -1,3 +1,4 @@
+use crate::ann_index::AnnIndex;
 use crate::embedding::EmbeddingProvider;
 use crate::embedding_index::EmbeddingIndex;
 use crate::indexing::IndexingEntrySet;
@@ -32,6 +33,7 @@ pub struct WorktreeIndex {
     embedding_index: EmbeddingIndex,
     summary_index: SummaryIndex,
     entry_ids_being_indexed: Arc<IndexingEntrySet>,
+    _index_missing_ann_files: Task<()>,
     _index_entries: Task<Result<()>>,
     _subscription: Subscription,
 }
@@ -63,12 +65,14 @@ impl WorktreeIndex {
                         let embedding_index = {
                             let db_name = worktree_abs_path.to_string_lossy();
                             let db = db_connection.create_database(&mut txn, Some(&db_name))?;
+                            let ann_index = AnnIndex::create(&db_connection, &mut txn, &db_name)?;
 
                             EmbeddingIndex::new(
                                 worktree_for_index,
                                 embedding_fs,
                                 db_connection.clone(),
                                 db,
+                                ann_index,
                                 language_registry,
                                 embedding_provider,
                                 Arc::clone(&entries_being_indexed),
@@ -136,12 +140,24 @@ impl WorktreeIndex {
             }
         });
 
+        // Files embedded before the ANN index existed are added to it in the background,
+        // rather than while opening the worktree's databases.
+        let _index_missing_ann_files = cx.background_executor().spawn({
+            let db_connection = db_connection.clone();
+            let db = *embedding_index.db();
+            let ann_index = *embedding_index.ann_index();
+            async move {
+                ann_index.index_missing_files(&db_connection, &db).log_err();
+            }
+        });
+
         Self {
             db_connection,
             embedding_index,
             summary_index,
             worktree,
             entry_ids_being_indexed,
+            _index_missing_ann_files,
             _index_entries: cx.spawn(|this, cx| Self::index_entries(this, updated_entries_rx, cx)),
             _subscription,
         }
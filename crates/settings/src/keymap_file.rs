@@ -76,8 +76,25 @@ impl KeymapFile {
     }
 
     pub fn add_to_cx(self, cx: &mut AppContext) -> Result<()> {
+        for bindings in self.block_bindings(cx) {
+            cx.bind_keys(bindings?);
+        }
+        Ok(())
+    }
+
+    /// Builds the key bindings in this keymap without adding them to the app's keymap.
+    pub fn load_bindings(self, cx: &AppContext) -> Result<Vec<KeyBinding>> {
+        let mut result = Vec::new();
+        for bindings in self.block_bindings(cx) {
+            result.extend(bindings?);
+        }
+        Ok(result)
+    }
+
+    fn block_bindings(self, cx: &AppContext) -> Vec<Result<Vec<KeyBinding>>> {
         let key_equivalents = crate::key_equivalents::get_key_equivalents(&cx.keyboard_layout());
 
+        let mut result = Vec::new();
         for KeymapBlock {
             context,
             use_key_equivalents,
@@ -132,11 +149,10 @@ impl KeymapFile {
                         )
                     })
                 })
-                .collect::<Result<Vec<_>>>()?;
-
-            cx.bind_keys(bindings);
+                .collect::<Result<Vec<_>>>();
+            result.push(bindings);
         }
-        Ok(())
+        result
     }
 
     pub fn generate_json_schema(action_names: &[SharedString]) -> serde_json::Value {
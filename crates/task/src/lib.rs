@@ -1,6 +1,7 @@
 //! Baseline interface of Tasks in Zed: all tasks in Zed are intended to use those for implementing their own logic.
 #![deny(missing_docs)]
 
+mod problem_matcher;
 pub mod static_source;
 mod task_template;
 mod vscode_format;
@@ -13,7 +14,13 @@ use std::borrow::Cow;
 use std::path::PathBuf;
 use std::str::FromStr;
 
-pub use task_template::{HideStrategy, RevealStrategy, TaskTemplate, TaskTemplates};
+pub use problem_matcher::{
+    match_background_problems, match_problems, BackgroundPatterns, Problem, ProblemMatcher,
+    ProblemMatcherDefinition, ProblemPattern, ProblemSeverity,
+};
+pub use task_template::{
+    DependsOrder, HideStrategy, RevealStrategy, TaskInput, TaskTemplate, TaskTemplates,
+};
 pub use vscode_format::VsCodeTaskFile;
 pub use zed_actions::RevealTarget;
 
@@ -58,6 +65,13 @@ pub struct SpawnInTerminal {
     pub show_summary: bool,
     /// Whether to show the command line in the task output.
     pub show_command: bool,
+    /// Problem matchers to turn the task output into diagnostics with.
+    pub problem_matchers: Vec<ProblemMatcher>,
+    /// Tasks to run before this one, resolved from the template's `depends_on` by the task inventory.
+    /// When the command is empty, the task only runs these.
+    pub dependencies: Vec<SpawnInTerminal>,
+    /// How to run the [`Self::dependencies`].
+    pub depends_order: DependsOrder,
 }
 
 /// A final form of the [`TaskTemplate`], that got resolved with a particualar [`TaskContext`] and now is ready to spawn the actual task.
@@ -78,6 +92,8 @@ pub struct ResolvedTask {
     /// Further actions that need to take place after the resolved task is spawned,
     /// with all task variables resolved.
     pub resolved: Option<SpawnInTerminal>,
+    /// Why the tasks this one depends on could not be resolved, in which case it cannot be spawned.
+    pub dependency_error: Option<String>,
 }
 
 impl ResolvedTask {
@@ -259,6 +275,8 @@ pub struct TaskContext {
     /// This is the environment one would get when `cd`ing in a terminal
     /// into the project's root directory.
     pub project_env: HashMap<String, String>,
+    /// Values the user entered for the task's `${input:id}` variables, by input id.
+    pub input_values: HashMap<String, String>,
 }
 
 /// This is a new type representing a 'tag' on a 'runnable symbol', typically a test of main() function, found via treesitter.
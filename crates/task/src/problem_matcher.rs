@@ -0,0 +1,695 @@
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use collections::HashMap;
+
+use regex::{Captures, Regex};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use util::ResultExt;
+
+/// Turns the output of a task into [`Problem`]s, similar to VS Code's `problemMatcher`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ProblemMatcher {
+    /// The name of a built-in matcher: `$rustc`, `$tsc`, `$eslint-stylish`, `$eslint-compact` or `$gcc`.
+    /// `-watch` variants, such as `$tsc-watch`, match the same output; those of `$rustc` and `$tsc`
+    /// report problems every time a compilation ends.
+    Builtin(String),
+    /// A custom, regex-based matcher.
+    Custom(ProblemMatcherDefinition),
+}
+
+/// A regex-based matcher, that reads one problem from one or more consecutive lines of output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ProblemMatcherDefinition {
+    /// The source of the produced diagnostics, shown next to them.
+    /// Diagnostics of a source are replaced every time a task with that source runs.
+    pub source: String,
+    /// The severity of the problems whose pattern does not capture one.
+    #[serde(default)]
+    pub severity: ProblemSeverity,
+    /// Patterns matching consecutive lines of a single problem.
+    /// The last pattern may set `loop` to match several problems sharing the lines before it.
+    pub pattern: Vec<ProblemPattern>,
+    /// Marks the runs of a task that does not exit, such as a compiler in watch mode.
+    /// Its problems are reported every time a run ends, rather than when the task completes.
+    #[serde(default)]
+    pub background: Option<BackgroundPatterns>,
+}
+
+/// Regexes matching the lines a task in watch mode prints when each of its runs begins and ends.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BackgroundPatterns {
+    /// The regex matching the line printed when a run begins.
+    pub begins_pattern: String,
+    /// The regex matching the line printed when a run ends.
+    pub ends_pattern: String,
+}
+
+/// A regex matching a line of output, with the indices of the capture groups holding parts of the problem.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ProblemPattern {
+    /// The regex to match the line against.
+    pub regexp: String,
+    /// The group with the path of the file, absolute or relative to the task's working directory.
+    #[serde(default)]
+    pub file: Option<usize>,
+    /// The group with the 1-based line of the problem.
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// The group with the 1-based column of the problem.
+    #[serde(default)]
+    pub column: Option<usize>,
+    /// The group with the 1-based line the problem ends at.
+    #[serde(default)]
+    pub end_line: Option<usize>,
+    /// The group with the 1-based column the problem ends at.
+    #[serde(default)]
+    pub end_column: Option<usize>,
+    /// The group with the severity, such as `error` or `warning`.
+    #[serde(default)]
+    pub severity: Option<usize>,
+    /// The group with the code of the problem.
+    #[serde(default)]
+    pub code: Option<usize>,
+    /// The group with the message.
+    #[serde(default)]
+    pub message: Option<usize>,
+    /// Whether this pattern, when it is the last one, may match several lines in a row.
+    #[serde(default, rename = "loop")]
+    pub repeat: bool,
+}
+
+/// How severe a [`Problem`] is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemSeverity {
+    /// An error.
+    #[default]
+    Error,
+    /// A warning.
+    Warning,
+    /// An informational message, such as a note or a hint.
+    Info,
+}
+
+impl ProblemSeverity {
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+        if text.starts_with("err") || text.starts_with("fatal") {
+            Some(Self::Error)
+        } else if text.starts_with("warn") {
+            Some(Self::Warning)
+        } else if ["info", "note", "help", "hint"]
+            .iter()
+            .any(|prefix| text.starts_with(prefix))
+        {
+            Some(Self::Info)
+        } else {
+            None
+        }
+    }
+}
+
+/// A problem found in the output of a task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    /// The source of the matcher that found the problem.
+    pub source: String,
+    /// The path of the file, as printed by the task.
+    pub path: PathBuf,
+    /// The 1-based line of the problem.
+    pub line: u32,
+    /// The 1-based column of the problem, if known.
+    pub column: Option<u32>,
+    /// The 1-based line the problem ends at, if known.
+    pub end_line: Option<u32>,
+    /// The 1-based column the problem ends at, if known.
+    pub end_column: Option<u32>,
+    /// How severe the problem is.
+    pub severity: ProblemSeverity,
+    /// The code of the problem, such as `E0308`.
+    pub code: Option<String>,
+    /// The description of the problem.
+    pub message: String,
+}
+
+impl ProblemMatcher {
+    /// Returns the definition of the matcher, or `None` if it names an unknown built-in matcher.
+    pub fn definition(&self) -> Option<Cow<'_, ProblemMatcherDefinition>> {
+        match self {
+            Self::Builtin(name) => builtin_matcher(name).map(Cow::Owned),
+            Self::Custom(definition) => Some(Cow::Borrowed(definition)),
+        }
+    }
+
+    /// The source of the diagnostics produced by the matcher.
+    pub fn source(&self) -> Option<String> {
+        Some(self.definition()?.source.clone())
+    }
+}
+
+fn builtin_matcher(name: &str) -> Option<ProblemMatcherDefinition> {
+    let pattern = |regexp: &str| ProblemPattern {
+        regexp: regexp.to_string(),
+        ..ProblemPattern::default()
+    };
+    let definition = |source: &str, pattern: Vec<ProblemPattern>| ProblemMatcherDefinition {
+        source: source.to_string(),
+        severity: ProblemSeverity::Error,
+        pattern,
+        background: None,
+    };
+    // Watch variants print the same problems, only between compilation markers.
+    let name = name.strip_prefix('$').unwrap_or(name);
+    let (name, watch) = match name.strip_suffix("-watch") {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+    let matcher = match name {
+        "rustc" | "cargo" => definition(
+            "rustc",
+            vec![
+                ProblemPattern {
+                    severity: Some(1),
+                    code: Some(2),
+                    message: Some(3),
+                    ..pattern(r"^(warning|error)(?:\[(\w+)\])?: (.+)$")
+                },
+                ProblemPattern {
+                    file: Some(1),
+                    line: Some(2),
+                    column: Some(3),
+                    ..pattern(r"^\s*--> (.+?):(\d+):(\d+)$")
+                },
+            ],
+        ),
+        "tsc" => definition(
+            "tsc",
+            vec![ProblemPattern {
+                file: Some(1),
+                line: Some(2),
+                column: Some(3),
+                severity: Some(4),
+                code: Some(5),
+                message: Some(6),
+                ..pattern(
+                    r"^([^\s].*)[\(:](\d+)[,:](\d+)(?:\):\s+|\s+-\s+)(error|warning|info)\s+(TS\d+)\s*:\s*(.*)$",
+                )
+            }],
+        ),
+        "eslint-stylish" => definition(
+            "eslint",
+            vec![
+                ProblemPattern {
+                    file: Some(1),
+                    ..pattern(r"^([^\s].*)$")
+                },
+                ProblemPattern {
+                    line: Some(1),
+                    column: Some(2),
+                    severity: Some(3),
+                    message: Some(4),
+                    code: Some(5),
+                    repeat: true,
+                    ..pattern(r"^\s+(\d+):(\d+)\s+(error|warning|info)\s+(.*?)(?:\s\s+(\S+))?$")
+                },
+            ],
+        ),
+        "eslint-compact" => definition(
+            "eslint",
+            vec![ProblemPattern {
+                file: Some(1),
+                line: Some(2),
+                column: Some(3),
+                severity: Some(4),
+                message: Some(5),
+                code: Some(6),
+                ..pattern(
+                    r"^(.+):\sline\s(\d+),\scol\s(\d+),\s(Error|Warning|Info)\s-\s(.+)\s\((.+)\)$",
+                )
+            }],
+        ),
+        "gcc" => definition(
+            "gcc",
+            vec![ProblemPattern {
+                file: Some(1),
+                line: Some(2),
+                column: Some(3),
+                severity: Some(4),
+                message: Some(5),
+                ..pattern(r"^(.*?):(\d+):(\d*):?\s+(?:fatal\s+)?(warning|error|note):\s+(.*)$")
+            }],
+        ),
+        _ => return None,
+    };
+    if !watch {
+        return Some(matcher);
+    }
+    let (begins_pattern, ends_pattern) = match name {
+        "rustc" | "cargo" => (r"^\[Running\b", r"^\[Finished running\b"),
+        "tsc" => (
+            r"(Starting compilation in watch mode|File change detected\. Starting incremental compilation)\.\.\.",
+            r"(Compilation complete\.|Found \d+ errors?\.) Watching for file changes\.",
+        ),
+        _ => return Some(matcher),
+    };
+    Some(ProblemMatcherDefinition {
+        background: Some(BackgroundPatterns {
+            begins_pattern: begins_pattern.to_string(),
+            ends_pattern: ends_pattern.to_string(),
+        }),
+        ..matcher
+    })
+}
+
+/// Finds the problems reported in a task's output by each of the given matchers.
+/// Matchers with [`BackgroundPatterns`] only look at the output of the last run.
+/// Unknown built-in matchers and invalid regexes are logged and skipped.
+pub fn match_problems(matchers: &[ProblemMatcher], output: &str) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for matcher in matchers {
+        let Some((definition, regexes)) = compile_matcher(matcher) else {
+            continue;
+        };
+        let output = match &regexes.background {
+            Some((begins, _)) => last_run(begins, output),
+            None => output,
+        };
+        match_lines(&definition, &regexes.pattern, output, &mut problems);
+    }
+    problems
+}
+
+/// Finds the problems of the last completed run of a task in watch mode, for each of the given
+/// matchers that has [`BackgroundPatterns`], by source.
+/// Sources with no completed run yet are omitted, as their previous problems still stand.
+pub fn match_background_problems(
+    matchers: &[ProblemMatcher],
+    output: &str,
+) -> HashMap<String, Vec<Problem>> {
+    let mut problems_by_source = HashMap::default();
+    for matcher in matchers {
+        let Some((definition, regexes)) = compile_matcher(matcher) else {
+            continue;
+        };
+        let Some((begins, ends)) = &regexes.background else {
+            continue;
+        };
+        let Some(run) = last_completed_run(begins, ends, output) else {
+            continue;
+        };
+        let mut problems = Vec::new();
+        match_lines(&definition, &regexes.pattern, run, &mut problems);
+        problems_by_source
+            .entry(definition.source.clone())
+            .or_insert_with(Vec::new)
+            .extend(problems);
+    }
+    problems_by_source
+}
+
+struct MatcherRegexes {
+    pattern: Vec<Regex>,
+    background: Option<(Regex, Regex)>,
+}
+
+fn compile_matcher(
+    matcher: &ProblemMatcher,
+) -> Option<(Cow<'_, ProblemMatcherDefinition>, MatcherRegexes)> {
+    let Some(definition) = matcher.definition() else {
+        log::error!("unknown problem matcher {matcher:?}");
+        return None;
+    };
+    let pattern = definition
+        .pattern
+        .iter()
+        .map(|pattern| Regex::new(&pattern.regexp))
+        .collect::<Result<Vec<_>, _>>()
+        .log_err()?;
+    if pattern.is_empty() {
+        return None;
+    }
+    let background = match &definition.background {
+        Some(background) => Some((
+            Regex::new(&background.begins_pattern).log_err()?,
+            Regex::new(&background.ends_pattern).log_err()?,
+        )),
+        None => None,
+    };
+    Some((
+        definition,
+        MatcherRegexes {
+            pattern,
+            background,
+        },
+    ))
+}
+
+/// The output after the last line that matches `begins`, or all of it if none does.
+fn last_run<'a>(begins: &Regex, output: &'a str) -> &'a str {
+    let mut start = 0;
+    let mut offset = 0;
+    for line in output.split_inclusive('\n') {
+        offset += line.len();
+        if begins.is_match(line.trim_end()) {
+            start = offset;
+        }
+    }
+    &output[start..]
+}
+
+/// The output of the last run that ended with a line matching `ends`, from the line matching
+/// `begins` before it, or `None` if no run has ended yet.
+fn last_completed_run<'a>(begins: &Regex, ends: &Regex, output: &'a str) -> Option<&'a str> {
+    let mut run_start = 0;
+    let mut offset = 0;
+    let mut completed = None;
+    for line in output.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let line = line.trim_end();
+        if begins.is_match(line) {
+            run_start = offset;
+        } else if ends.is_match(line) {
+            completed = Some(&output[run_start..line_start]);
+        }
+    }
+    completed
+}
+
+/// The parts of a problem captured so far, from one or more lines.
+#[derive(Clone, Default)]
+struct CapturedProblem {
+    file: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    end_line: Option<u32>,
+    end_column: Option<u32>,
+    severity: Option<ProblemSeverity>,
+    code: Option<String>,
+    message: Option<String>,
+}
+
+impl CapturedProblem {
+    fn capture(&mut self, pattern: &ProblemPattern, captures: &Captures) {
+        let group = |index: Option<usize>| {
+            captures
+                .get(index?)
+                .map(|group| group.as_str())
+                .filter(|text| !text.is_empty())
+        };
+        let number = |index: Option<usize>| group(index)?.parse::<u32>().ok();
+        if let Some(file) = group(pattern.file) {
+            self.file = Some(file.trim().to_string());
+        }
+        self.line = number(pattern.line).or(self.line);
+        self.column = number(pattern.column).or(self.column);
+        self.end_line = number(pattern.end_line).or(self.end_line);
+        self.end_column = number(pattern.end_column).or(self.end_column);
+        if let Some(severity) = group(pattern.severity).and_then(ProblemSeverity::parse) {
+            self.severity = Some(severity);
+        }
+        if let Some(code) = group(pattern.code) {
+            self.code = Some(code.to_string());
+        }
+        if let Some(message) = group(pattern.message) {
+            self.message = Some(message.trim().to_string());
+        }
+    }
+
+    fn into_problem(self, definition: &ProblemMatcherDefinition) -> Option<Problem> {
+        Some(Problem {
+            source: definition.source.clone(),
+            path: PathBuf::from(self.file?),
+            line: self.line?,
+            column: self.column,
+            end_line: self.end_line,
+            end_column: self.end_column,
+            severity: self.severity.unwrap_or(definition.severity),
+            code: self.code,
+            message: self.message?,
+        })
+    }
+}
+
+fn match_lines(
+    definition: &ProblemMatcherDefinition,
+    regexes: &[Regex],
+    output: &str,
+    problems: &mut Vec<Problem>,
+) {
+    let last_pattern = regexes.len() - 1;
+    // The index of the pattern the next line has to match, and what the previous lines captured.
+    let mut next_pattern = 0;
+    let mut captured = CapturedProblem::default();
+    for line in output.lines() {
+        let line = line.trim_end();
+        if next_pattern > 0 {
+            let pattern = &definition.pattern[next_pattern];
+            if let Some(captures) = regexes[next_pattern].captures(line) {
+                if next_pattern == last_pattern {
+                    let mut problem = captured.clone();
+                    problem.capture(pattern, &captures);
+                    problems.extend(problem.into_problem(definition));
+                    if !pattern.repeat {
+                        next_pattern = 0;
+                    }
+                } else {
+                    captured.capture(pattern, &captures);
+                    next_pattern += 1;
+                }
+                continue;
+            }
+            // The problem ended, so the line may start a new one.
+            next_pattern = 0;
+        }
+
+        if let Some(captures) = regexes[0].captures(line) {
+            captured = CapturedProblem::default();
+            captured.capture(&definition.pattern[0], &captures);
+            if last_pattern == 0 {
+                problems.extend(captured.clone().into_problem(definition));
+            } else {
+                next_pattern = 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(matcher: &str, output: &str) -> Vec<Problem> {
+        match_problems(&[ProblemMatcher::Builtin(matcher.to_string())], output)
+    }
+
+    #[test]
+    fn test_rustc_matcher() {
+        let output = r#"   Compiling app v0.1.0 (/work/app)
+warning: unused variable: `x`
+ --> src/main.rs:2:9
+  |
+2 |     let x = 5;
+  |         ^ help: if this is intentional, prefix it with an underscore: `_x`
+
+error[E0308]: mismatched types
+  --> src/lib.rs:10:18
+   |
+error: aborting due to 1 previous error
+"#;
+        assert_eq!(
+            problems("$rustc", output),
+            vec![
+                Problem {
+                    source: "rustc".into(),
+                    path: PathBuf::from("src/main.rs"),
+                    line: 2,
+                    column: Some(9),
+                    end_line: None,
+                    end_column: None,
+                    severity: ProblemSeverity::Warning,
+                    code: None,
+                    message: "unused variable: `x`".into(),
+                },
+                Problem {
+                    source: "rustc".into(),
+                    path: PathBuf::from("src/lib.rs"),
+                    line: 10,
+                    column: Some(18),
+                    end_line: None,
+                    end_column: None,
+                    severity: ProblemSeverity::Error,
+                    code: Some("E0308".into()),
+                    message: "mismatched types".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tsc_matcher() {
+        let output = "src/index.ts(3,7): error TS2322: Type 'string' is not assignable to type 'number'.\n\
+                      src/app.ts:12:1 - warning TS6133: 'foo' is declared but its value is never read.\n\
+                      Found 2 errors.";
+        let problems = problems("$tsc", output);
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].path, PathBuf::from("src/index.ts"));
+        assert_eq!((problems[0].line, problems[0].column), (3, Some(7)));
+        assert_eq!(problems[0].code.as_deref(), Some("TS2322"));
+        assert_eq!(
+            problems[0].message,
+            "Type 'string' is not assignable to type 'number'."
+        );
+        assert_eq!(problems[1].path, PathBuf::from("src/app.ts"));
+        assert_eq!(problems[1].severity, ProblemSeverity::Warning);
+    }
+
+    #[test]
+    fn test_eslint_matchers() {
+        let stylish = "\n/work/src/a.js\n  1:10  error    'foo' is defined but never used  no-unused-vars\n  3:1   warning  Unexpected console statement     no-console\n\n/work/src/b.js\n  7:5  error  Missing semicolon  semi\n\n✖ 3 problems (2 errors, 1 warning)\n";
+        let problems_found = problems("$eslint-stylish", stylish);
+        assert_eq!(
+            problems_found
+                .iter()
+                .map(|problem| (
+                    problem.path.to_string_lossy().into_owned(),
+                    problem.line,
+                    problem.severity,
+                    problem.message.as_str(),
+                    problem.code.as_deref(),
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "/work/src/a.js".to_string(),
+                    1,
+                    ProblemSeverity::Error,
+                    "'foo' is defined but never used",
+                    Some("no-unused-vars"),
+                ),
+                (
+                    "/work/src/a.js".to_string(),
+                    3,
+                    ProblemSeverity::Warning,
+                    "Unexpected console statement",
+                    Some("no-console"),
+                ),
+                (
+                    "/work/src/b.js".to_string(),
+                    7,
+                    ProblemSeverity::Error,
+                    "Missing semicolon",
+                    Some("semi"),
+                ),
+            ]
+        );
+
+        let compact = "/work/src/a.js: line 1, col 10, Error - 'foo' is defined but never used (no-unused-vars)";
+        let problems_found = problems("$eslint-compact", compact);
+        assert_eq!(problems_found.len(), 1);
+        assert_eq!(problems_found[0].code.as_deref(), Some("no-unused-vars"));
+        assert_eq!(problems_found[0].column, Some(10));
+    }
+
+    #[test]
+    fn test_gcc_matcher() {
+        let output = "main.c: In function 'main':\n\
+                      main.c:5:3: warning: implicit declaration of function 'foo'\n\
+                      main.c:9:1: fatal error: missing.h: No such file or directory\n\
+                      compilation terminated.";
+        let problems = problems("$gcc", output);
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].severity, ProblemSeverity::Warning);
+        assert_eq!((problems[1].line, problems[1].column), (9, Some(1)));
+        assert_eq!(problems[1].severity, ProblemSeverity::Error);
+        assert_eq!(problems[1].message, "missing.h: No such file or directory");
+    }
+
+    #[test]
+    fn test_watch_matchers() {
+        let matchers = [
+            ProblemMatcher::Builtin("$tsc-watch".to_string()),
+            ProblemMatcher::Builtin("$gcc".to_string()),
+        ];
+        let paths = |problems: &[Problem]| {
+            problems
+                .iter()
+                .map(|problem| problem.path.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        let background_paths = |output: &str| {
+            match_background_problems(&matchers, output)
+                .into_iter()
+                .map(|(source, problems)| (source, paths(&problems)))
+                .collect::<Vec<_>>()
+        };
+
+        let mut output = "[12:00:00] Starting compilation in watch mode...\n\
+                          src/a.ts(3,7): error TS2322: Type 'string' is not assignable to type 'number'.\n"
+            .to_string();
+        assert_eq!(background_paths(&output), Vec::new());
+
+        output.push_str("[12:00:01] Found 1 error. Watching for file changes.\n");
+        assert_eq!(
+            background_paths(&output),
+            vec![("tsc".to_string(), vec!["src/a.ts".to_string()])]
+        );
+
+        output.push_str(
+            "[12:00:05] File change detected. Starting incremental compilation...\n\
+             src/b.ts(1,1): error TS1005: ';' expected.\n",
+        );
+        assert_eq!(
+            background_paths(&output),
+            vec![("tsc".to_string(), vec!["src/a.ts".to_string()])],
+            "a run in progress should not replace the problems of the previous one"
+        );
+        assert_eq!(paths(&match_problems(&matchers, &output)), vec!["src/b.ts"]);
+
+        output.push_str(
+            "[12:00:06] Found 1 error. Watching for file changes.\n\
+             [12:00:09] File change detected. Starting incremental compilation...\n\
+             [12:00:10] Found 0 errors. Watching for file changes.\n",
+        );
+        assert_eq!(
+            background_paths(&output),
+            vec![("tsc".to_string(), Vec::new())]
+        );
+    }
+
+    #[test]
+    fn test_custom_matcher() {
+        let matcher: ProblemMatcher = serde_json_lenient::from_str(
+            r#"{
+                "source": "lint",
+                "severity": "warning",
+                "pattern": [{ "regexp": "^(\\S+):(\\d+) (.*)$", "file": 1, "line": 2, "message": 3 }]
+            }"#,
+        )
+        .unwrap();
+        let problems = match_problems(
+            &[matcher, ProblemMatcher::Builtin("$unknown".into())],
+            "a.py:4 too long\nnot a problem",
+        );
+        assert_eq!(
+            problems,
+            vec![Problem {
+                source: "lint".into(),
+                path: PathBuf::from("a.py"),
+                line: 4,
+                column: None,
+                end_line: None,
+                end_column: None,
+                severity: ProblemSeverity::Warning,
+                code: None,
+                message: "too long".into(),
+            }]
+        );
+    }
+}
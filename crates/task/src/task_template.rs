@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{iter, path::PathBuf};
 use util::serde::default_true;
 
 use anyhow::{bail, Context};
@@ -9,8 +9,8 @@ use sha2::{Digest, Sha256};
 use util::{truncate_and_remove_front, ResultExt};
 
 use crate::{
-    ResolvedTask, RevealTarget, Shell, SpawnInTerminal, TaskContext, TaskId, VariableName,
-    ZED_VARIABLE_NAME_PREFIX,
+    ProblemMatcher, ResolvedTask, RevealTarget, Shell, SpawnInTerminal, TaskContext, TaskId,
+    VariableName, ZED_VARIABLE_NAME_PREFIX,
 };
 
 /// A template definition of a Zed task to run.
@@ -24,7 +24,8 @@ use crate::{
 pub struct TaskTemplate {
     /// Human readable name of the task to display in the UI.
     pub label: String,
-    /// Executable command to spawn.
+    /// Executable command to spawn. May be left empty for compound tasks, that only run the tasks they depend on.
+    #[serde(default)]
     pub command: String,
     /// Arguments to the command.
     #[serde(default)]
@@ -70,6 +71,50 @@ pub struct TaskTemplate {
     /// Whether to show the command line in the task output.
     #[serde(default = "default_true")]
     pub show_command: bool,
+    /// Problem matchers that turn the task output into diagnostics: either names of built-in matchers
+    /// (`$rustc`, `$tsc`, `$eslint-stylish`, `$eslint-compact`, `$gcc`) or regex-based definitions.
+    /// Diagnostics from a matcher are cleared whenever the task is re-run.
+    #[serde(default)]
+    pub problem_matchers: Vec<ProblemMatcher>,
+    /// Labels of the tasks to run before this one. If any of them fails, this task is not run.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// How to run the tasks this one depends on:
+    /// * `parallel` — start all of them at once (default)
+    /// * `sequence` — run them one after another, in the order they are listed
+    #[serde(default)]
+    pub depends_order: DependsOrder,
+    /// Values to ask the user for before running the task, referenced in its fields as `${input:id}`.
+    #[serde(default)]
+    pub inputs: Vec<TaskInput>,
+}
+
+/// A value the user is asked for before a task runs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TaskInput {
+    /// The name to reference the input by, as in `${input:id}`.
+    pub id: String,
+    /// The prompt shown when asking for the value.
+    #[serde(default)]
+    pub description: String,
+    /// The value to suggest.
+    #[serde(default)]
+    pub default: Option<String>,
+    /// Values to pick from. When empty, any text can be entered.
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+/// How to run the tasks a task depends on.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DependsOrder {
+    /// Start all dependencies at once.
+    #[default]
+    Parallel,
+    /// Run dependencies one after another.
+    Sequence,
 }
 
 /// What to do with the terminal pane and tab, after the command was started.
@@ -115,6 +160,27 @@ impl TaskTemplates {
 }
 
 impl TaskTemplate {
+    /// Returns the inputs that the template references and the [`TaskContext`] has no values for.
+    /// The template can only be resolved into a runnable task once the user provides them.
+    pub fn unresolved_inputs(&self, cx: &TaskContext) -> Vec<&TaskInput> {
+        self.inputs
+            .iter()
+            .filter(|input| {
+                !cx.input_values.contains_key(&input.id) && self.references_input(&input.id)
+            })
+            .collect()
+    }
+
+    pub(crate) fn references_input(&self, id: &str) -> bool {
+        let reference = input_reference(id);
+        iter::once(&self.label)
+            .chain(iter::once(&self.command))
+            .chain(&self.args)
+            .chain(&self.cwd)
+            .chain(self.env.values())
+            .any(|field| field.contains(&reference))
+    }
+
     /// Replaces all `VariableName` task variables in the task template string fields.
     /// If any replacement fails or the new string substitutions still have [`ZED_VARIABLE_NAME_PREFIX`],
     /// `None` is returned.
@@ -122,7 +188,9 @@ impl TaskTemplate {
     /// Every [`ResolvedTask`] gets a [`TaskId`], based on the `id_base` (to avoid collision with various task sources),
     /// and hashes of its template and [`TaskContext`], see [`ResolvedTask`] fields' documentation for more details.
     pub fn resolve_task(&self, id_base: &str, cx: &TaskContext) -> Option<ResolvedTask> {
-        if self.label.trim().is_empty() || self.command.trim().is_empty() {
+        if self.label.trim().is_empty()
+            || (self.command.trim().is_empty() && self.depends_on.is_empty())
+        {
             return None;
         }
 
@@ -149,7 +217,10 @@ impl TaskTemplate {
                     &variable_names,
                     &mut substituted_variables,
                 )?;
-                Some(PathBuf::from(substitured_cwd))
+                Some(PathBuf::from(substitute_inputs(
+                    &substitured_cwd,
+                    &cx.input_values,
+                )))
             }
             None => None,
         }
@@ -189,6 +260,15 @@ impl TaskTemplate {
             &mut substituted_variables,
         )?;
 
+        // Inputs go last, so that the values the user entered are taken verbatim.
+        let human_readable_label = substitute_inputs(&human_readable_label, &cx.input_values);
+        let full_label = substitute_inputs(&full_label, &cx.input_values);
+        let command = substitute_inputs(&command, &cx.input_values);
+        let args_with_substitutions = args_with_substitutions
+            .iter()
+            .map(|arg| substitute_inputs(arg, &cx.input_values))
+            .collect::<Vec<_>>();
+
         let task_hash = to_hex_hash(self)
             .context("hashing task template")
             .log_err()?;
@@ -214,6 +294,9 @@ impl TaskTemplate {
 
             // Last step: set the task variables as environment variables too
             env.extend(task_variables.into_iter().map(|(k, v)| (k, v.to_owned())));
+            for value in env.values_mut() {
+                *value = substitute_inputs(value, &cx.input_values);
+            }
             env
         };
 
@@ -222,6 +305,7 @@ impl TaskTemplate {
             substituted_variables,
             original_task: self.clone(),
             resolved_label: full_label.clone(),
+            dependency_error: None,
             resolved: Some(SpawnInTerminal {
                 id,
                 cwd,
@@ -236,7 +320,11 @@ impl TaskTemplate {
                     },
                 ),
                 command,
-                args: self.args.clone(),
+                args: self
+                    .args
+                    .iter()
+                    .map(|arg| substitute_inputs(arg, &cx.input_values))
+                    .collect(),
                 env,
                 use_new_terminal: self.use_new_terminal,
                 allow_concurrent_runs: self.allow_concurrent_runs,
@@ -246,6 +334,9 @@ impl TaskTemplate {
                 shell: self.shell.clone(),
                 show_summary: self.show_summary,
                 show_command: self.show_command,
+                problem_matchers: self.problem_matchers.clone(),
+                dependencies: Vec::new(),
+                depends_order: self.depends_order,
             }),
         })
     }
@@ -309,6 +400,34 @@ fn substitute_all_template_variables_in_str<A: AsRef<str>>(
     Some(substituted_string.into_owned())
 }
 
+fn input_reference(id: &str) -> String {
+    format!("${{input:{id}}}")
+}
+
+/// Replaces `${input:id}` references with the values entered by the user, in a single pass:
+/// references inside the values are left as they are.
+/// Variable substitution leaves these references as they are, as `input` is not a known variable.
+fn substitute_inputs(template_str: &str, input_values: &HashMap<String, String>) -> String {
+    const INPUT_REFERENCE_PREFIX: &str = "${input:";
+    let mut substituted = String::with_capacity(template_str.len());
+    let mut rest = template_str;
+    while let Some(start) = rest.find(INPUT_REFERENCE_PREFIX) {
+        let id_start = start + INPUT_REFERENCE_PREFIX.len();
+        let Some(id_len) = rest[id_start..].find('}') else {
+            break;
+        };
+        let reference_end = id_start + id_len + 1;
+        substituted.push_str(&rest[..start]);
+        match input_values.get(&rest[id_start..id_start + id_len]) {
+            Some(value) => substituted.push_str(value),
+            None => substituted.push_str(&rest[start..reference_end]),
+        }
+        rest = &rest[reference_end..];
+    }
+    substituted.push_str(rest);
+    substituted
+}
+
 fn substitute_all_template_variables_in_vec(
     template_strs: &[String],
     task_variables: &HashMap<String, &str>,
@@ -396,6 +515,102 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_resolving_compound_templates() {
+        let compound_task = TaskTemplate {
+            label: "build and test".to_string(),
+            depends_on: vec!["build".to_string(), "test".to_string()],
+            depends_order: DependsOrder::Sequence,
+            ..TaskTemplate::default()
+        };
+        let resolved = compound_task
+            .resolve_task(TEST_ID_BASE, &TaskContext::default())
+            .expect("compound task should resolve without a command")
+            .resolved
+            .unwrap();
+        assert_eq!(resolved.command, "");
+        assert_eq!(resolved.depends_order, DependsOrder::Sequence);
+        assert!(
+            resolved.dependencies.is_empty(),
+            "dependencies are resolved by the inventory, not the template"
+        );
+    }
+
+    #[test]
+    fn test_resolving_templates_with_inputs() {
+        let task = TaskTemplate {
+            label: "deploy to ${input:target}".to_string(),
+            command: "deploy".to_string(),
+            args: vec!["--target".to_string(), "${input:target}".to_string()],
+            env: HashMap::from_iter([("DEPLOY_USER".to_string(), "${input:user}".to_string())]),
+            inputs: vec![
+                TaskInput {
+                    id: "target".to_string(),
+                    description: "Where to deploy".to_string(),
+                    default: None,
+                    options: vec!["staging".to_string(), "production".to_string()],
+                },
+                TaskInput {
+                    id: "user".to_string(),
+                    description: "Deploy as".to_string(),
+                    default: Some("$USER".to_string()),
+                    options: Vec::new(),
+                },
+                TaskInput {
+                    id: "unused".to_string(),
+                    description: "Not referenced anywhere".to_string(),
+                    default: None,
+                    options: Vec::new(),
+                },
+            ],
+            ..TaskTemplate::default()
+        };
+
+        let cx = TaskContext::default();
+        assert_eq!(
+            task.unresolved_inputs(&cx)
+                .into_iter()
+                .map(|input| input.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["target", "user"],
+        );
+        let unresolved = task.resolve_task(TEST_ID_BASE, &cx).unwrap();
+        assert_eq!(
+            unresolved.resolved.unwrap().args,
+            vec!["--target", "${input:target}"],
+            "references without values should be left as they are"
+        );
+
+        let cx = TaskContext {
+            input_values: HashMap::from_iter([
+                ("target".to_string(), "staging".to_string()),
+                ("user".to_string(), "$ZED_FILE".to_string()),
+            ]),
+            ..TaskContext::default()
+        };
+        assert!(task.unresolved_inputs(&cx).is_empty());
+        let resolved_task = task.resolve_task(TEST_ID_BASE, &cx).unwrap();
+        assert_eq!(resolved_task.resolved_label, "deploy to staging");
+        let spawn_in_terminal = resolved_task.resolved.unwrap();
+        assert_eq!(spawn_in_terminal.args, vec!["--target", "staging"]);
+        assert_eq!(spawn_in_terminal.command_label, "deploy --target staging");
+        assert_eq!(
+            spawn_in_terminal.env.get("DEPLOY_USER").map(String::as_str),
+            Some("$ZED_FILE"),
+            "input values should be taken verbatim"
+        );
+
+        let input_values = HashMap::from_iter([
+            ("a".to_string(), "${input:b}".to_string()),
+            ("b".to_string(), "${input:a}".to_string()),
+        ]);
+        assert_eq!(
+            substitute_inputs("${input:a} ${input:b} ${input:c} ${input:", &input_values),
+            "${input:b} ${input:a} ${input:c} ${input:",
+            "references in values and to unknown inputs should be left as they are"
+        );
+    }
+
     #[test]
     fn test_template_cwd_resolution() {
         let task_without_cwd = TaskTemplate {
@@ -422,6 +637,7 @@ mod tests {
             cwd: None,
             task_variables: TaskVariables::default(),
             project_env: HashMap::default(),
+            input_values: HashMap::default(),
         };
         assert_eq!(
             resolved_task(&task_without_cwd, &cx).cwd,
@@ -434,6 +650,7 @@ mod tests {
             cwd: Some(context_cwd.clone()),
             task_variables: TaskVariables::default(),
             project_env: HashMap::default(),
+            input_values: HashMap::default(),
         };
         assert_eq!(
             resolved_task(&task_without_cwd, &cx).cwd,
@@ -450,6 +667,7 @@ mod tests {
             cwd: None,
             task_variables: TaskVariables::default(),
             project_env: HashMap::default(),
+            input_values: HashMap::default(),
         };
         assert_eq!(
             resolved_task(&task_with_cwd, &cx).cwd,
@@ -461,6 +679,7 @@ mod tests {
             cwd: Some(context_cwd.clone()),
             task_variables: TaskVariables::default(),
             project_env: HashMap::default(),
+            input_values: HashMap::default(),
         };
         assert_eq!(
             resolved_task(&task_with_cwd, &cx).cwd,
@@ -537,6 +756,7 @@ mod tests {
                     cwd: None,
                     task_variables: TaskVariables::from_iter(all_variables.clone()),
                     project_env: HashMap::default(),
+                    input_values: HashMap::default(),
                 },
             ).unwrap_or_else(|| panic!("Should successfully resolve task {task_with_all_variables:?} with variables {all_variables:?}"));
 
@@ -625,6 +845,7 @@ mod tests {
                     cwd: None,
                     task_variables: TaskVariables::from_iter(not_all_variables),
                     project_env: HashMap::default(),
+                    input_values: HashMap::default(),
                 },
             );
             assert_eq!(resolved_task_attempt, None, "If any of the Zed task variables is not substituted, the task should not be resolved, but got some resolution without the variable {removed_variable:?} (index {i})");
@@ -678,6 +899,7 @@ mod tests {
                 "test_symbol".to_string(),
             ))),
             project_env: HashMap::default(),
+            input_values: HashMap::default(),
         };
 
         for (i, symbol_dependent_task) in [
@@ -806,6 +1028,7 @@ mod tests {
             cwd: None,
             task_variables: TaskVariables::from_iter(all_variables.clone()),
             project_env,
+            input_values: HashMap::default(),
         };
 
         let resolved = template
@@ -1,9 +1,12 @@
-use anyhow::bail;
+use anyhow::{bail, Context as _};
 use collections::HashMap;
 use serde::Deserialize;
 use util::ResultExt;
 
-use crate::{TaskTemplate, TaskTemplates, VariableName};
+use crate::{
+    BackgroundPatterns, DependsOrder, ProblemMatcher, ProblemMatcherDefinition, ProblemPattern,
+    ProblemSeverity, TaskInput, TaskTemplate, TaskTemplates, VariableName,
+};
 
 #[derive(Clone, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -41,6 +44,128 @@ enum Command {
     },
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            Self::One(item) => vec![item],
+            Self::Many(items) => items,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum VsCodeProblemMatcher {
+    Name(String),
+    Definition(VsCodeProblemMatcherDefinition),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct VsCodeProblemMatcherDefinition {
+    base: Option<String>,
+    owner: Option<String>,
+    source: Option<String>,
+    severity: Option<ProblemSeverity>,
+    pattern: Option<VsCodeProblemPatterns>,
+    background: Option<VsCodeBackgroundPatterns>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct VsCodeBackgroundPatterns {
+    begins_pattern: VsCodeBackgroundPattern,
+    ends_pattern: VsCodeBackgroundPattern,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum VsCodeBackgroundPattern {
+    Regexp(String),
+    Pattern { regexp: String },
+}
+
+impl VsCodeBackgroundPattern {
+    fn into_regexp(self) -> String {
+        match self {
+            Self::Regexp(regexp) | Self::Pattern { regexp } => regexp,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum VsCodeProblemPatterns {
+    Name(String),
+    Patterns(OneOrMany<VsCodeProblemPattern>),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct VsCodeProblemPattern {
+    regexp: String,
+    file: Option<usize>,
+    line: Option<usize>,
+    column: Option<usize>,
+    end_line: Option<usize>,
+    end_column: Option<usize>,
+    severity: Option<usize>,
+    code: Option<usize>,
+    message: Option<usize>,
+    #[serde(default, rename = "loop")]
+    repeat: bool,
+}
+
+impl VsCodeProblemMatcher {
+    fn into_zed_format(self) -> Option<ProblemMatcher> {
+        let definition = match self {
+            Self::Name(name) => return Some(ProblemMatcher::Builtin(name)),
+            Self::Definition(definition) => definition,
+        };
+        // Only the patterns are taken from the definition: `fileLocation` and the like are not supported.
+        let patterns = match definition.pattern {
+            Some(VsCodeProblemPatterns::Name(name)) => {
+                return Some(ProblemMatcher::Builtin(name));
+            }
+            Some(VsCodeProblemPatterns::Patterns(patterns)) => patterns.into_vec(),
+            None => return definition.base.map(ProblemMatcher::Builtin),
+        };
+        Some(ProblemMatcher::Custom(ProblemMatcherDefinition {
+            source: definition
+                .source
+                .or(definition.owner)
+                .unwrap_or_else(|| "task".to_string()),
+            severity: definition.severity.unwrap_or_default(),
+            pattern: patterns
+                .into_iter()
+                .map(|pattern| ProblemPattern {
+                    regexp: pattern.regexp,
+                    file: pattern.file,
+                    line: pattern.line,
+                    column: pattern.column,
+                    end_line: pattern.end_line,
+                    end_column: pattern.end_column,
+                    severity: pattern.severity,
+                    code: pattern.code,
+                    message: pattern.message,
+                    repeat: pattern.repeat,
+                })
+                .collect(),
+            background: definition.background.map(|background| BackgroundPatterns {
+                begins_pattern: background.begins_pattern.into_regexp(),
+                ends_pattern: background.ends_pattern.into_regexp(),
+            }),
+        }))
+    }
+}
+
 type VsCodeEnvVariable = String;
 type ZedEnvVariable = String;
 
@@ -85,21 +210,26 @@ impl EnvVariableReplacer {
 
 impl VsCodeTaskDefinition {
     fn into_zed_format(self, replacer: &EnvVariableReplacer) -> anyhow::Result<TaskTemplate> {
-        if self.other_attributes.contains_key("dependsOn") {
-            bail!("Encountered unsupported `dependsOn` key during deserialization");
-        }
-        // `type` might not be set in e.g. tasks that use `dependsOn`; we still want to deserialize the whole object though (hence command is an Option),
-        // as that way we can provide more specific description of why deserialization failed.
-        // E.g. if the command is missing due to `dependsOn` presence, we can check other_attributes first before doing this (and provide nice error message)
-        // catch-all if on value.command presence.
-        let Some(command) = self.command else {
-            bail!("Missing `type` field in task");
+        let depends_on = match self.other_attributes.get("dependsOn") {
+            Some(depends_on) => {
+                serde_json_lenient::from_value::<OneOrMany<String>>(depends_on.clone())
+                    .context("parsing `dependsOn`")?
+                    .into_vec()
+            }
+            None => Vec::new(),
         };
-
-        let (command, args) = match command {
-            Command::Npm { script } => ("npm".to_owned(), vec!["run".to_string(), script]),
-            Command::Shell { command, args } => (command, args),
-            Command::Gulp { task } => ("gulp".to_owned(), vec![task]),
+        let depends_order = match self.other_attributes.get("dependsOrder") {
+            Some(depends_order) => serde_json_lenient::from_value(depends_order.clone())
+                .context("parsing `dependsOrder`")?,
+            None => DependsOrder::default(),
+        };
+        // `type` might not be set in compound tasks, that only have `dependsOn`.
+        let (command, args) = match self.command {
+            Some(Command::Npm { script }) => ("npm".to_owned(), vec!["run".to_string(), script]),
+            Some(Command::Shell { command, args }) => (command, args),
+            Some(Command::Gulp { task }) => ("gulp".to_owned(), vec![task]),
+            None if !depends_on.is_empty() => (String::new(), Vec::new()),
+            None => bail!("Missing `type` field in task"),
         };
         // Per VSC docs, only `command`, `args` and `options` support variable substitution.
         let command = replacer.replace(&command);
@@ -108,12 +238,30 @@ impl VsCodeTaskDefinition {
             label: self.label,
             command,
             args,
+            depends_on,
+            depends_order,
             ..Default::default()
         };
         if let Some(options) = self.options {
             ret.cwd = options.cwd.map(|cwd| replacer.replace(&cwd));
             ret.env = options.env;
         }
+        if let Some(problem_matchers) = self.other_attributes.get("problemMatcher") {
+            ret.problem_matchers =
+                serde_json_lenient::from_value::<OneOrMany<VsCodeProblemMatcher>>(
+                    problem_matchers.clone(),
+                )
+                .context("parsing `problemMatcher`")
+                .log_err()
+                .map(|problem_matchers| {
+                    problem_matchers
+                        .into_vec()
+                        .into_iter()
+                        .filter_map(VsCodeProblemMatcher::into_zed_format)
+                        .collect()
+                })
+                .unwrap_or_default();
+        }
         Ok(ret)
     }
 }
@@ -122,6 +270,49 @@ impl VsCodeTaskDefinition {
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct VsCodeTaskFile {
     tasks: Vec<VsCodeTaskDefinition>,
+    #[serde(default)]
+    inputs: Vec<VsCodeInput>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct VsCodeInput {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    description: Option<String>,
+    default: Option<String>,
+    #[serde(default)]
+    options: Vec<VsCodePickOption>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum VsCodePickOption {
+    Value(String),
+    Labeled { value: String },
+}
+
+impl VsCodeInput {
+    fn into_zed_format(self) -> anyhow::Result<TaskInput> {
+        let options = match self.kind.as_str() {
+            "promptString" => Vec::new(),
+            "pickString" => self
+                .options
+                .into_iter()
+                .map(|option| match option {
+                    VsCodePickOption::Value(value) | VsCodePickOption::Labeled { value } => value,
+                })
+                .collect(),
+            kind => bail!("Unsupported input type `{kind}` of input `{}`", self.id),
+        };
+        Ok(TaskInput {
+            id: self.id,
+            description: self.description.unwrap_or_default(),
+            default: self.default,
+            options,
+        })
+    }
 }
 
 impl TryFrom<VsCodeTaskFile> for TaskTemplates {
@@ -140,10 +331,24 @@ impl TryFrom<VsCodeTaskFile> for TaskTemplates {
                 VariableName::SelectedText.to_string(),
             ),
         ]));
+        let inputs = value
+            .inputs
+            .into_iter()
+            .filter_map(|input| input.into_zed_format().log_err())
+            .collect::<Vec<_>>();
         let templates = value
             .tasks
             .into_iter()
             .filter_map(|vscode_definition| vscode_definition.into_zed_format(&replacer).log_err())
+            .map(|mut template| {
+                // Inputs are declared for the whole file in VS Code, and per task in Zed.
+                template.inputs = inputs
+                    .iter()
+                    .filter(|input| template.references_input(&input.id))
+                    .cloned()
+                    .collect();
+                template
+            })
             .collect();
         Ok(Self(templates))
     }
@@ -155,7 +360,7 @@ mod tests {
 
     use crate::{
         vscode_format::{Command, VsCodeTaskDefinition},
-        TaskTemplate, TaskTemplates, VsCodeTaskFile,
+        ProblemMatcher, TaskInput, TaskTemplate, TaskTemplates, VsCodeTaskFile,
     };
 
     use super::EnvVariableReplacer;
@@ -257,6 +462,7 @@ mod tests {
                 label: "gulp: tests".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build:tests:notypecheck".to_string()],
+                problem_matchers: vec![ProblemMatcher::Builtin("$tsc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
@@ -268,18 +474,21 @@ mod tests {
                     "${ZED_WORKTREE_ROOT}/src".to_string(),
                     "--watch".to_string(),
                 ],
+                problem_matchers: vec![ProblemMatcher::Builtin("$tsc-watch".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "npm: build:compiler".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build:compiler".to_string()],
+                problem_matchers: vec![ProblemMatcher::Builtin("$tsc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "npm: build:tests".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build:tests:notypecheck".to_string()],
+                problem_matchers: vec![ProblemMatcher::Builtin("$tsc".to_string())],
                 ..Default::default()
             },
         ];
@@ -288,6 +497,58 @@ mod tests {
         assert_eq!(tasks.0, expected);
     }
 
+    #[test]
+    fn can_deserialize_inputs() {
+        let vscode_definitions: VsCodeTaskFile = serde_json_lenient::from_str(
+            r#"{
+                "version": "2.0.0",
+                "tasks": [
+                    {
+                        "label": "deploy",
+                        "type": "shell",
+                        "command": "./deploy.sh ${input:environment} ${input:tag}"
+                    }
+                ],
+                "inputs": [
+                    {
+                        "id": "environment",
+                        "type": "pickString",
+                        "description": "Where to deploy",
+                        "options": ["staging", { "label": "Production", "value": "production" }],
+                        "default": "staging"
+                    },
+                    { "id": "tag", "type": "promptString", "description": "Image tag" },
+                    { "id": "unused", "type": "promptString" },
+                    { "id": "token", "type": "command", "command": "extension.getToken" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let tasks: TaskTemplates = vscode_definitions.try_into().unwrap();
+        assert_eq!(
+            tasks.0,
+            vec![TaskTemplate {
+                label: "deploy".to_string(),
+                command: "./deploy.sh ${input:environment} ${input:tag}".to_string(),
+                inputs: vec![
+                    TaskInput {
+                        id: "environment".to_string(),
+                        description: "Where to deploy".to_string(),
+                        default: Some("staging".to_string()),
+                        options: vec!["staging".to_string(), "production".to_string()],
+                    },
+                    TaskInput {
+                        id: "tag".to_string(),
+                        description: "Image tag".to_string(),
+                        default: None,
+                        options: Vec::new(),
+                    },
+                ],
+                ..Default::default()
+            }]
+        );
+    }
+
     #[test]
     fn can_deserialize_rust_analyzer_tasks() {
         const RUST_ANALYZER_TASKS: &str = include_str!("../test_data/rust-analyzer.json");
@@ -360,28 +621,48 @@ mod tests {
                 label: "Build Extension in Background".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "watch".to_string()],
+                problem_matchers: vec![ProblemMatcher::Builtin("$tsc-watch".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Build Extension".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build".to_string()],
+                problem_matchers: vec![ProblemMatcher::Builtin("$tsc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Build Server".to_string(),
                 command: "cargo build --package rust-analyzer".to_string(),
+                problem_matchers: vec![ProblemMatcher::Builtin("$rustc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Build Server (Release)".to_string(),
                 command: "cargo build --release --package rust-analyzer".to_string(),
+                problem_matchers: vec![ProblemMatcher::Builtin("$rustc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Pretest".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "pretest".to_string()],
+                problem_matchers: vec![ProblemMatcher::Builtin("$tsc".to_string())],
+                ..Default::default()
+            },
+            TaskTemplate {
+                label: "Build Server and Extension".to_string(),
+                depends_on: vec!["Build Server".to_string(), "Build Extension".to_string()],
+                problem_matchers: vec![ProblemMatcher::Builtin("$rustc".to_string())],
+                ..Default::default()
+            },
+            TaskTemplate {
+                label: "Build Server (Release) and Extension".to_string(),
+                depends_on: vec![
+                    "Build Server (Release)".to_string(),
+                    "Build Extension".to_string(),
+                ],
+                problem_matchers: vec![ProblemMatcher::Builtin("$rustc".to_string())],
                 ..Default::default()
             },
         ];
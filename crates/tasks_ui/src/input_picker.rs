@@ -0,0 +1,488 @@
+use std::sync::Arc;
+
+use futures::channel::oneshot;
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{
+    rems, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Render, Subscription,
+    Task, View, ViewContext, VisualContext,
+};
+use picker::{Picker, PickerDelegate};
+use task::TaskInput;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+/// Asks the user for the value of a task input.
+/// Resolves to `None` if the picker is dismissed without choosing a value.
+pub(crate) fn prompt_for_input(
+    workspace: &mut Workspace,
+    input: TaskInput,
+    cx: &mut ViewContext<Workspace>,
+) -> Task<Option<String>> {
+    let (value_tx, value_rx) = oneshot::channel();
+    workspace.toggle_modal(cx, |cx| TaskInputPicker::new(input, value_tx, cx));
+    cx.background_executor()
+        .spawn(async move { value_rx.await.ok() })
+}
+
+/// A modal that asks for the value of a task's `${input:id}` variable, either as free text or
+/// as one of the input's options.
+pub(crate) struct TaskInputPicker {
+    picker: View<Picker<TaskInputPickerDelegate>>,
+    _subscription: Subscription,
+}
+
+impl TaskInputPicker {
+    fn new(
+        input: TaskInput,
+        value_tx: oneshot::Sender<String>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let default = input.default.clone();
+        let free_text = input.options.is_empty();
+        let delegate = TaskInputPickerDelegate {
+            input,
+            matches: Vec::new(),
+            selected_index: 0,
+            query: String::new(),
+            value_tx: Some(value_tx),
+        };
+        let picker = cx.new_view(|cx| {
+            let picker = Picker::uniform_list(delegate, cx);
+            if free_text {
+                if let Some(default) = default {
+                    picker.set_query(default, cx);
+                }
+            }
+            picker
+        });
+        let _subscription = cx.subscribe(&picker, |_, _, _, cx| cx.emit(DismissEvent));
+        Self {
+            picker,
+            _subscription,
+        }
+    }
+}
+
+impl ModalView for TaskInputPicker {}
+impl EventEmitter<DismissEvent> for TaskInputPicker {}
+
+impl FocusableView for TaskInputPicker {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for TaskInputPicker {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("TaskInputPicker")
+            .w(rems(34.))
+            .child(self.picker.clone())
+    }
+}
+
+pub(crate) struct TaskInputPickerDelegate {
+    input: TaskInput,
+    /// The options matching the query; unused when the input takes free text.
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+    query: String,
+    value_tx: Option<oneshot::Sender<String>>,
+}
+
+impl TaskInputPickerDelegate {
+    fn free_text(&self) -> bool {
+        self.input.options.is_empty()
+    }
+
+    fn free_text_value(&self) -> String {
+        if self.query.is_empty() {
+            self.input.default.clone().unwrap_or_default()
+        } else {
+            self.query.clone()
+        }
+    }
+}
+
+impl PickerDelegate for TaskInputPickerDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        if self.input.description.is_empty() {
+            format!("Enter a value for {}", self.input.id).into()
+        } else {
+            self.input.description.clone().into()
+        }
+    }
+
+    fn match_count(&self) -> usize {
+        if self.free_text() {
+            1
+        } else {
+            self.matches.len()
+        }
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        if self.free_text() {
+            self.query = query;
+            return Task::ready(());
+        }
+
+        let candidates = self
+            .input
+            .options
+            .iter()
+            .enumerate()
+            .map(|(ix, option)| StringMatchCandidate::new(ix, option))
+            .collect::<Vec<_>>();
+        let default = self.input.default.clone();
+        cx.spawn(move |picker, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                fuzzy::match_strings(
+                    &candidates,
+                    &query,
+                    true,
+                    candidates.len(),
+                    &Default::default(),
+                    cx.background_executor().clone(),
+                )
+                .await
+            };
+            picker
+                .update(&mut cx, |picker, _| {
+                    let delegate = &mut picker.delegate;
+                    delegate.selected_index = if query.is_empty() {
+                        matches
+                            .iter()
+                            .position(|option| Some(&option.string) == default.as_ref())
+                            .unwrap_or(0)
+                    } else {
+                        0
+                    };
+                    delegate.matches = matches;
+                    delegate.query = query;
+                })
+                .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _secondary: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let value = if self.free_text() {
+            self.free_text_value()
+        } else {
+            let Some(option) = self.matches.get(self.selected_index) else {
+                return;
+            };
+            option.string.clone()
+        };
+        if let Some(value_tx) = self.value_tx.take() {
+            value_tx.send(value).ok();
+        }
+        cx.emit(DismissEvent);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let label = if self.free_text() {
+            let value = self.free_text_value();
+            if value.is_empty() {
+                Label::new("Run with an empty value")
+                    .color(Color::Muted)
+                    .into_any_element()
+            } else {
+                Label::new(value).into_any_element()
+            }
+        } else {
+            let option = self.matches.get(ix)?;
+            HighlightedLabel::new(option.string.clone(), option.positions.clone())
+                .into_any_element()
+        };
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .toggle_state(selected)
+                .child(label),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::{FakeFs, Project, TaskSourceKind};
+    use serde_json::json;
+    use task::{TaskContext, TaskTemplate};
+
+    use crate::{modal::Spawn, tests::init_test};
+
+    use super::*;
+
+    #[gpui::test]
+    async fn test_input_with_options(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/dir", json!({})).await;
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+        let value = workspace.update(cx, |workspace, cx| {
+            prompt_for_input(
+                workspace,
+                TaskInput {
+                    id: "target".to_string(),
+                    description: "Where to deploy".to_string(),
+                    default: Some("production".to_string()),
+                    options: vec!["staging".to_string(), "production".to_string()],
+                },
+                cx,
+            )
+        });
+        cx.run_until_parked();
+        let picker = input_picker(&workspace, cx);
+        assert_eq!(
+            options(&picker, cx),
+            (vec!["staging".to_string(), "production".to_string()], 1),
+            "All options should be listed, with the default one selected"
+        );
+
+        cx.simulate_input("stag");
+        assert_eq!(options(&picker, cx), (vec!["staging".to_string()], 0));
+        cx.dispatch_action(menu::Confirm);
+        assert_eq!(value.await, Some("staging".to_string()));
+        workspace.update(cx, |workspace, cx| {
+            assert!(workspace.active_modal::<TaskInputPicker>(cx).is_none());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_free_text_input(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/dir", json!({})).await;
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+        let input = TaskInput {
+            id: "jobs".to_string(),
+            description: String::new(),
+            default: Some("4".to_string()),
+            options: Vec::new(),
+        };
+
+        let value = workspace.update(cx, |workspace, cx| {
+            prompt_for_input(workspace, input.clone(), cx)
+        });
+        cx.run_until_parked();
+        let picker = input_picker(&workspace, cx);
+        picker.update(cx, |picker, cx| {
+            assert_eq!(picker.query(cx), "4", "The default should be suggested");
+            assert_eq!(picker.delegate.match_count(), 1);
+        });
+        cx.dispatch_action(menu::Confirm);
+        assert_eq!(value.await, Some("4".to_string()));
+
+        let value = workspace.update(cx, |workspace, cx| {
+            prompt_for_input(workspace, input.clone(), cx)
+        });
+        cx.run_until_parked();
+        let picker = input_picker(&workspace, cx);
+        picker.update(cx, |picker, cx| picker.set_query("", cx));
+        cx.simulate_input("8 --verbose");
+        cx.dispatch_action(menu::Confirm);
+        assert_eq!(value.await, Some("8 --verbose".to_string()));
+
+        let value = workspace.update(cx, |workspace, cx| prompt_for_input(workspace, input, cx));
+        cx.run_until_parked();
+        cx.dispatch_action(menu::Cancel);
+        assert_eq!(value.await, None, "Dismissing the picker should cancel");
+    }
+
+    #[gpui::test]
+    async fn test_task_and_dependency_inputs(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                ".zed": {
+                    "tasks.json": r#"[
+                        {
+                            "label": "build",
+                            "command": "make",
+                            "args": ["${input:target}", "-j${input:jobs}"],
+                            "inputs": [
+                                { "id": "target", "options": ["debug", "release"] },
+                                { "id": "jobs", "default": "4" }
+                            ]
+                        },
+                        {
+                            "label": "deploy",
+                            "command": "deploy",
+                            "args": ["${input:host}", "${input:target}"],
+                            "depends_on": ["build"],
+                            "inputs": [
+                                { "id": "host" },
+                                { "id": "target", "options": ["debug", "release"], "default": "release" }
+                            ]
+                        }
+                    ]"#,
+                },
+                "a.ts": "a"
+            }),
+        )
+        .await;
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_abs_path(PathBuf::from("/dir/a.ts"), true, cx)
+            })
+            .await
+            .unwrap();
+
+        cx.dispatch_action(Spawn::modal());
+        cx.simulate_input("deploy");
+        cx.dispatch_action(menu::Confirm);
+        cx.run_until_parked();
+
+        let mut prompts = Vec::new();
+        for value in ["example.com", "", ""] {
+            let picker = input_picker(&workspace, cx);
+            prompts.push(picker.update(cx, |picker, _| picker.delegate.input.id.clone()));
+            if !value.is_empty() {
+                cx.simulate_input(value);
+            }
+            cx.dispatch_action(menu::Confirm);
+            cx.run_until_parked();
+        }
+        assert_eq!(
+            prompts,
+            vec!["host", "target", "jobs"],
+            "The inputs of the task should be asked for, then those of its dependencies"
+        );
+
+        let (_, scheduled_task) = project
+            .update(cx, |project, cx| {
+                let inventory = project.task_store().read(cx).task_inventory().cloned()?;
+                inventory.read(cx).last_scheduled_task(None)
+            })
+            .expect("the task should be scheduled once all inputs are entered");
+        let spawn_in_terminal = scheduled_task.resolved.unwrap();
+        assert_eq!(spawn_in_terminal.args, vec!["example.com", "release"]);
+        assert_eq!(spawn_in_terminal.dependencies.len(), 1);
+        assert_eq!(
+            spawn_in_terminal.dependencies[0].args,
+            vec!["release", "-j4"]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_inputs_of_tasks_scheduled_outside_the_modal(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/dir", json!({})).await;
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let template = TaskTemplate {
+            label: "test".to_string(),
+            command: "cargo".to_string(),
+            args: vec!["test".to_string(), "${input:filter}".to_string()],
+            inputs: vec![TaskInput {
+                id: "filter".to_string(),
+                description: String::new(),
+                default: None,
+                options: Vec::new(),
+            }],
+            ..TaskTemplate::default()
+        };
+
+        // Editor runnables are scheduled through the workspace, which can't show the picker itself.
+        workspace.update(cx, |workspace, cx| {
+            workspace::tasks::schedule_task_with_inputs(
+                workspace,
+                TaskSourceKind::UserInput,
+                template,
+                TaskContext::default(),
+                false,
+                cx,
+            )
+        });
+        cx.run_until_parked();
+        input_picker(&workspace, cx);
+        cx.simulate_input("parser");
+        cx.dispatch_action(menu::Confirm);
+        cx.run_until_parked();
+
+        let (_, scheduled_task) = project
+            .update(cx, |project, cx| {
+                let inventory = project.task_store().read(cx).task_inventory().cloned()?;
+                inventory.read(cx).last_scheduled_task(None)
+            })
+            .expect("the task should be scheduled once its input is entered");
+        assert_eq!(
+            scheduled_task.resolved.unwrap().args,
+            vec!["test", "parser"]
+        );
+    }
+
+    fn input_picker(
+        workspace: &View<Workspace>,
+        cx: &mut VisualTestContext,
+    ) -> View<Picker<TaskInputPickerDelegate>> {
+        workspace.update(cx, |workspace, cx| {
+            workspace
+                .active_modal::<TaskInputPicker>(cx)
+                .expect("no input picker is shown")
+                .read(cx)
+                .picker
+                .clone()
+        })
+    }
+
+    fn options(
+        picker: &View<Picker<TaskInputPickerDelegate>>,
+        cx: &mut VisualTestContext,
+    ) -> (Vec<String>, usize) {
+        picker.update(cx, |picker, _| {
+            let delegate = &picker.delegate;
+            (
+                delegate
+                    .matches
+                    .iter()
+                    .map(|option| option.string.clone())
+                    .collect(),
+                delegate.selected_index,
+            )
+        })
+    }
+}
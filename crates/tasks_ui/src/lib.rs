@@ -2,11 +2,12 @@ use ::settings::Settings;
 use editor::{tasks::task_context, Editor};
 use gpui::{AppContext, Task as AsyncTask, ViewContext, WindowContext};
 use modal::{TaskOverrides, TasksModal};
-use project::{Location, WorktreeId};
-use task::{RevealTarget, TaskId};
+use project::{Location, TaskSourceKind, WorktreeId};
+use task::{RevealTarget, TaskContext, TaskId, TaskInput, TaskTemplate};
 use workspace::tasks::schedule_task;
 use workspace::{tasks::schedule_resolved_task, Workspace};
 
+mod input_picker;
 mod modal;
 mod settings;
 
@@ -14,6 +15,7 @@ pub use modal::{Rerun, Spawn};
 
 pub fn init(cx: &mut AppContext) {
     settings::TaskSettings::register(cx);
+    workspace::tasks::set_schedule_task_with_inputs(schedule_task_with_inputs, cx);
     cx.observe_new_views(
         |workspace: &mut Workspace, _: &mut ViewContext<Workspace>| {
             workspace
@@ -48,11 +50,11 @@ pub fn init(cx: &mut AppContext) {
                                 let task_context = context_task.await;
                                 workspace
                                     .update(&mut cx, |workspace, cx| {
-                                        schedule_task(
+                                        schedule_task_with_inputs(
                                             workspace,
                                             task_source_kind,
-                                            &original_task,
-                                            &task_context,
+                                            original_task,
+                                            task_context,
                                             false,
                                             cx,
                                         )
@@ -87,6 +89,85 @@ pub fn init(cx: &mut AppContext) {
     .detach();
 }
 
+/// Schedules the task once the user has entered values for the `${input:id}` variables it references.
+/// Nothing is scheduled if any of the input pickers is dismissed.
+pub(crate) fn schedule_task_with_inputs(
+    workspace: &mut Workspace,
+    task_source_kind: TaskSourceKind,
+    task: TaskTemplate,
+    mut task_context: TaskContext,
+    omit_history: bool,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let inputs = unresolved_inputs(workspace, &task_source_kind, &task, &task_context, cx);
+    if inputs.is_empty() {
+        schedule_task(
+            workspace,
+            task_source_kind,
+            &task,
+            &task_context,
+            omit_history,
+            cx,
+        );
+        return;
+    }
+    cx.spawn(|workspace, mut cx| async move {
+        for input in inputs {
+            let value = workspace
+                .update(&mut cx, |workspace, cx| {
+                    input_picker::prompt_for_input(workspace, input.clone(), cx)
+                })?
+                .await;
+            let Some(value) = value else {
+                return Ok(());
+            };
+            task_context.input_values.insert(input.id, value);
+        }
+        workspace.update(&mut cx, |workspace, cx| {
+            schedule_task(
+                workspace,
+                task_source_kind,
+                &task,
+                &task_context,
+                omit_history,
+                cx,
+            )
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Returns the inputs to ask the user for before running the task: its own and those of the tasks
+/// it depends on.
+pub(crate) fn unresolved_inputs(
+    workspace: &Workspace,
+    task_source_kind: &TaskSourceKind,
+    task: &TaskTemplate,
+    task_context: &TaskContext,
+    cx: &AppContext,
+) -> Vec<TaskInput> {
+    let worktree = match task_source_kind {
+        TaskSourceKind::Worktree { id, .. } => Some(*id),
+        _ => None,
+    };
+    match workspace
+        .project()
+        .read(cx)
+        .task_store()
+        .read(cx)
+        .task_inventory()
+    {
+        Some(inventory) => inventory
+            .read(cx)
+            .unresolved_inputs(worktree, task, task_context),
+        None => task
+            .unresolved_inputs(task_context)
+            .into_iter()
+            .cloned()
+            .collect(),
+    }
+}
+
 fn spawn_task_or_modal(workspace: &mut Workspace, action: &Spawn, cx: &mut ViewContext<Workspace>) {
     match action {
         Spawn::ByName {
@@ -181,11 +262,11 @@ fn spawn_task_with_name(
                         target_task.reveal_target = target_override;
                     }
                 }
-                schedule_task(
+                schedule_task_with_inputs(
                     workspace,
                     task_source_kind,
-                    &target_task,
-                    &task_context,
+                    target_task,
+                    task_context,
                     false,
                     cx,
                 );
@@ -374,6 +455,7 @@ mod tests {
                     (VariableName::Column, "1".into()),
                 ]),
                 project_env: HashMap::default(),
+                input_values: HashMap::default(),
             }
         );
 
@@ -401,6 +483,7 @@ mod tests {
                     (VariableName::Symbol, "this_is_a_rust_file".into()),
                 ]),
                 project_env: HashMap::default(),
+                input_values: HashMap::default(),
             }
         );
 
@@ -426,6 +509,7 @@ mod tests {
                     (VariableName::Symbol, "this_is_a_test".into()),
                 ]),
                 project_env: HashMap::default(),
+                input_values: HashMap::default(),
             }
         );
     }
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::active_item_selection_properties;
+use crate::{active_item_selection_properties, schedule_task_with_inputs, unresolved_inputs};
 use fuzzy::{StringMatch, StringMatchCandidate};
 use gpui::{
     rems, Action, AnyElement, AppContext, DismissEvent, EventEmitter, FocusableView,
@@ -285,6 +285,47 @@ impl PickerDelegate for TasksModalDelegate {
         let Some((task_source_kind, mut task)) = task else {
             return;
         };
+        // Tasks that need input, themselves or through their dependencies, are resolved anew
+        // once the user enters it. Those that cannot run anyway are scheduled right away,
+        // to report why.
+        let needs_inputs = task.dependency_error.is_none()
+            && self
+                .workspace
+                .update(cx, |workspace, cx| {
+                    !unresolved_inputs(
+                        workspace,
+                        &task_source_kind,
+                        task.original_task(),
+                        &self.task_context,
+                        cx,
+                    )
+                    .is_empty()
+                })
+                .unwrap_or(false);
+        if needs_inputs {
+            let mut template = task.original_task().clone();
+            if let Some(TaskOverrides {
+                reveal_target: Some(reveal_target),
+            }) = &self.task_overrides
+            {
+                template.reveal_target = *reveal_target;
+            }
+            let task_context = self.task_context.clone();
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    schedule_task_with_inputs(
+                        workspace,
+                        task_source_kind,
+                        template,
+                        task_context,
+                        omit_history_entry,
+                        cx,
+                    );
+                })
+                .ok();
+            cx.emit(DismissEvent);
+            return;
+        }
         if let Some(TaskOverrides {
             reveal_target: Some(reveal_target),
         }) = &self.task_overrides
@@ -333,6 +374,12 @@ impl PickerDelegate for TasksModalDelegate {
                 tooltip_label_text.push_str(&resolved.command_label);
             }
         }
+        if let Some(error) = &resolved_task.dependency_error {
+            if !tooltip_label_text.trim().is_empty() {
+                tooltip_label_text.push('\n');
+            }
+            tooltip_label_text.push_str(error);
+        }
         let tooltip_label = if tooltip_label_text.trim().is_empty() {
             None
         } else {
@@ -345,15 +392,20 @@ impl PickerDelegate for TasksModalDelegate {
             char_count: hit.string.chars().count(),
             color: Color::Default,
         };
-        let icon = match source_kind {
-            TaskSourceKind::UserInput => Some(Icon::new(IconName::Terminal)),
-            TaskSourceKind::AbsPath { .. } => Some(Icon::new(IconName::Settings)),
-            TaskSourceKind::Worktree { .. } => Some(Icon::new(IconName::FileTree)),
-            TaskSourceKind::Language { name } => file_icons::FileIcons::get(cx)
-                .get_type_icon(&name.to_lowercase())
-                .map(Icon::from_path),
+        let icon = if resolved_task.dependency_error.is_some() {
+            Some(Icon::new(IconName::Warning).color(Color::Warning))
+        } else {
+            match source_kind {
+                TaskSourceKind::UserInput => Some(Icon::new(IconName::Terminal)),
+                TaskSourceKind::AbsPath { .. } => Some(Icon::new(IconName::Settings)),
+                TaskSourceKind::Worktree { .. } => Some(Icon::new(IconName::FileTree)),
+                TaskSourceKind::Language { name } => file_icons::FileIcons::get(cx)
+                    .get_type_icon(&name.to_lowercase())
+                    .map(Icon::from_path),
+            }
+            .map(|icon| icon.color(Color::Muted))
         }
-        .map(|icon| icon.color(Color::Muted).size(IconSize::Small));
+        .map(|icon| icon.size(IconSize::Small));
         let history_run_icon = if Some(ix) <= self.divider_index {
             Some(
                 Icon::new(IconName::HistoryRerun)
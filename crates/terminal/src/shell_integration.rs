@@ -0,0 +1,758 @@
+//! Support for the semantic prompt sequences (OSC 133) that shells emit around their prompts and
+//! the commands run from them.
+//!
+//! Alacritty ignores these sequences, so [`ShellIntegrationPty`] rewrites them as they're read
+//! from the pty. Each mark becomes a run of zero-width Unicode tag characters inserted right after
+//! the next printable character, which the parser then attaches to that character's cell. This
+//! keeps the marks on the text they annotate as it scrolls into history, and lets
+//! [`command_blocks`] find them again by scanning the grid.
+
+use std::{
+    io::{self, Read},
+    ops::RangeInclusive,
+    sync::Arc,
+};
+
+use alacritty_terminal::{
+    event::{OnResize, WindowSize},
+    grid::Dimensions,
+    index::{Column, Direction as AlacDirection, Line, Point as AlacPoint},
+    term::cell::Cell,
+    tty::{self, ChildEvent, EventedPty, EventedReadWrite},
+    Term,
+};
+use polling::{Event as PollingEvent, PollMode, Poller};
+
+/// Tag characters mirror ASCII at this offset, and are never rendered.
+const TAG_OFFSET: u32 = 0xE0000;
+
+/// The pieces of a command line reported by the shell, in the order they are emitted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Mark {
+    /// `OSC 133 ; A`: the prompt starts.
+    PromptStart,
+    /// `OSC 133 ; B`: the prompt ends and the command being typed starts.
+    CommandStart,
+    /// `OSC 133 ; C`: the command was submitted and its output starts.
+    OutputStart,
+    /// `OSC 133 ; D [; exit code]`: the command finished.
+    CommandFinished(Option<i32>),
+}
+
+impl Mark {
+    fn parse(payload: &[u8]) -> Option<Self> {
+        let payload = std::str::from_utf8(payload.strip_prefix(b"133;")?).ok()?;
+        let mut params = payload.split(';');
+        match params.next()? {
+            "A" => Some(Mark::PromptStart),
+            "B" => Some(Mark::CommandStart),
+            "C" => Some(Mark::OutputStart),
+            "D" => Some(Mark::CommandFinished(
+                params.next().and_then(|code| code.parse().ok()),
+            )),
+            _ => None,
+        }
+    }
+
+    fn encode(self, output: &mut Vec<u8>) {
+        let mut push = |ascii: u8| {
+            let tag = char::from_u32(TAG_OFFSET + ascii as u32).unwrap();
+            let mut buf = [0; 4];
+            output.extend_from_slice(tag.encode_utf8(&mut buf).as_bytes());
+        };
+        match self {
+            Mark::PromptStart => push(b'A'),
+            Mark::CommandStart => push(b'B'),
+            Mark::OutputStart => push(b'C'),
+            Mark::CommandFinished(exit_code) => {
+                push(b'D');
+                if let Some(exit_code) = exit_code {
+                    exit_code.to_string().bytes().for_each(push);
+                }
+            }
+        }
+    }
+
+    /// Decodes the marks attached to a cell.
+    fn decode(chars: &[char]) -> Vec<Mark> {
+        fn set_exit_code(marks: &mut [Mark], exit_code: &mut String) {
+            if let Some(Mark::CommandFinished(code)) = marks.last_mut() {
+                *code = exit_code.parse().ok();
+            }
+            exit_code.clear();
+        }
+
+        let mut marks = Vec::new();
+        let mut exit_code = String::new();
+        for ascii in chars.iter().filter_map(|c| tag_to_ascii(*c)) {
+            let mark = match ascii {
+                b'A' => Mark::PromptStart,
+                b'B' => Mark::CommandStart,
+                b'C' => Mark::OutputStart,
+                b'D' => Mark::CommandFinished(None),
+                _ => {
+                    exit_code.push(ascii as char);
+                    continue;
+                }
+            };
+            set_exit_code(&mut marks, &mut exit_code);
+            marks.push(mark);
+        }
+        set_exit_code(&mut marks, &mut exit_code);
+        marks
+    }
+}
+
+fn tag_to_ascii(c: char) -> Option<u8> {
+    let ascii = (c as u32).checked_sub(TAG_OFFSET)?;
+    u8::try_from(ascii).ok().filter(u8::is_ascii)
+}
+
+/// Removes the characters encoding marks from text read out of the grid.
+pub(crate) fn strip_marks(text: String) -> String {
+    if text.chars().any(|c| tag_to_ascii(c).is_some()) {
+        text.chars()
+            .filter(|c| tag_to_ascii(*c).is_none())
+            .collect()
+    } else {
+        text
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ScanState {
+    #[default]
+    Ground,
+    /// Within a multi-byte UTF-8 character, with this many bytes left.
+    Utf8(u8),
+    Escape,
+    EscapeIntermediate,
+    Csi,
+    /// Within an OSC sequence. Its bytes are held back until it's known not to be a mark.
+    Osc {
+        passthrough: bool,
+    },
+    OscEscape {
+        passthrough: bool,
+    },
+    /// Within a DCS, SOS, PM or APC string, which is passed through untouched.
+    String,
+    StringEscape,
+}
+
+/// Rewrites OSC 133 sequences in the pty output into tag characters.
+#[derive(Default)]
+pub(crate) struct MarkScanner {
+    state: ScanState,
+    osc: Vec<u8>,
+    /// Marks waiting for a printable character to attach to.
+    pending: Vec<Mark>,
+}
+
+impl MarkScanner {
+    pub(crate) fn rewrite(&mut self, input: &[u8], output: &mut Vec<u8>) {
+        for &byte in input {
+            self.state = match self.state {
+                // The escape is held back until it's known not to start a mark.
+                ScanState::Ground | ScanState::Utf8(_) if byte == 0x1b => ScanState::Escape,
+                ScanState::Ground => {
+                    output.push(byte);
+                    match byte {
+                        0xc0..=0xdf => ScanState::Utf8(1),
+                        0xe0..=0xef => ScanState::Utf8(2),
+                        0xf0..=0xf7 => ScanState::Utf8(3),
+                        0x20..=0x7e => {
+                            self.flush_pending(output);
+                            ScanState::Ground
+                        }
+                        _ => ScanState::Ground,
+                    }
+                }
+                ScanState::Utf8(remaining) => {
+                    output.push(byte);
+                    if remaining > 1 {
+                        ScanState::Utf8(remaining - 1)
+                    } else {
+                        self.flush_pending(output);
+                        ScanState::Ground
+                    }
+                }
+                ScanState::Escape if byte == b']' => {
+                    self.osc.clear();
+                    ScanState::Osc { passthrough: false }
+                }
+                ScanState::Escape => {
+                    output.extend_from_slice(&[0x1b, byte]);
+                    match byte {
+                        b'[' => ScanState::Csi,
+                        b'P' | b'X' | b'^' | b'_' => ScanState::String,
+                        0x20..=0x2f => ScanState::EscapeIntermediate,
+                        _ => ScanState::Ground,
+                    }
+                }
+                ScanState::EscapeIntermediate => {
+                    output.push(byte);
+                    match byte {
+                        0x20..=0x2f => ScanState::EscapeIntermediate,
+                        _ => ScanState::Ground,
+                    }
+                }
+                ScanState::Csi => {
+                    output.push(byte);
+                    match byte {
+                        0x40..=0x7e => ScanState::Ground,
+                        _ => ScanState::Csi,
+                    }
+                }
+                ScanState::Osc { passthrough } => match byte {
+                    0x07 => {
+                        self.finish_osc(passthrough, b"\x07", output);
+                        ScanState::Ground
+                    }
+                    0x1b => ScanState::OscEscape { passthrough },
+                    _ if passthrough => {
+                        output.push(byte);
+                        ScanState::Osc { passthrough }
+                    }
+                    _ => {
+                        self.osc.push(byte);
+                        if self.osc.len() >= 4 && !self.osc.starts_with(b"133;") {
+                            output.extend_from_slice(b"\x1b]");
+                            output.append(&mut self.osc);
+                            ScanState::Osc { passthrough: true }
+                        } else {
+                            ScanState::Osc { passthrough }
+                        }
+                    }
+                },
+                ScanState::OscEscape { passthrough } => {
+                    if byte == b'\\' {
+                        self.finish_osc(passthrough, b"\x1b\\", output);
+                        ScanState::Ground
+                    } else {
+                        // An escape that isn't a terminator aborts the string and starts a new
+                        // sequence.
+                        self.finish_osc(passthrough, b"", output);
+                        self.state = ScanState::Escape;
+                        self.rewrite(&[byte], output);
+                        continue;
+                    }
+                }
+                ScanState::String => {
+                    output.push(byte);
+                    match byte {
+                        0x1b => ScanState::StringEscape,
+                        _ => ScanState::String,
+                    }
+                }
+                ScanState::StringEscape => {
+                    output.push(byte);
+                    match byte {
+                        b'\\' => ScanState::Ground,
+                        0x1b => ScanState::StringEscape,
+                        _ => ScanState::String,
+                    }
+                }
+            };
+        }
+    }
+
+    /// Ends an OSC sequence, either dropping it in favor of a pending mark or passing it through
+    /// followed by its terminator.
+    fn finish_osc(&mut self, passthrough: bool, terminator: &[u8], output: &mut Vec<u8>) {
+        if passthrough {
+            output.extend_from_slice(terminator);
+        } else if let Some(mark) = Mark::parse(&self.osc) {
+            self.pending.push(mark);
+        } else {
+            output.extend_from_slice(b"\x1b]");
+            output.extend_from_slice(&self.osc);
+            output.extend_from_slice(terminator);
+        }
+        self.osc.clear();
+    }
+
+    fn flush_pending(&mut self, output: &mut Vec<u8>) {
+        for mark in self.pending.drain(..) {
+            mark.encode(output);
+        }
+    }
+}
+
+/// Wraps a pty so that its output goes through a [`MarkScanner`] before reaching the parser.
+pub(crate) struct ShellIntegrationPty {
+    pty: tty::Pty,
+    scanner: MarkScanner,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    /// How much of `output` has been handed out already.
+    output_read: usize,
+}
+
+impl ShellIntegrationPty {
+    pub(crate) fn new(pty: tty::Pty) -> Self {
+        Self {
+            pty,
+            scanner: MarkScanner::default(),
+            input: Vec::new(),
+            output: Vec::new(),
+            output_read: 0,
+        }
+    }
+}
+
+impl Read for ShellIntegrationPty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.output_read == self.output.len() {
+            // Marks can grow the output, so leave room for it to fit into `buf` in one go. Any
+            // leftovers have to wait for the next read, which might only come with more output.
+            self.input.resize((buf.len() / 4).max(1), 0);
+            let len = self.pty.reader().read(&mut self.input)?;
+            if len == 0 {
+                return Ok(0);
+            }
+            self.output.clear();
+            self.output_read = 0;
+            self.scanner.rewrite(&self.input[..len], &mut self.output);
+        }
+
+        let output = &self.output[self.output_read..];
+        let len = output.len().min(buf.len());
+        buf[..len].copy_from_slice(&output[..len]);
+        self.output_read += len;
+        Ok(len)
+    }
+}
+
+impl EventedReadWrite for ShellIntegrationPty {
+    type Reader = Self;
+    type Writer = <tty::Pty as EventedReadWrite>::Writer;
+
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: PollingEvent,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.register(poll, interest, mode)
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: PollingEvent,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.reregister(poll, interest, mode)
+    }
+
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        self.pty.deregister(poll)
+    }
+
+    fn reader(&mut self) -> &mut Self::Reader {
+        self
+    }
+
+    fn writer(&mut self) -> &mut Self::Writer {
+        self.pty.writer()
+    }
+}
+
+impl EventedPty for ShellIntegrationPty {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        self.pty.next_child_event()
+    }
+}
+
+impl OnResize for ShellIntegrationPty {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        self.pty.on_resize(window_size)
+    }
+}
+
+/// A command line as reported by the shell. Points are those of the first character printed
+/// after each mark.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandBlock {
+    /// Where the prompt starts, unless it has scrolled out of the history.
+    pub prompt_start: Option<AlacPoint>,
+    /// Where the typed command starts.
+    pub command_start: Option<AlacPoint>,
+    /// Where the command's output starts, once the command has been submitted.
+    pub output_start: Option<AlacPoint>,
+    /// Where the command's output ends, once the command has finished.
+    pub output_end: Option<AlacPoint>,
+    /// The command's exit code, once it has finished and if the shell reported one.
+    pub exit_code: Option<i32>,
+}
+
+impl CommandBlock {
+    pub fn failed(&self) -> bool {
+        self.exit_code.map_or(false, |code| code != 0)
+    }
+}
+
+/// A command that was run in a terminal, as reported by the shell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandOutput {
+    pub command: String,
+    pub output: String,
+    pub exit_code: Option<i32>,
+    /// Where the output is in the grid. Empty when the command printed nothing.
+    pub output_range: RangeInclusive<AlacPoint>,
+}
+
+/// Groups the marks attached to the given cells into command blocks, oldest first.
+pub(crate) fn command_blocks<'a>(
+    cells: impl IntoIterator<Item = (AlacPoint, &'a Cell)>,
+) -> Vec<CommandBlock> {
+    let mut blocks = Vec::<CommandBlock>::new();
+    for (point, cell) in cells {
+        let Some(chars) = cell.zerowidth() else {
+            continue;
+        };
+        for mark in Mark::decode(chars) {
+            if mark == Mark::PromptStart || blocks.is_empty() {
+                blocks.push(CommandBlock::default());
+            }
+            let block = blocks.last_mut().unwrap();
+            match mark {
+                Mark::PromptStart => block.prompt_start = Some(point),
+                Mark::CommandStart => block.command_start = Some(point),
+                Mark::OutputStart => block.output_start = Some(point),
+                Mark::CommandFinished(exit_code) => {
+                    block.output_end = Some(point);
+                    block.exit_code = exit_code;
+                }
+            }
+        }
+    }
+    blocks
+}
+
+/// Returns the command blocks in the terminal's grid, including its history.
+pub(crate) fn grid_command_blocks<T>(term: &Term<T>) -> Vec<CommandBlock> {
+    let grid = term.grid();
+    let columns = term.columns();
+    let cells = (term.topmost_line().0..=term.bottommost_line().0).flat_map(|line| {
+        let line = Line(line);
+        (0..columns).map(move |column| {
+            let point = AlacPoint::new(line, Column(column));
+            (point, &grid[point])
+        })
+    });
+    command_blocks(cells)
+}
+
+/// Returns the lines on which the failed commands among the given cells start.
+pub(crate) fn failed_command_lines<'a>(
+    cells: impl IntoIterator<Item = (AlacPoint, &'a Cell)>,
+) -> Vec<Line> {
+    command_blocks(cells)
+        .into_iter()
+        .filter(CommandBlock::failed)
+        .filter_map(|block| Some(block.command_start.or(block.prompt_start)?.line))
+        .collect()
+}
+
+/// Returns the last command that was run along with its output, if the shell reports them.
+pub(crate) fn last_command_output<T>(term: &Term<T>) -> Option<CommandOutput> {
+    let blocks = grid_command_blocks(term);
+    let (ix, block) = blocks
+        .iter()
+        .enumerate()
+        .rfind(|(_, block)| block.output_start.is_some())?;
+    let output_start = block.output_start?;
+    let command_start = block.command_start.or(block.prompt_start)?;
+    let output_end = block
+        .output_end
+        .or_else(|| blocks.get(ix + 1)?.prompt_start)
+        .map(|end| point_before(term, end))
+        .unwrap_or_else(|| AlacPoint::new(term.bottommost_line(), term.last_column()));
+
+    let command =
+        strip_marks(term.bounds_to_string(command_start, point_before(term, output_start)));
+    let output = if output_start <= output_end {
+        strip_marks(term.bounds_to_string(output_start, output_end))
+    } else {
+        String::new()
+    };
+    Some(CommandOutput {
+        command: command.trim().to_string(),
+        output: output.trim_end().to_string(),
+        exit_code: block.exit_code,
+        output_range: output_start..=output_end,
+    })
+}
+
+/// Returns how far to scroll to bring the nearest prompt before or after the top of the viewport
+/// to the top, in lines towards the history.
+pub(crate) fn prompt_scroll_delta<T>(term: &Term<T>, direction: AlacDirection) -> Option<i32> {
+    let top = -(term.grid().display_offset() as i32);
+    let mut prompt_lines = grid_command_blocks(term)
+        .into_iter()
+        .filter_map(|block| Some(block.prompt_start?.line.0));
+    let target = match direction {
+        AlacDirection::Left => prompt_lines.filter(|line| *line < top).last(),
+        AlacDirection::Right => prompt_lines.find(|line| *line > top),
+    };
+    Some(top - target?)
+}
+
+/// Returns the point just before the given one, wrapping to the end of the previous line.
+pub(crate) fn point_before<T>(term: &Term<T>, point: AlacPoint) -> AlacPoint {
+    if point.column.0 > 0 {
+        AlacPoint::new(point.line, Column(point.column.0 - 1))
+    } else {
+        AlacPoint::new(Line(point.line.0 - 1), term.last_column())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{
+        event::VoidListener, grid::Scroll, term::Config, vte::ansi::Processor,
+    };
+
+    use super::*;
+
+    struct TestSize {
+        columns: usize,
+        screen_lines: usize,
+    }
+
+    impl Dimensions for TestSize {
+        fn total_lines(&self) -> usize {
+            self.screen_lines
+        }
+
+        fn screen_lines(&self) -> usize {
+            self.screen_lines
+        }
+
+        fn columns(&self) -> usize {
+            self.columns
+        }
+    }
+
+    /// Runs the given shell output through a [`MarkScanner`] into a terminal with the given
+    /// number of screen lines, 20 columns wide.
+    fn term_with_output(screen_lines: usize, output: &str) -> Term<VoidListener> {
+        let config = Config {
+            scrolling_history: 100,
+            ..Config::default()
+        };
+        let size = TestSize {
+            columns: 20,
+            screen_lines,
+        };
+        let mut term = Term::new(config, &size, VoidListener);
+        let mut bytes = Vec::new();
+        MarkScanner::default().rewrite(output.as_bytes(), &mut bytes);
+        let mut processor: Processor = Processor::new();
+        for byte in bytes {
+            processor.advance(&mut term, byte);
+        }
+        term
+    }
+
+    /// Output of a shell that marks its `$ ` prompt, for each of the given commands and the
+    /// lines they print and exit codes. A `None` exit code leaves the command running, otherwise
+    /// the output ends with a new prompt for the marks to attach to.
+    fn shell_output(commands: &[(&str, &[&str], Option<i32>)]) -> String {
+        let mut output = String::new();
+        for (command, lines, exit_code) in commands {
+            output.push_str("\x1b]133;A\x07$ \x1b]133;B\x07");
+            output.push_str(command);
+            output.push_str("\r\n\x1b]133;C\x07");
+            for line in *lines {
+                output.push_str(line);
+                output.push_str("\r\n");
+            }
+            if let Some(exit_code) = exit_code {
+                output.push_str(&format!("\x1b]133;D;{exit_code}\x07"));
+            }
+        }
+        if commands
+            .last()
+            .map_or(false, |(_, _, exit_code)| exit_code.is_some())
+        {
+            output.push_str("\x1b]133;A\x07$ ");
+        }
+        output
+    }
+
+    fn rewrite(chunks: &[&[u8]]) -> Vec<u8> {
+        let mut scanner = MarkScanner::default();
+        let mut output = Vec::new();
+        for chunk in chunks {
+            scanner.rewrite(chunk, &mut output);
+        }
+        output
+    }
+
+    fn tags(ascii: &str) -> String {
+        ascii
+            .chars()
+            .map(|c| char::from_u32(TAG_OFFSET + c as u32).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_marks_attach_to_next_printable_character() {
+        let output = rewrite(&[
+            b"\x1b]133;D;1\x07\x1b]133;A\x07\x1b[32m$\x1b[0m \x1b]133;B\x1b\\",
+            b"ls\r\n\x1b]133;C\x07",
+            "é\n".as_bytes(),
+        ]);
+        let expected = format!(
+            "\x1b[32m${}\x1b[0m l{}s\r\né{}\n",
+            tags("D1A"),
+            tags("B"),
+            tags("C")
+        );
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_other_sequences_pass_through() {
+        let input: &[&[u8]] = &[
+            b"\x1b]0;title\x07\x1b",
+            b"]133;A\x1b",
+            b"\\\x1b]52;c;Zm9v\x1b\\\x1bP1$r\x1b\\x",
+        ];
+        let output = rewrite(input);
+        let expected = format!(
+            "\x1b]0;title\x07\x1b]52;c;Zm9v\x1b\\\x1bP1$r\x1b\\x{}",
+            tags("A")
+        );
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_decode_marks() {
+        let chars = tags("D127AB").chars().collect::<Vec<_>>();
+        assert_eq!(
+            Mark::decode(&chars),
+            vec![
+                Mark::CommandFinished(Some(127)),
+                Mark::PromptStart,
+                Mark::CommandStart
+            ]
+        );
+        assert_eq!(strip_marks(format!("a{}b", tags("C"))), "ab".to_string());
+    }
+
+    #[test]
+    fn test_grid_command_blocks() {
+        let term = term_with_output(
+            10,
+            &shell_output(&[("ls", &["a.txt"], Some(0)), ("false", &[], Some(1))]),
+        );
+        let point = |line, column| Some(AlacPoint::new(Line(line), Column(column)));
+        assert_eq!(
+            grid_command_blocks(&term),
+            [
+                CommandBlock {
+                    prompt_start: point(0, 0),
+                    command_start: point(0, 2),
+                    output_start: point(1, 0),
+                    output_end: point(2, 0),
+                    exit_code: Some(0),
+                },
+                // Without any output, the marks after the command attach to the next prompt.
+                CommandBlock {
+                    prompt_start: point(2, 0),
+                    command_start: point(2, 2),
+                    output_start: point(3, 0),
+                    output_end: point(3, 0),
+                    exit_code: Some(1),
+                },
+                CommandBlock {
+                    prompt_start: point(3, 0),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_last_command_output() {
+        let term = term_with_output(10, &shell_output(&[("echo hi", &["hi"], Some(0))]));
+        let output = last_command_output(&term).unwrap();
+        assert_eq!(output.command, "echo hi");
+        assert_eq!(output.output, "hi");
+        assert_eq!(output.exit_code, Some(0));
+        assert_eq!(
+            output.output_range,
+            AlacPoint::new(Line(1), Column(0))..=AlacPoint::new(Line(1), Column(19))
+        );
+
+        // The output of a command that's still running reaches the bottom of the grid.
+        let term = term_with_output(
+            10,
+            &shell_output(&[
+                ("echo hi", &["hi"], Some(0)),
+                ("cargo build", &["Compiling a", "Compiling b"], None),
+            ]),
+        );
+        let output = last_command_output(&term).unwrap();
+        assert_eq!(output.command, "cargo build");
+        assert_eq!(output.output, "Compiling a\nCompiling b");
+        assert_eq!(output.exit_code, None);
+
+        let term = term_with_output(10, "$ echo hi\r\nhi\r\n");
+        assert_eq!(last_command_output(&term), None);
+    }
+
+    #[test]
+    fn test_failed_command_lines() {
+        let term = term_with_output(
+            10,
+            &shell_output(&[
+                ("true", &[], Some(0)),
+                ("false", &[], Some(1)),
+                ("ls", &["a.txt"], None),
+                ("exit 2", &[], Some(2)),
+            ]),
+        );
+        let grid = term.grid();
+        let cells = (0..10).flat_map(|line| {
+            (0..term.columns()).map(move |column| {
+                let point = AlacPoint::new(Line(line), Column(column));
+                (point, &grid[point])
+            })
+        });
+        assert_eq!(failed_command_lines(cells), [Line(1), Line(4)]);
+    }
+
+    #[test]
+    fn test_prompt_scroll_delta() {
+        // Seven lines on a three line screen, so only the last prompt is on screen.
+        let mut term = term_with_output(
+            3,
+            &shell_output(&[("a", &["1", "2"], Some(0)), ("b", &["1", "2"], Some(0))]),
+        );
+        let prompt_lines = grid_command_blocks(&term)
+            .into_iter()
+            .map(|block| block.prompt_start.unwrap().line)
+            .collect::<Vec<_>>();
+        assert_eq!(prompt_lines, [Line(-4), Line(-1), Line(2)]);
+
+        assert_eq!(prompt_scroll_delta(&term, AlacDirection::Left), Some(1));
+        assert_eq!(prompt_scroll_delta(&term, AlacDirection::Right), Some(-2));
+
+        term.scroll_display(Scroll::Delta(1));
+        assert_eq!(prompt_scroll_delta(&term, AlacDirection::Left), Some(3));
+        assert_eq!(prompt_scroll_delta(&term, AlacDirection::Right), Some(-3));
+
+        term.scroll_display(Scroll::Delta(1));
+        assert_eq!(prompt_scroll_delta(&term, AlacDirection::Left), Some(2));
+        assert_eq!(prompt_scroll_delta(&term, AlacDirection::Right), Some(-1));
+
+        term.scroll_display(Scroll::Delta(2));
+        assert_eq!(prompt_scroll_delta(&term, AlacDirection::Left), None);
+        assert_eq!(prompt_scroll_delta(&term, AlacDirection::Right), Some(-3));
+    }
+}
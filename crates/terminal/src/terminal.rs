@@ -3,6 +3,7 @@ pub mod mappings;
 pub use alacritty_terminal;
 
 mod pty_info;
+mod shell_integration;
 pub mod terminal_settings;
 
 use alacritty_terminal::{
@@ -21,6 +22,7 @@ use alacritty_terminal::{
     vi_mode::{ViModeCursor, ViMotion},
     vte::ansi::{
         ClearMode, CursorStyle as AlacCursorStyle, Handler, NamedPrivateMode, PrivateMode,
+        Processor,
     },
     Term,
 };
@@ -41,6 +43,7 @@ use futures::StreamExt;
 use pty_info::PtyProcessInfo;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
+use shell_integration::{grid_command_blocks, strip_marks, ShellIntegrationPty};
 use smol::channel::{Receiver, Sender};
 use task::{HideStrategy, Shell, TaskId};
 use terminal_settings::{AlternateScroll, CursorShape, TerminalSettings};
@@ -50,13 +53,15 @@ use util::{paths::home_dir, truncate_and_trailoff};
 use std::{
     cmp::{self, min},
     fmt::Display,
-    ops::{Deref, Index, RangeInclusive},
+    ops::{Deref, Index, Range, RangeInclusive},
     path::PathBuf,
     sync::Arc,
     time::Duration,
 };
 use thiserror::Error;
 
+pub use shell_integration::{CommandBlock, CommandOutput};
+
 use gpui::{
     actions, black, px, AnyWindowHandle, AppContext, Bounds, ClipboardItem, EventEmitter, Hsla,
     Keystroke, ModelContext, Modifiers, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent,
@@ -79,6 +84,10 @@ actions!(
         ScrollPageDown,
         ScrollToTop,
         ScrollToBottom,
+        ScrollToPreviousPrompt,
+        ScrollToNextPrompt,
+        SelectLastCommandOutput,
+        CopyLastCommand,
         ToggleViMode,
     ]
 );
@@ -331,6 +340,7 @@ impl TerminalBuilder {
         cursor_shape: CursorShape,
         alternate_scroll: AlternateScroll,
         max_scroll_history_lines: Option<usize>,
+        restored_scrollback: Option<String>,
         is_ssh_terminal: bool,
         window: AnyWindowHandle,
         completion_tx: Sender<()>,
@@ -415,6 +425,11 @@ impl TerminalBuilder {
             term.unset_private_mode(PrivateMode::Named(NamedPrivateMode::AlternateScroll));
         }
 
+        // Restore before the pty is read, so the new shell's prompt ends up below the old output.
+        if let Some(scrollback) = restored_scrollback {
+            restore_scrollback(&mut term, &scrollback);
+        }
+
         let term = Arc::new(FairMutex::new(term));
 
         //Setup the pty...
@@ -439,7 +454,7 @@ impl TerminalBuilder {
         let event_loop = EventLoop::new(
             term.clone(),
             ZedListener(events_tx.clone()),
-            pty,
+            ShellIntegrationPty::new(pty),
             pty_options.hold,
             false,
         )?;
@@ -571,6 +586,8 @@ pub struct TerminalContent {
     pub cursor_char: char,
     pub size: TerminalSize,
     pub last_hovered_word: Option<HoveredWord>,
+    /// The visible lines of commands that exited with a non-zero code.
+    pub failed_command_lines: Vec<Line>,
 }
 
 #[derive(Clone)]
@@ -595,6 +612,7 @@ impl Default for TerminalContent {
             cursor_char: Default::default(),
             size: Default::default(),
             last_hovered_word: None,
+            failed_command_lines: Vec::new(),
         }
     }
 }
@@ -824,7 +842,7 @@ impl Terminal {
                         term.selection = Some(selection);
 
                         #[cfg(any(target_os = "linux", target_os = "freebsd"))]
-                        if let Some(selection_text) = term.selection_to_string() {
+                        if let Some(selection_text) = term.selection_to_string().map(strip_marks) {
                             cx.write_to_primary(ClipboardItem::new_string(selection_text));
                         }
 
@@ -837,7 +855,7 @@ impl Terminal {
                 term.selection = selection.as_ref().map(|(sel, _)| sel.clone());
 
                 #[cfg(any(target_os = "linux", target_os = "freebsd"))]
-                if let Some(selection_text) = term.selection_to_string() {
+                if let Some(selection_text) = term.selection_to_string().map(strip_marks) {
                     cx.write_to_primary(ClipboardItem::new_string(selection_text));
                 }
 
@@ -858,7 +876,7 @@ impl Terminal {
                     term.selection = Some(selection);
 
                     #[cfg(any(target_os = "linux", target_os = "freebsd"))]
-                    if let Some(selection_text) = term.selection_to_string() {
+                    if let Some(selection_text) = term.selection_to_string().map(strip_marks) {
                         cx.write_to_primary(ClipboardItem::new_string(selection_text));
                     }
 
@@ -868,7 +886,7 @@ impl Terminal {
             }
 
             InternalEvent::Copy => {
-                if let Some(txt) = term.selection_to_string() {
+                if let Some(txt) = term.selection_to_string().map(strip_marks) {
                     cx.write_to_clipboard(ClipboardItem::new_string(txt))
                 }
             }
@@ -927,24 +945,21 @@ impl Terminal {
                     let url = term.bounds_to_string(*url_match.start(), *url_match.end());
                     Some((url, true, url_match))
                 } else if let Some(word_match) = regex_match_at(term, point, &mut self.word_regex) {
-                    let file_path = term.bounds_to_string(*word_match.start(), *word_match.end());
-
-                    let (sanitized_match, sanitized_word) = if file_path.starts_with('[')
-                        && file_path.ends_with(']')
-                        // this is to avoid sanitizing the match '[]' to an empty string,
-                        // which would be considered a valid navigation target
-                        && file_path.len() > 2
-                    {
-                        (
-                            Match::new(
-                                word_match.start().add(term, Boundary::Cursor, 1),
-                                word_match.end().sub(term, Boundary::Cursor, 1),
-                            ),
-                            file_path[1..file_path.len() - 1].to_owned(),
-                        )
-                    } else {
-                        (word_match, file_path)
-                    };
+                    let word = term.bounds_to_string(*word_match.start(), *word_match.end());
+                    let path_range = path_like_range(&word);
+                    let sanitized_match = Match::new(
+                        word_match.start().add(
+                            term,
+                            Boundary::Cursor,
+                            word[..path_range.start].chars().count(),
+                        ),
+                        word_match.end().sub(
+                            term,
+                            Boundary::Cursor,
+                            word[path_range.end..].chars().count(),
+                        ),
+                    );
+                    let sanitized_word = word[path_range].to_owned();
 
                     Some((sanitized_word, false, sanitized_match))
                 } else {
@@ -1313,29 +1328,33 @@ impl Terminal {
 
     fn make_content(term: &Term<ZedListener>, last_content: &TerminalContent) -> TerminalContent {
         let content = term.renderable_content();
+        let cells = content
+            .display_iter
+            //TODO: Add this once there's a way to retain empty lines
+            // .filter(|ic| {
+            //     !ic.flags.contains(Flags::HIDDEN)
+            //         && !(ic.bg == Named(NamedColor::Background)
+            //             && ic.c == ' '
+            //             && !ic.flags.contains(Flags::INVERSE))
+            // })
+            .map(|ic| IndexedCell {
+                point: ic.point,
+                cell: ic.cell.clone(),
+            })
+            .collect::<Vec<IndexedCell>>();
+        let failed_command_lines =
+            shell_integration::failed_command_lines(cells.iter().map(|ic| (ic.point, &ic.cell)));
         TerminalContent {
-            cells: content
-                .display_iter
-                //TODO: Add this once there's a way to retain empty lines
-                // .filter(|ic| {
-                //     !ic.flags.contains(Flags::HIDDEN)
-                //         && !(ic.bg == Named(NamedColor::Background)
-                //             && ic.c == ' '
-                //             && !ic.flags.contains(Flags::INVERSE))
-                // })
-                .map(|ic| IndexedCell {
-                    point: ic.point,
-                    cell: ic.cell.clone(),
-                })
-                .collect::<Vec<IndexedCell>>(),
+            cells,
             mode: content.mode,
             display_offset: content.display_offset,
-            selection_text: term.selection_to_string(),
+            selection_text: term.selection_to_string().map(strip_marks),
             selection: content.selection,
             cursor: content.cursor,
             cursor_char: term.grid()[content.cursor.point].c,
             size: last_content.size,
             last_hovered_word: last_content.last_hovered_word.clone(),
+            failed_command_lines,
         }
     }
 
@@ -1364,6 +1383,75 @@ impl Terminal {
         lines
     }
 
+    /// Returns up to `max_lines` of the most recent output as plain text, for restoring
+    /// the terminal in a later session. Returns `None` while a full-screen program is running.
+    pub fn scrollback_text(&self, max_lines: usize) -> Option<String> {
+        let term = self.term.lock_unfair();
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return None;
+        }
+
+        let bottom = term.bottommost_line();
+        let max_lines = i32::try_from(max_lines).unwrap_or(i32::MAX);
+        let top = cmp::max(
+            term.topmost_line(),
+            Line(bottom.0.saturating_sub(max_lines.saturating_sub(1))),
+        );
+        let text = strip_marks(term.bounds_to_string(
+            AlacPoint::new(top, Column(0)),
+            AlacPoint::new(bottom, term.last_column()),
+        ));
+        let mut lines = text.lines().map(str::trim_end).collect::<Vec<_>>();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Returns the commands reported by the shell through OSC 133 sequences, oldest first.
+    pub fn command_blocks(&self) -> Vec<CommandBlock> {
+        grid_command_blocks(&self.term.lock())
+    }
+
+    /// Returns the last command that was run along with its output, if the shell reports them.
+    pub fn last_command_output(&self) -> Option<CommandOutput> {
+        shell_integration::last_command_output(&self.term.lock())
+    }
+
+    pub fn scroll_to_previous_prompt(&mut self) {
+        self.scroll_to_prompt(AlacDirection::Left);
+    }
+
+    pub fn scroll_to_next_prompt(&mut self) {
+        self.scroll_to_prompt(AlacDirection::Right);
+    }
+
+    fn scroll_to_prompt(&mut self, direction: AlacDirection) {
+        let delta = shell_integration::prompt_scroll_delta(&self.term.lock(), direction);
+        if let Some(delta) = delta {
+            self.events
+                .push_back(InternalEvent::Scroll(AlacScroll::Delta(delta)));
+        }
+    }
+
+    pub fn select_last_command_output(&mut self) {
+        if let Some(output) = self.last_command_output() {
+            if !output.output_range.is_empty() {
+                let end = *output.output_range.end();
+                self.events.push_back(InternalEvent::ScrollToAlacPoint(
+                    *output.output_range.start(),
+                ));
+                self.set_selection(Some((make_selection(&output.output_range), end)));
+            }
+        }
+    }
+
+    pub fn copy_last_command(&mut self, cx: &mut ModelContext<Self>) {
+        if let Some(output) = self.last_command_output() {
+            cx.write_to_clipboard(ClipboardItem::new_string(output.command));
+        }
+    }
+
     pub fn focus_in(&self) {
         if self.last_content.mode.contains(TermMode::FOCUS_IN_OUT) {
             self.write_to_pty("\x1b[I".to_string());
@@ -1743,6 +1831,8 @@ impl Terminal {
         cx: &mut ModelContext<'_, Terminal>,
     ) {
         self.completion_tx.try_send(()).ok();
+        // Each message reaches a single receiver, so close the channel to wake all the others.
+        self.completion_tx.close();
         let task = match &mut self.task {
             Some(task) => task,
             None => {
@@ -1883,6 +1973,103 @@ pub fn visible_regex_match_iter<'a, T>(
         .take_while(move |rm| rm.start().line <= viewport_end)
 }
 
+/// Narrows a word matched by [`WORD_REGEX`] down to the part that may be a path with an
+/// optional position, dropping the punctuation that tools print around locations:
+///
+/// - `(/abs/path.js:10:3)` in Node.js stack traces
+/// - `src/main.rs:4:5:` in Rust panics, and `./main.go:10:5:` in Go errors
+/// - `tests/test_foo.py::test_bar` in pytest node ids
+/// - `[src/lib.rs:10:5]` in `dbg!` output
+///
+/// MSBuild-style positions such as `src/index.ts(3,7)` are kept, as they are balanced.
+fn path_like_range(word: &str) -> Range<usize> {
+    let mut start = 0;
+    let mut end = word.find("::").filter(|ix| *ix > 0).unwrap_or(word.len());
+    while word[start..end].starts_with(['(', '[']) {
+        start += 1;
+    }
+    loop {
+        let candidate = &word[start..end];
+        let unbalanced = |open: char, close: char| {
+            candidate.ends_with(close)
+                && candidate.matches(close).count() > candidate.matches(open).count()
+        };
+        if candidate.ends_with(':') || unbalanced('(', ')') || unbalanced('[', ']') {
+            end -= 1;
+        } else {
+            break;
+        }
+    }
+
+    // Words made only of punctuation, like `[]`, are kept as they are.
+    if start < end {
+        start..end
+    } else {
+        0..word.len()
+    }
+}
+
+const RESTORED_SCROLLBACK_SEPARATOR: &str = "─── Restored from previous session ───";
+
+/// Writes output saved by [`Terminal::scrollback_text`] into a new terminal, dimmed and
+/// followed by a separator, so it reads as history rather than live output.
+fn restore_scrollback(term: &mut Term<ZedListener>, scrollback: &str) {
+    let mut bytes = Vec::with_capacity(scrollback.len() + 64);
+    bytes.extend_from_slice(b"\x1b[2m");
+    for line in scrollback.lines() {
+        // Output restored in earlier sessions is saved along with its separator. Only the
+        // latest separator is kept, so they don't pile up with every restart.
+        if line.trim_end() == RESTORED_SCROLLBACK_SEPARATOR {
+            continue;
+        }
+        bytes.extend_from_slice(strip_control_sequences(line).as_bytes());
+        bytes.extend_from_slice(b"\r\n");
+    }
+    bytes.extend_from_slice(
+        format!("\x1b[0;2m{RESTORED_SCROLLBACK_SEPARATOR}\x1b[0m\r\n").as_bytes(),
+    );
+
+    let mut processor: Processor = Processor::new();
+    for byte in bytes {
+        processor.advance(term, byte);
+    }
+}
+
+/// Removes escape sequences and other control characters from a line of saved output, so
+/// writing it to a terminal can't do anything but print it.
+fn strip_control_sequences(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            if !c.is_control() {
+                text.push(c);
+            }
+            continue;
+        }
+        match chars.next() {
+            // CSI sequences end with a byte in the `@` to `~` range.
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC, DCS, SOS, PM and APC strings end with a BEL or a string terminator.
+            Some(']' | 'P' | 'X' | '^' | '_') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
 fn make_selection(range: &RangeInclusive<AlacPoint>) -> Selection {
     let mut selection = Selection::new(SelectionType::Simple, *range.start(), AlacDirection::Left);
     selection.update(*range.end(), AlacDirection::Right);
@@ -1994,13 +2181,16 @@ pub fn rgba_color(r: u8, g: u8, b: u8) -> Hsla {
 mod tests {
     use alacritty_terminal::{
         index::{Column, Line, Point as AlacPoint},
-        term::cell::Cell,
+        term::{cell::Cell, Config},
+        Term,
     };
+    use futures::channel::mpsc::unbounded;
     use gpui::{point, size, Pixels};
     use rand::{distributions::Alphanumeric, rngs::ThreadRng, thread_rng, Rng};
 
     use crate::{
-        content_index_for_mouse, rgb_for_index, IndexedCell, TerminalContent, TerminalSize,
+        content_index_for_mouse, path_like_range, restore_scrollback, rgb_for_index, IndexedCell,
+        TerminalContent, TerminalSize, ZedListener,
     };
 
     #[test]
@@ -2133,6 +2323,37 @@ mod tests {
             .collect();
         assert_eq!(results, expected);
     }
+    #[test]
+    fn test_restore_scrollback() {
+        let (events_tx, _events_rx) = unbounded();
+        let mut term = Term::new(
+            Config::default(),
+            &TerminalSize::default(),
+            ZedListener(events_tx),
+        );
+        // Text restored in an earlier session comes with its own separator.
+        restore_scrollback(
+            &mut term,
+            "$ ls\n─── Restored from previous session ───\n$ cargo build\n\x1b[31merror\x1b[0m: \x1b]0;title\x07failed\n",
+        );
+
+        let text = term.bounds_to_string(
+            AlacPoint::new(term.topmost_line(), Column(0)),
+            AlacPoint::new(Line(3), term.last_column()),
+        );
+        let lines = text.lines().map(str::trim_end).collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "$ ls",
+                "$ cargo build",
+                "error: failed",
+                "─── Restored from previous session ───",
+            ]
+        );
+        assert_eq!(term.grid().cursor.point, AlacPoint::new(Line(4), Column(0)));
+    }
+
     #[test]
     fn test_url_regex() {
         re_test(
@@ -2149,6 +2370,59 @@ mod tests {
             vec!["hello", "world", "What", "is", "this"],
         );
     }
+    fn path_like_words(line: &str) -> Vec<&str> {
+        regex::Regex::new(crate::WORD_REGEX)
+            .unwrap()
+            .find_iter(line)
+            .map(|m| &m.as_str()[path_like_range(m.as_str())])
+            .collect()
+    }
+
+    #[test]
+    fn test_path_like_words() {
+        let cases = [
+            // rustc
+            ("  --> src/main.rs:42:7", "src/main.rs:42:7"),
+            (
+                "thread 'main' panicked at src/main.rs:4:5:",
+                "src/main.rs:4:5",
+            ),
+            ("[src/lib.rs:10:5] x = 1", "src/lib.rs:10:5"),
+            // tsc
+            (
+                "src/index.ts(3,7): error TS2322: Type 'string' is not assignable",
+                "src/index.ts(3,7)",
+            ),
+            (
+                "src/index.ts:3:7 - error TS2322: Type 'string' is not assignable",
+                "src/index.ts:3:7",
+            ),
+            // pytest
+            (
+                "tests/test_foo.py:12: AssertionError",
+                "tests/test_foo.py:12",
+            ),
+            (
+                "FAILED tests/test_foo.py::TestFoo::test_bar - assert 1 == 2",
+                "tests/test_foo.py",
+            ),
+            // Go
+            ("./main.go:10:5: undefined: x", "./main.go:10:5"),
+            ("    main_test.go:15: expected 1, got 2", "main_test.go:15"),
+            // Node.js
+            ("    at foo (/abs/path.js:10:3)", "/abs/path.js:10:3"),
+        ];
+        for (line, expected) in cases {
+            let words = path_like_words(line);
+            assert!(
+                words.contains(&expected),
+                "expected {expected:?} in {words:?} for {line:?}"
+            );
+        }
+
+        assert_eq!(path_like_words("[] ::"), vec!["[]", "::"]);
+    }
+
     #[test]
     fn test_word_regex_with_linenum() {
         // filename(line) and filename(line,col) as used in MSBuild output
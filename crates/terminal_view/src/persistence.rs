@@ -60,14 +60,10 @@ fn serialize_pane(pane: &View<Pane>, active: bool, cx: &WindowContext) -> Serial
     let children = pane
         .items()
         .filter_map(|item| {
-            let terminal_view = item.act_as::<TerminalView>(cx)?;
-            if terminal_view.read(cx).terminal().read(cx).task().is_some() {
-                None
-            } else {
-                let id = item.item_id().as_u64();
-                items_to_serialize.insert(id);
-                Some(id)
-            }
+            item.act_as::<TerminalView>(cx)?;
+            let id = item.item_id().as_u64();
+            items_to_serialize.insert(id);
+            Some(id)
         })
         .collect::<Vec<_>>();
     let active_item = pane
@@ -395,6 +391,9 @@ define_connection! {
             DROP TABLE terminals;
 
             ALTER TABLE terminals2 RENAME TO terminals;
+        ),
+        sql!(
+            ALTER TABLE terminals ADD COLUMN scrollback TEXT;
         )];
 }
 
@@ -412,13 +411,17 @@ impl TerminalDb {
     }
 
     query! {
-        pub async fn save_working_directory(
+        pub async fn save_terminal(
             item_id: ItemId,
             workspace_id: WorkspaceId,
-            working_directory: PathBuf
+            working_directory: PathBuf,
+            scrollback: Option<String>
         ) -> Result<()> {
-            INSERT OR REPLACE INTO terminals(item_id, workspace_id, working_directory)
-            VALUES (?, ?, ?)
+            INSERT INTO terminals(item_id, workspace_id, working_directory, scrollback)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT DO UPDATE SET
+                working_directory = ?3,
+                scrollback = COALESCE(?4, scrollback)
         }
     }
 
@@ -430,6 +433,14 @@ impl TerminalDb {
         }
     }
 
+    query! {
+        pub fn get_scrollback(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<String>> {
+            SELECT scrollback
+            FROM terminals
+            WHERE item_id = ? AND workspace_id = ?
+        }
+    }
+
     pub async fn delete_unloaded_items(
         &self,
         workspace: WorkspaceId,
@@ -47,6 +47,8 @@ pub struct LayoutState {
     gutter: Pixels,
     last_hovered_word: Option<HoveredWord>,
     block_below_cursor_element: Option<AnyElement>,
+    /// Display lines of commands that exited with a non-zero code, marked in the gutter.
+    failed_command_lines: Vec<usize>,
 }
 
 /// Helper struct for converting data between Alacritty's cursor points, and displayed cursor points.
@@ -735,10 +737,15 @@ impl Element for TerminalElement {
                     cursor_char,
                     selection,
                     cursor,
+                    failed_command_lines,
                     ..
                 } = &self.terminal.read(cx).last_content;
                 let mode = *mode;
                 let display_offset = *display_offset;
+                let failed_command_lines = failed_command_lines
+                    .iter()
+                    .filter_map(|line| usize::try_from(line.0 + display_offset as i32).ok())
+                    .collect();
 
                 // searches, highlights to a single range representations
                 let mut relative_highlighted_ranges = Vec::new();
@@ -857,6 +864,7 @@ impl Element for TerminalElement {
                     gutter,
                     last_hovered_word,
                     block_below_cursor_element,
+                    failed_command_lines,
                 }
             })
     }
@@ -919,6 +927,19 @@ impl Element for TerminalElement {
                         rect.paint(origin, &layout.dimensions, cx);
                     }
 
+                    let error_color = cx.theme().status().error;
+                    for display_line in &layout.failed_command_lines {
+                        let line_height = layout.dimensions.line_height;
+                        let marker = Bounds::new(
+                            point(
+                                bounds.origin.x,
+                                origin.y + *display_line as f32 * line_height,
+                            ),
+                            size(layout.gutter * 0.5, line_height),
+                        );
+                        cx.paint_quad(fill(marker, error_color));
+                    }
+
                     for (relative_highlighted_range, color) in
                         layout.relative_highlighted_ranges.iter()
                     {
@@ -1,4 +1,6 @@
-use std::{cmp, ops::ControlFlow, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    cell::RefCell, cmp, mem, ops::ControlFlow, path::PathBuf, rc::Rc, sync::Arc, time::Duration,
+};
 
 use crate::{
     default_working_directory,
@@ -10,7 +12,10 @@ use crate::{
 use breadcrumbs::Breadcrumbs;
 use collections::HashMap;
 use db::kvp::KEY_VALUE_STORE;
-use futures::future::join_all;
+use futures::{
+    future::{join_all, Shared},
+    FutureExt as _,
+};
 use gpui::{
     actions, Action, AnyView, AppContext, AsyncWindowContext, Corner, Entity, EventEmitter,
     ExternalPaths, FocusHandle, FocusableView, IntoElement, Model, ParentElement, Pixels, Render,
@@ -20,10 +25,10 @@ use itertools::Itertools;
 use project::{terminals::TerminalKind, Fs, Project, ProjectEntryId};
 use search::{buffer_search::DivRegistrar, BufferSearchBar};
 use settings::Settings;
-use task::{RevealStrategy, RevealTarget, Shell, SpawnInTerminal, TaskId};
+use task::{DependsOrder, RevealStrategy, RevealTarget, Shell, SpawnInTerminal, TaskId};
 use terminal::{
     terminal_settings::{TerminalDockPosition, TerminalSettings},
-    Terminal,
+    TaskStatus, Terminal,
 };
 use ui::{
     prelude::*, ButtonCommon, Clickable, ContextMenu, FluentBuilder, PopoverMenu, Toggleable,
@@ -46,6 +51,9 @@ use zed_actions::InlineAssist;
 
 const TERMINAL_PANEL_KEY: &str = "TerminalPanel";
 
+/// The runs of the dependencies of a spawned task, by task id.
+type DependencyRuns = Rc<RefCell<HashMap<TaskId, Shared<Task<bool>>>>>;
+
 actions!(terminal_panel, [ToggleFocus]);
 
 pub fn init(cx: &mut AppContext) {
@@ -380,7 +388,7 @@ impl TerminalPanel {
         let window = cx.window_handle();
         let terminal = project
             .update(cx, |project, cx| {
-                project.create_terminal_with_venv(kind, python_venv_directory, window, cx)
+                project.create_terminal_with_venv(kind, python_venv_directory, None, window, cx)
             })
             .ok()?;
 
@@ -428,47 +436,163 @@ impl TerminalPanel {
     }
 
     fn spawn_task(&mut self, spawn_in_terminal: &SpawnInTerminal, cx: &mut ViewContext<Self>) {
+        if !spawn_in_terminal.dependencies.is_empty() {
+            self.run_task_with_dependencies(
+                spawn_in_terminal.clone(),
+                DependencyRuns::default(),
+                cx,
+            )
+            .detach_and_log_err(cx);
+            return;
+        }
+        let spawn = self.spawn_task_in_terminal(spawn_in_terminal, cx);
+        if spawn_in_terminal.allow_concurrent_runs {
+            spawn.detach_and_log_err(cx);
+            return;
+        }
+        // A run waiting for the previous ones to finish is replaced when the task is spawned again.
+        self.deferred_tasks.insert(
+            spawn_in_terminal.id.clone(),
+            cx.spawn(|_, _| async move {
+                spawn.await.log_err();
+            }),
+        );
+    }
+
+    /// Runs the task's dependencies, then the task itself if all of them succeeded.
+    /// Resolves to whether the task and its dependencies succeeded.
+    fn run_task_with_dependencies(
+        &mut self,
+        mut spawn_in_terminal: SpawnInTerminal,
+        runs: DependencyRuns,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<bool>> {
+        let dependencies = mem::take(&mut spawn_in_terminal.dependencies);
+        let depends_order = spawn_in_terminal.depends_order;
+        cx.spawn(|terminal_panel, mut cx| async move {
+            let dependencies_succeeded = match depends_order {
+                DependsOrder::Sequence => {
+                    let mut succeeded = true;
+                    for dependency in dependencies {
+                        succeeded = terminal_panel
+                            .update(&mut cx, |terminal_panel, cx| {
+                                terminal_panel.run_dependency(dependency, &runs, cx)
+                            })?
+                            .await;
+                        if !succeeded {
+                            break;
+                        }
+                    }
+                    succeeded
+                }
+                DependsOrder::Parallel => {
+                    let dependency_runs =
+                        terminal_panel.update(&mut cx, |terminal_panel, cx| {
+                            dependencies
+                                .into_iter()
+                                .map(|dependency| {
+                                    terminal_panel.run_dependency(dependency, &runs, cx)
+                                })
+                                .collect::<Vec<_>>()
+                        })?;
+                    join_all(dependency_runs)
+                        .await
+                        .into_iter()
+                        .all(|succeeded| succeeded)
+                }
+            };
+            if !dependencies_succeeded {
+                log::info!(
+                    "Skipping task {:?} as a task it depends on failed",
+                    spawn_in_terminal.full_label
+                );
+                return Ok(false);
+            }
+            // Compound tasks only run their dependencies.
+            if spawn_in_terminal.command.is_empty() {
+                return Ok(true);
+            }
+
+            let terminal = terminal_panel
+                .update(&mut cx, |terminal_panel, cx| {
+                    terminal_panel.spawn_task_in_terminal(&spawn_in_terminal, cx)
+                })?
+                .await?;
+            terminal
+                .update(&mut cx, |terminal, cx| terminal.wait_for_completed_task(cx))?
+                .await;
+            terminal.update(&mut cx, |terminal, _| {
+                terminal.task().map_or(false, |task| {
+                    task.status == TaskStatus::Completed { success: true }
+                })
+            })
+        })
+    }
+
+    /// Runs a dependency once per spawned task, however many of the tasks it runs depend on it:
+    /// they all wait for the same run.
+    fn run_dependency(
+        &mut self,
+        dependency: SpawnInTerminal,
+        runs: &DependencyRuns,
+        cx: &mut ViewContext<Self>,
+    ) -> Shared<Task<bool>> {
+        if let Some(run) = runs.borrow().get(&dependency.id) {
+            return run.clone();
+        }
+        let id = dependency.id.clone();
+        let run = self.run_task_with_dependencies(dependency, runs.clone(), cx);
+        let run = cx
+            .spawn(|_, _| async move { run.await.log_err().unwrap_or(false) })
+            .shared();
+        runs.borrow_mut().insert(id, run.clone());
+        run
+    }
+
+    /// Spawns the task the way [`Self::spawn_task`] does, resolving to the terminal it runs in.
+    fn spawn_task_in_terminal(
+        &mut self,
+        spawn_in_terminal: &SpawnInTerminal,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<Model<Terminal>>> {
         let mut spawn_task = spawn_in_terminal.clone();
-        let Ok(is_local) = self
+        let is_local = match self
             .workspace
             .update(cx, |workspace, cx| workspace.project().read(cx).is_local())
-        else {
-            return;
+        {
+            Ok(is_local) => is_local,
+            Err(e) => return Task::ready(Err(e)),
         };
         if let ControlFlow::Break(_) =
             Self::fill_command(is_local, spawn_in_terminal, &mut spawn_task)
         {
-            return;
+            return Task::ready(Err(anyhow!("No shell found to run the task in")));
         }
-        let spawn_task = spawn_task;
 
         let allow_concurrent_runs = spawn_in_terminal.allow_concurrent_runs;
         let use_new_terminal = spawn_in_terminal.use_new_terminal;
-
-        if allow_concurrent_runs && use_new_terminal {
-            self.spawn_in_new_terminal(spawn_task, cx)
-                .detach_and_log_err(cx);
-            return;
-        }
-
         let terminals_for_task = self.terminals_for_task(&spawn_in_terminal.full_label, cx);
-        if terminals_for_task.is_empty() {
-            self.spawn_in_new_terminal(spawn_task, cx)
-                .detach_and_log_err(cx);
-            return;
+        let Some((existing_item_index, task_pane, existing_terminal)) =
+            terminals_for_task.last().cloned()
+        else {
+            return self.spawn_in_new_terminal(spawn_task, cx);
+        };
+        if allow_concurrent_runs && use_new_terminal {
+            return self.spawn_in_new_terminal(spawn_task, cx);
         }
-        let (existing_item_index, task_pane, existing_terminal) = terminals_for_task
-            .last()
-            .expect("covered no terminals case above")
-            .clone();
-        let id = spawn_in_terminal.id.clone();
-        cx.spawn(move |this, mut cx| async move {
-            if allow_concurrent_runs {
-                debug_assert!(
-                    !use_new_terminal,
-                    "Should have handled 'allow_concurrent_runs && use_new_terminal' case above"
-                );
-                this.update(&mut cx, |terminal_panel, cx| {
+        cx.spawn(move |terminal_panel, mut cx| async move {
+            if !allow_concurrent_runs {
+                wait_for_terminals_tasks(terminals_for_task, &mut cx).await;
+            }
+            if use_new_terminal {
+                return terminal_panel
+                    .update(&mut cx, |terminal_panel, cx| {
+                        terminal_panel.spawn_in_new_terminal(spawn_task, cx)
+                    })?
+                    .await;
+            }
+            terminal_panel
+                .update(&mut cx, |terminal_panel, cx| {
                     terminal_panel.replace_terminal(
                         spawn_task,
                         task_pane,
@@ -477,42 +601,9 @@ impl TerminalPanel {
                         cx,
                     )
                 })?
-                .await;
-            } else {
-                this.update(&mut cx, |this, cx| {
-                    this.deferred_tasks.insert(
-                        id,
-                        cx.spawn(|terminal_panel, mut cx| async move {
-                            wait_for_terminals_tasks(terminals_for_task, &mut cx).await;
-                            let Ok(Some(new_terminal_task)) =
-                                terminal_panel.update(&mut cx, |terminal_panel, cx| {
-                                    if use_new_terminal {
-                                        terminal_panel
-                                            .spawn_in_new_terminal(spawn_task, cx)
-                                            .detach_and_log_err(cx);
-                                        None
-                                    } else {
-                                        Some(terminal_panel.replace_terminal(
-                                            spawn_task,
-                                            task_pane,
-                                            existing_item_index,
-                                            existing_terminal,
-                                            cx,
-                                        ))
-                                    }
-                                })
-                            else {
-                                return;
-                            };
-                            new_terminal_task.await;
-                        }),
-                    );
-                })
-                .ok();
-            }
-            anyhow::Result::<_, anyhow::Error>::Ok(())
+                .await
+                .context("replacing the task terminal")
         })
-        .detach()
     }
 
     pub fn fill_command(
@@ -825,7 +916,7 @@ impl TerminalPanel {
         terminal_item_index: usize,
         terminal_to_replace: View<TerminalView>,
         cx: &mut ViewContext<'_, Self>,
-    ) -> Task<Option<()>> {
+    ) -> Task<Option<Model<Terminal>>> {
         let reveal = spawn_task.reveal;
         let reveal_target = spawn_task.reveal_target;
         let window = cx.window_handle();
@@ -848,7 +939,7 @@ impl TerminalPanel {
                 .log_err()?;
             terminal_to_replace
                 .update(&mut cx, |terminal_to_replace, cx| {
-                    terminal_to_replace.set_terminal(new_terminal, cx);
+                    terminal_to_replace.set_terminal(new_terminal.clone(), cx);
                 })
                 .ok()?;
 
@@ -918,7 +1009,7 @@ impl TerminalPanel {
                 RevealStrategy::Never => {}
             }
 
-            Some(())
+            Some(new_terminal)
         })
     }
 
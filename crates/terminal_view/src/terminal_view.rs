@@ -20,9 +20,10 @@ use terminal::{
         term::{search::RegexSearch, TermMode},
     },
     terminal_settings::{CursorShape, TerminalBlink, TerminalSettings, WorkingDirectory},
-    Clear, Copy, Event, MaybeNavigationTarget, Paste, ScrollLineDown, ScrollLineUp, ScrollPageDown,
-    ScrollPageUp, ScrollToBottom, ScrollToTop, ShowCharacterPalette, TaskStatus, Terminal,
-    TerminalSize, ToggleViMode,
+    Clear, Copy, CopyLastCommand, Event, MaybeNavigationTarget, Paste, ScrollLineDown,
+    ScrollLineUp, ScrollPageDown, ScrollPageUp, ScrollToBottom, ScrollToNextPrompt,
+    ScrollToPreviousPrompt, ScrollToTop, SelectLastCommandOutput, ShowCharacterPalette, TaskStatus,
+    Terminal, TerminalSize, ToggleViMode,
 };
 use terminal_element::{is_blank, TerminalElement};
 use terminal_panel::TerminalPanel;
@@ -59,6 +60,12 @@ const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(500);
 
 const GIT_DIFF_PATH_PREFIXES: &[char] = &['a', 'b'];
 
+/// How much output is kept for restoring a terminal after a restart.
+const MAX_SERIALIZED_SCROLLBACK_LINES: usize = 1_000;
+
+/// How often the scrollback of a terminal producing output is written to the database.
+const SERIALIZATION_THROTTLE: Duration = Duration::from_secs(5);
+
 ///Event to transmit the scroll from the element to the view
 #[derive(Clone, Debug, PartialEq)]
 pub struct ScrollTerminal(pub i32);
@@ -112,6 +119,7 @@ pub struct TerminalView {
     show_breadcrumbs: bool,
     block_below_cursor: Option<Rc<BlockProperties>>,
     scroll_top: Pixels,
+    serialize_task: Option<Task<()>>,
     _subscriptions: Vec<Subscription>,
     _terminal_subscriptions: Vec<Subscription>,
 }
@@ -158,6 +166,21 @@ impl TerminalView {
         let cursor_shape = TerminalSettings::get_global(cx)
             .cursor_shape
             .unwrap_or_default();
+        // Workspaces don't serialize their items when quitting, so save the latest output.
+        let app_quit = cx.on_app_quit({
+            let view = cx.view().downgrade();
+            move |cx| {
+                let save = view.upgrade().and_then(|view| {
+                    let item_id = view.entity_id().as_u64();
+                    view.read(cx).save_state(item_id, cx)
+                });
+                async move {
+                    if let Some(save) = save {
+                        save.await.log_err();
+                    }
+                }
+            }
+        });
 
         Self {
             terminal,
@@ -176,9 +199,11 @@ impl TerminalView {
             show_breadcrumbs: TerminalSettings::get_global(cx).toolbar.breadcrumbs,
             block_below_cursor: None,
             scroll_top: Pixels::ZERO,
+            serialize_task: None,
             _subscriptions: vec![
                 focus_in,
                 focus_out,
+                app_quit,
                 cx.observe_global::<SettingsStore>(Self::settings_changed),
             ],
             _terminal_subscriptions: terminal_subscriptions,
@@ -189,6 +214,47 @@ impl TerminalView {
         &self.terminal
     }
 
+    /// Saves the terminal's scrollback once the throttle interval has passed, so a terminal
+    /// producing a lot of output isn't written to the database on every wakeup.
+    fn schedule_serialization(&mut self, cx: &mut ViewContext<Self>) {
+        if self.serialize_task.is_some() || self.workspace_id.is_none() {
+            return;
+        }
+        self.serialize_task = Some(cx.spawn(|this, mut cx| async move {
+            cx.background_executor().timer(SERIALIZATION_THROTTLE).await;
+            let save = this
+                .update(&mut cx, |this, cx| {
+                    this.serialize_task.take();
+                    this.save_state(cx.entity_id().as_u64(), cx)
+                })
+                .ok()
+                .flatten();
+            if let Some(save) = save {
+                save.await.log_err();
+            }
+        }));
+    }
+
+    fn save_state(
+        &self,
+        item_id: workspace::ItemId,
+        cx: &AppContext,
+    ) -> Option<Task<gpui::Result<()>>> {
+        let workspace_id = self.workspace_id?;
+        let terminal = self.terminal().read(cx);
+        // Task terminals aren't restored, so there's nothing to save for them.
+        if terminal.task().is_some() {
+            return None;
+        }
+        let cwd = terminal.working_directory().unwrap_or_default();
+        let scrollback = terminal.scrollback_text(MAX_SERIALIZED_SCROLLBACK_LINES);
+        Some(cx.background_executor().spawn(async move {
+            TERMINAL_DB
+                .save_terminal(item_id, workspace_id, cwd, scrollback)
+                .await
+        }))
+    }
+
     pub fn has_bell(&self) -> bool {
         self.has_bell
     }
@@ -415,6 +481,37 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn scroll_to_previous_prompt(
+        &mut self,
+        _: &ScrollToPreviousPrompt,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_previous_prompt());
+        cx.notify();
+    }
+
+    fn scroll_to_next_prompt(&mut self, _: &ScrollToNextPrompt, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_next_prompt());
+        cx.notify();
+    }
+
+    fn select_last_command_output(
+        &mut self,
+        _: &SelectLastCommandOutput,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.select_last_command_output());
+        cx.notify();
+    }
+
+    fn copy_last_command(&mut self, _: &CopyLastCommand, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, cx| term.copy_last_command(cx));
+    }
+
     fn toggle_vi_mode(&mut self, _: &ToggleViMode, cx: &mut ViewContext<Self>) {
         self.terminal.update(cx, |term, _| term.toggle_vi_mode());
         cx.notify();
@@ -626,6 +723,7 @@ fn subscribe_for_terminal_events(
                 cx.emit(Event::Wakeup);
                 cx.emit(ItemEvent::UpdateTab);
                 cx.emit(SearchEvent::MatchesInvalidated);
+                this.schedule_serialization(cx);
             }
 
             Event::Bell => {
@@ -957,6 +1055,10 @@ impl Render for TerminalView {
             .on_action(cx.listener(TerminalView::scroll_page_down))
             .on_action(cx.listener(TerminalView::scroll_to_top))
             .on_action(cx.listener(TerminalView::scroll_to_bottom))
+            .on_action(cx.listener(TerminalView::scroll_to_previous_prompt))
+            .on_action(cx.listener(TerminalView::scroll_to_next_prompt))
+            .on_action(cx.listener(TerminalView::select_last_command_output))
+            .on_action(cx.listener(TerminalView::copy_last_command))
             .on_action(cx.listener(TerminalView::toggle_vi_mode))
             .on_action(cx.listener(TerminalView::show_character_palette))
             .on_action(cx.listener(TerminalView::select_all))
@@ -1093,6 +1195,7 @@ impl Item for TerminalView {
                 project.create_terminal_with_venv(
                     TerminalKind::Shell(working_directory),
                     python_venv_directory,
+                    None,
                     window,
                     cx,
                 )
@@ -1182,24 +1285,12 @@ impl SerializableItem for TerminalView {
         _closing: bool,
         cx: &mut ViewContext<Self>,
     ) -> Option<Task<gpui::Result<()>>> {
-        let terminal = self.terminal().read(cx);
-        if terminal.task().is_some() {
-            return None;
-        }
-
-        if let Some((cwd, workspace_id)) = terminal.working_directory().zip(self.workspace_id) {
-            Some(cx.background_executor().spawn(async move {
-                TERMINAL_DB
-                    .save_working_directory(item_id, workspace_id, cwd)
-                    .await
-            }))
-        } else {
-            None
-        }
+        self.save_state(item_id, cx)
     }
 
-    fn should_serialize(&self, event: &Self::Event) -> bool {
-        matches!(event, ItemEvent::UpdateTab)
+    fn should_serialize(&self, _: &Self::Event) -> bool {
+        // Output is saved by `schedule_serialization` instead of on every tab update.
+        false
     }
 
     fn deserialize(
@@ -1211,6 +1302,11 @@ impl SerializableItem for TerminalView {
     ) -> Task<anyhow::Result<View<Self>>> {
         let window = cx.window_handle();
         cx.spawn(|mut cx| async move {
+            let scrollback = TERMINAL_DB
+                .get_scrollback(item_id, workspace_id)
+                .log_err()
+                .flatten()
+                .filter(|scrollback| !scrollback.is_empty());
             let cwd = cx
                 .update(|cx| {
                     let from_db = TERMINAL_DB
@@ -1233,7 +1329,12 @@ impl SerializableItem for TerminalView {
 
             let terminal = project
                 .update(&mut cx, |project, cx| {
-                    project.create_terminal(TerminalKind::Shell(cwd), window, cx)
+                    project.create_restored_terminal(
+                        TerminalKind::Shell(cwd),
+                        scrollback,
+                        window,
+                        cx,
+                    )
                 })?
                 .await?;
             cx.update(|cx| {
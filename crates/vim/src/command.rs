@@ -1,31 +1,49 @@
 use std::{
     iter::Peekable,
     ops::{Deref, Range},
+    path::{Component, Path, PathBuf},
+    process::Stdio,
     str::Chars,
-    sync::OnceLock,
+    sync::{Arc, OnceLock},
+    time::Instant,
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashMap;
 use command_palette_hooks::CommandInterceptResult;
 use editor::{
     actions::{SortLinesCaseInsensitive, SortLinesCaseSensitive},
-    Editor, ToPoint,
+    display_map::ToDisplayPoint,
+    scroll::Autoscroll,
+    Anchor, Editor, ToPoint,
+};
+use gpui::{
+    actions, impl_actions, Action, AppContext, Global, Keystroke, Model, Modifiers, Task, View,
+    ViewContext,
 };
-use gpui::{actions, impl_actions, Action, AppContext, Global, ViewContext};
 use language::Point;
 use multi_buffer::MultiBufferRow;
+use project::{Fs as _, Project, ProjectPath, ResolvedPath, WorktreeId};
+use regex::Regex;
+use search::{BufferSearchBar, SearchOptions};
 use serde::Deserialize;
+use smol::io::AsyncWriteExt;
 use ui::WindowContext;
-use util::ResultExt;
-use workspace::{notifications::NotifyResultExt, SaveIntent};
+use util::{paths, ResultExt};
+use workspace::{
+    notifications::{NotificationId, NotifyResultExt, NotifyTaskExt},
+    SaveIntent, SplitDirection, Toast,
+};
 
 use crate::{
+    insert::NormalBefore,
     motion::{EndOfDocument, Motion, StartOfDocument},
     normal::{
+        repeat::Replayer,
         search::{FindCommand, ReplaceCommand, Replacement},
         JoinLines,
     },
-    state::Mode,
+    state::{Mode, ReplayableAction, VimGlobals},
     visual::VisualDeleteLine,
     Vim,
 };
@@ -53,11 +71,72 @@ pub struct WithCount {
     action: WrappedAction,
 }
 
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct OnMatchingLines {
+    range: CommandRange,
+    search: String,
+    action: WrappedAction,
+    invert: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NormalCommand {
+    range: Option<CommandRange>,
+    keys: String,
+    noremap: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ShellExec {
+    command: String,
+    range: Option<CommandRange>,
+    is_read: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ReadFile {
+    path: String,
+    range: Option<CommandRange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct VimSave {
+    save_intent: Option<SaveIntent>,
+    filename: String,
+    save_as: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct VimEdit {
+    filename: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct VimSplit {
+    vertical: bool,
+    filename: String,
+}
+
 #[derive(Debug)]
 struct WrappedAction(Box<dyn Action>);
 
-actions!(vim, [VisualCommand, CountCommand]);
-impl_actions!(vim, [GoToLine, YankCommand, WithRange, WithCount]);
+actions!(vim, [VisualCommand, CountCommand, NextNormalCommandLine]);
+impl_actions!(
+    vim,
+    [
+        GoToLine,
+        YankCommand,
+        WithRange,
+        WithCount,
+        OnMatchingLines,
+        NormalCommand,
+        ShellExec,
+        ReadFile,
+        VimSave,
+        VimEdit,
+        VimSplit
+    ]
+);
 
 impl<'de> Deserialize<'de> for WrappedAction {
     fn deserialize<D>(_: D) -> Result<Self, D::Error>
@@ -204,6 +283,67 @@ pub fn register(editor: &mut Editor, cx: &mut ViewContext<Vim>) {
             });
         });
     });
+
+    Vim::action(editor, cx, |vim, action: &OnMatchingLines, cx| {
+        action.run(vim, cx)
+    });
+
+    Vim::action(editor, cx, |vim, action: &NormalCommand, cx| {
+        action.run(vim, cx)
+    });
+
+    Vim::action(editor, cx, |vim, _: &NextNormalCommandLine, cx| {
+        vim.next_normal_command_line(cx)
+    });
+
+    Vim::action(editor, cx, |vim, action: &ShellExec, cx| {
+        action.run(vim, cx)
+    });
+
+    Vim::action(editor, cx, |vim, action: &ReadFile, cx| action.run(vim, cx));
+
+    Vim::action(editor, cx, |vim, action: &VimSave, cx| {
+        vim.save_to_path(action, cx)
+    });
+
+    Vim::action(editor, cx, |vim, action: &VimEdit, cx| {
+        let Some(workspace) = vim.workspace(cx) else {
+            return;
+        };
+        let project_path = vim.resolve_project_path(&action.filename, cx);
+        cx.spawn(|_, mut cx| async move {
+            let project_path = project_path.await?;
+            workspace
+                .update(&mut cx, |workspace, cx| {
+                    workspace.open_path(project_path, None, true, cx)
+                })?
+                .await?;
+            anyhow::Ok(())
+        })
+        .detach_and_notify_err(cx);
+    });
+
+    Vim::action(editor, cx, |vim, action: &VimSplit, cx| {
+        let Some(workspace) = vim.workspace(cx) else {
+            return;
+        };
+        let direction = if action.vertical {
+            SplitDirection::Right
+        } else {
+            SplitDirection::Down
+        };
+        let project_path = vim.resolve_project_path(&action.filename, cx);
+        cx.spawn(|_, mut cx| async move {
+            let project_path = project_path.await?;
+            workspace
+                .update(&mut cx, |workspace, cx| {
+                    workspace.split_path_preview(project_path, false, Some(direction), cx)
+                })?
+                .await?;
+            anyhow::Ok(())
+        })
+        .detach_and_notify_err(cx);
+    });
 }
 
 #[derive(Default)]
@@ -221,6 +361,9 @@ struct VimCommand {
                 + 'static,
         >,
     >,
+    args: Option<
+        Box<dyn Fn(Box<dyn Action>, String) -> Option<Box<dyn Action>> + Send + Sync + 'static>,
+    >,
     has_count: bool,
 }
 
@@ -262,12 +405,33 @@ impl VimCommand {
         self
     }
 
+    // args are file paths, relative to the current worktree, and are completed as such.
+    fn args(
+        mut self,
+        f: impl Fn(Box<dyn Action>, String) -> Option<Box<dyn Action>> + Send + Sync + 'static,
+    ) -> Self {
+        self.args = Some(Box::new(f));
+        self
+    }
+
+    /// Splits "w foo.txt" into the command and its argument, for commands that take one.
+    fn split_args<'a>(&self, query: &'a str) -> (&'a str, Option<&'a str>) {
+        if self.args.is_none() {
+            return (query, None);
+        }
+        match query.split_once(char::is_whitespace) {
+            Some((command, args)) => (command, Some(args.trim())),
+            None => (query, None),
+        }
+    }
+
     fn parse(
         &self,
-        mut query: &str,
+        query: &str,
         range: &Option<CommandRange>,
         cx: &AppContext,
     ) -> Option<Box<dyn Action>> {
+        let (mut query, args) = self.split_args(query);
         let has_bang = query.ends_with('!');
         if has_bang {
             query = &query[..query.len() - 1];
@@ -288,6 +452,11 @@ impl VimCommand {
             return None;
         };
 
+        let action = match (args, self.args.as_ref()) {
+            (Some(args), Some(f)) if !args.is_empty() => f(action, args.to_string())?,
+            _ => action,
+        };
+
         if let Some(range) = range {
             self.range.as_ref().and_then(|f| f(action, range))
         } else {
@@ -302,13 +471,7 @@ impl VimCommand {
         match chars.peek() {
             Some('%') => {
                 chars.next();
-                return (
-                    Some(CommandRange {
-                        start: Position::Line { row: 1, offset: 0 },
-                        end: Some(Position::LastLine { offset: 0 }),
-                    }),
-                    chars.collect(),
-                );
+                return (Some(CommandRange::whole_file()), chars.collect());
             }
             Some('*') => {
                 chars.next();
@@ -461,6 +624,20 @@ pub(crate) struct CommandRange {
 }
 
 impl CommandRange {
+    pub(crate) fn current_line() -> Self {
+        CommandRange {
+            start: Position::CurrentLine { offset: 0 },
+            end: None,
+        }
+    }
+
+    fn whole_file() -> Self {
+        CommandRange {
+            start: Position::Line { row: 1, offset: 0 },
+            end: Some(Position::LastLine { offset: 0 }),
+        }
+    }
+
     fn head(&self) -> &Position {
         self.end.as_ref().unwrap_or(&self.start)
     }
@@ -507,7 +684,37 @@ fn generate_commands(_: &AppContext) -> Vec<VimCommand> {
         )
         .bang(workspace::Save {
             save_intent: Some(SaveIntent::Overwrite),
+        })
+        .args(|action, filename| {
+            Some(
+                VimSave {
+                    save_intent: action
+                        .as_any()
+                        .downcast_ref::<workspace::Save>()
+                        .and_then(|action| action.save_intent),
+                    filename,
+                    save_as: false,
+                }
+                .boxed_clone(),
+            )
         }),
+        VimCommand::new(("sav", "eas"), workspace::SaveAs)
+            .bang(workspace::Save {
+                save_intent: Some(SaveIntent::Overwrite),
+            })
+            .args(|action, filename| {
+                Some(
+                    VimSave {
+                        save_intent: action
+                            .as_any()
+                            .downcast_ref::<workspace::Save>()
+                            .and_then(|action| action.save_intent),
+                        filename,
+                        save_as: true,
+                    }
+                    .boxed_clone(),
+                )
+            }),
         VimCommand::new(
             ("q", "uit"),
             workspace::CloseActiveItem {
@@ -596,8 +803,24 @@ fn generate_commands(_: &AppContext) -> Vec<VimCommand> {
             save_intent: Some(SaveIntent::Overwrite),
         }),
         VimCommand::new(("cq", "uit"), zed_actions::Quit),
-        VimCommand::new(("sp", "lit"), workspace::SplitHorizontal),
-        VimCommand::new(("vs", "plit"), workspace::SplitVertical),
+        VimCommand::new(("sp", "lit"), workspace::SplitHorizontal).args(|_, filename| {
+            Some(
+                VimSplit {
+                    vertical: false,
+                    filename,
+                }
+                .boxed_clone(),
+            )
+        }),
+        VimCommand::new(("vs", "plit"), workspace::SplitVertical).args(|_, filename| {
+            Some(
+                VimSplit {
+                    vertical: true,
+                    filename,
+                }
+                .boxed_clone(),
+            )
+        }),
         VimCommand::new(
             ("bd", "elete"),
             workspace::CloseActiveItem {
@@ -691,7 +914,8 @@ fn generate_commands(_: &AppContext) -> Vec<VimCommand> {
         VimCommand::new(("%", ""), EndOfDocument),
         VimCommand::new(("0", ""), StartOfDocument),
         VimCommand::new(("e", "dit"), editor::actions::ReloadFile)
-            .bang(editor::actions::ReloadFile),
+            .bang(editor::actions::ReloadFile)
+            .args(|_, filename| Some(VimEdit { filename }.boxed_clone())),
         VimCommand::new(("cpp", "link"), editor::actions::CopyPermalinkToLine).range(act_on_range),
     ]
 }
@@ -741,16 +965,810 @@ fn wrap_count(action: Box<dyn Action>, range: &CommandRange) -> Option<Box<dyn A
     })
 }
 
-pub fn command_interceptor(mut input: &str, cx: &AppContext) -> Option<CommandInterceptResult> {
-    // NOTE: We also need to support passing arguments to commands like :w
-    // (ideally with filename autocompletion).
+impl OnMatchingLines {
+    // parses :g/pattern/command, :g!/pattern/command and :v/pattern/command.
+    // as with :s, the pattern has \( and \) flipped so that common idioms work.
+    fn parse(query: &str, range: &Option<CommandRange>, cx: &AppContext) -> Option<Self> {
+        let mut chars = query.chars().peekable();
+        let mut invert = chars.peek() == Some(&'v');
+        let mut name = if invert { "vglobal" } else { "global" }.chars().peekable();
+        while name.peek().is_some_and(|char| Some(char) == chars.peek()) {
+            name.next();
+            chars.next();
+        }
+        if !invert && chars.peek() == Some(&'!') {
+            chars.next();
+            invert = true;
+        }
+
+        let delimiter = chars
+            .next()
+            .filter(|c| !c.is_alphanumeric() && *c != '"' && *c != '|' && *c != '\'')?;
+
+        let mut search = String::new();
+        let mut escaped = false;
+        for c in chars.by_ref() {
+            if escaped {
+                escaped = false;
+                if c != '(' && c != ')' && c != delimiter {
+                    search.push('\\')
+                }
+                search.push(c)
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == delimiter {
+                break;
+            } else {
+                if c == '(' || c == ')' {
+                    search.push('\\')
+                }
+                search.push(c)
+            }
+        }
+        if search.is_empty() {
+            return None;
+        }
+
+        let command: String = chars.collect();
+        let action = command_interceptor(command.trim_start(), cx)
+            .into_iter()
+            .next()?
+            .action;
+        // vim does not allow :g to be nested.
+        if action.as_any().downcast_ref::<OnMatchingLines>().is_some() {
+            return None;
+        }
+
+        Some(Self {
+            range: range.clone().unwrap_or(CommandRange::whole_file()),
+            search,
+            action: WrappedAction(action),
+            invert,
+        })
+    }
+
+    // Places a cursor at the start of each line in the range that matches (or doesn't match)
+    // the pattern, then runs the command once over all of them so that it is a single edit.
+    fn run(&self, vim: &mut Vim, cx: &mut ViewContext<Vim>) {
+        let result = vim.update_editor(cx, |vim, editor, cx| {
+            let range = self.range.buffer_range(vim, editor, cx)?;
+            let regex = Regex::new(&self.search)?;
+            let snapshot = editor.snapshot(cx).buffer_snapshot;
+
+            let mut rows = Vec::new();
+            let mut line = String::new();
+            for row in range.start.0..=range.end.0 {
+                let end = Point::new(row, snapshot.line_len(MultiBufferRow(row)));
+                line.clear();
+                line.extend(snapshot.text_for_range(Point::new(row, 0)..end));
+                if regex.is_match(&line) != self.invert {
+                    rows.push(row);
+                }
+            }
+            if rows.is_empty() {
+                if self.invert {
+                    return Err(anyhow!("Pattern found in every line: {}", self.search));
+                }
+                return Err(anyhow!("Pattern not found: {}", self.search));
+            }
+            anyhow::Ok(rows)
+        });
+
+        let rows = match result {
+            None => return,
+            Some(e @ Err(_)) => {
+                let Some(workspace) = vim.workspace(cx) else {
+                    return;
+                };
+                workspace.update(cx, |workspace, cx| {
+                    e.notify_err(workspace, cx);
+                });
+                return;
+            }
+            Some(Ok(result)) => result,
+        };
+
+        vim.switch_mode(Mode::Normal, false, cx);
+
+        // like vim, the pattern becomes the last search so that :g/pat/s//x/ works.
+        if let Some(pane) = vim.pane(cx) {
+            pane.update(cx, |pane, cx| {
+                if let Some(search_bar) = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>()
+                {
+                    search_bar.update(cx, |search_bar, cx| {
+                        if search_bar.show(cx) {
+                            let _ = search_bar.search(
+                                &self.search,
+                                Some(SearchOptions::REGEX | SearchOptions::CASE_SENSITIVE),
+                                cx,
+                            );
+                        }
+                    });
+                }
+            });
+        }
+
+        let action = self.action.boxed_clone();
+        vim.update_editor(cx, |_, editor, cx| {
+            editor.start_transaction_at(Instant::now(), cx);
+            editor.change_selections(None, cx, |s| {
+                s.replace_cursors_with(|map| {
+                    rows.iter()
+                        .map(|row| Point::new(*row, 0).to_display_point(map))
+                        .collect()
+                });
+            });
+            cx.dispatch_action(action);
+            cx.defer(|editor, cx| {
+                let newest = editor.selections.newest::<Point>(cx);
+                editor.change_selections(None, cx, |s| s.select(vec![newest]));
+                editor.end_transaction_at(Instant::now(), cx);
+            });
+        });
+    }
+}
+
+impl NormalCommand {
+    // parses :norm[al][!] {keys}. Everything after the command name (and the
+    // separating whitespace) is typed literally, including trailing spaces.
+    fn parse(query: &str, range: &Option<CommandRange>) -> Option<Self> {
+        let rest = ["normal", "norma", "norm"]
+            .iter()
+            .find_map(|name| query.strip_prefix(name))?;
+        let (noremap, rest) = match rest.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None if rest.starts_with(char::is_alphanumeric) => return None,
+            None => (false, rest),
+        };
+        let keys = rest.trim_start();
+        if keys.is_empty() {
+            return None;
+        }
+        Some(Self {
+            range: range.clone(),
+            keys: keys.to_string(),
+            noremap,
+        })
+    }
+
+    // Types the keys once for each line in the range (or at each cursor if there is no
+    // range). Between lines any unfinished command is aborted, as if <Esc> was typed.
+    fn run(&self, vim: &mut Vim, cx: &mut ViewContext<Vim>) {
+        let result = vim.update_editor(cx, |vim, editor, cx| {
+            let points = if let Some(range) = &self.range {
+                let range = range.buffer_range(vim, editor, cx)?;
+                (range.start.0..=range.end.0)
+                    .map(|row| Point::new(row, 0))
+                    .collect::<Vec<_>>()
+            } else {
+                editor
+                    .selections
+                    .all::<Point>(cx)
+                    .into_iter()
+                    .map(|selection| selection.head())
+                    .collect()
+            };
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            anyhow::Ok(
+                points
+                    .into_iter()
+                    .rev()
+                    .map(|point| snapshot.anchor_before(point))
+                    .collect::<Vec<_>>(),
+            )
+        });
+
+        let lines = match result {
+            None => return,
+            Some(e @ Err(_)) => {
+                let Some(workspace) = vim.workspace(cx) else {
+                    return;
+                };
+                workspace.update(cx, |workspace, cx| {
+                    e.notify_err(workspace, cx);
+                });
+                return;
+            }
+            Some(Ok(result)) => result,
+        };
+
+        vim.switch_mode(Mode::Normal, false, cx);
+
+        let keystrokes = self
+            .keys
+            .chars()
+            .map(keystroke_for_char)
+            .collect::<Vec<_>>();
+        let mut actions = Vec::new();
+        for _ in 0..lines.len() {
+            actions.push(ReplayableAction::Action(
+                NextNormalCommandLine.boxed_clone(),
+            ));
+            actions.extend(
+                keystrokes
+                    .iter()
+                    .map(|keystroke| ReplayableAction::Keystroke {
+                        keystroke: keystroke.clone(),
+                        noremap: self.noremap,
+                    }),
+            );
+        }
+        actions.push(ReplayableAction::Action(
+            NextNormalCommandLine.boxed_clone(),
+        ));
+
+        vim.normal_command_lines = Some(lines);
+        vim.normal_command_tx = None;
+        let mut replayer = Vim::globals(cx)
+            .replayer
+            .get_or_insert_with(Replayer::new)
+            .clone();
+        replayer.replay(actions, cx);
+    }
+}
+
+fn keystroke_for_char(char: char) -> Keystroke {
+    let keystroke = match char {
+        ' ' => Keystroke {
+            modifiers: Modifiers::default(),
+            key: "space".into(),
+            key_char: None,
+        },
+        '\t' => Keystroke {
+            modifiers: Modifiers::default(),
+            key: "tab".into(),
+            key_char: None,
+        },
+        char if char.is_ascii_uppercase() => Keystroke {
+            modifiers: Modifiers::shift(),
+            key: char.to_ascii_lowercase().to_string(),
+            key_char: None,
+        },
+        char => Keystroke {
+            modifiers: Modifiers::default(),
+            key: char.to_string(),
+            key_char: None,
+        },
+    };
+    keystroke.with_simulated_ime()
+}
+
+impl Vim {
+    fn next_normal_command_line(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(lines) = self.normal_command_lines.as_mut() else {
+            return;
+        };
+        let next = lines.pop();
+
+        if self.active_operator().is_some() {
+            self.clear_operator(cx);
+        }
+        match self.mode {
+            Mode::Insert | Mode::Replace => self.normal_before(&NormalBefore, cx),
+            Mode::Normal => {}
+            _ => self.switch_mode(Mode::Normal, false, cx),
+        }
+
+        let Some(anchor) = next else {
+            self.normal_command_lines = None;
+            if let Some(transaction_id) = self.normal_command_tx.take() {
+                self.update_editor(cx, |_, editor, cx| {
+                    editor.group_until_transaction(transaction_id, cx)
+                });
+            }
+            return;
+        };
+        self.update_editor(cx, |_, editor, cx| {
+            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_anchor_ranges([anchor..anchor])
+            });
+        });
+    }
+}
+
+/// Where the output of a shell command or file read goes.
+enum CommandOutput {
+    Notify,
+    Replace(Range<Anchor>),
+    InsertBelow(Anchor),
+}
+
+impl ShellExec {
+    // parses :r[ead] !cmd and :r[ead] file. The latter becomes a ReadFile.
+    fn parse_read(query: &str, range: &Option<CommandRange>) -> Option<Box<dyn Action>> {
+        let rest = ["read", "rea", "re", "r"]
+            .iter()
+            .find_map(|name| query.strip_prefix(name))?;
+        if rest.starts_with(char::is_alphanumeric) {
+            return None;
+        }
+        let rest = rest.trim();
+        if let Some(command) = rest.strip_prefix('!') {
+            let command = command.trim();
+            if command.is_empty() {
+                return None;
+            }
+            return Some(
+                ShellExec {
+                    command: command.to_string(),
+                    range: range.clone(),
+                    is_read: true,
+                }
+                .boxed_clone(),
+            );
+        }
+        if rest.is_empty() {
+            return None;
+        }
+        Some(
+            ReadFile {
+                path: rest.to_string(),
+                range: range.clone(),
+            }
+            .boxed_clone(),
+        )
+    }
+
+    // With a range the lines are piped through the command and replaced by its output,
+    // with :r the output is inserted below the cursor, otherwise it is just shown.
+    fn run(&self, vim: &mut Vim, cx: &mut ViewContext<Vim>) {
+        let Some(workspace) = vim.workspace(cx) else {
+            return;
+        };
+        let project = workspace.read(cx).project().clone();
+        if !project.read(cx).is_local() {
+            workspace.update(cx, |workspace, cx| {
+                workspace.show_error(
+                    &anyhow!("Shell commands are not supported in remote projects"),
+                    cx,
+                )
+            });
+            return;
+        }
+
+        vim.switch_mode(Mode::Normal, false, cx);
+        let result = vim.update_editor(cx, |vim, editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let range = match &self.range {
+                Some(range) => Some(range.buffer_range(vim, editor, cx)?),
+                None => None,
+            };
+            if self.is_read {
+                let row = match range {
+                    Some(range) => range.end,
+                    None => MultiBufferRow(editor.selections.newest::<Point>(cx).head().row),
+                };
+                let end = Point::new(row.0, snapshot.line_len(row));
+                return anyhow::Ok((None, CommandOutput::InsertBelow(snapshot.anchor_after(end))));
+            }
+            let Some(range) = range else {
+                return anyhow::Ok((None, CommandOutput::Notify));
+            };
+            let range =
+                Point::new(range.start.0, 0)..Point::new(range.end.0, snapshot.line_len(range.end));
+            let mut input = snapshot.text_for_range(range.clone()).collect::<String>();
+            input.push('\n');
+            let range = snapshot.anchor_before(range.start)..snapshot.anchor_after(range.end);
+            anyhow::Ok((Some(input), CommandOutput::Replace(range)))
+        });
+        let (input, output) = match result {
+            None => return,
+            Some(Err(err)) => {
+                workspace.update(cx, |workspace, cx| workspace.show_error(&err, cx));
+                return;
+            }
+            Some(Ok(result)) => result,
+        };
+
+        let directory = project_directory(vim.editor().as_ref(), &project, cx);
+        let environment = project.read(cx).environment().clone();
+        let environment = environment.update(cx, |environment, cx| {
+            environment.get_environment(
+                directory.as_ref().map(|(worktree_id, _)| *worktree_id),
+                directory.as_ref().map(|(_, path)| path.clone()),
+                cx,
+            )
+        });
+        let command = self.command.clone();
+        vim.pending_command = Some(cx.spawn(|vim, mut cx| async move {
+            let environment = environment.await.unwrap_or_default();
+            let cwd = directory.map(|(_, path)| path);
+            let result = cx
+                .background_executor()
+                .spawn(run_shell_command(command, cwd, environment, input))
+                .await;
+            vim.update(&mut cx, |vim, cx| {
+                vim.insert_command_output(result, output, cx)
+            })
+            .log_err();
+        }));
+    }
+}
+
+impl ReadFile {
+    fn run(&self, vim: &mut Vim, cx: &mut ViewContext<Vim>) {
+        let Some(workspace) = vim.workspace(cx) else {
+            return;
+        };
+        let project = workspace.read(cx).project().clone();
+        if !project.read(cx).is_local() {
+            workspace.update(cx, |workspace, cx| {
+                workspace.show_error(
+                    &anyhow!("Reading files is not supported in remote projects"),
+                    cx,
+                )
+            });
+            return;
+        }
+
+        vim.switch_mode(Mode::Normal, false, cx);
+        let result = vim.update_editor(cx, |vim, editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let row = match &self.range {
+                Some(range) => range.buffer_range(vim, editor, cx)?.end,
+                None => MultiBufferRow(editor.selections.newest::<Point>(cx).head().row),
+            };
+            let end = Point::new(row.0, snapshot.line_len(row));
+            anyhow::Ok(snapshot.anchor_after(end))
+        });
+        let anchor = match result {
+            None => return,
+            Some(Err(err)) => {
+                workspace.update(cx, |workspace, cx| workspace.show_error(&err, cx));
+                return;
+            }
+            Some(Ok(anchor)) => anchor,
+        };
+
+        let path = if let Some(path) = self.path.strip_prefix("~/") {
+            paths::home_dir().join(path)
+        } else {
+            match project_directory(vim.editor().as_ref(), &project, cx) {
+                Some((_, directory)) => directory.join(&self.path),
+                None => PathBuf::from(&self.path),
+            }
+        };
+        let fs = project.read(cx).fs().clone();
+        vim.pending_command = Some(cx.spawn(|vim, mut cx| async move {
+            let result = fs
+                .load(&path)
+                .await
+                .with_context(|| format!("Can't open file {}", path.display()))
+                .map(|text| (text, String::new()));
+            vim.update(&mut cx, |vim, cx| {
+                vim.insert_command_output(result, CommandOutput::InsertBelow(anchor), cx)
+            })
+            .log_err();
+        }));
+    }
+}
+
+/// The worktree containing the current file (or the first worktree), which commands run in.
+fn project_directory(
+    editor: Option<&View<Editor>>,
+    project: &Model<Project>,
+    cx: &AppContext,
+) -> Option<(WorktreeId, Arc<Path>)> {
+    let worktree_id = editor.and_then(|editor| {
+        let buffer = editor.read(cx).buffer().read(cx).as_singleton()?;
+        let file = buffer.read(cx).file()?;
+        Some(file.worktree_id(cx))
+    });
+    let project = project.read(cx);
+    let worktree = worktree_id
+        .and_then(|worktree_id| project.worktree_for_id(worktree_id, cx))
+        .or_else(|| project.visible_worktrees(cx).next())?;
+    let worktree = worktree.read(cx);
+    Some((worktree.id(), worktree.abs_path()))
+}
+
+impl Vim {
+    /// Resolves a path given to a command relative to the current worktree. Paths outside
+    /// of the project are added as invisible worktrees, as when opening them from the CLI.
+    /// `~/` is expanded against the home directory of the host the project lives on.
+    pub(crate) fn resolve_project_path(
+        &self,
+        filename: &str,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<ProjectPath>> {
+        let Some(workspace) = self.workspace(cx) else {
+            return Task::ready(Err(anyhow!("No workspace")));
+        };
+        let project = workspace.read(cx).project().clone();
+        let editor = self.editor();
+        let filename = filename.to_string();
+        let remote_home = if filename.starts_with("~/") && !project.read(cx).is_local() {
+            Some(project.update(cx, |project, cx| project.resolve_abs_path("~", cx)))
+        } else {
+            None
+        };
+
+        cx.spawn(|_, mut cx| async move {
+            let mut path = match (filename.strip_prefix("~/"), remote_home) {
+                (Some(path), Some(remote_home)) => {
+                    let Some(ResolvedPath::AbsPath { path: home, .. }) = remote_home.await else {
+                        return Err(anyhow!(
+                            "Cannot expand ~ in {filename}: the remote home directory is unknown"
+                        ));
+                    };
+                    home.join(path)
+                }
+                (Some(path), None) => paths::home_dir().join(path),
+                (None, _) => PathBuf::from(&filename),
+            };
+            if path.is_relative() {
+                let directory = cx.update(|cx| project_directory(editor.as_ref(), &project, cx))?;
+                let Some((_, directory)) = directory else {
+                    return Err(anyhow!("No worktree to open {filename} in"));
+                };
+                path = directory.join(path);
+            }
+
+            let mut normalized = PathBuf::new();
+            for component in path.components() {
+                match component {
+                    Component::CurDir => {}
+                    Component::ParentDir => {
+                        normalized.pop();
+                    }
+                    component => normalized.push(component),
+                }
+            }
+
+            let existing = project.read_with(&cx, |project, cx| {
+                project
+                    .find_worktree(&normalized, cx)
+                    .map(|(worktree, relative_path)| ProjectPath {
+                        worktree_id: worktree.read(cx).id(),
+                        path: relative_path.into(),
+                    })
+            })?;
+            if let Some(project_path) = existing {
+                return Ok(project_path);
+            }
+            let (worktree, relative_path) = project
+                .update(&mut cx, |project, cx| {
+                    project.find_or_create_worktree(&normalized, false, cx)
+                })?
+                .await?;
+            let worktree_id = worktree.read_with(&cx, |worktree, _| worktree.id())?;
+            Ok(ProjectPath {
+                worktree_id,
+                path: relative_path.into(),
+            })
+        })
+    }
+
+    // :w {file} writes a copy of the buffer, :sav {file} also renames it.
+    fn save_to_path(&mut self, action: &VimSave, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace(cx) else {
+            return;
+        };
+        let project = workspace.read(cx).project().clone();
+        let Some(buffer) = self
+            .editor()
+            .and_then(|editor| editor.read(cx).buffer().read(cx).as_singleton())
+        else {
+            return;
+        };
+        let overwrite = action.save_intent == Some(SaveIntent::Overwrite);
+        let save_as = action.save_as;
+        let project_path = self.resolve_project_path(&action.filename, cx);
+        cx.spawn(|_, mut cx| async move {
+            let project_path = project_path.await?;
+            let is_current_file = buffer.read_with(&cx, |buffer, cx| {
+                buffer.file().is_some_and(|file| {
+                    file.worktree_id(cx) == project_path.worktree_id
+                        && file.path() == &project_path.path
+                })
+            })?;
+            let exists = project.read_with(&cx, |project, cx| {
+                project.entry_for_path(&project_path, cx).is_some()
+            })?;
+            if exists && !is_current_file && !overwrite {
+                return Err(anyhow!(
+                    "{} already exists (add ! to override)",
+                    project_path.path.display()
+                ));
+            }
+
+            if save_as {
+                project
+                    .update(&mut cx, |project, cx| {
+                        project.save_buffer_as(buffer, project_path, cx)
+                    })?
+                    .await
+            } else {
+                let target = project
+                    .update(&mut cx, |project, cx| project.open_buffer(project_path, cx))?
+                    .await?;
+                let text = buffer.read_with(&cx, |buffer, _| buffer.text())?;
+                target.update(&mut cx, |target, cx| target.set_text(text, cx))?;
+                project
+                    .update(&mut cx, |project, cx| project.save_buffer(target, cx))?
+                    .await
+            }
+        })
+        .detach_and_notify_err(cx);
+    }
+}
+
+/// Completes the last component of a path relative to the current worktree.
+fn complete_path(partial: &str, cx: &AppContext) -> Vec<String> {
+    let Some(editor) = cx
+        .try_global::<VimGlobals>()
+        .and_then(|globals| globals.focused_vim())
+        .and_then(|vim| vim.read(cx).editor())
+    else {
+        return Vec::new();
+    };
+    let Some(workspace) = editor.read(cx).workspace() else {
+        return Vec::new();
+    };
+    let project = workspace.read(cx).project();
+    let Some(worktree) = project_directory(Some(&editor), project, cx)
+        .and_then(|(worktree_id, _)| project.read(cx).worktree_for_id(worktree_id, cx))
+    else {
+        return Vec::new();
+    };
+
+    let (directory, prefix) = match partial.rfind('/') {
+        Some(ix) => partial.split_at(ix + 1),
+        None => ("", partial),
+    };
+    worktree
+        .read(cx)
+        .child_entries(Path::new(directory.trim_end_matches('/')))
+        .filter_map(|entry| {
+            let name = entry.path.file_name()?.to_str()?;
+            // like vim, hidden files are only completed when asked for.
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let separator = if entry.is_dir() { "/" } else { "" };
+            Some(format!("{directory}{name}{separator}"))
+        })
+        .collect()
+}
+
+/// Runs the command in the user's shell, returning its stdout and stderr.
+async fn run_shell_command(
+    command: String,
+    cwd: Option<Arc<Path>>,
+    environment: HashMap<String, String>,
+    input: Option<String>,
+) -> Result<(String, String)> {
+    #[cfg(target_os = "windows")]
+    let mut process = {
+        let mut process = util::command::new_smol_command("cmd");
+        process.arg("/C").arg(&command);
+        process
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut process = {
+        let shell = environment
+            .get("SHELL")
+            .cloned()
+            .unwrap_or_else(|| "/bin/sh".to_string());
+        let mut process = util::command::new_smol_command(shell);
+        process.arg("-c").arg(&command);
+        process
+    };
+    if let Some(cwd) = cwd {
+        process.current_dir(cwd);
+    }
+    let mut child = process
+        .envs(environment)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {command}"))?;
+
+    let stdin = child.stdin.take();
+    let write_input = async move {
+        if let Some((mut stdin, input)) = stdin.zip(input) {
+            stdin.write_all(input.as_bytes()).await?;
+        }
+        anyhow::Ok(())
+    };
+    let (write_result, output) = smol::future::zip(write_input, child.output()).await;
+    let output = output?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    if !output.status.success() {
+        if stderr.trim().is_empty() {
+            return Err(anyhow!("{command} failed with {}", output.status));
+        }
+        return Err(anyhow!("{command} failed: {}", stderr.trim()));
+    }
+    // a command that doesn't read its input (e.g. `:%!date`) may close stdin early.
+    write_result.log_err();
+    Ok((stdout, stderr))
+}
+
+impl Vim {
+    fn insert_command_output(
+        &mut self,
+        result: Result<(String, String)>,
+        output: CommandOutput,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(workspace) = self.workspace(cx) else {
+            return;
+        };
+        let (text, errors) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                workspace.update(cx, |workspace, cx| workspace.show_error(&err, cx));
+                return;
+            }
+        };
+        if !errors.trim().is_empty() {
+            workspace.update(cx, |workspace, cx| {
+                workspace.show_error(&anyhow!("{}", errors.trim()), cx)
+            });
+        }
+        let text = text.strip_suffix('\n').unwrap_or(&text);
+
+        match output {
+            CommandOutput::Notify => {
+                if text.is_empty() {
+                    return;
+                }
+                struct ShellCommandOutput;
+                workspace.update(cx, |workspace, cx| {
+                    workspace.show_toast(
+                        Toast::new(
+                            NotificationId::unique::<ShellCommandOutput>(),
+                            text.to_string(),
+                        ),
+                        cx,
+                    )
+                });
+            }
+            CommandOutput::Replace(range) => {
+                self.update_editor(cx, |_, editor, cx| {
+                    editor.transact(cx, |editor, cx| {
+                        editor.edit([(range.clone(), text)], cx);
+                        editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                            s.select_anchor_ranges([range.start..range.start])
+                        });
+                    });
+                });
+            }
+            CommandOutput::InsertBelow(anchor) => {
+                if text.is_empty() {
+                    return;
+                }
+                self.update_editor(cx, |_, editor, cx| {
+                    editor.transact(cx, |editor, cx| {
+                        let snapshot = editor.buffer().read(cx).snapshot(cx);
+                        let point = anchor.to_point(&snapshot);
+                        editor.edit([(point..point, format!("\n{text}"))], cx);
+                        let start = Point::new(point.row + 1, 0);
+                        editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                            s.select_ranges([start..start])
+                        });
+                    });
+                });
+            }
+        }
+    }
+}
+
+pub fn command_interceptor(mut input: &str, cx: &AppContext) -> Vec<CommandInterceptResult> {
     while input.starts_with(':') {
         input = &input[1..];
     }
 
-    let (range, query) = VimCommand::parse_range(input);
-    let range_prefix = input[0..(input.len() - query.len())].to_string();
-    let query = query.as_str().trim();
+    let (range, raw_query) = VimCommand::parse_range(input);
+    let range_prefix = input[0..(input.len() - raw_query.len())].to_string();
+    let query = raw_query.as_str().trim();
 
     let action = if range.is_some() && query.is_empty() {
         Some(
@@ -767,6 +1785,22 @@ pub fn command_interceptor(mut input: &str, cx: &AppContext) -> Option<CommandIn
             }
             .boxed_clone(),
         )
+    } else if query.starts_with('g') || query.starts_with('v') {
+        OnMatchingLines::parse(query, &range, cx).map(|action| action.boxed_clone())
+    } else if query.starts_with("norm") {
+        NormalCommand::parse(raw_query.trim_start(), &range).map(|action| action.boxed_clone())
+    } else if let Some(command) = query.strip_prefix('!') {
+        let command = command.trim();
+        (!command.is_empty()).then(|| {
+            ShellExec {
+                command: command.to_string(),
+                range: range.clone(),
+                is_read: false,
+            }
+            .boxed_clone()
+        })
+    } else if query.starts_with('r') {
+        ShellExec::parse_read(query, &range)
     } else if query.starts_with('s') {
         let mut substitute = "substitute".chars().peekable();
         let mut query = query.chars().peekable();
@@ -778,10 +1812,7 @@ pub fn command_interceptor(mut input: &str, cx: &AppContext) -> Option<CommandIn
             query.next();
         }
         if let Some(replacement) = Replacement::parse(query) {
-            let range = range.clone().unwrap_or(CommandRange {
-                start: Position::CurrentLine { offset: 0 },
-                end: None,
-            });
+            let range = range.clone().unwrap_or(CommandRange::current_line());
             Some(ReplaceCommand { replacement, range }.boxed_clone())
         } else {
             None
@@ -792,29 +1823,56 @@ pub fn command_interceptor(mut input: &str, cx: &AppContext) -> Option<CommandIn
     if let Some(action) = action {
         let string = input.to_string();
         let positions = generate_positions(&string, &(range_prefix + query));
-        return Some(CommandInterceptResult {
+        return vec![CommandInterceptResult {
             action,
             string,
             positions,
-        });
+        }];
     }
 
     for command in commands(cx).iter() {
-        if let Some(action) = command.parse(query, &range, cx) {
-            let mut string = ":".to_owned() + &range_prefix + command.prefix + command.suffix;
-            if query.ends_with('!') {
-                string.push('!');
-            }
-            let positions = generate_positions(&string, &(range_prefix + query));
+        let Some(action) = command.parse(query, &range, cx) else {
+            continue;
+        };
+        let (command_query, args) = command.split_args(raw_query.trim_start());
+        let mut string = ":".to_owned() + &range_prefix + command.prefix + command.suffix;
+        if command_query.ends_with('!') {
+            string.push('!');
+        }
+        let query = range_prefix + query;
+        let args = args.filter(|args| !args.is_empty());
 
-            return Some(CommandInterceptResult {
-                action,
-                string,
-                positions,
-            });
+        let mut results = vec![CommandInterceptResult {
+            action,
+            string: match args {
+                Some(args) => format!("{string} {args}"),
+                None => string.clone(),
+            },
+            positions: Vec::new(),
+        }];
+        // Once a space has been typed after a command that takes a path, offer completions.
+        if command.args.is_some() && raw_query.trim_start() != command_query {
+            for path in complete_path(args.unwrap_or_default(), cx) {
+                if Some(path.as_str()) == args {
+                    continue;
+                }
+                let Some(action) = command.parse(&format!("{command_query} {path}"), &range, cx)
+                else {
+                    continue;
+                };
+                results.push(CommandInterceptResult {
+                    action,
+                    string: format!("{string} {path}"),
+                    positions: Vec::new(),
+                });
+            }
+        }
+        for result in &mut results {
+            result.positions = generate_positions(&result.string, &query);
         }
+        return results;
     }
-    None
+    Vec::new()
 }
 
 fn generate_positions(string: &str, query: &str) -> Vec<usize> {
@@ -843,15 +1901,18 @@ fn generate_positions(string: &str, query: &str) -> Vec<usize> {
 mod test {
     use std::path::Path;
 
+    use super::{command_interceptor, CommandRange, ReadFile, ShellExec, VimSave, VimSplit};
     use crate::{
+        motion,
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
+        VimAddon,
     };
     use editor::Editor;
-    use gpui::TestAppContext;
+    use gpui::{KeyBinding, TestAppContext};
     use indoc::indoc;
     use ui::ViewContext;
-    use workspace::Workspace;
+    use workspace::{SaveIntent, Workspace};
 
     #[gpui::test]
     async fn test_command_basics(cx: &mut TestAppContext) {
@@ -971,6 +2032,78 @@ mod test {
         assert_eq!(fs.load(path).await.unwrap(), "@@\n");
     }
 
+    #[gpui::test]
+    async fn test_command_write_to_path(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+        let copy = Path::new("/root/dir/copy.rs");
+
+        cx.simulate_keystrokes("i @ escape");
+        cx.simulate_keystrokes(": w space d i r / c o p y . r s enter");
+        cx.run_until_parked();
+        assert_eq!(fs.load(copy).await.unwrap(), "@\n");
+
+        // the copy is not overwritten without !
+        cx.simulate_keystrokes("i @ escape");
+        cx.simulate_keystrokes(": w space d i r / c o p y . r s enter");
+        cx.run_until_parked();
+        assert_eq!(fs.load(copy).await.unwrap(), "@\n");
+        cx.simulate_keystrokes(": w ! space d i r / c o p y . r s enter");
+        cx.run_until_parked();
+        assert_eq!(fs.load(copy).await.unwrap(), "@@\n");
+
+        cx.simulate_keystrokes(": s a v space n e w . r s enter");
+        cx.run_until_parked();
+        assert_eq!(fs.load(Path::new("/root/new.rs")).await.unwrap(), "@@\n");
+        cx.workspace(|workspace, cx| {
+            let path = workspace.active_item(cx).unwrap().project_path(cx).unwrap();
+            assert_eq!(path.path.as_ref(), Path::new("new.rs"));
+        });
+
+        cx.simulate_keystrokes(": e space d i r / c o p y . r s enter");
+        cx.run_until_parked();
+        cx.workspace(|workspace, cx| {
+            let path = workspace.active_item(cx).unwrap().project_path(cx).unwrap();
+            assert_eq!(path.path.as_ref(), Path::new("dir/copy.rs"));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_command_path_completion(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.update(|cx| {
+            let complete = |query: &str| {
+                command_interceptor(query, cx)
+                    .into_iter()
+                    .map(|result| result.string)
+                    .collect::<Vec<_>>()
+            };
+
+            assert_eq!(complete(":e"), vec![":edit"]);
+            assert_eq!(complete(":e d"), vec![":edit d", ":edit dir/"]);
+            assert_eq!(
+                complete(":vs dir/"),
+                vec![":vsplit dir/", ":vsplit dir/file.rs"]
+            );
+            assert_eq!(complete(":sp dir/file.rs"), vec![":split dir/file.rs"]);
+
+            assert!(command_interceptor(":vs dir/file.rs", cx)[0]
+                .action
+                .partial_eq(&VimSplit {
+                    vertical: true,
+                    filename: "dir/file.rs".to_string(),
+                }));
+            assert!(command_interceptor(":w! out.txt", cx)[0]
+                .action
+                .partial_eq(&VimSave {
+                    save_intent: Some(SaveIntent::Overwrite),
+                    filename: "out.txt".to_string(),
+                    save_as: false,
+                }));
+        });
+    }
+
     #[gpui::test]
     async fn test_command_quit(cx: &mut TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
@@ -1040,6 +2173,234 @@ mod test {
         cx.shared_state().await.assert_eq("k\nk\nˇk\n4\n4\n3\n2\n1");
     }
 
+    #[gpui::test]
+    async fn test_command_matching_lines(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇa
+            b
+            a
+            b
+            c"})
+            .await;
+        cx.simulate_shared_keystrokes(": g / a / d enter").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            b
+            ˇb
+            c"});
+
+        cx.simulate_shared_keystrokes("u").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            ˇa
+            b
+            a
+            b
+            c"});
+
+        cx.set_shared_state(indoc! {"
+            ˇa
+            b
+            a
+            b
+            a"})
+            .await;
+        cx.simulate_shared_keystrokes(": v / a / d enter").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            a
+            a
+            ˇa"});
+
+        cx.set_shared_state(indoc! {"
+            ˇabc
+            bcd
+            ccc"})
+            .await;
+        cx.simulate_shared_keystrokes(": g / b / s / / x / enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            axc
+            ˇxcd
+            ccc"});
+    }
+
+    #[gpui::test]
+    async fn test_command_matching_lines_range(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇ1\n2\n1\n2\n1\n2\n1").await;
+        cx.simulate_shared_keystrokes(": 2 , 5 g ! / 2 / d enter")
+            .await;
+        cx.shared_state().await.assert_eq("1\n2\n2\nˇ2\n1");
+    }
+
+    #[gpui::test]
+    async fn test_shell_commands_parse(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.update(|cx| {
+            let parse = |query: &str| command_interceptor(query, cx).remove(0).action;
+
+            assert!(parse(":%!sort -u").partial_eq(&ShellExec {
+                command: "sort -u".to_string(),
+                range: Some(CommandRange::whole_file()),
+                is_read: false,
+            }));
+            assert!(parse(":!ls").partial_eq(&ShellExec {
+                command: "ls".to_string(),
+                range: None,
+                is_read: false,
+            }));
+            assert!(parse(":r !git log").partial_eq(&ShellExec {
+                command: "git log".to_string(),
+                range: None,
+                is_read: true,
+            }));
+            assert!(parse(":read notes.txt").partial_eq(&ReadFile {
+                path: "notes.txt".to_string(),
+                range: None,
+            }));
+            assert!(parse(":rev").as_any().downcast_ref::<ReadFile>().is_none());
+        });
+    }
+
+    /// Runs an ex command that spawns a shell and waits for its output to be inserted.
+    #[cfg(not(target_os = "windows"))]
+    async fn run_shell_exec(cx: &mut VimTestContext, keystrokes: &str) {
+        cx.executor().allow_parking();
+        // The test worktree only exists on the fake filesystem, so run commands
+        // in the process's working directory instead.
+        cx.workspace(|workspace, cx| {
+            workspace.project().update(cx, |project, cx| {
+                let worktree_ids = project
+                    .worktrees(cx)
+                    .map(|worktree| worktree.read(cx).id())
+                    .collect::<Vec<_>>();
+                for worktree_id in worktree_ids {
+                    project.remove_worktree(worktree_id, cx);
+                }
+            })
+        });
+        cx.simulate_keystrokes(keystrokes);
+        let task = cx.update_editor(|editor, cx| {
+            let vim = editor.addon::<VimAddon>().unwrap().view.clone();
+            vim.update(cx, |vim, _| vim.pending_command.take())
+        });
+        task.expect("shell command was not started").await;
+        cx.run_until_parked();
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[gpui::test]
+    async fn test_shell_filter(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇcherry\napple\nbanana", Mode::Normal);
+        run_shell_exec(&mut cx, ": % ! s o r t enter").await;
+        cx.assert_state("ˇapple\nbanana\ncherry", Mode::Normal);
+        assert!(cx.workspace(|workspace, _| workspace.notification_ids().is_empty()));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[gpui::test]
+    async fn test_shell_filter_failure(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇcherry\napple\nbanana", Mode::Normal);
+        run_shell_exec(&mut cx, ": % ! e x i t space 3 enter").await;
+        cx.assert_state("ˇcherry\napple\nbanana", Mode::Normal);
+        assert!(!cx.workspace(|workspace, _| workspace.notification_ids().is_empty()));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[gpui::test]
+    async fn test_shell_filter_stderr(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // Output on stderr is reported, but doesn't stop a successful command
+        // from replacing the text.
+        cx.set_state("ˇb\na\nc", Mode::Normal);
+        run_shell_exec(
+            &mut cx,
+            ": % ! e c h o space o o p s space > & 2 ; space s o r t enter",
+        )
+        .await;
+        cx.assert_state("ˇa\nb\nc", Mode::Normal);
+        assert!(!cx.workspace(|workspace, _| workspace.notification_ids().is_empty()));
+    }
+
+    #[gpui::test]
+    async fn test_normal_command(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇa
+            b
+            c"})
+            .await;
+        cx.simulate_shared_keystrokes(": % n o r m space shift-a ; enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            a;
+            b;
+            cˇ;"});
+
+        cx.simulate_shared_keystrokes("u").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            ˇa
+            b
+            c"});
+
+        cx.set_shared_state(indoc! {"
+            ˇfoo
+            bar
+            foo"})
+            .await;
+        cx.simulate_shared_keystrokes(": g / f o o / n o r m a l space shift-a x enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            foox
+            bar
+            fooˇx"});
+
+        cx.set_shared_state("one twoˇ three").await;
+        cx.simulate_shared_keystrokes(": n o r m space space d w enter")
+            .await;
+        cx.shared_state().await.assert_eq("one twoˇthree");
+    }
+
+    #[gpui::test]
+    async fn test_normal_command_noremap(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.update(|cx| {
+            cx.bind_keys([KeyBinding::new(
+                "x",
+                motion::Down {
+                    display_lines: false,
+                },
+                Some("vim_mode == normal"),
+            )])
+        });
+        cx.neovim.exec("nmap x j").await;
+
+        cx.set_shared_state(indoc! {"
+            ˇabc
+            def"})
+            .await;
+        cx.simulate_shared_keystrokes(": n o r m space x enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            abc
+            ˇdef"});
+
+        cx.simulate_shared_keystrokes(": n o r m ! space x enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            abc
+            ˇef"});
+    }
+
     fn assert_active_item(
         workspace: &mut Workspace,
         expected_path: &str,
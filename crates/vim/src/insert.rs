@@ -11,7 +11,7 @@ pub fn register(editor: &mut Editor, cx: &mut ViewContext<Vim>) {
 }
 
 impl Vim {
-    fn normal_before(&mut self, action: &NormalBefore, cx: &mut ViewContext<Self>) {
+    pub(crate) fn normal_before(&mut self, action: &NormalBefore, cx: &mut ViewContext<Self>) {
         if self.active_operator().is_some() {
             self.operator_stack.clear();
             self.sync_vim_settings(cx);
@@ -0,0 +1,83 @@
+use editor::{Anchor, Editor, ToPoint};
+use gpui::ViewContext;
+use settings::Settings;
+use workspace::pane::{GoBack, GoForward};
+
+use crate::{Vim, VimSettings};
+
+// The pane's navigation history handles ctrl-o and ctrl-i within a session.
+// Vim's jump list takes over once that runs out, so jumps from previous
+// sessions can still be revisited.
+pub(crate) fn register(editor: &mut Editor, cx: &mut ViewContext<Vim>) {
+    Vim::action(editor, cx, |vim, _: &GoBack, cx| {
+        vim.move_in_jump_list(true, cx);
+    });
+    Vim::action(editor, cx, |vim, _: &GoForward, cx| {
+        vim.move_in_jump_list(false, cx);
+    });
+}
+
+impl Vim {
+    fn move_in_jump_list(&mut self, backward: bool, cx: &mut ViewContext<Self>) {
+        let can_navigate = self.pane(cx).map_or(true, |pane| {
+            let pane = pane.read(cx);
+            if backward {
+                pane.can_navigate_backward()
+            } else {
+                pane.can_navigate_forward()
+            }
+        });
+        if Vim::globals(cx).jump_list_position.is_none() && (can_navigate || !backward) {
+            cx.propagate();
+            return;
+        }
+
+        let current = self.file_position(cx);
+        let globals = Vim::globals(cx);
+        let position = globals.jump_list_position.unwrap_or(globals.jumps.len());
+        let is_elsewhere = |ix: &usize| Some(&globals.jumps[*ix]) != current.as_ref();
+        let next = if backward {
+            (0..position).rev().find(is_elsewhere)
+        } else {
+            (position + 1..globals.jumps.len()).find(is_elsewhere)
+        };
+        let Some(next) = next else {
+            if !backward {
+                globals.jump_list_position.take();
+            }
+            cx.propagate();
+            return;
+        };
+        globals.jump_list_position = Some(next);
+        let jump = globals.jumps[next].clone();
+        self.open_file_position(jump, false, cx);
+    }
+
+    /// Records where the cursor was before a jump. `from` defaults to the newest cursor.
+    pub(crate) fn push_to_jump_list(&mut self, from: Option<Anchor>, cx: &mut ViewContext<Self>) {
+        let Some(mut position) = self.file_position(cx) else {
+            return;
+        };
+        if let Some(from) = from {
+            let point = self.update_editor(cx, |_, editor, cx| {
+                from.to_point(&editor.buffer().read(cx).snapshot(cx))
+            });
+            if let Some(point) = point {
+                position.row = point.row;
+                position.column = point.column;
+            }
+        }
+
+        let max_len = VimSettings::get_global(cx).persistence.history_size;
+        let globals = Vim::globals(cx);
+        globals.jump_list_position.take();
+        // like vim, only the latest jump from each line is kept.
+        globals
+            .jumps
+            .retain(|jump| jump.row != position.row || jump.path != position.path);
+        globals.jumps.push(position);
+        if globals.jumps.len() > max_len {
+            globals.jumps.drain(..globals.jumps.len() - max_len);
+        }
+    }
+}
@@ -514,6 +514,11 @@ impl Vim {
             prior_selections, ..
         } = &m
         {
+            // by the time a search motion runs the cursor has already moved.
+            if self.active_operator().is_none() {
+                let from = prior_selections.last().map(|selection| selection.start);
+                self.push_to_jump_list(from, cx);
+            }
             match self.mode {
                 Mode::Visual | Mode::VisualLine | Mode::VisualBlock => {
                     if !prior_selections.is_empty() {
@@ -547,6 +552,9 @@ impl Vim {
         let count = Vim::take_count(cx);
         let active_operator = self.active_operator();
         let mut waiting_operator: Option<Operator> = None;
+        if active_operator.is_none() && motion.is_jump() {
+            self.push_to_jump_list(None, cx);
+        }
         match self.mode {
             Mode::Normal | Mode::Replace | Mode::Insert => {
                 if active_operator == Some(Operator::AddSurrounds { target: None }) {
@@ -632,6 +640,29 @@ impl Motion {
         }
     }
 
+    /// Whether the position before this motion is added to the jump list.
+    pub fn is_jump(&self) -> bool {
+        use Motion::*;
+        matches!(
+            self,
+            StartOfDocument
+                | EndOfDocument
+                | Matching
+                | SentenceBackward
+                | SentenceForward
+                | StartOfParagraph
+                | EndOfParagraph
+                | WindowTop
+                | WindowMiddle
+                | WindowBottom
+                | NextSectionStart
+                | NextSectionEnd
+                | PreviousSectionStart
+                | PreviousSectionEnd
+                | Jump { .. }
+        )
+    }
+
     pub fn infallible(&self) -> bool {
         use Motion::*;
         match self {
@@ -4,19 +4,29 @@ use editor::{
     display_map::{DisplaySnapshot, ToDisplayPoint},
     movement,
     scroll::Autoscroll,
-    Anchor, Bias, DisplayPoint,
+    Anchor, Bias, DisplayPoint, Editor,
 };
 use gpui::ViewContext;
-use language::SelectionGoal;
+use language::{Point, SelectionGoal};
+use multi_buffer::MultiBufferRow;
+use workspace::notifications::NotifyTaskExt;
 
 use crate::{
     motion::{self, Motion},
+    persistence::FilePosition,
     state::Mode,
     Vim,
 };
 
 impl Vim {
     pub fn create_mark(&mut self, text: Arc<str>, tail: bool, cx: &mut ViewContext<Self>) {
+        if let Some(mark) = global_mark(&text) {
+            if let Some(position) = self.file_position(cx) {
+                Vim::globals(cx).global_marks.insert(mark, position);
+            }
+            self.clear_operator(cx);
+            return;
+        }
         let Some(anchors) = self.update_editor(cx, |_, editor, _| {
             editor
                 .selections
@@ -68,6 +78,26 @@ impl Vim {
     pub fn jump(&mut self, text: Arc<str>, line: bool, cx: &mut ViewContext<Self>) {
         self.pop_operator(cx);
 
+        if let Some(mark) = global_mark(&text) {
+            let Some(position) = Vim::globals(cx).global_marks.get(&mark).cloned() else {
+                return;
+            };
+            let current = self.file_position(cx);
+            if current.is_some_and(|current| current.path == position.path) {
+                let anchor = self.update_editor(cx, |_, editor, cx| {
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    snapshot.anchor_before(snapshot.clip_point(position.point(), Bias::Left))
+                });
+                if let Some(anchor) = anchor {
+                    self.jump_to_anchors(vec![anchor], line, cx);
+                }
+            } else if self.active_operator().is_none() {
+                self.push_to_jump_list(None, cx);
+                self.open_file_position(position, line, cx);
+            }
+            return;
+        }
+
         let anchors = match &*text {
             "{" | "}" => self.update_editor(cx, |_, editor, cx| {
                 let (map, selections) = editor.selections.all_display(cx);
@@ -89,7 +119,10 @@ impl Vim {
         };
 
         let Some(anchors) = anchors else { return };
+        self.jump_to_anchors(anchors, line, cx);
+    }
 
+    fn jump_to_anchors(&mut self, anchors: Vec<Anchor>, line: bool, cx: &mut ViewContext<Self>) {
         let is_active_operator = self.active_operator().is_some();
         if is_active_operator {
             if let Some(anchor) = anchors.last() {
@@ -102,6 +135,7 @@ impl Vim {
                 )
             }
         } else {
+            self.push_to_jump_list(None, cx);
             self.update_editor(cx, |_, editor, cx| {
                 let map = editor.snapshot(cx);
                 let mut ranges: Vec<Range<Anchor>> = Vec::new();
@@ -126,6 +160,73 @@ impl Vim {
     }
 }
 
+impl Vim {
+    /// The position of the newest cursor, if the editor is showing a single file.
+    pub(crate) fn file_position(&mut self, cx: &mut ViewContext<Self>) -> Option<FilePosition> {
+        self.update_editor(cx, |_, editor, cx| {
+            let head = editor.selections.newest::<Point>(cx).head();
+            let buffer = editor.buffer().read(cx).as_singleton()?;
+            let file = project::File::from_dyn(buffer.read(cx).file())?;
+            let path = file.worktree.read(cx).abs_path().join(&file.path);
+            Some(FilePosition::new(path, head))
+        })
+        .flatten()
+    }
+
+    /// Moves the cursor to the given position, opening its file if necessary.
+    pub(crate) fn open_file_position(
+        &mut self,
+        position: FilePosition,
+        line: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let path = position.path.clone();
+        let move_cursor = move |editor: &mut Editor, cx: &mut ViewContext<Editor>| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let mut point = snapshot.clip_point(position.point(), Bias::Left);
+            if line {
+                point.column = snapshot.indent_size_for_line(MultiBufferRow(point.row)).len;
+            }
+            editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                s.select_ranges([point..point])
+            });
+        };
+
+        if self
+            .file_position(cx)
+            .is_some_and(|current| current.path == path)
+        {
+            self.update_editor(cx, |_, editor, cx| move_cursor(editor, cx));
+            return;
+        }
+
+        let Some(workspace) = self.workspace(cx) else {
+            return;
+        };
+        let project_path = self.resolve_project_path(&path.to_string_lossy(), cx);
+        cx.spawn(|_, mut cx| async move {
+            let project_path = project_path.await?;
+            let item = workspace
+                .update(&mut cx, |workspace, cx| {
+                    workspace.open_path(project_path, None, true, cx)
+                })?
+                .await?;
+            if let Some(editor) = item.downcast::<Editor>() {
+                editor.update(&mut cx, |editor, cx| move_cursor(editor, cx))?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_notify_err(cx);
+    }
+}
+
+// Marks `A-Z` are global, and remember which file they were set in.
+fn global_mark(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    let mark = chars.next().filter(|char| char.is_ascii_uppercase())?;
+    chars.next().is_none().then_some(mark)
+}
+
 pub fn jump_motion(
     map: &DisplaySnapshot,
     anchor: Anchor,
@@ -1,4 +1,4 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, mem, ops::Range, rc::Rc, sync::Arc};
 
 use crate::{
     insert::NormalBefore,
@@ -8,7 +8,8 @@ use crate::{
     Vim,
 };
 use editor::Editor;
-use gpui::{actions, Action, ViewContext, WindowContext};
+use gpui::{actions, Action, Keymap, Keystroke, ViewContext, WindowContext};
+use settings::KeymapFile;
 use util::ResultExt;
 use workspace::Workspace;
 
@@ -41,7 +42,7 @@ fn repeatable_insert(action: &ReplayableAction) -> Option<Box<dyn Action>> {
                 None
             }
         }
-        ReplayableAction::Insertion { .. } => None,
+        ReplayableAction::Insertion { .. } | ReplayableAction::Keystroke { .. } => None,
     }
 }
 
@@ -74,6 +75,7 @@ pub struct ReplayerState {
     actions: Vec<ReplayableAction>,
     running: bool,
     ix: usize,
+    pending_keystrokes: Vec<Keystroke>,
 }
 
 #[derive(Clone)]
@@ -85,6 +87,7 @@ impl Replayer {
             actions: vec![],
             running: false,
             ix: 0,
+            pending_keystrokes: Vec::new(),
         })))
     }
 
@@ -128,24 +131,105 @@ impl Replayer {
             ReplayableAction::Insertion {
                 text,
                 utf16_range_to_replace,
-            } => {
-                cx.window_handle()
-                    .update(cx, |handle, cx| {
-                        let Ok(workspace) = handle.downcast::<Workspace>() else {
-                            return;
-                        };
-                        let Some(editor) = workspace.read(cx).active_item_as::<Editor>(cx) else {
-                            return;
-                        };
-                        editor.update(cx, |editor, cx| {
-                            editor.replay_insert_event(&text, utf16_range_to_replace.clone(), cx)
-                        })
-                    })
-                    .log_err();
+            } => replay_insertion(&text, utf16_range_to_replace, cx),
+            ReplayableAction::Keystroke { keystroke, noremap } => {
+                if noremap {
+                    self.replay_builtin_keystroke(keystroke, cx);
+                } else {
+                    cx.dispatch_keystroke(keystroke);
+                }
             }
         }
         cx.defer(move |cx| self.next(cx));
     }
+
+    /// Resolves the keystroke against the built-in keymap, so that user
+    /// bindings don't change its meaning.
+    fn replay_builtin_keystroke(&self, keystroke: Keystroke, cx: &mut WindowContext) {
+        let keymap = builtin_keymap(cx);
+        cx.draw();
+        let context_stack = cx.context_stack();
+
+        let mut lock = self.0.borrow_mut();
+        lock.pending_keystrokes.push(keystroke);
+        let (mut bindings, pending) =
+            keymap.bindings_for_input(&lock.pending_keystrokes, &context_stack);
+        let next_is_builtin_keystroke = matches!(
+            lock.actions.get(lock.ix),
+            Some(ReplayableAction::Keystroke { noremap: true, .. })
+        );
+        if pending && next_is_builtin_keystroke {
+            return;
+        }
+
+        let mut keystrokes = mem::take(&mut lock.pending_keystrokes);
+        if bindings.is_empty() && keystrokes.len() > 1 {
+            // No multi-key binding matched, so handle the first keystroke on
+            // its own and try the remaining ones again.
+            let remaining = keystrokes.split_off(1);
+            let ix = lock.ix;
+            lock.actions.splice(
+                ix..ix,
+                remaining
+                    .into_iter()
+                    .map(|keystroke| ReplayableAction::Keystroke {
+                        keystroke,
+                        noremap: true,
+                    }),
+            );
+            bindings = keymap.bindings_for_input(&keystrokes, &context_stack).0;
+        }
+        drop(lock);
+
+        if let Some(binding) = bindings.first() {
+            let action = binding.action().boxed_clone();
+            if should_replay(&*action) {
+                cx.dispatch_action(action.boxed_clone());
+                cx.defer(move |cx| Vim::globals(cx).observe_action(action));
+            }
+        } else if let Some(text) = keystrokes
+            .into_iter()
+            .next()
+            .and_then(|keystroke| keystroke.key_char)
+        {
+            replay_insertion(&text.into(), None, cx);
+        }
+    }
+}
+
+fn replay_insertion(
+    text: &Arc<str>,
+    utf16_range_to_replace: Option<Range<isize>>,
+    cx: &mut WindowContext,
+) {
+    cx.window_handle()
+        .update(cx, |handle, cx| {
+            let Ok(workspace) = handle.downcast::<Workspace>() else {
+                return;
+            };
+            let Some(editor) = workspace.read(cx).active_item_as::<Editor>(cx) else {
+                return;
+            };
+            editor.update(cx, |editor, cx| {
+                editor.replay_insert_event(text, utf16_range_to_replace.clone(), cx)
+            })
+        })
+        .log_err();
+}
+
+/// The default and vim keymaps, without any of the user's customizations.
+fn builtin_keymap(cx: &mut WindowContext) -> Rc<Keymap> {
+    if let Some(keymap) = Vim::globals(cx).builtin_keymap.clone() {
+        return keymap;
+    }
+    let bindings = [settings::default_keymap(), settings::vim_keymap()]
+        .iter()
+        .filter_map(|content| KeymapFile::parse(content).log_err())
+        .flat_map(|keymap| keymap.load_bindings(cx).log_err().unwrap_or_default())
+        .collect();
+    let keymap = Rc::new(Keymap::new(bindings));
+    Vim::globals(cx).builtin_keymap = Some(keymap.clone());
+    keymap
 }
 
 impl Vim {
@@ -3,16 +3,18 @@ use std::{iter::Peekable, str::Chars, time::Duration};
 use editor::Editor;
 use gpui::{actions, impl_actions, ViewContext};
 use language::Point;
+use multi_buffer::MultiBufferRow;
 use search::{buffer_search, BufferSearchBar, SearchOptions};
 use serde_derive::Deserialize;
+use settings::Settings;
 use util::serde::default_true;
 use workspace::{notifications::NotifyResultExt, searchable::Direction};
 
 use crate::{
     command::CommandRange,
     motion::Motion,
-    state::{Mode, SearchState},
-    Vim,
+    state::{push_history, Mode, SearchState},
+    Vim, VimSettings,
 };
 
 #[derive(Clone, Deserialize, PartialEq)]
@@ -122,12 +124,14 @@ impl Vim {
         };
         let count = Vim::take_count(cx).unwrap_or(1);
         let prior_selections = self.editor_selections(cx);
+        let search_history = Vim::globals(cx).search_history.clone();
         pane.update(cx, |pane, cx| {
             if let Some(search_bar) = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>() {
                 search_bar.update(cx, |search_bar, cx| {
                     if !search_bar.show(cx) {
                         return;
                     }
+                    search_bar.restore_search_history(search_history);
                     let query = search_bar.query(cx);
 
                     search_bar.select_query(cx);
@@ -187,8 +191,11 @@ impl Vim {
                 let prior_mode = self.search.prior_mode;
                 let prior_operator = self.search.prior_operator.take();
 
-                let query = search_bar.query(cx).into();
-                Vim::globals(cx).registers.insert('/', query);
+                let query = search_bar.query(cx);
+                let max_len = VimSettings::get_global(cx).persistence.history_size;
+                let globals = Vim::globals(cx);
+                push_history(&mut globals.search_history, query.clone(), max_len);
+                globals.registers.insert('/', query.into());
                 Some((prior_selections, prior_mode, prior_operator))
             })
         });
@@ -378,12 +385,31 @@ impl Vim {
             return;
         };
         if let Some(result) = self.update_editor(cx, |vim, editor, cx| {
-            let range = action.range.buffer_range(vim, editor, cx)?;
+            // with several cursors (e.g. from :g) and no explicit range,
+            // substitute on the line of each cursor.
+            let ranges = if action.range == CommandRange::current_line() {
+                editor
+                    .selections
+                    .all::<Point>(cx)
+                    .into_iter()
+                    .map(|selection| {
+                        let row = MultiBufferRow(selection.head().row);
+                        row..row
+                    })
+                    .collect::<Vec<_>>()
+            } else {
+                vec![action.range.buffer_range(vim, editor, cx)?]
+            };
             let snapshot = &editor.snapshot(cx).buffer_snapshot;
-            let end_point = Point::new(range.end.0, snapshot.line_len(range.end));
-            let range = snapshot.anchor_before(Point::new(range.start.0, 0))
-                ..snapshot.anchor_after(end_point);
-            editor.set_search_within_ranges(&[range], cx);
+            let ranges = ranges
+                .into_iter()
+                .map(|range| {
+                    let end_point = Point::new(range.end.0, snapshot.line_len(range.end));
+                    snapshot.anchor_before(Point::new(range.start.0, 0))
+                        ..snapshot.anchor_after(end_point)
+                })
+                .collect::<Vec<_>>();
+            editor.set_search_within_ranges(&ranges, cx);
             anyhow::Ok(())
         }) {
             workspace.update(cx, |workspace, cx| {
@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use collections::HashMap;
+use db::kvp::KEY_VALUE_STORE;
+use language::Point;
+use serde::{Deserialize, Serialize};
+
+const VIM_STATE_KEY: &str = "vim_state";
+
+/// Reads the state saved by [`write_state`], if any.
+pub(crate) fn read_state() -> Result<Option<SerializedVimState>> {
+    let Some(state) = KEY_VALUE_STORE.read_kvp(VIM_STATE_KEY)? else {
+        return Ok(None);
+    };
+    Ok(Some(serde_json::from_str(&state)?))
+}
+
+pub(crate) async fn write_state(state: &SerializedVimState) -> Result<()> {
+    KEY_VALUE_STORE
+        .write_kvp(VIM_STATE_KEY.to_string(), serde_json::to_string(state)?)
+        .await
+}
+
+/// A position in a file, used for global marks and the jump list.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct FilePosition {
+    pub(crate) path: PathBuf,
+    pub(crate) row: u32,
+    pub(crate) column: u32,
+}
+
+impl FilePosition {
+    pub(crate) fn new(path: PathBuf, point: Point) -> Self {
+        Self {
+            path,
+            row: point.row,
+            column: point.column,
+        }
+    }
+
+    pub(crate) fn point(&self) -> Point {
+        Point::new(self.row, self.column)
+    }
+}
+
+/// The parts of [`crate::state::VimGlobals`] that are restored when Zed is restarted.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct SerializedVimState {
+    pub(crate) registers: HashMap<char, String>,
+    pub(crate) marks: HashMap<char, FilePosition>,
+    pub(crate) jumps: Vec<FilePosition>,
+    pub(crate) command_history: Vec<String>,
+    pub(crate) search_history: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{state::Mode, test::VimTestContext, PersistenceSettings, Vim};
+
+    #[gpui::test]
+    async fn test_serialize_vim_state(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("one\nˇtwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes("m A \" a y y g g ' A");
+
+        let state = cx.update(|cx| {
+            let settings = PersistenceSettings {
+                history_size: 1,
+                ..Default::default()
+            };
+            Vim::globals(cx).serialize(&settings)
+        });
+        let path = PathBuf::from("/root/dir/file.rs");
+        assert_eq!(state.registers.get(&'a').map(String::as_str), Some("two\n"));
+        assert_eq!(
+            state.marks.get(&'A'),
+            Some(&FilePosition::new(path.clone(), Point::new(1, 0)))
+        );
+        // only the latest jump is kept with a history size of 1.
+        assert_eq!(
+            state.jumps,
+            vec![FilePosition::new(path.clone(), Point::new(0, 0))]
+        );
+
+        // restoring replaces the state from this session.
+        cx.update(|cx| {
+            Vim::globals(cx).restore_state(SerializedVimState {
+                registers: HashMap::from_iter([
+                    ('a', "old".to_string()),
+                    ('b', "restored".to_string()),
+                ]),
+                ..Default::default()
+            });
+            assert!(Vim::globals(cx).global_marks.is_empty());
+            assert!(Vim::globals(cx).jumps.is_empty());
+        });
+        cx.simulate_keystrokes("\" b p");
+        cx.assert_state("one\ntrestoreˇdwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes("\" a p");
+        cx.assert_state("one\ntrestoredolˇdwo\nthree", Mode::Normal);
+    }
+}
@@ -1,21 +1,24 @@
 use std::borrow::BorrowMut;
-use std::{fmt::Display, ops::Range, sync::Arc};
+use std::{fmt::Display, ops::Range, rc::Rc, sync::Arc};
 
 use crate::command::command_interceptor;
 use crate::normal::repeat::Replayer;
+use crate::persistence::{self, FilePosition, SerializedVimState};
 use crate::surrounds::SurroundsType;
 use crate::{motion::Motion, object::Object};
-use crate::{UseSystemClipboard, Vim, VimSettings};
+use crate::{PersistenceSettings, UseSystemClipboard, Vim, VimSettings};
 use collections::HashMap;
 use command_palette_hooks::{CommandPaletteFilter, CommandPaletteInterceptor};
 use editor::{Anchor, ClipboardSelection, Editor};
 use gpui::{
-    Action, AppContext, BorrowAppContext, ClipboardEntry, ClipboardItem, Global, View, WeakView,
+    Action, AppContext, BorrowAppContext, ClipboardEntry, ClipboardItem, Global, Keymap, Keystroke,
+    Task, View, WeakView,
 };
 use language::Point;
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsStore};
 use ui::{SharedString, ViewContext};
+use util::ResultExt;
 use workspace::searchable::Direction;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
@@ -174,8 +177,16 @@ pub struct VimGlobals {
     pub last_yank: Option<SharedString>,
     pub registers: HashMap<char, Register>,
     pub recordings: HashMap<char, Vec<ReplayableAction>>,
+    pub builtin_keymap: Option<Rc<Keymap>>,
 
     pub focused_vim: Option<WeakView<Vim>>,
+
+    /// Marks `A-Z`, which remember the file they were set in.
+    pub(crate) global_marks: HashMap<char, FilePosition>,
+    pub(crate) jumps: Vec<FilePosition>,
+    pub(crate) jump_list_position: Option<usize>,
+    pub(crate) command_history: Vec<String>,
+    pub(crate) search_history: Vec<String>,
 }
 impl Global for VimGlobals {}
 
@@ -191,13 +202,27 @@ impl VimGlobals {
         })
         .detach();
 
+        // The state is shared by every window, so it's restored once, the first
+        // time vim is enabled, and saved when quitting.
+        let mut state_restored = false;
         cx.observe_global::<SettingsStore>(move |cx| {
             if Vim::enabled(cx) {
+                if !state_restored {
+                    state_restored = true;
+                    VimGlobals::restore(cx);
+                }
                 CommandPaletteFilter::update_global(cx, |filter, _| {
                     filter.show_namespace(Vim::NAMESPACE);
                 });
                 CommandPaletteInterceptor::update_global(cx, |interceptor, _| {
                     interceptor.set(Box::new(command_interceptor));
+                    interceptor.set_confirm_handler(Box::new(
+                        |command: &str, cx: &mut AppContext| {
+                            let command = command.trim_start_matches(':').to_string();
+                            let max_len = VimSettings::get_global(cx).persistence.history_size;
+                            push_history(&mut Vim::globals(cx).command_history, command, max_len);
+                        },
+                    ));
                 });
             } else {
                 *Vim::globals(cx) = VimGlobals::default();
@@ -210,6 +235,68 @@ impl VimGlobals {
             }
         })
         .detach();
+
+        cx.on_app_quit(VimGlobals::save).detach();
+    }
+
+    /// Replaces the registers, marks, jumps and histories with the state saved by
+    /// [`VimGlobals::save`].
+    fn restore(cx: &mut AppContext) {
+        if !VimSettings::get_global(cx).persistence.enabled {
+            return;
+        }
+        if let Some(state) = persistence::read_state().log_err().flatten() {
+            Vim::globals(cx).restore_state(state);
+        }
+    }
+
+    /// Writes the state to be restored the next time Zed is started.
+    fn save(cx: &mut AppContext) -> Task<()> {
+        let settings = VimSettings::get_global(cx).persistence;
+        if !Vim::enabled(cx) || !settings.enabled {
+            return Task::ready(());
+        }
+        let state = Vim::globals(cx).serialize(&settings);
+        cx.background_executor().spawn(async move {
+            persistence::write_state(&state).await.log_err();
+        })
+    }
+
+    pub(crate) fn serialize(&self, settings: &PersistenceSettings) -> SerializedVimState {
+        let last = |history: &Vec<String>| {
+            history[history.len().saturating_sub(settings.history_size)..].to_vec()
+        };
+        SerializedVimState {
+            registers: self
+                .registers
+                .iter()
+                .filter(|(register, content)| {
+                    register.is_ascii_alphanumeric()
+                        && content.text.len() <= settings.max_register_size
+                })
+                .map(|(register, content)| (*register, content.text.to_string()))
+                .collect(),
+            marks: self.global_marks.clone(),
+            jumps: self.jumps[self.jumps.len().saturating_sub(settings.history_size)..].to_vec(),
+            command_history: last(&self.command_history),
+            search_history: last(&self.search_history),
+        }
+    }
+
+    pub(crate) fn restore_state(&mut self, state: SerializedVimState) {
+        self.registers
+            .retain(|register, _| !register.is_ascii_alphanumeric());
+        self.registers.extend(
+            state
+                .registers
+                .into_iter()
+                .map(|(register, text)| (register, text.into())),
+        );
+        self.global_marks = state.marks;
+        self.jumps = state.jumps;
+        self.jump_list_position = None;
+        self.command_history = state.command_history;
+        self.search_history = state.search_history;
     }
 
     pub(crate) fn write_registers(
@@ -306,7 +393,8 @@ impl VimGlobals {
         };
         let lower = register.to_lowercase().next().unwrap_or(register);
         match lower {
-            '_' | ':' | '.' | '#' | '=' => None,
+            '_' | '.' | '#' | '=' => None,
+            ':' => self.command_history.last().cloned().map(Into::into),
             '+' => cx.read_from_clipboard().map(|item| item.into()),
             '*' => {
                 #[cfg(any(target_os = "linux", target_os = "freebsd"))]
@@ -399,6 +487,15 @@ impl VimGlobals {
     }
 }
 
+/// Adds an entry to a `:` or `/` history, moving it to the end if it's already present.
+pub(crate) fn push_history(history: &mut Vec<String>, entry: String, max_len: usize) {
+    history.retain(|existing| existing != &entry);
+    history.push(entry);
+    if history.len() > max_len {
+        history.drain(..history.len() - max_len);
+    }
+}
+
 impl Vim {
     pub fn globals(cx: &mut AppContext) -> &mut VimGlobals {
         cx.global_mut::<VimGlobals>()
@@ -419,6 +516,12 @@ pub enum ReplayableAction {
         text: Arc<str>,
         utf16_range_to_replace: Option<Range<isize>>,
     },
+    Keystroke {
+        keystroke: Keystroke,
+        /// Resolve the keystroke against the built-in keymap only, ignoring
+        /// any user bindings (as `:normal!` does).
+        noremap: bool,
+    },
 }
 
 impl Clone for ReplayableAction {
@@ -432,6 +535,10 @@ impl Clone for ReplayableAction {
                 text: text.clone(),
                 utf16_range_to_replace: utf16_range_to_replace.clone(),
             },
+            Self::Keystroke { keystroke, noremap } => Self::Keystroke {
+                keystroke: keystroke.clone(),
+                noremap: *noremap,
+            },
         }
     }
 }
@@ -9,10 +9,12 @@ mod digraph;
 mod helix;
 mod indent;
 mod insert;
+mod jump_list;
 mod mode_indicator;
 mod motion;
 mod normal;
 mod object;
+mod persistence;
 mod replace;
 mod rewrap;
 mod state;
@@ -27,7 +29,7 @@ use editor::{
 };
 use gpui::{
     actions, impl_actions, Action, AppContext, Axis, Entity, EventEmitter, KeyContext,
-    KeystrokeEvent, Render, Subscription, View, ViewContext, WeakView,
+    KeystrokeEvent, Render, Subscription, Task, View, ViewContext, WeakView,
 };
 use insert::{NormalBefore, TemporaryNormal};
 use language::{CursorShape, Point, Selection, SelectionGoal, TransactionId};
@@ -103,7 +105,7 @@ pub fn init(cx: &mut AppContext) {
     cx.observe_new_views(|editor: &mut Editor, cx| Vim::register(editor, cx))
         .detach();
 
-    cx.observe_new_views(|workspace: &mut Workspace, _| {
+    cx.observe_new_views(|workspace: &mut Workspace, cx| {
         workspace.register_action(|workspace, _: &ToggleVimMode, cx| {
             let fs = workspace.app_state().fs.clone();
             let currently_enabled = Vim::enabled(cx);
@@ -214,9 +216,15 @@ pub(crate) struct Vim {
     pub(crate) current_anchor: Option<Selection<Anchor>>,
     pub(crate) undo_modes: HashMap<TransactionId, Mode>,
 
+    pub(crate) normal_command_lines: Option<Vec<Anchor>>,
+    pub(crate) normal_command_tx: Option<TransactionId>,
+
     selected_register: Option<char>,
     pub search: SearchState,
 
+    /// The shell command or file read whose output is still being inserted.
+    pub(crate) pending_command: Option<Task<()>>,
+
     editor: WeakView<Editor>,
 
     _subscriptions: Vec<Subscription>,
@@ -259,9 +267,14 @@ impl Vim {
             current_anchor: None,
             undo_modes: HashMap::default(),
 
+            normal_command_lines: None,
+            normal_command_tx: None,
+
             selected_register: None,
             search: SearchState::default(),
 
+            pending_command: None,
+
             editor: editor.downgrade(),
             _subscriptions: vec![
                 cx.observe_keystrokes(Self::observe_keystrokes),
@@ -347,6 +360,7 @@ impl Vim {
             object::register(editor, cx);
             visual::register(editor, cx);
             change_list::register(editor, cx);
+            jump_list::register(editor, cx);
             digraph::register(editor, cx);
 
             cx.defer(|vim, cx| {
@@ -979,6 +993,9 @@ impl Vim {
         if mode == Mode::VisualLine || mode == Mode::VisualBlock {
             self.undo_modes.insert(transaction_id, mode);
         }
+        if self.normal_command_lines.is_some() && self.normal_command_tx.is_none() {
+            self.normal_command_tx = Some(transaction_id);
+        }
     }
 
     fn transaction_undone(&mut self, transaction_id: &TransactionId, cx: &mut ViewContext<Self>) {
@@ -1225,6 +1242,7 @@ struct VimSettings {
     pub use_smartcase_find: bool,
     pub custom_digraphs: HashMap<String, Arc<str>>,
     pub highlight_on_yank_duration: u64,
+    pub persistence: PersistenceSettings,
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
@@ -1235,6 +1253,36 @@ struct VimSettingsContent {
     pub use_smartcase_find: Option<bool>,
     pub custom_digraphs: Option<HashMap<String, Arc<str>>>,
     pub highlight_on_yank_duration: Option<u64>,
+    pub persistence: Option<PersistenceSettings>,
+}
+
+/// Controls which parts of vim's state are restored when Zed is restarted.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(default)]
+pub struct PersistenceSettings {
+    /// Whether to restore named registers, global marks, the jump list and
+    /// the `:` and `/` histories.
+    ///
+    /// Default: true
+    pub enabled: bool,
+    /// The number of entries kept in the jump list and in each history.
+    ///
+    /// Default: 100
+    pub history_size: usize,
+    /// Registers larger than this many bytes are not saved.
+    ///
+    /// Default: 10000
+    pub max_register_size: usize,
+}
+
+impl Default for PersistenceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            history_size: 100,
+            max_register_size: 10000,
+        }
+    }
 }
 
 impl Settings for VimSettings {
@@ -1,3 +1,4 @@
+use gpui::{AppContext, Global};
 use project::TaskSourceKind;
 use remote::ConnectionState;
 use task::{ResolvedTask, TaskContext, TaskTemplate};
@@ -5,6 +6,52 @@ use ui::ViewContext;
 
 use crate::Workspace;
 
+type ScheduleTaskWithInputs = fn(
+    &mut Workspace,
+    TaskSourceKind,
+    TaskTemplate,
+    TaskContext,
+    bool,
+    &mut ViewContext<'_, Workspace>,
+);
+
+struct GlobalScheduleTaskWithInputs(ScheduleTaskWithInputs);
+
+impl Global for GlobalScheduleTaskWithInputs {}
+
+/// Sets how [`schedule_task_with_inputs`] asks the user for the values of `${input:id}` variables
+/// before scheduling a task.
+pub fn set_schedule_task_with_inputs(schedule: ScheduleTaskWithInputs, cx: &mut AppContext) {
+    cx.set_global(GlobalScheduleTaskWithInputs(schedule));
+}
+
+/// Schedules the task once the user has entered values for the `${input:id}` variables it and its
+/// dependencies reference. Without a way to ask for them registered through
+/// [`set_schedule_task_with_inputs`], the task is scheduled right away.
+pub fn schedule_task_with_inputs(
+    workspace: &mut Workspace,
+    task_source_kind: TaskSourceKind,
+    task: TaskTemplate,
+    task_cx: TaskContext,
+    omit_history: bool,
+    cx: &mut ViewContext<'_, Workspace>,
+) {
+    let schedule = cx
+        .try_global::<GlobalScheduleTaskWithInputs>()
+        .map(|schedule| schedule.0);
+    match schedule {
+        Some(schedule) => schedule(workspace, task_source_kind, task, task_cx, omit_history, cx),
+        None => schedule_task(
+            workspace,
+            task_source_kind,
+            &task,
+            &task_cx,
+            omit_history,
+            cx,
+        ),
+    }
+}
+
 pub fn schedule_task(
     workspace: &mut Workspace,
     task_source_kind: TaskSourceKind,
@@ -26,9 +73,29 @@ pub fn schedule_task(
         }
     }
 
-    if let Some(spawn_in_terminal) =
+    if let Some(mut spawn_in_terminal) =
         task_to_resolve.resolve_task(&task_source_kind.to_id_base(), task_cx)
     {
+        let worktree = match &task_source_kind {
+            TaskSourceKind::Worktree { id, .. } => Some(*id),
+            _ => None,
+        };
+        if let Some(task_inventory) = workspace
+            .project
+            .read(cx)
+            .task_store()
+            .read(cx)
+            .task_inventory()
+        {
+            let resolved_dependencies = task_inventory.read(cx).resolve_dependencies(
+                worktree,
+                &mut spawn_in_terminal,
+                task_cx,
+            );
+            if let Err(e) = resolved_dependencies {
+                spawn_in_terminal.dependency_error = Some(format!("{e:#}"));
+            }
+        }
         schedule_resolved_task(
             workspace,
             task_source_kind,
@@ -46,6 +113,16 @@ pub fn schedule_resolved_task(
     omit_history: bool,
     cx: &mut ViewContext<'_, Workspace>,
 ) {
+    if let Some(error) = &resolved_task.dependency_error {
+        workspace.show_error(
+            &format!(
+                "Cannot run task {:?}: {error}",
+                resolved_task.resolved_label
+            ),
+            cx,
+        );
+        return;
+    }
     if let Some(spawn_in_terminal) = resolved_task.resolved.take() {
         if !omit_history {
             resolved_task.resolved = Some(spawn_in_terminal.clone());
@@ -20,9 +20,10 @@ use futures::{
 use fuzzy::CharBag;
 use git::GitHostingProviderRegistry;
 use git::{
-    repository::{GitFileStatus, GitRepository, RepoPath},
+    log::{CommitFileDiff, LogEntry},
+    repository::{DiffBase, GitFileStatus, GitRepository, RepoPath},
     status::GitStatus,
-    COOKIES, DOT_GIT, FSMONITOR_DAEMON, GITIGNORE,
+    Oid, COOKIES, DOT_GIT, FSMONITOR_DAEMON, GITIGNORE,
 };
 use gpui::{
     AppContext, AsyncAppContext, BackgroundExecutor, Context, EventEmitter, Model, ModelContext,
@@ -238,6 +239,18 @@ impl RepositoryEntry {
             relativize_path(path)
         }
     }
+
+    /// unrelativize is the inverse of relativize, returning the path within the worktree of a
+    /// path relative to the root folder of the repository. Returns `None` for paths outside of
+    /// the worktree, which can only happen if the repository is located in a parent folder.
+    pub fn unrelativize(&self, worktree: &Snapshot, path: &RepoPath) -> Option<Arc<Path>> {
+        let entry = worktree.entry_for_id(self.work_directory.0)?;
+        let path = match &self.location_in_repo {
+            Some(location_in_repo) => path.strip_prefix(location_in_repo).ok()?,
+            None => path.as_path(),
+        };
+        Some(entry.path.join(path).into())
+    }
 }
 
 impl From<&RepositoryEntry> for proto::RepositoryEntry {
@@ -709,7 +722,14 @@ impl Worktree {
         }
     }
 
-    pub fn load_staged_file(&self, path: &Path, cx: &AppContext) -> Task<Result<Option<String>>> {
+    /// Loads the contents a file has in the given diff base, or `None` if it isn't in a
+    /// repository or doesn't exist there.
+    pub fn load_diff_base_file(
+        &self,
+        path: &Path,
+        diff_base: DiffBase,
+        cx: &AppContext,
+    ) -> Task<Result<Option<String>>> {
         match self {
             Worktree::Local(this) => {
                 let path = Arc::from(path);
@@ -720,7 +740,7 @@ impl Worktree {
                             if let Some(git_repo) =
                                 snapshot.git_repositories.get(&*repo.work_directory)
                             {
-                                return Ok(git_repo.repo_ptr.load_index_text(&repo_path));
+                                return diff_base.load_text(git_repo.repo_ptr.as_ref(), &repo_path);
                             }
                         }
                     }
@@ -733,6 +753,115 @@ impl Worktree {
         }
     }
 
+    pub fn load_committed_file(
+        &self,
+        path: &Path,
+        cx: &AppContext,
+    ) -> Task<Result<Option<String>>> {
+        match self {
+            Worktree::Local(this) => {
+                let path = Arc::from(path);
+                let snapshot = this.snapshot();
+                cx.background_executor().spawn(async move {
+                    if let Some(repo) = snapshot.repository_for_path(&path) {
+                        if let Some(repo_path) = repo.relativize(&snapshot, &path).log_err() {
+                            if let Some(git_repo) =
+                                snapshot.git_repositories.get(&*repo.work_directory)
+                            {
+                                return Ok(git_repo.repo_ptr.load_committed_text(&repo_path));
+                            }
+                        }
+                    }
+                    Ok(None)
+                })
+            }
+            Worktree::Remote(_) => Task::ready(Err(anyhow!(
+                "remote worktrees can't yet load committed files"
+            ))),
+        }
+    }
+
+    /// Replaces the staged contents of a file, or removes it from the index when `text` is `None`.
+    pub fn write_staged_file(
+        &self,
+        path: &Path,
+        text: Option<String>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        match self {
+            Worktree::Local(this) => {
+                let path = Arc::from(path);
+                let snapshot = this.snapshot();
+                cx.background_executor().spawn(async move {
+                    let repo = snapshot
+                        .repository_for_path(&path)
+                        .with_context(|| format!("{path:?} is not in a git repository"))?;
+                    let repo_path = repo.relativize(&snapshot, &path)?;
+                    let git_repo = snapshot
+                        .git_repositories
+                        .get(&*repo.work_directory)
+                        .context("git repository is not loaded")?;
+                    git_repo.repo_ptr.set_index_text(&repo_path, text)
+                })
+            }
+            Worktree::Remote(_) => Task::ready(Err(anyhow!(
+                "remote worktrees can't yet write staged files"
+            ))),
+        }
+    }
+
+    /// Reads the history of the repository containing `path`. Unless `path` is the root of the
+    /// repository, only commits touching it are returned.
+    pub fn git_log(
+        &self,
+        path: &Path,
+        range: Option<String>,
+        limit: usize,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<LogEntry>>> {
+        match self {
+            Worktree::Local(this) => {
+                let path = Arc::from(path);
+                let snapshot = this.snapshot();
+                cx.background_executor().spawn(async move {
+                    let (repo, git_repo) = snapshot
+                        .repo_for_path(&path)
+                        .with_context(|| format!("{path:?} is not in a git repository"))?;
+                    let repo_path = repo.relativize(&snapshot, &path)?;
+                    let path_filter = (!repo_path.as_os_str().is_empty()).then_some(&repo_path);
+                    git_repo.repo_ptr.log(path_filter, range.as_deref(), limit)
+                })
+            }
+            Worktree::Remote(_) => {
+                Task::ready(Err(anyhow!("remote worktrees can't yet read git history")))
+            }
+        }
+    }
+
+    /// Loads the changes made by a commit in the repository containing `path`.
+    pub fn load_commit_diff(
+        &self,
+        path: &Path,
+        sha: Oid,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<CommitFileDiff>>> {
+        match self {
+            Worktree::Local(this) => {
+                let path = Arc::from(path);
+                let snapshot = this.snapshot();
+                cx.background_executor().spawn(async move {
+                    let (_, git_repo) = snapshot
+                        .repo_for_path(&path)
+                        .with_context(|| format!("{path:?} is not in a git repository"))?;
+                    git_repo.repo_ptr.commit_diff(sha)
+                })
+            }
+            Worktree::Remote(_) => {
+                Task::ready(Err(anyhow!("remote worktrees can't yet read git history")))
+            }
+        }
+    }
+
     pub fn load_binary_file(
         &self,
         path: &Path,
@@ -26,6 +26,7 @@ use gpui::{
     PathPromptOptions, PromptLevel, ReadGlobal, Task, TitlebarOptions, View, ViewContext,
     VisualContext, WindowKind, WindowOptions,
 };
+use hierarchy_panel::HierarchyPanel;
 pub use open_listener::*;
 use outline_panel::OutlinePanel;
 use paths::{local_settings_file_relative_path, local_tasks_file_relative_path};
@@ -309,6 +310,7 @@ fn initialize_panels(prompt_builder: Arc<PromptBuilder>, cx: &mut ViewContext<Wo
     cx.spawn(|workspace_handle, mut cx| async move {
         let project_panel = ProjectPanel::load(workspace_handle.clone(), cx.clone());
         let outline_panel = OutlinePanel::load(workspace_handle.clone(), cx.clone());
+        let hierarchy_panel = HierarchyPanel::load(workspace_handle.clone(), cx.clone());
         let terminal_panel = TerminalPanel::load(workspace_handle.clone(), cx.clone());
         let channels_panel =
             collab_ui::collab_panel::CollabPanel::load(workspace_handle.clone(), cx.clone());
@@ -324,6 +326,7 @@ fn initialize_panels(prompt_builder: Arc<PromptBuilder>, cx: &mut ViewContext<Wo
         let (
             project_panel,
             outline_panel,
+            hierarchy_panel,
             terminal_panel,
             channels_panel,
             chat_panel,
@@ -332,6 +335,7 @@ fn initialize_panels(prompt_builder: Arc<PromptBuilder>, cx: &mut ViewContext<Wo
         ) = futures::try_join!(
             project_panel,
             outline_panel,
+            hierarchy_panel,
             terminal_panel,
             channels_panel,
             chat_panel,
@@ -342,6 +346,7 @@ fn initialize_panels(prompt_builder: Arc<PromptBuilder>, cx: &mut ViewContext<Wo
         workspace_handle.update(&mut cx, |workspace, cx| {
             workspace.add_panel(project_panel, cx);
             workspace.add_panel(outline_panel, cx);
+            workspace.add_panel(hierarchy_panel, cx);
             workspace.add_panel(terminal_panel, cx);
             workspace.add_panel(channels_panel, cx);
             workspace.add_panel(chat_panel, cx);
@@ -3536,6 +3541,7 @@ mod tests {
             git_ui::init(cx);
             project_panel::init((), cx);
             outline_panel::init((), cx);
+            hierarchy_panel::init(cx);
             terminal_view::init(cx);
             copilot::copilot_chat::init(
                 app_state.fs.clone(),